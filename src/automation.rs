@@ -0,0 +1,239 @@
+use crate::{
+    context::TimelineCtx,
+    interaction,
+    ruler::MusicalInfo,
+    snap,
+};
+use std::ops::RangeInclusive;
+
+/// The radius of each automation point.
+const POINT_RADIUS: f32 = 4.0;
+/// The distance from a point within which the pointer grabs it.
+const GRAB_RADIUS: f32 = 7.0;
+
+/// Show an editable automation lane within the given track area.
+///
+/// `points` are `(tick, value)` pairs sorted by tick, where ticks are absolute and values lie
+/// within `range`. They are only re-sorted after a point is added or moved. Points are drawn as
+/// circles connected by lines, holding the first and last values out to the edges of the track.
+///
+/// - Clicking empty space adds a point.
+/// - Dragging a point moves it, clamped between its neighbours' ticks and to `range`.
+/// - Right-clicking a point removes it.
+///
+/// Ticks are snapped to the timeline's `SnapMode`, which can be bypassed by holding `Alt`. The
+/// lane claims the pointer while hovered so that the track does not also move the playhead or
+/// start a selection. `id_salt` must be unique among the lanes shown within the `Ui`, e.g. the
+/// track's id.
+///
+/// Returns `true` if `points` changed.
+pub fn lane(
+    ui: &mut egui::Ui,
+    id_salt: impl std::hash::Hash,
+    timeline: &TimelineCtx,
+    info: &dyn MusicalInfo,
+    track_rect: egui::Rect,
+    points: &mut Vec<(f32, f32)>,
+    range: RangeInclusive<f32>,
+) -> bool {
+    let response = ui.interact(track_rect, ui.make_persistent_id(id_salt), egui::Sense::click_and_drag());
+    if response.hovered() || response.dragged() {
+        interaction::claim_pointer(ui, timeline.id);
+    }
+
+    // Conversions between points and screen positions, leaving room for the circles.
+    let (min, max) = (*range.start(), *range.end());
+    let value_rect = track_rect.shrink2(egui::Vec2::new(0.0, POINT_RADIUS));
+    let value_span = (max - min).max(f32::EPSILON);
+    let to_pos = |(tick, value): (f32, f32)| {
+        let x = timeline.tick_to_x(tick - timeline.timeline_start);
+        let y = value_rect.bottom() - (value - min) / value_span * value_rect.height();
+        egui::Pos2::new(x, y)
+    };
+    let snap_mode = timeline.effective_snap(ui);
+    let from_pos = |pos: egui::Pos2| {
+        let tick = timeline.x_to_tick(pos.x).max(-timeline.timeline_start);
+        let tick = timeline.timeline_start + snap::snap_tick(tick, info, snap_mode);
+        let value = min + (value_rect.bottom() - pos.y) / value_rect.height() * value_span;
+        (tick, value.clamp(min, max))
+    };
+    let point_at = |points: &[(f32, f32)], pos: egui::Pos2| {
+        points
+            .iter()
+            .map(|&p| to_pos(p).distance(pos))
+            .enumerate()
+            .filter(|&(_, d)| d <= GRAB_RADIUS)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(ix, _)| ix)
+    };
+
+    let mut changed = false;
+    let hovered_point = response.hover_pos().and_then(|pos| point_at(points, pos));
+    if hovered_point.is_some() {
+        ui.ctx().set_cursor_icon(egui::CursorIcon::Grab);
+    }
+
+    // Begin dragging the point under the press origin, if any.
+    let drag_id = response.id.with("automation_drag");
+    if response.drag_started_by(egui::PointerButton::Primary)
+        && let Some(origin) = ui.input(|i| i.pointer.press_origin())
+        && let Some(ix) = point_at(points, origin)
+    {
+        ui.data_mut(|d| d.insert_temp(drag_id, ix));
+    }
+    let dragged_point = ui.data(|d| d.get_temp::<usize>(drag_id));
+    if response.drag_stopped() {
+        ui.data_mut(|d| d.remove::<usize>(drag_id));
+    }
+
+    if let Some(ix) = dragged_point
+        && ix < points.len()
+        && response.dragged_by(egui::PointerButton::Primary)
+        && let Some(pos) = response.interact_pointer_pos()
+    {
+        // Keep the point between its neighbours so that the order never changes.
+        let (tick, value) = from_pos(pos);
+        let lo = if ix > 0 { points[ix - 1].0 } else { 0.0 };
+        let hi = points.get(ix + 1).map_or(f32::INFINITY, |p| p.0);
+        let new_point = (tick.clamp(lo, hi), value);
+        if points[ix] != new_point {
+            points[ix] = new_point;
+            points.sort_by(|a, b| a.0.total_cmp(&b.0));
+            changed = true;
+        }
+    } else if response.clicked()
+        && let Some(pos) = response.interact_pointer_pos()
+        && point_at(points, pos).is_none()
+    {
+        points.push(from_pos(pos));
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        changed = true;
+    } else if response.secondary_clicked()
+        && let Some(pos) = response.interact_pointer_pos()
+        && let Some(ix) = point_at(points, pos)
+    {
+        points.remove(ix);
+        changed = true;
+    }
+
+    // Paint the lines between points, holding the outer values to the edges of the track.
+    let painter = ui.painter().with_clip_rect(ui.clip_rect().intersect(track_rect));
+    let vis = ui.style().noninteractive();
+    let color = ui.visuals().selection.stroke.color;
+    if let (Some(&first), Some(&last)) = (points.first(), points.last()) {
+        let mut line = Vec::with_capacity(points.len() + 2);
+        line.push(egui::Pos2::new(track_rect.left(), to_pos(first).y));
+        line.extend(points.iter().map(|&p| to_pos(p)));
+        line.push(egui::Pos2::new(track_rect.right(), to_pos(last).y));
+        painter.add(egui::Shape::line(line, egui::Stroke::new(1.5, color)));
+    }
+
+    // Paint the points on top, highlighting the one under the pointer.
+    let active_point = dragged_point.or(hovered_point);
+    for (ix, &point) in points.iter().enumerate() {
+        let (radius, fill) = if active_point == Some(ix) {
+            (POINT_RADIUS + 1.0, color)
+        } else {
+            (POINT_RADIUS, vis.bg_fill)
+        };
+        painter.circle(to_pos(point), radius, fill, egui::Stroke::new(1.5, color));
+    }
+
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{state::SimpleTimelineState, style::TimelineStyle, SnapMode, ToolMode};
+    use egui::PointerButton::{Primary, Secondary};
+
+    /// A lane shown across an 800 by 100 point screen, at 60 ticks per point.
+    struct Lane {
+        ctx: egui::Context,
+        time: f64,
+        points: Vec<(f32, f32)>,
+    }
+
+    impl Lane {
+        fn new(points: Vec<(f32, f32)>) -> Self {
+            Self { ctx: egui::Context::default(), time: 0.0, points }
+        }
+
+        /// Run a frame with the given pointer events, returning whether the points changed.
+        fn frame(&mut self, events: Vec<egui::Event>) -> bool {
+            let screen_rect = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(800.0, 100.0));
+            self.time += 0.1;
+            let input =
+                egui::RawInput { screen_rect: Some(screen_rect), time: Some(self.time), events, ..Default::default() };
+            let mut changed = false;
+            let points = &mut self.points;
+            let _ = self.ctx.run(input, |ctx| {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    let style = TimelineStyle::from_visuals(ui.visuals());
+                    let visible_ticks = 60.0 * screen_rect.width();
+                    let id = egui::Id::new("timeline");
+                    let timeline =
+                        TimelineCtx::new(id, screen_rect, visible_ticks, 0.0, style, SnapMode::Off, ToolMode::Select);
+                    let info = SimpleTimelineState::new();
+                    changed = lane(ui, "lane", &timeline, &info, screen_rect, points, 0.0..=1.0);
+                });
+            });
+            changed
+        }
+
+        fn click(&mut self, x: f32, y: f32, button: egui::PointerButton) -> bool {
+            self.frame(vec![pointer_moved(x, y)]);
+            self.frame(vec![pointer_button(x, y, button, true)]);
+            self.frame(vec![pointer_button(x, y, button, false)])
+        }
+    }
+
+    fn pointer_moved(x: f32, y: f32) -> egui::Event {
+        egui::Event::PointerMoved(egui::pos2(x, y))
+    }
+
+    fn pointer_button(x: f32, y: f32, button: egui::PointerButton, pressed: bool) -> egui::Event {
+        let pos = egui::pos2(x, y);
+        egui::Event::PointerButton { pos, button, pressed, modifiers: egui::Modifiers::NONE }
+    }
+
+    /// The y position of the given value within the lane, inset by the point radius.
+    fn value_y(value: f32) -> f32 {
+        96.0 - value * 92.0
+    }
+
+    #[test]
+    fn click_adds_point_in_order() {
+        let mut lane = Lane::new(vec![(0.0, 0.5), (24_000.0, 0.5)]);
+        assert!(lane.click(100.0, value_y(0.75), Primary));
+        assert_eq!(lane.points, vec![(0.0, 0.5), (6000.0, 0.75), (24_000.0, 0.5)]);
+        // Clicking an existing point doesn't add another
+        assert!(!lane.click(100.0, value_y(0.75), Primary));
+        assert_eq!(lane.points.len(), 3);
+    }
+
+    #[test]
+    fn drag_moves_point_between_neighbours() {
+        let mut lane = Lane::new(vec![(0.0, 0.5), (6000.0, 0.75), (24_000.0, 0.5)]);
+        lane.frame(vec![pointer_moved(100.0, value_y(0.75))]);
+        lane.frame(vec![pointer_button(100.0, value_y(0.75), Primary, true)]);
+        lane.frame(vec![pointer_moved(200.0, value_y(0.75))]);
+        assert!(lane.frame(vec![pointer_moved(300.0, value_y(0.25))]));
+        assert_eq!(lane.points[1], (18_000.0, 0.25));
+        // Dragging past the next point stops at it, keeping the order
+        lane.frame(vec![pointer_moved(500.0, value_y(0.25))]);
+        lane.frame(vec![pointer_button(500.0, value_y(0.25), Primary, false)]);
+        assert_eq!(lane.points, vec![(0.0, 0.5), (24_000.0, 0.25), (24_000.0, 0.5)]);
+        assert!(!lane.frame(vec![]));
+    }
+
+    #[test]
+    fn right_click_removes_point() {
+        let mut lane = Lane::new(vec![(0.0, 0.5), (6000.0, 0.75), (24_000.0, 0.5)]);
+        // Right-clicking empty space does nothing
+        assert!(!lane.click(200.0, value_y(0.75), Secondary));
+        assert!(lane.click(100.0, value_y(0.75), Secondary));
+        assert_eq!(lane.points, vec![(0.0, 0.5), (24_000.0, 0.5)]);
+    }
+}
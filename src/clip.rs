@@ -0,0 +1,287 @@
+use crate::{
+    context::TimelineCtx,
+    interaction,
+    ruler::MusicalInfo,
+    snap,
+    types::TrackId,
+};
+use egui::{Color32, Stroke};
+
+/// The colours and strokes used to paint a clip.
+#[derive(Clone, Debug)]
+pub struct ClipStyle {
+    /// Fill painted over the area of the clip.
+    pub fill: Color32,
+    /// Stroke painted around the clip.
+    pub stroke: Stroke,
+    /// Stroke painted around the clip while hovered or dragged.
+    pub hovered_stroke: Stroke,
+    /// The rounding of the clip's corners.
+    pub rounding: f32,
+}
+
+impl ClipStyle {
+    /// Derive a clip style from the given visuals.
+    pub fn from_visuals(visuals: &egui::Visuals) -> Self {
+        let color = visuals.selection.bg_fill;
+        Self {
+            fill: color.linear_multiply(0.5),
+            stroke: Stroke::new(1.0, color),
+            hovered_stroke: Stroke::new(1.0, visuals.widgets.hovered.fg_stroke.color),
+            rounding: 2.0,
+        }
+    }
+
+    /// Set the fill painted over the area of the clip.
+    pub fn fill(mut self, fill: Color32) -> Self {
+        self.fill = fill;
+        self
+    }
+
+    /// Set the stroke painted around the clip.
+    pub fn stroke(mut self, stroke: Stroke) -> Self {
+        self.stroke = stroke;
+        self
+    }
+
+    /// Set the stroke painted around the clip while hovered or dragged.
+    pub fn hovered_stroke(mut self, stroke: Stroke) -> Self {
+        self.hovered_stroke = stroke;
+        self
+    }
+
+    /// Set the rounding of the clip's corners.
+    pub fn rounding(mut self, rounding: f32) -> Self {
+        self.rounding = rounding;
+        self
+    }
+}
+
+impl Default for ClipStyle {
+    fn default() -> Self {
+        Self::from_visuals(&egui::Visuals::dark())
+    }
+}
+
+/// The result of showing a clip.
+///
+/// The clip does not modify its own position. Instead, hosts apply the reported changes to their
+/// own data so that the new position is shown on the next frame.
+#[derive(Clone, Debug)]
+pub struct ClipResponse {
+    /// The response of the clip's area.
+    pub response: egui::Response,
+    /// The number of ticks the clip was moved by this frame, if it was moved.
+    pub moved_by_ticks: Option<f32>,
+    /// The new absolute start tick if the clip's start edge was dragged this frame.
+    pub resized_start_to: Option<f32>,
+    /// The new absolute end tick if the clip's end edge was dragged this frame.
+    pub resized_end_to: Option<f32>,
+    /// Whether the clip was clicked.
+    pub clicked: bool,
+    /// Whether the clip was double-clicked.
+    pub double_clicked: bool,
+    /// The track with an id that the clip was dropped on this frame, if dragged to a track other
+    /// than its own.
+    ///
+    /// The horizontal component of the move is reported via `moved_by_ticks` during the drag as
+    /// usual, so hosts only need to move the clip to the new track.
+    pub moved_to_track: Option<TrackId>,
+}
+
+/// A drag of a clip in progress, stored in egui memory between frames.
+#[derive(Copy, Clone, Debug)]
+struct ClipDragState {
+    /// The part of the clip being dragged.
+    part: ClipDrag,
+    /// The absolute start and end ticks of the clip when the drag began, restored on `Escape`.
+    origin: (f32, f32),
+    /// The track that the clip was dragged from, if it has an id.
+    track: Option<(TrackId, egui::Rect)>,
+}
+
+/// The part of a clip being dragged.
+#[derive(Copy, Clone, Debug)]
+enum ClipDrag {
+    /// Move the whole clip, grabbed at the given offset in ticks from its start.
+    Move { grab_offset: f32 },
+    /// Resize the clip by moving its start edge.
+    Start,
+    /// Resize the clip by moving its end edge.
+    End,
+}
+
+/// The width in points of the resize handles at either edge of a clip.
+const EDGE_WIDTH: f32 = 5.0;
+
+/// Show a clip spanning the absolute ticks `start_tick..end_tick` within the given track area.
+///
+/// Dragging the middle of the clip moves it, while dragging either edge resizes it. Positions
+/// are snapped to the timeline's `SnapMode`, which can be bypassed by holding `Alt`. Reported
+/// positions are clamped so that the start never precedes tick `0.0` and the end always follows
+/// the start. Pressing `Escape` during a drag restores the clip's position from before the drag.
+///
+/// A clip may also be dragged vertically onto another track with an id. While over another
+/// track, its lane is highlighted and an outline of the clip is drawn where it would land, and
+/// on release `ClipResponse::moved_to_track` reports the new track.
+///
+/// While hovered or dragged, the clip claims the pointer so that the track does not also move
+/// the playhead or start a selection.
+///
+/// The clip's id is derived from `id_salt`, which must be unique among the clips shown within the
+/// same `ui`, e.g. the clip's index or key, so that drags persist while other clips scroll in and
+/// out of view.
+#[allow(clippy::too_many_arguments)]
+pub fn clip(
+    ui: &mut egui::Ui,
+    id_salt: impl std::hash::Hash,
+    timeline: &TimelineCtx,
+    info: &dyn MusicalInfo,
+    track_rect: egui::Rect,
+    start_tick: f32,
+    end_tick: f32,
+    style: &ClipStyle,
+) -> ClipResponse {
+    let timeline_start = info.timeline_start().unwrap_or(0.0);
+    let start_x = timeline.tick_to_x(start_tick - timeline_start);
+    let end_x = timeline.tick_to_x(end_tick - timeline_start);
+    let rect = egui::Rect::from_x_y_ranges(start_x..=end_x, track_rect.y_range());
+    let response = ui.interact(rect, ui.make_persistent_id(id_salt), egui::Sense::click_and_drag());
+
+    // Absolute ticks at the given x position, before and after snapping.
+    let snap_mode = timeline.effective_snap(ui);
+    let x_to_raw_tick = |x: f32| timeline_start + timeline.x_to_tick(x);
+    let snap_abs = |tick: f32| {
+        let tick = (tick - timeline_start).max(-timeline_start);
+        timeline_start + snap::snap_tick(tick, info, snap_mode)
+    };
+
+    // Shrink the edge handles on narrow clips so that they may still be moved.
+    let edge_w = EDGE_WIDTH.min(rect.width() / 3.0);
+    let drag_at = |x: f32| {
+        if x <= rect.left() + edge_w {
+            ClipDrag::Start
+        } else if x >= rect.right() - edge_w {
+            ClipDrag::End
+        } else {
+            ClipDrag::Move { grab_offset: x_to_raw_tick(x) - start_tick }
+        }
+    };
+
+    if response.hovered() || response.dragged() {
+        interaction::claim_pointer(ui, timeline.id);
+    }
+
+    // Indicate the available interaction with the cursor.
+    if let Some(pos) = response.hover_pos() {
+        let icon = match drag_at(pos.x) {
+            ClipDrag::Start | ClipDrag::End => egui::CursorIcon::ResizeHorizontal,
+            ClipDrag::Move { .. } if response.dragged() => egui::CursorIcon::Grabbing,
+            ClipDrag::Move { .. } => egui::CursorIcon::Grab,
+        };
+        ui.ctx().set_cursor_icon(icon);
+    }
+
+    let drag_id = response.id.with("clip_drag");
+    if response.drag_started_by(egui::PointerButton::Primary)
+        && let Some(origin) = ui.input(|i| i.pointer.press_origin())
+    {
+        let drag = ClipDragState {
+            part: drag_at(origin.x),
+            origin: (start_tick, end_tick),
+            track: interaction::track_at(ui, timeline.id, track_rect.center().y),
+        };
+        ui.data_mut(|d| d.insert_temp(drag_id, drag));
+    }
+
+    // The smallest length a clip may be resized to.
+    let min_len = info.ticks_per_point();
+    let mut moved_by_ticks = None;
+    let mut resized_start_to = None;
+    let mut resized_end_to = None;
+    let mut moved_to_track = None;
+    let mut drag = ui.data(|d| d.get_temp::<ClipDragState>(drag_id));
+    if let Some(state) = drag
+        && response.dragged_by(egui::PointerButton::Primary)
+        && interaction::cancel_pressed(ui)
+    {
+        // Restore the clip and ignore the rest of the drag.
+        let (start, end) = state.origin;
+        match state.part {
+            ClipDrag::Move { .. } => moved_by_ticks = Some(start - start_tick).filter(|&d| d != 0.0),
+            ClipDrag::Start => resized_start_to = Some(start),
+            ClipDrag::End => resized_end_to = Some(end),
+        }
+        ui.data_mut(|d| d.remove::<ClipDragState>(drag_id));
+        drag = None;
+    } else if let Some(drag) = drag
+        && response.dragged_by(egui::PointerButton::Primary)
+        && let Some(pos) = response.interact_pointer_pos()
+    {
+        match drag.part {
+            ClipDrag::Move { grab_offset } => {
+                let new_start = snap_abs(x_to_raw_tick(pos.x) - grab_offset).max(0.0);
+                if new_start != start_tick {
+                    moved_by_ticks = Some(new_start - start_tick);
+                }
+            }
+            ClipDrag::Start => {
+                let new_start = snap_abs(x_to_raw_tick(pos.x)).min(end_tick - min_len).max(0.0);
+                if new_start != start_tick {
+                    resized_start_to = Some(new_start);
+                }
+            }
+            ClipDrag::End => {
+                let new_end = snap_abs(x_to_raw_tick(pos.x)).max(start_tick + min_len);
+                if new_end != end_tick {
+                    resized_end_to = Some(new_end);
+                }
+            }
+        }
+    }
+
+    // The other track that a moved clip is over, if any.
+    let target = match (drag, response.interact_pointer_pos()) {
+        (Some(ClipDragState { part: ClipDrag::Move { .. }, track: Some((from, from_rect)), .. }), Some(pos))
+            if response.dragged() || response.drag_stopped() =>
+        {
+            interaction::track_at(ui, timeline.id, pos.y)
+                .filter(|(to, _)| *to != from)
+                .map(|(to, to_rect)| (to, to_rect, from_rect))
+        }
+        _ => None,
+    };
+    if response.drag_stopped() {
+        moved_to_track = target.map(|(to, _, _)| to);
+        ui.data_mut(|d| d.remove::<ClipDragState>(drag_id));
+    } else if let Some((_, to_rect, from_rect)) = target {
+        // Highlight the target lane and outline where the clip would land.
+        let painter = crate::playhead::foreground_painter(ui).with_clip_rect(timeline.full_rect);
+        let selection = ui.visuals().selection;
+        painter.rect_filled(to_rect, 0.0, selection.bg_fill.linear_multiply(0.15));
+        let dx = moved_by_ticks.unwrap_or(0.0) / info.ticks_per_point();
+        let ghost = rect
+            .translate(egui::Vec2::new(dx, to_rect.top() - from_rect.top()))
+            .intersect(to_rect);
+        painter.rect_stroke(ghost, style.rounding, style.hovered_stroke);
+    }
+
+    // Paint the clip, clipped to the visible timeline.
+    let painter = ui.painter().with_clip_rect(ui.clip_rect().intersect(timeline.full_rect));
+    let stroke = if response.hovered() || response.dragged() {
+        style.hovered_stroke
+    } else {
+        style.stroke
+    };
+    painter.rect(rect, style.rounding, style.fill, stroke);
+
+    ClipResponse {
+        clicked: response.clicked(),
+        double_clicked: response.double_clicked(),
+        response,
+        moved_by_ticks,
+        resized_start_to,
+        resized_end_to,
+        moved_to_track,
+    }
+}
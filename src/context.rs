@@ -1,4 +1,6 @@
+use crate::{snap::{SnapGrid, SnapMode}, style::{TimelineStyle, TracksStyle}, tool::ToolMode, types::TrackId};
 use egui::Rect;
+use std::{cell::{Cell, RefCell}, ops::Range};
 
 /// A context for instantiating tracks, either pinned or unpinned.
 pub struct TracksCtx {
@@ -9,14 +11,46 @@ pub struct TracksCtx {
     pub header_full_rect: Option<Rect>,
     /// Context specific to the timeline (non-header) area.
     pub timeline: TimelineCtx,
+    /// Whether selections are created by rectangular selection across tracks rather than per
+    /// track. See `Show::tracks_with_rect_select`.
+    pub(crate) rect_select: bool,
+    /// The id and area of each track with an id shown so far this frame.
+    pub(crate) track_rects: RefCell<Vec<(TrackId, Rect)>>,
+    /// The distance that headers are indented by the groups that the next track is nested within.
+    pub(crate) header_indent: Cell<f32>,
+    /// The vertical distance the timeline was panned this frame, applied to the tracks' scroll area.
+    pub(crate) pan_y: f32,
 }
 
 /// Some context for the timeline, providing short-hand for setting some useful widgets.
 pub struct TimelineCtx {
+    /// The unique identifier of the timeline widget, for storing state in egui memory.
+    pub id: egui::Id,
     /// The total visible rect of the timeline area including pinned and unpinned tracks.
     pub full_rect: Rect,
     /// The total number of ticks visible on the timeline area.
     pub visible_ticks: f32,
+    /// The absolute tick at the left edge of the timeline area.
+    pub timeline_start: f32,
+    /// The end of the timeline in absolute ticks, as given by `TimelineApi::timeline_end`.
+    ///
+    /// The area past the end is dimmed, and playhead seeks and selection edges on the tracks are
+    /// clamped to it.
+    pub timeline_end: Option<f32>,
+    /// The number of ticks before tick `0.0` that may be scrolled into view, as set via
+    /// `Timeline::pre_roll`.
+    pub pre_roll: f32,
+    /// The distance in points that the contents are shifted while rubber-banding past either end
+    /// of the timeline. See `Timeline::elastic_overscroll`.
+    pub(crate) overscroll: f32,
+    /// The colours and strokes used to paint the timeline.
+    pub style: TimelineStyle,
+    /// The grid that playhead seeks and selection edges are snapped to.
+    pub snap: SnapMode,
+    /// What pressing and dragging on the tracks does.
+    pub tool_mode: ToolMode,
+    /// The bars across the view that positions are snapped to.
+    pub(crate) snap_grid: SnapGrid,
 }
 
 /// A type used to assist with setting a track with an optional `header`.
@@ -25,20 +59,137 @@ pub struct TrackCtx<'a> {
     ui: &'a mut egui::Ui,
     available_rect: Rect,
     header_height: f32,
-    track_id: Option<String>,
+    track_id: Option<TrackId>,
+    /// The `(min, max)` height of the track if it may be resized by dragging its bottom border.
+    resizable: Option<(f32, f32)>,
+    /// Whether the track is collapsed, if it may be collapsed via a chevron in its header.
+    collapsible: Option<bool>,
+    /// Whether the header's chevron was clicked.
+    collapse_toggled: bool,
+    /// The interaction beneath the header's contents, allocated by `header`.
+    header_response: Option<egui::Response>,
+    /// Whether the children of the group that this track heads are hidden, if it heads a group.
+    group_collapsed: Option<bool>,
+    /// The height of the track if known up front, set via `fixed_height`.
+    fixed_height: Option<f32>,
+    /// How the header is sized and positioned, set via `header_height`.
+    header_sizing: Option<HeaderSizing>,
+    /// Shown when the track's timeline area is right-clicked, set via `context_menu`.
+    context_menu: Option<TrackMenu<'a>>,
+    /// The absolute tick at which recording started on this track, set via `recording`.
+    recording: Option<f32>,
+    /// Shown over the track's contents and selection, set via `overlay`.
+    overlay: Option<TrackOverlay<'a>>,
+    /// The colour identifying the track, set via `color`.
+    color: Option<egui::Color32>,
+}
+
+/// A type used to assist with setting a group of tracks, begun via `TracksCtx::group`.
+///
+/// The group is headed by a track of its own, whose header shows a disclosure chevron. Its child
+/// tracks are shown via `TracksCtx::next` within the closure given to `show` and are indented
+/// within the header column. Groups may be nested.
+pub struct GroupCtx<'a> {
+    track: TrackCtx<'a>,
+    collapsed: bool,
+}
+
+/// How a track's header is sized relative to the track, set via `TrackCtx::header_height`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum HeaderSizing {
+    /// The header is measured from its contents and centred vertically within the track.
+    ///
+    /// The track's height is that of the taller of the header and the track's contents, as
+    /// measured when the track was last shown.
+    MatchContent,
+    /// The header is the given height, with contents beyond it clipped.
+    Fixed(f32),
+}
+
+/// A track's context menu, given the clicked absolute tick and the track's id.
+type TrackMenu<'a> = Box<dyn FnOnce(&mut egui::Ui, f32, TrackId) + 'a>;
+
+/// A track's overlay, given the timeline context and the track's timeline area.
+type TrackOverlay<'a> = Box<dyn FnOnce(&TimelineCtx, &mut egui::Ui, Rect) + 'a>;
+
+/// The result of showing a track, along with the value `R` returned by its contents.
+#[derive(Clone, Debug)]
+pub struct TrackResponse<R = ()> {
+    /// The area of the whole track, including the header.
+    pub full_rect: Rect,
+    /// The area of the track within the timeline, excluding the header.
+    pub timeline_rect: Rect,
+    /// The area of the track within the header column, if the timeline has a header.
+    pub header_rect: Option<Rect>,
+    /// The interaction over the track's timeline area, e.g. for `hovered` or `context_menu`.
+    ///
+    /// Widgets within the track's contents take priority over this response. The response only
+    /// senses hover while the track's height is unknown, i.e. the first time that a track without
+    /// a fixed height is shown, and for tracks that were skipped.
+    pub response: egui::Response,
+    /// The absolute tick that the track's timeline area was clicked at this frame, snapped in the
+    /// same manner as clicks that move the playhead.
+    pub clicked_tick: Option<f32>,
+    /// The absolute tick that the track's timeline area was double-clicked at this frame, snapped
+    /// in the same manner as `clicked_tick`.
+    pub double_clicked_tick: Option<f32>,
+    /// The new height of the track if it was resized this frame.
+    ///
+    /// The height is also persisted in egui memory, so hosts only need to store it if they wish
+    /// to restore it across sessions.
+    pub new_height: Option<f32>,
+    /// Whether the collapse chevron in the header was clicked this frame.
+    ///
+    /// Hosts should flip the `collapsed` state that they pass to `TrackCtx::collapsible`.
+    pub collapse_toggled: bool,
+    /// The value returned by the track's contents, or `None` if they weren't shown because the
+    /// track was collapsed or skipped.
+    pub inner: Option<R>,
 }
 
 /// Context for instantiating the playhead after all tracks have been set.
 pub struct SetPlayhead {
+    /// The unique identifier of the timeline widget.
+    pub(crate) id: egui::Id,
     timeline_rect: Rect,
     /// The y position at the bottom of the last track, or the bottom of the
     /// tracks' scrollable area in the case that the size of the tracks
     /// exceed the visible height.
     tracks_bottom: f32,
-    /// The bottom bar rectangle (20px height at the bottom).
+    /// The bottom bar rectangle, if reserved with `Timeline::bottom_bar`.
     pub(crate) bottom_bar_rect: Option<Rect>,
-    /// The top panel rectangle (40px height at the top).
+    /// The top panel rectangle, if reserved with `Timeline::top_panel`.
     pub(crate) top_panel_rect: Option<Rect>,
+    /// The style used to paint the playhead and panels.
+    pub(crate) style: TimelineStyle,
+    /// The grid that playhead drags are snapped to.
+    pub(crate) snap: SnapMode,
+    /// The vertical scroll offset of the tracks.
+    pub(crate) scroll_offset: f32,
+    /// The visible area of the tracks relative to the top of the tracks.
+    pub(crate) tracks_viewport: Rect,
+    /// The y position at the bottom of the tracks pinned via `Show::pinned_tracks_bottom`.
+    pub(crate) pinned_bottom: Option<f32>,
+    /// The id and area of each track with an id, pinned tracks first.
+    pub(crate) track_rects: Vec<(TrackId, Rect)>,
+    /// The end of the timeline in absolute ticks, as given by `TimelineApi::timeline_end`.
+    pub(crate) timeline_end: Option<f32>,
+    /// The number of ticks before tick `0.0` that may be scrolled into view.
+    pub(crate) pre_roll: f32,
+}
+
+/// The areas of the timeline once the tracks have been shown, e.g. for painting custom overlays.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TimelineRects {
+    /// The timeline area right of the header, spanning the pinned and scrolled tracks.
+    pub timeline: Rect,
+    /// The y position at the bottom of the last track, or the bottom of the tracks' scrollable
+    /// area if the tracks exceed the visible height.
+    pub tracks_bottom: f32,
+    /// The top panel's area, if reserved with `Timeline::top_panel`.
+    pub top_panel: Option<Rect>,
+    /// The bottom bar's area, if reserved with `Timeline::bottom_bar`.
+    pub bottom_bar: Option<Rect>,
 }
 
 /// Relevant information for displaying a background for the timeline.
@@ -48,6 +199,17 @@ pub struct BackgroundCtx<'a> {
 }
 
 impl TracksCtx {
+    /// Begin showing a group of tracks headed by a track with the given id.
+    ///
+    /// The group's header shows a disclosure chevron and its child tracks are indented within the
+    /// header column. See `GroupCtx`.
+    pub fn group<'a>(&'a self, ui: &'a mut egui::Ui, group_id: impl Into<TrackId>) -> GroupCtx<'a> {
+        GroupCtx {
+            track: self.next(ui).with_id(group_id),
+            collapsed: false,
+        }
+    }
+
     /// Begin showing the next `Track`.
     pub fn next<'a>(&'a self, ui: &'a mut egui::Ui) -> TrackCtx<'a> {
         let available_rect = ui.available_rect_before_wrap();
@@ -57,133 +219,359 @@ impl TracksCtx {
             available_rect,
             header_height: 0.0,
             track_id: None,
+            resizable: None,
+            collapsible: None,
+            collapse_toggled: false,
+            header_response: None,
+            group_collapsed: None,
+            fixed_height: None,
+            header_sizing: None,
+            context_menu: None,
+            recording: None,
+            overlay: None,
+            color: None,
         }
     }
 }
 
 impl<'a> TrackCtx<'a> {
+    /// The height of a track collapsed via the chevron enabled by `collapsible`.
+    pub const COLLAPSED_HEIGHT: f32 = 12.0;
+    /// The width of the stripe painted along the left edge of the header of a track with a `color`.
+    pub const COLOR_STRIPE_WIDTH: f32 = 3.0;
+
     /// Set the track identifier for selection tracking.
-    pub fn with_id(mut self, track_id: impl Into<String>) -> Self {
+    ///
+    /// Accepts a `TrackId` or, for convenience, a string which is converted via `TrackId::from`.
+    pub fn with_id(mut self, track_id: impl Into<TrackId>) -> Self {
         self.track_id = Some(track_id.into());
         self
     }
 
+    /// Allow the track to be resized by dragging its bottom border, between the given heights.
+    ///
+    /// The height is persisted in egui memory keyed by the track's id, so tracks that may be
+    /// resized should be given a unique id via `with_id`. The header and timeline areas of the
+    /// track are both stretched to the chosen height.
+    pub fn resizable(mut self, min: f32, max: f32) -> Self {
+        self.resizable = Some((min, max.max(min)));
+        self
+    }
+
+    /// Allow the track to be collapsed to a slim bar via a chevron in its header.
+    ///
+    /// The track owns no collapsed state of its own. Instead, `TrackResponse::collapse_toggled`
+    /// reports when the chevron was clicked so that the host may flip its `collapsed` state.
+    /// While collapsed, the track is `TrackCtx::COLLAPSED_HEIGHT` high, its content is replaced by
+    /// a coloured strip and its header is shown on a single line of small text.
+    ///
+    /// Must be called before `header` for the chevron to be shown.
+    pub fn collapsible(mut self, collapsed: bool) -> Self {
+        self.collapsible = Some(collapsed);
+        self
+    }
+
+    /// Set the height of the track up front, rather than measuring it from its contents.
+    ///
+    /// The header and timeline areas of the track are both stretched to the given height. This
+    /// allows `is_visible` to cull the track before it has ever been shown. If the track is also
+    /// `resizable`, this is its height until first resized. As with resized tracks, the header
+    /// and timeline contents are clipped to the track so that they cannot paint over the next.
+    pub fn fixed_height(mut self, height: f32) -> Self {
+        self.fixed_height = Some(height);
+        self
+    }
+
+    /// Show a context menu when the track's timeline area is right-clicked.
+    ///
+    /// The menu is given the absolute tick that was clicked, snapped like the playhead, and the
+    /// id of the track. Only tracks with an id have a context menu. Right-clicking a track with a
+    /// context menu doesn't clear all selections, so the menu may wish to offer that instead.
+    pub fn context_menu(mut self, menu: impl FnOnce(&mut egui::Ui, f32, TrackId) + 'a) -> Self {
+        self.context_menu = Some(Box::new(menu));
+        self
+    }
+
+    /// Identify the track by the given colour.
+    ///
+    /// A stripe of the colour is painted along the left edge of the track's header. Unless
+    /// disabled via `TracksStyle::tint_lanes`, the track's timeline area is also tinted with a
+    /// faint version of the colour beneath its contents and selection.
+    pub fn color(mut self, color: egui::Color32) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    /// Paint the region recorded so far, from the given absolute tick to the playhead.
+    ///
+    /// The region is filled with `TimelineStyle::record_fill` over the track's contents and
+    /// grows with the playhead each frame, so the host should keep requesting repaints while
+    /// recording. Pass `None` once recording stops. Requires the `playhead_api` given to `show`.
+    pub fn recording(mut self, start_tick: Option<f32>) -> Self {
+        self.recording = start_tick;
+        self
+    }
+
+    /// UI shown over the track's contents and selection, e.g. for fades or hover affordances.
+    ///
+    /// The overlay is given the track's timeline area at its final height and is clipped to it.
+    /// It is laid out in a child `Ui` that takes up no space within the tracks.
+    pub fn overlay(mut self, overlay: impl FnOnce(&TimelineCtx, &mut egui::Ui, Rect) + 'a) -> Self {
+        self.overlay = Some(Box::new(overlay));
+        self
+    }
+
+    /// Whether any part of the track lies within the visible area of the tracks.
+    ///
+    /// For use with `skip` to avoid the cost of showing tracks that are scrolled out of view. The
+    /// track's height is taken from `fixed_height`, its resized or collapsed height, or otherwise
+    /// its height when last shown. Tracks of unknown height are always considered visible so that
+    /// they may be measured.
+    pub fn is_visible(&self) -> bool {
+        match self.known_height() {
+            Some(h) => {
+                let mut rect = self.tracks.full_rect;
+                rect.min.y = self.available_rect.min.y;
+                rect.max.y = rect.min.y + h;
+                self.ui.is_rect_visible(rect)
+            }
+            None => true,
+        }
+    }
+
+    /// Reserve the space for the track without showing it, e.g. when `is_visible` is `false`.
+    ///
+    /// The space reserved is the height used by `is_visible`, so that the scroll area keeps its
+    /// size. The track may still be scrolled to via `ScrollTo::Track`.
+    pub fn skip(self) -> TrackResponse {
+        let h = self.known_height().unwrap_or(0.0);
+        let mut timeline_rect = self.tracks.timeline.full_rect;
+        timeline_rect.min.y = self.available_rect.min.y;
+        timeline_rect.max.y = timeline_rect.min.y + h;
+        if let Some(track_id) = self.track_id {
+            self.tracks.track_rects.borrow_mut().push((track_id, timeline_rect));
+        }
+        let full_rect = Rect::from_x_y_ranges(self.tracks.full_rect.x_range(), timeline_rect.y_range());
+        let response = self.ui.interact(timeline_rect, self.response_id(), egui::Sense::hover());
+        add_track_space(self.ui, self.tracks.full_rect.width(), h, self.tracks.timeline.style.tracks.spacing);
+        TrackResponse {
+            full_rect,
+            timeline_rect,
+            header_rect: self.header_rect(full_rect),
+            response,
+            clicked_tick: None,
+            double_clicked_tick: None,
+            new_height: None,
+            collapse_toggled: false,
+            inner: None,
+        }
+    }
+
+    /// Set how the header is sized relative to the track. Call this before `header`.
+    ///
+    /// Default: the header is measured from its contents and aligned to the top of the track.
+    pub fn header_height(mut self, sizing: HeaderSizing) -> Self {
+        self.header_sizing = Some(sizing);
+        self
+    }
+
     /// UI for the track's header.
     ///
     /// The header content (text, buttons, etc.) is automatically padded 4px from the left edge
-    /// to provide consistent spacing for track labels and controls like mute/solo buttons.
+    /// to provide consistent spacing for track labels and controls like mute/solo buttons. See
+    /// `header_height` for sizing the header relative to the track.
     pub fn header(mut self, header: impl FnOnce(&mut egui::Ui)) -> Self {
         const LEFT_PADDING: f32 = 4.0;
+        const CHEVRON_SIZE: f32 = 12.0;
+        let collapsed = self.is_collapsed();
+        let sizing = self.header_sizing.filter(|_| !collapsed);
+        let content_h_id = self.tracks.timeline.id.with("track_header_height").with(self.track_id);
         let header_h = self
             .tracks
             .header_full_rect
             .map(|mut rect| {
-                rect.min.y = self.available_rect.min.y;
+                let track_top = self.available_rect.min.y;
+                rect.min.y = track_top;
+                // The header's area is allocated beneath its contents so that clicking it selects
+                // the track without taking clicks from the widgets within. It spans the area the
+                // track occupied when last shown.
+                if let Some(track_h) = self.known_height().filter(|_| self.track_id.is_some()) {
+                    let area = Rect::from_x_y_ranges(rect.x_range(), track_top..=track_top + track_h);
+                    let id = self.response_id().with("header");
+                    self.header_response = Some(self.ui.interact(area, id, egui::Sense::click()));
+                }
+                // Centre the header within the track using the heights measured when last shown.
+                if sizing == Some(HeaderSizing::MatchContent)
+                    && self.track_id.is_some()
+                    && let Some(track_h) = self.known_height()
+                    && let Some(content_h) = self.ui.data(|d| d.get_temp::<f32>(content_h_id))
+                {
+                    rect.min.y += ((track_h - content_h) * 0.5).max(0.0);
+                }
                 // Constrain header height to available rect to prevent overlap with next track
                 rect.max.y = rect.min.y.min(self.available_rect.max.y);
-                // Add 4px left padding by adjusting the rect
-                rect.min.x += LEFT_PADDING;
+                // Add 4px left padding by adjusting the rect, indented within any groups
+                rect.min.x += LEFT_PADDING + self.tracks.header_indent.get();
+
+                // Show the collapse chevron to the left of the header content
+                if self.collapsible.is_some() || self.group_collapsed.is_some() {
+                    let chevron_rect = egui::Rect::from_min_size(rect.min, egui::Vec2::splat(CHEVRON_SIZE));
+                    let id = self.tracks.timeline.id.with("track_chevron").with(self.track_id);
+                    let response = self.ui.interact(chevron_rect, id, egui::Sense::click());
+                    let collapsed = collapsed || self.group_collapsed == Some(true);
+                    paint_chevron(self.ui, &response, chevron_rect, collapsed);
+                    self.collapse_toggled = response.clicked();
+                    rect.min.x += CHEVRON_SIZE + 2.0;
+                }
+
                 let ui = &mut self.ui.new_child(
                     egui::UiBuilder::new()
                         .max_rect(rect)
                         .layout(*self.ui.layout()),
                 );
+                // Keep the header within the header column, and within the track if its height
+                // is known up front.
+                let fixed_h = match sizing {
+                    Some(HeaderSizing::Fixed(h)) => Some(h),
+                    _ => None,
+                };
+                let mut clip_rect = rect;
+                clip_rect.max.y = match [fixed_h, self.chosen_height()].into_iter().flatten().reduce(f32::min) {
+                    Some(h) => track_top + h,
+                    None => f32::INFINITY,
+                };
+                ui.set_clip_rect(clip_rect.intersect(ui.clip_rect()));
+                if collapsed {
+                    // Show a single line of small text, clipped to the collapsed height
+                    ui.style_mut().override_text_style = Some(egui::TextStyle::Small);
+                    ui.spacing_mut().interact_size.y = Self::COLLAPSED_HEIGHT;
+                    ui.spacing_mut().button_padding.y = 0.0;
+                }
                 header(ui);
-                ui.min_rect().height()
+                let content_h = ui.min_rect().height();
+                match sizing {
+                    Some(HeaderSizing::Fixed(h)) => h,
+                    Some(HeaderSizing::MatchContent) => {
+                        if self.track_id.is_some() {
+                            self.ui.data_mut(|d| d.insert_temp(content_h_id, content_h));
+                        }
+                        content_h
+                    }
+                    None => content_h,
+                }
             })
             .unwrap_or(0.0);
-        self.header_height = header_h;
+        self.header_height = if collapsed { Self::COLLAPSED_HEIGHT } else { header_h };
         self
     }
 
     /// Set the track, with a function for instantiating contents for the timeline.
-    /// `on_track_click` is called when the full track area (header + content) is clicked.
-    pub fn show(
-        self,
-        track: impl FnOnce(&TimelineCtx, &mut egui::Ui),
+    /// `on_track_click` is called when the header or timeline area of the track is clicked, other
+    /// than on a widget within it.
+    ///
+    /// Content outside of `TimelineCtx::absolute_visible_range` is off-screen and may be skipped.
+    /// The returned `TrackResponse` gives the areas that the track occupies and the interaction
+    /// over its timeline area, e.g. for custom overlays or per-track behaviour.
+    pub fn show<R>(
+        mut self,
+        track: impl FnOnce(&TimelineCtx, &mut egui::Ui) -> R,
+        playhead_api: Option<&dyn crate::playhead::PlayheadApi>,
+        selection_api: Option<&dyn crate::interaction::TrackSelectionApi>,
+        on_track_click: Option<impl FnOnce(TrackId)>,
+        is_selected: bool,
+    ) -> TrackResponse<R> {
+        self.show_inner(track, playhead_api, selection_api, on_track_click, is_selected)
+    }
+
+    /// Shared by `show` and `GroupCtx::show`, which shows the group's children afterwards.
+    fn show_inner<R>(
+        &mut self,
+        track: impl FnOnce(&TimelineCtx, &mut egui::Ui) -> R,
         playhead_api: Option<&dyn crate::playhead::PlayheadApi>,
         selection_api: Option<&dyn crate::interaction::TrackSelectionApi>,
-        on_track_click: Option<impl FnOnce(String)>,
+        on_track_click: Option<impl FnOnce(TrackId)>,
         is_selected: bool,
-    ) {
+    ) -> TrackResponse<R> {
+        // The height chosen by the user, if the track is resizable and has been resized.
+        let height_id = self
+            .tracks
+            .timeline
+            .id
+            .with("track_height")
+            .with(self.track_id);
+        let collapsed = self.is_collapsed();
+        // A fixed height is treated like a resized height.
+        let resized_h = self.chosen_height();
+
         // The UI and area for the track timeline.
         let track_timeline_rect = {
             let mut rect = self.tracks.timeline.full_rect;
             rect.min.y = self.available_rect.min.y;
+            if let Some(h) = resized_h {
+                rect.max.y = rect.min.y + h;
+            }
             rect
         };
         
-        let track_h = {
+        // The absolute tick at the given x position, snapped as when moving the playhead.
+        let timeline = &self.tracks.timeline;
+        let snap_mode = timeline.effective_snap(self.ui);
+        let x_to_tick = |x| {
+            let tick = crate::snap::snap_tick(timeline.x_to_tick(x).max(0.0), &timeline.snap_grid, snap_mode);
+            timeline.timeline_start + timeline.clamp_to_end(tick)
+        };
+
+        // The track's area is allocated beneath its contents so that widgets within the track keep
+        // their own clicks and drags. It spans the area the track occupied when last shown.
+        let response_id = self.response_id();
+        let interaction = self.known_height().map(|h| {
+            let mut rect = track_timeline_rect;
+            rect.max.y = rect.min.y + h;
+            self.ui.interact(rect, response_id, egui::Sense::click_and_drag())
+        });
+        let menu = self.context_menu.take();
+        let has_menu = menu.is_some();
+        if let Some(menu) = menu
+            && let Some(track_id) = self.track_id
+            && let Some(response) = &interaction
+        {
+            let menu = |ui: &mut egui::Ui, tick| menu(ui, tick, track_id);
+            crate::interaction::context_menu(self.ui, response, response_id.with("menu_tick"), x_to_tick, menu);
+        }
+
+        // Reserve a place for the lane's tint beneath the contents, as the height isn't known yet.
+        let tint_idx = self
+            .color
+            .filter(|_| self.tracks.timeline.style.tracks.tint_lanes)
+            .map(|_| self.ui.painter().add(egui::Shape::Noop));
+
+        let (track_h, inner) = if collapsed {
+            // Replace the content with a coloured strip while collapsed
+            let strip_rect = track_timeline_rect.shrink2(egui::Vec2::new(0.0, 2.0));
+            let fill = self.tracks.timeline.style.collapsed_track_fill;
+            self.ui.painter().rect_filled(strip_rect, 0.0, fill);
+            (Self::COLLAPSED_HEIGHT, None)
+        } else {
+            // The contents follow the timeline while it is overscrolled.
+            let overscroll = egui::Vec2::new(self.tracks.timeline.overscroll, 0.0);
             let ui = &mut self.ui.new_child(
                 egui::UiBuilder::new()
-                    .max_rect(track_timeline_rect)
+                    .max_rect(track_timeline_rect.translate(overscroll))
                     .layout(*self.ui.layout()),
             );
-            track(&self.tracks.timeline, ui);
-            ui.min_rect().height()
-        };
-        
-        // Calculate the actual track area (only the height of this track, not the full timeline)
-        let actual_track_rect = {
-            let mut rect = track_timeline_rect;
-            rect.max.y = track_timeline_rect.min.y + track_h;
-            rect
+            // Keep the contents within the timeline area, and within the track if its height is
+            // known up front.
+            ui.set_clip_rect(track_timeline_rect.intersect(ui.clip_rect()));
+            let inner = track(&self.tracks.timeline, ui);
+            (resized_h.unwrap_or_else(|| ui.min_rect().height()), Some(inner))
         };
         
-        // Handle interaction for this track
-        if let Some(track_id) = &self.track_id {
-            // Get selection data before calling handle_track_interaction (which takes ownership)
-            // Check if this track has the selection (only one selection exists across all tracks)
-            let selection_data = selection_api.as_ref().and_then(|api| {
-                if api.get_selected_track_id().as_ref() == Some(track_id) {
-                    api.get_selection(track_id)
-                } else {
-                    None
-                }
-            });
-            let ticks_per_point_for_selection = selection_api.as_ref().map(|api| api.ticks_per_point());
-            
-            crate::interaction::handle_track_interaction(
-                self.ui,
-                actual_track_rect,
-                track_timeline_rect, // Pass full timeline rect for tick calculation
-                track_id,
-                playhead_api,
-                selection_api,
-            );
-            
-            // Draw selection if it exists on this track
-            if let (Some((absolute_start_tick, absolute_end_tick)), Some(ticks_per_point)) = (selection_data, ticks_per_point_for_selection) {
-                let timeline_w = track_timeline_rect.width();
-                let visible_ticks = ticks_per_point * timeline_w;
-                let timeline_start = selection_api.as_ref().map(|api| api.timeline_start()).unwrap_or(0.0);
-                
-                // Convert absolute ticks to relative ticks for drawing
-                let relative_start_tick = absolute_start_tick - timeline_start;
-                let relative_end_tick = absolute_end_tick - timeline_start;
-                
-                // Only draw if selection is visible in current viewport
-                if relative_end_tick >= 0.0 && relative_start_tick <= visible_ticks {
-                    let start_x = track_timeline_rect.min.x + (relative_start_tick.max(0.0) / visible_ticks) * timeline_w;
-                    let end_x = track_timeline_rect.min.x + (relative_end_tick.min(visible_ticks) / visible_ticks) * timeline_w;
-                    
-                    // Selection height should match track height only (not extend to bottom of screen)
-                    // Use track_h to determine the actual bottom of this track
-                    let track_top = track_timeline_rect.min.y;
-                    let track_bottom = track_timeline_rect.min.y + track_h;
-                    let selection_rect = egui::Rect::from_min_max(
-                        egui::Pos2::new(start_x.min(end_x), track_top),
-                        egui::Pos2::new(start_x.max(end_x), track_bottom),
-                    );
-                    
-                    let selection_fill = egui::Color32::from_rgba_unmultiplied(100, 150, 255, 100);
-                    self.ui.painter().rect_filled(selection_rect, 0.0, selection_fill);
-                }
-            }
-        }
-        
         // Calculate the full track rect (header + timeline, 100% width)
-        let full_track_height = self.header_height.max(track_h);
+        // A resized track stretches the header to the chosen height.
+        let full_track_height = match resized_h {
+            Some(h) => h,
+            None => self.header_height.max(track_h),
+        };
         let full_track_rect = egui::Rect::from_min_max(
             egui::Pos2::new(
                 self.tracks.full_rect.min.x, // Left edge (includes header)
@@ -195,65 +583,327 @@ impl<'a> TrackCtx<'a> {
             ),
         );
         
-        if is_selected {
-            let selection_overlay = egui::Color32::from_rgba_unmultiplied(128, 128, 128, 5);
-            self.ui.painter().rect_filled(full_track_rect, 0.0, selection_overlay);
-        }
-        
-        // Handle track selection click (on full track area, 100% width and height)
-        if let Some(track_id) = &self.track_id {
-            if let Some(on_click) = on_track_click {
-                // Check if pointer clicked on the full track area
-                let pointer_pos = self.ui.input(|i| i.pointer.interact_pos());
-                let pointer_pressed = self.ui.input(|i| i.pointer.primary_pressed());
-                
-                if pointer_pressed {
-                    if let Some(pos) = pointer_pos {
-                        if full_track_rect.contains(pos) {
-                            // Select track on any click within the full track area (header + content)
-                            // This includes the input string area and the timeline content area
-                            on_click(track_id.clone());
-                        }
-                    }
+        // Handle resizing via a drag handle along the bottom border. This is handled before the
+        // track interaction so that resizing claims the pointer from selection and the playhead.
+        let mut new_height = None;
+        let mut resizing = false;
+        if let Some((min, max)) = self.resizable.filter(|_| !collapsed) {
+            const HANDLE_HEIGHT: f32 = 4.0;
+            let handle_rect = egui::Rect::from_x_y_ranges(
+                full_track_rect.x_range(),
+                full_track_rect.bottom() - HANDLE_HEIGHT * 0.5..=full_track_rect.bottom() + HANDLE_HEIGHT * 0.5,
+            );
+            let handle = self
+                .ui
+                .interact(handle_rect, height_id.with("resize_handle"), egui::Sense::drag())
+                .on_hover_cursor(egui::CursorIcon::ResizeVertical);
+            resizing = handle.hovered() || handle.dragged();
+            if resizing {
+                crate::interaction::claim_pointer(self.ui, self.tracks.timeline.id);
+            }
+            if handle.dragged()
+                && let Some(pos) = handle.interact_pointer_pos()
+            {
+                let new_h = (pos.y - full_track_rect.top()).clamp(min, max);
+                if new_h != full_track_height {
+                    self.ui.data_mut(|d| d.insert_temp(height_id, new_h));
+                    new_height = Some(new_h);
                 }
             }
         }
         
-        // Draw a pink border around the entire track (header + timeline) to visualize its boundaries
-        // For the ruler (track_id is None), draw full border. For regular tracks, skip top border to avoid double border.
-        let pink_border = egui::Stroke {
-            width: 1.0,
-            color: egui::Color32::from_rgb(255, 192, 203), // Pink
+        // Calculate the actual track area (only the height of this track, not the full timeline)
+        let actual_track_rect = {
+            let mut rect = track_timeline_rect;
+            rect.max.y = track_timeline_rect.min.y + track_h;
+            rect
         };
+
+        if let Some(color) = self.color {
+            if let Some(idx) = tint_idx {
+                let lane_rect = egui::Rect::from_x_y_ranges(track_timeline_rect.x_range(), full_track_rect.y_range());
+                let tint = egui::Shape::rect_filled(lane_rect, 0.0, color.linear_multiply(TracksStyle::LANE_TINT));
+                self.ui.painter().set(idx, tint);
+            }
+            if let Some(header_rect) = self.tracks.header_full_rect {
+                let left = header_rect.left() + self.tracks.header_indent.get();
+                let x = left..=left + Self::COLOR_STRIPE_WIDTH;
+                let stripe_rect = egui::Rect::from_x_y_ranges(x, full_track_rect.y_range());
+                self.ui.painter().rect_filled(stripe_rect, 0.0, color);
+            }
+        }
         
-        if self.track_id.is_none() {
-            // Ruler: draw full border
-            self.ui.painter().rect_stroke(full_track_rect, 0.0, pink_border);
-        } else {
-            // Regular tracks: draw left, right, and bottom borders only (skip top to avoid double border with ruler)
-            let left_top = egui::Pos2::new(full_track_rect.min.x, full_track_rect.min.y);
-            let right_top = egui::Pos2::new(full_track_rect.max.x, full_track_rect.min.y);
-            let left_bottom = egui::Pos2::new(full_track_rect.min.x, full_track_rect.max.y);
-            let right_bottom = egui::Pos2::new(full_track_rect.max.x, full_track_rect.max.y);
+        // Handle interaction for this track
+        if let Some(track_id) = self.track_id {
+            // Rectangular selections are created across all tracks once they have been shown.
+            self.tracks.track_rects.borrow_mut().push((track_id, actual_track_rect));
+            if let Some(response) = &interaction
+                && (playhead_api.is_some() || selection_api.is_some())
+            {
+                crate::interaction::track_interaction(
+                    self.ui,
+                    response,
+                    &self.tracks.timeline, // Pass the timeline for tick calculation
+                    track_id,
+                    playhead_api,
+                    selection_api,
+                    !self.tracks.rect_select,
+                    !has_menu,
+                );
+            }
             
-            // Left border
-            self.ui.painter().line_segment([left_top, left_bottom], pink_border);
-            // Right border
-            self.ui.painter().line_segment([right_top, right_bottom], pink_border);
-            // Bottom border
-            self.ui.painter().line_segment([left_bottom, right_bottom], pink_border);
+            // Draw each selected range on this track
+            if let Some(api) = selection_api {
+                let timeline = &self.tracks.timeline;
+                let visible_ticks = timeline.visible_ticks;
+                let timeline_start = api.timeline_start();
+                let painter = self.ui.painter();
+                api.for_each_selection(track_id, &mut |absolute_start_tick, absolute_end_tick| {
+                    // Convert absolute ticks to relative ticks for drawing
+                    let relative_start_tick = absolute_start_tick - timeline_start;
+                    let relative_end_tick = absolute_end_tick - timeline_start;
+
+                    // Only draw if selection is visible in current viewport
+                    if relative_end_tick >= 0.0 && relative_start_tick <= visible_ticks {
+                        let start_x = timeline.tick_to_x(relative_start_tick.max(0.0));
+                        let end_x = timeline.tick_to_x(relative_end_tick.min(visible_ticks));
+
+                        // Selection height should match track height only (not extend to bottom of screen)
+                        // Use track_h to determine the actual bottom of this track
+                        let track_top = track_timeline_rect.min.y;
+                        let track_bottom = track_timeline_rect.min.y + track_h;
+                        // Align the edges to physical pixels so that they are not blurred.
+                        let selection_rect = painter.round_rect_to_pixels(egui::Rect::from_min_max(
+                            egui::Pos2::new(start_x.min(end_x), track_top),
+                            egui::Pos2::new(start_x.max(end_x), track_bottom),
+                        ));
+                        painter.rect_filled(selection_rect, 0.0, timeline.style.selection_fill);
+                    }
+                });
+            }
         }
         
-        // Manually add space occuppied by the child UIs, otherwise `ScrollArea` won't consider the
-        // space occuppied. TODO: Is there a better way to handle this?
-        let w = self.tracks.full_rect.width();
-        let h = full_track_height;
-        self.ui.scope(|ui| {
-            ui.spacing_mut().item_spacing.y = 0.0;
-            ui.spacing_mut().interact_size.y = 0.0;
-            ui.horizontal(|ui| ui.add_space(w));
-            ui.add_space(h);
-        });
+        // Paint the region recorded so far, clipped to the visible part of the timeline.
+        if let Some(start_tick) = self.recording
+            && let Some(api) = playhead_api
+        {
+            let timeline = &self.tracks.timeline;
+            let start_x = timeline.tick_to_x(start_tick - timeline.timeline_start);
+            let end_x = timeline.tick_to_x(api.playhead_ticks());
+            let rect = egui::Rect::from_x_y_ranges(
+                start_x.min(end_x)..=start_x.max(end_x),
+                track_timeline_rect.min.y..=track_timeline_rect.min.y + track_h,
+            );
+            let rect = rect.intersect(timeline.full_rect);
+            if rect.is_positive() {
+                let rect = self.ui.painter().round_rect_to_pixels(rect);
+                self.ui.painter().rect_filled(rect, 0.0, timeline.style.record_fill);
+            }
+        }
+
+        if is_selected {
+            let selection_overlay = self.tracks.timeline.style.selected_track_fill;
+            self.ui.painter().rect_filled(full_track_rect, 0.0, selection_overlay);
+        }
+
+        if let Some(overlay) = self.overlay.take() {
+            let ui = &mut self.ui.new_child(
+                egui::UiBuilder::new()
+                    .max_rect(actual_track_rect)
+                    .layout(*self.ui.layout()),
+            );
+            ui.set_clip_rect(actual_track_rect.intersect(ui.clip_rect()));
+            overlay(&self.tracks.timeline, ui, actual_track_rect);
+        }
+        
+        // Select the track when its header or timeline area is clicked. Widgets within either
+        // area keep their own clicks.
+        if let Some(track_id) = self.track_id
+            && let Some(on_click) = on_track_click
+            && !resizing
+            && [&self.header_response, &interaction].into_iter().flatten().any(|response| response.clicked())
+        {
+            on_click(track_id);
+        }
+
+        // Draw the track border (header + timeline) if the style enables one.
+        // For the ruler (track_id is None), draw full border. For regular tracks, skip top border to avoid double border.
+        if let Some(border) = self.tracks.timeline.style.track_border {
+            if self.track_id.is_none() {
+                // Ruler: draw full border
+                self.ui.painter().rect_stroke(full_track_rect, 0.0, border);
+            } else {
+                // Regular tracks: draw left, right, and bottom borders only (skip top to avoid double border with ruler)
+                let left_top = egui::Pos2::new(full_track_rect.min.x, full_track_rect.min.y);
+                let right_top = egui::Pos2::new(full_track_rect.max.x, full_track_rect.min.y);
+                let left_bottom = egui::Pos2::new(full_track_rect.min.x, full_track_rect.max.y);
+                let right_bottom = egui::Pos2::new(full_track_rect.max.x, full_track_rect.max.y);
+
+                // Left border
+                self.ui.painter().line_segment([left_top, left_bottom], border);
+                // Right border
+                self.ui.painter().line_segment([right_top, right_bottom], border);
+                // Bottom border
+                self.ui.painter().line_segment([left_bottom, right_bottom], border);
+            }
+        }
+
+        // Separate the track from the next along its bottom edge, within the scroll viewport's clip.
+        if let Some(separator) = self.tracks.timeline.style.tracks.separator {
+            self.ui.painter().hline(full_track_rect.x_range(), full_track_rect.bottom(), separator);
+        }
+
+        // Remember the height so that the track may be culled via `is_visible` next frame.
+        if self.track_id.is_some() {
+            let shown_height_id = self.shown_height_id();
+            self.ui.data_mut(|d| d.insert_temp(shown_height_id, full_track_height));
+        }
+
+        let spacing = self.tracks.timeline.style.tracks.spacing;
+        add_track_space(self.ui, self.tracks.full_rect.width(), full_track_height, spacing);
+
+        // Without an interaction registered beneath the contents, only sense hover so that the
+        // contents keep their clicks and drags.
+        let response = interaction
+            .unwrap_or_else(|| self.ui.interact(actual_track_rect, response_id, egui::Sense::hover()));
+        let tick_at = |clicked: bool| response.interact_pointer_pos().filter(|_| clicked).map(|pos| x_to_tick(pos.x));
+        TrackResponse {
+            full_rect: full_track_rect,
+            timeline_rect: actual_track_rect,
+            header_rect: self.header_rect(full_track_rect),
+            clicked_tick: tick_at(response.clicked()),
+            double_clicked_tick: tick_at(response.double_clicked()),
+            response,
+            new_height,
+            collapse_toggled: self.collapse_toggled,
+            inner,
+        }
+    }
+
+    /// Whether the track is collapsible and currently collapsed.
+    fn is_collapsed(&self) -> bool {
+        self.collapsible == Some(true)
+    }
+
+    /// The id under which the height of the track when last shown is stored in egui memory.
+    fn shown_height_id(&self) -> egui::Id {
+        self.tracks.timeline.id.with("track_shown_height").with(self.track_id)
+    }
+
+    /// The id of the interaction over the track's timeline area.
+    fn response_id(&self) -> egui::Id {
+        match self.track_id {
+            Some(track_id) => self.tracks.timeline.id.with("track_interaction").with(track_id),
+            None => self.ui.next_auto_id(),
+        }
+    }
+
+    /// The area of the track within the header column, given the area of the whole track.
+    fn header_rect(&self, full_track_rect: Rect) -> Option<Rect> {
+        self.tracks
+            .header_full_rect
+            .map(|header| Rect::from_x_y_ranges(header.x_range(), full_track_rect.y_range()))
+    }
+
+    /// The height of the track if collapsed, resized or fixed, rather than measured from its
+    /// contents.
+    fn chosen_height(&self) -> Option<f32> {
+        if self.is_collapsed() {
+            return Some(Self::COLLAPSED_HEIGHT);
+        }
+        let height_id = self.tracks.timeline.id.with("track_height").with(self.track_id);
+        self.resizable
+            .and_then(|(min, max)| self.ui.data(|d| d.get_temp::<f32>(height_id)).map(|h| h.clamp(min, max)))
+            .or(self.fixed_height)
+    }
+
+    /// The height of the track if it can be determined without showing it.
+    fn known_height(&self) -> Option<f32> {
+        self.chosen_height().or_else(|| {
+            self.track_id
+                .and_then(|_| self.ui.data(|d| d.get_temp::<f32>(self.shown_height_id())))
+        })
+    }
+}
+
+/// Add the space occupied by a track of the given size to the `ui`, followed by `spacing`.
+fn add_track_space(ui: &mut egui::Ui, w: f32, h: f32, spacing: f32) {
+    // Manually add space occuppied by the child UIs, otherwise `ScrollArea` won't consider the
+    // space occuppied. TODO: Is there a better way to handle this?
+    ui.scope(|ui| {
+        ui.spacing_mut().item_spacing.y = 0.0;
+        ui.spacing_mut().interact_size.y = 0.0;
+        ui.horizontal(|ui| ui.add_space(w));
+        ui.add_space(h);
+    });
+    // Replace the item spacing that the `ui` adds after the scope with the tracks' own spacing.
+    ui.add_space(spacing - ui.spacing().item_spacing.y);
+}
+
+/// Paint a chevron pointing right when collapsed, or down when expanded.
+fn paint_chevron(ui: &egui::Ui, response: &egui::Response, rect: Rect, collapsed: bool) {
+    let color = ui.style().interact(response).fg_stroke.color;
+    let r = rect.shrink(3.0);
+    let points = if collapsed {
+        vec![r.left_top(), r.right_center(), r.left_bottom()]
+    } else {
+        vec![r.left_top(), r.right_top(), r.center_bottom()]
+    };
+    ui.painter().add(egui::Shape::convex_polygon(points, color, egui::Stroke::NONE));
+}
+
+impl<'a> GroupCtx<'a> {
+    /// The distance that the headers of the group's children are indented by.
+    pub const INDENT: f32 = 12.0;
+
+    /// Whether the group's children are hidden.
+    ///
+    /// As with `TrackCtx::collapsible`, the group owns no collapsed state of its own. Instead,
+    /// `TrackResponse::collapse_toggled` reports when the chevron was clicked so that the host may
+    /// flip its `collapsed` state. While collapsed, the children take up no space within the
+    /// tracks and the closure given to `show` is not called.
+    ///
+    /// Must be called before `header` for the chevron to show the state.
+    ///
+    /// Default: `false`
+    pub fn collapsed(mut self, collapsed: bool) -> Self {
+        self.collapsed = collapsed;
+        self
+    }
+
+    /// Identify the group's own track by the given colour. See `TrackCtx::color`.
+    pub fn color(mut self, color: egui::Color32) -> Self {
+        self.track = self.track.color(color);
+        self
+    }
+
+    /// Set the height of the group's own track up front. See `TrackCtx::fixed_height`.
+    pub fn fixed_height(mut self, height: f32) -> Self {
+        self.track = self.track.fixed_height(height);
+        self
+    }
+
+    /// UI for the group's header, shown to the right of the disclosure chevron.
+    pub fn header(mut self, header: impl FnOnce(&mut egui::Ui)) -> Self {
+        self.track.group_collapsed = Some(self.collapsed);
+        self.track = self.track.header(header);
+        self
+    }
+
+    /// Show the group's own track with an empty timeline area, followed by its children unless
+    /// the group is collapsed.
+    ///
+    /// The returned response is that of the group's own track.
+    pub fn show(mut self, children: impl FnOnce(&TracksCtx, &mut egui::Ui)) -> TrackResponse {
+        let response = self.track.show_inner(|_, _| {}, None, None, None::<fn(TrackId)>, false);
+        if !self.collapsed {
+            let tracks = self.track.tracks;
+            let indent = tracks.header_indent.get();
+            tracks.header_indent.set(indent + Self::INDENT);
+            children(tracks, self.track.ui);
+            tracks.header_indent.set(indent);
+        }
+        response
     }
 }
 
@@ -267,6 +917,104 @@ impl TimelineCtx {
     pub fn left_edge_x(&self) -> f32 {
         self.full_rect.min.x
     }
+
+    /// The x position of the given tick, relative to the start of the visible area.
+    pub fn tick_to_x(&self, tick: f32) -> f32 {
+        self.full_rect.left() + self.overscroll + (tick / self.visible_ticks) * self.full_rect.width()
+    }
+
+    /// The tick at the given x position, relative to the start of the visible area.
+    pub fn x_to_tick(&self, x: f32) -> f32 {
+        ((x - self.full_rect.left() - self.overscroll) / self.full_rect.width()) * self.visible_ticks
+    }
+
+    /// The range of ticks visible on the timeline, relative to the start of the visible area.
+    pub fn visible_tick_range(&self) -> Range<f32> {
+        0.0..self.visible_ticks
+    }
+
+    /// The range of absolute ticks visible on the timeline.
+    ///
+    /// Track content lying entirely outside of this range is off-screen, so hosts may skip
+    /// drawing it, e.g. to avoid laying out thousands of notes or clips in large projects.
+    pub fn absolute_visible_range(&self) -> Range<f32> {
+        self.timeline_start..self.timeline_start + self.visible_ticks
+    }
+
+    /// Clamp the given tick, relative to the start of the visible area, to the end of the
+    /// timeline if there is one.
+    pub(crate) fn clamp_to_end(&self, tick: f32) -> f32 {
+        match self.relative_end() {
+            Some(end) => tick.min(end),
+            None => tick,
+        }
+    }
+
+    /// The snap mode in effect given the modifiers currently held, as applied to seeks and drags.
+    ///
+    /// See `SnapMode::with_modifiers`.
+    pub fn effective_snap(&self, ui: &egui::Ui) -> SnapMode {
+        self.snap.with_modifiers(ui.input(|i| i.modifiers))
+    }
+
+    /// Quantize the given tick, relative to the start of the visible area, to the grid of the
+    /// snap mode in effect.
+    ///
+    /// The grid follows the bars of the timeline's `MusicalInfo`, as given by the `TimelineApi`.
+    pub fn snap_tick(&self, ui: &egui::Ui, tick: f32) -> f32 {
+        crate::snap::snap_tick(tick, &self.snap_grid, self.effective_snap(ui))
+    }
+
+    /// The end of the timeline relative to the start of the visible area, if there is one.
+    pub(crate) fn relative_end(&self) -> Option<f32> {
+        self.timeline_end.map(|end| end - self.timeline_start)
+    }
+}
+
+impl BackgroundCtx<'_> {
+    /// The number of visible ticks across the width of the timeline.
+    pub fn visible_ticks(&self) -> f32 {
+        self.timeline.visible_ticks
+    }
+
+    /// The absolute tick at the left edge of the timeline area.
+    pub fn timeline_start(&self) -> f32 {
+        self.timeline.timeline_start
+    }
+
+    /// The x position of the given tick, relative to the start of the visible area.
+    pub fn tick_to_x(&self, tick: f32) -> f32 {
+        self.timeline.tick_to_x(tick)
+    }
+
+    /// The range of ticks visible on the timeline, relative to the start of the visible area.
+    pub fn visible_tick_range(&self) -> Range<f32> {
+        self.timeline.visible_tick_range()
+    }
+
+    /// Fill the full height of the timeline area between the given ticks, relative to the start
+    /// of the visible area.
+    ///
+    /// The fill is clipped to the timeline area, and nothing is painted if the range lies
+    /// entirely outside the visible area.
+    pub fn paint_tick_range(&self, ui: &egui::Ui, range: Range<f32>, fill: egui::Color32) {
+        let visible = self.visible_tick_range();
+        if range.end <= visible.start || range.start >= visible.end {
+            return;
+        }
+        let timeline = self.timeline;
+        let x_range = timeline.tick_to_x(range.start)..=timeline.tick_to_x(range.end);
+        let rect = Rect::from_x_y_ranges(x_range, timeline.full_rect.y_range()).intersect(timeline.full_rect);
+        if rect.is_positive() {
+            ui.painter().rect_filled(rect, 0.0, fill);
+        }
+    }
+
+    /// The same as `paint_tick_range`, but with the range given in absolute ticks.
+    pub fn paint_absolute_tick_range(&self, ui: &egui::Ui, range: Range<f32>, fill: egui::Color32) {
+        let start = self.timeline.timeline_start;
+        self.paint_tick_range(ui, range.start - start..range.end - start, fill);
+    }
 }
 
 // Internal access for timeline module
@@ -276,34 +1024,239 @@ impl TracksCtx {
             full_rect,
             header_full_rect,
             timeline,
+            rect_select: false,
+            track_rects: RefCell::new(Vec::new()),
+            header_indent: Cell::new(0.0),
+            pan_y: 0.0,
         }
     }
 }
 
 impl TimelineCtx {
-    pub(crate) fn new(full_rect: Rect, visible_ticks: f32) -> Self {
+    pub(crate) fn new(
+        id: egui::Id,
+        full_rect: Rect,
+        visible_ticks: f32,
+        timeline_start: f32,
+        style: TimelineStyle,
+        snap: SnapMode,
+        tool_mode: ToolMode,
+    ) -> Self {
         Self {
+            id,
             full_rect,
             visible_ticks,
+            timeline_start,
+            timeline_end: None,
+            pre_roll: 0.0,
+            overscroll: 0.0,
+            style,
+            snap,
+            tool_mode,
+            snap_grid: SnapGrid::default(),
         }
     }
 }
 
 impl SetPlayhead {
-    pub(crate) fn new(timeline_rect: Rect, tracks_bottom: f32) -> Self {
+    pub(crate) fn new(
+        id: egui::Id,
+        timeline_rect: Rect,
+        tracks_bottom: f32,
+        style: TimelineStyle,
+        snap: SnapMode,
+    ) -> Self {
         Self {
+            id,
             timeline_rect,
             tracks_bottom,
             bottom_bar_rect: None,
             top_panel_rect: None,
+            style,
+            snap,
+            scroll_offset: 0.0,
+            tracks_viewport: Rect::NOTHING,
+            pinned_bottom: None,
+            track_rects: Vec::new(),
+            timeline_end: None,
+            pre_roll: 0.0,
         }
     }
 
-    pub(crate) fn timeline_rect(&self) -> Rect {
+    /// The vertical scroll offset of the tracks, e.g. to restore via `ScrollTo::Offset`.
+    pub fn scroll_offset(&self) -> f32 {
+        self.scroll_offset
+    }
+
+    /// The visible area of the tracks relative to the top of the tracks, as given to `tracks_fn`.
+    pub fn tracks_viewport(&self) -> Rect {
+        self.tracks_viewport
+    }
+
+    /// The timeline area right of the header, spanning the pinned and scrolled tracks.
+    pub fn timeline_rect(&self) -> Rect {
         self.timeline_rect
     }
 
-    pub(crate) fn tracks_bottom(&self) -> f32 {
+    /// The y position at the bottom of the last track, or the bottom of the tracks' scrollable
+    /// area if the tracks exceed the visible height.
+    pub fn tracks_bottom(&self) -> f32 {
         self.tracks_bottom
     }
+
+    /// The bottom bar's area, if reserved with `Timeline::bottom_bar`.
+    pub fn bottom_bar_rect(&self) -> Option<Rect> {
+        self.bottom_bar_rect
+    }
+
+    /// The top panel's area, if reserved with `Timeline::top_panel`.
+    pub fn top_panel_rect(&self) -> Option<Rect> {
+        self.top_panel_rect
+    }
+
+    /// All of the areas of the timeline, as given to `SetPlayhead::overlay`.
+    pub fn rects(&self) -> TimelineRects {
+        TimelineRects {
+            timeline: self.timeline_rect,
+            tracks_bottom: self.tracks_bottom,
+            top_panel: self.top_panel_rect,
+            bottom_bar: self.bottom_bar_rect,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{state::SimpleTimelineState, Timeline};
+
+    /// A timeline with a header column shown across an 800 by 400 point screen.
+    struct Harness {
+        ctx: egui::Context,
+        time: f64,
+        state: SimpleTimelineState,
+    }
+
+    impl Harness {
+        fn new() -> Self {
+            Self { ctx: egui::Context::default(), time: 0.0, state: SimpleTimelineState::new() }
+        }
+
+        /// Run a frame with the given pointer events, showing the tracks via `tracks_fn`.
+        fn frame(&mut self, events: Vec<egui::Event>, tracks_fn: impl Fn(&TracksCtx, &mut egui::Ui)) {
+            let screen_rect = Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(800.0, 400.0));
+            self.time += 0.1;
+            let input =
+                egui::RawInput { screen_rect: Some(screen_rect), time: Some(self.time), events, ..Default::default() };
+            let state = &mut self.state;
+            let _ = self.ctx.run(input, |ctx| {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    Timeline::new()
+                        .header(100.0)
+                        .show(ui, state)
+                        .tracks(|tracks, _viewport, ui, _, _| tracks_fn(tracks, ui), None, None);
+                });
+            });
+        }
+
+        fn click(&mut self, pos: egui::Pos2, tracks_fn: impl Fn(&TracksCtx, &mut egui::Ui)) {
+            let button = |pressed| egui::Event::PointerButton {
+                pos,
+                button: egui::PointerButton::Primary,
+                pressed,
+                modifiers: egui::Modifiers::NONE,
+            };
+            self.frame(vec![egui::Event::PointerMoved(pos)], &tracks_fn);
+            self.frame(vec![button(true)], &tracks_fn);
+            self.frame(vec![button(false)], &tracks_fn);
+        }
+    }
+
+    #[test]
+    fn track_click_leaves_clicks_on_contents_to_their_widgets() {
+        let mut harness = Harness::new();
+        let clicked_track = Cell::new(None);
+        let button_clicks = Cell::new(0);
+        let rects = Cell::new(None);
+        let tracks_fn = |tracks: &TracksCtx, ui: &mut egui::Ui| {
+            let response = tracks.next(ui).with_id("track").header(|ui| {
+                ui.label("Track");
+            }).show(
+                |_timeline, ui| {
+                    if ui.button("Button").clicked() {
+                        button_clicks.set(button_clicks.get() + 1);
+                    }
+                    ui.allocate_exact_size(egui::vec2(ui.available_width(), 40.0), egui::Sense::hover()).0
+                },
+                None,
+                None,
+                Some(|track_id| clicked_track.set(Some(track_id))),
+                false,
+            );
+            rects.set(Some((response.inner.unwrap(), response.header_rect.unwrap())));
+        };
+        // The track's height is only known once it has been shown
+        harness.frame(vec![], tracks_fn);
+        harness.frame(vec![], tracks_fn);
+        let (below_button, header) = rects.get().unwrap();
+
+        let button = egui::pos2(below_button.left() + 10.0, below_button.top() - 8.0);
+        harness.click(button, tracks_fn);
+        assert_eq!(button_clicks.get(), 1);
+        assert_eq!(clicked_track.take(), None);
+
+        harness.click(below_button.center(), tracks_fn);
+        assert_eq!(clicked_track.take(), Some(TrackId::new("track")));
+
+        harness.click(egui::pos2(header.center().x, header.bottom() - 4.0), tracks_fn);
+        assert_eq!(clicked_track.take(), Some(TrackId::new("track")));
+        assert_eq!(button_clicks.get(), 1);
+    }
+
+    #[test]
+    fn only_visible_tracks_show_their_contents() {
+        const TRACKS: usize = 200;
+        let mut harness = Harness::new();
+        let shown = RefCell::new(vec![]);
+        let rects = RefCell::new(vec![]);
+        let tracks_fn = |tracks: &TracksCtx, ui: &mut egui::Ui| {
+            shown.borrow_mut().clear();
+            rects.borrow_mut().clear();
+            for i in 0..TRACKS {
+                let track = tracks.next(ui).with_id(TrackId::new(i));
+                let response = if track.is_visible() {
+                    track.show(
+                        |_timeline, ui| {
+                            shown.borrow_mut().push(i);
+                            ui.allocate_exact_size(egui::vec2(ui.available_width(), 40.0), egui::Sense::hover());
+                        },
+                        None,
+                        None,
+                        None::<fn(TrackId)>,
+                        false,
+                    )
+                } else {
+                    track.skip()
+                };
+                rects.borrow_mut().push(response.timeline_rect);
+            }
+        };
+
+        // Tracks are measured the first time that they are shown
+        harness.frame(vec![], tracks_fn);
+        assert_eq!(shown.borrow().len(), TRACKS);
+        let measured = rects.borrow().clone();
+
+        // Afterwards, only those within the 400 point high screen are shown, which are the last
+        // tracks as the tracks stick to the bottom by default
+        harness.frame(vec![], tracks_fn);
+        let visible = shown.borrow().clone();
+        assert!(!visible.is_empty() && visible.len() <= 10, "showed {visible:?}");
+        assert_eq!(visible, (TRACKS - visible.len()..TRACKS).collect::<Vec<_>>());
+        // Skipped tracks keep their space, although the tracks have since been scrolled
+        let offset = rects.borrow()[0].min.y - measured[0].min.y;
+        for (rect, measured) in rects.borrow().iter().zip(&measured) {
+            assert_eq!(*rect, measured.translate(egui::vec2(0.0, offset)));
+        }
+    }
 }
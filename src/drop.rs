@@ -0,0 +1,72 @@
+//! Dropping files from the OS, or payloads dragged from other egui widgets, onto the tracks.
+
+use crate::{
+    ruler::MusicalInfo,
+    snap::{self, SnapMode},
+    types::TrackId,
+};
+use std::{any::Any, sync::Arc};
+
+/// What was dropped onto the tracks, passed to the callback given to `SetPlayhead::drop_target`.
+#[derive(Clone, Debug)]
+pub enum DropPayload<P> {
+    /// Files dragged from the OS, as given by `egui::RawInput::dropped_files`.
+    Files(Vec<egui::DroppedFile>),
+    /// A payload dragged from another egui widget via `egui::DragAndDrop`.
+    Item(Arc<P>),
+}
+
+/// Show where a drag hovering over the tracks would land, and report the drop on release.
+///
+/// The target is the track under the pointer, or `None` beneath the last track. The tick is
+/// absolute and snapped to the given `snap` grid unless `Alt` is held.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn drop_target<P>(
+    ui: &egui::Ui,
+    info: &dyn MusicalInfo,
+    timeline_rect: egui::Rect,
+    tracks_bottom: f32,
+    track_rects: &[(TrackId, egui::Rect)],
+    snap: SnapMode,
+    stroke: egui::Stroke,
+    on_drop: impl FnOnce(Option<TrackId>, f32, DropPayload<P>),
+) where
+    P: Any + Send + Sync,
+{
+    let (hovering_files, dropped_files) = ui.input(|i| (!i.raw.hovered_files.is_empty(), i.raw.dropped_files.clone()));
+    let has_payload = egui::DragAndDrop::has_payload_of_type::<P>(ui.ctx());
+    if !hovering_files && dropped_files.is_empty() && !has_payload {
+        return;
+    }
+    let Some(pos) = ui.input(|i| i.pointer.latest_pos()) else {
+        return;
+    };
+    if !timeline_rect.contains(pos) {
+        return;
+    }
+
+    // Pinned tracks are shown first, so take precedence over the tracks scrolled beneath them.
+    let target = track_rects.iter().find(|(_, rect)| rect.y_range().contains(pos.y));
+    let (track_id, y_range) = match target {
+        Some(&(track_id, rect)) => (Some(track_id), rect.y_range().intersection(timeline_rect.y_range())),
+        None if pos.y > tracks_bottom => (None, egui::Rangef::new(tracks_bottom, timeline_rect.bottom())),
+        None => return,
+    };
+
+    let ticks_per_point = info.ticks_per_point();
+    let tick = ((pos.x - timeline_rect.left()) * ticks_per_point).max(0.0);
+    let snap = snap.with_modifiers(ui.input(|i| i.modifiers));
+    let tick = snap::snap_tick(tick, info, snap);
+    let x = timeline_rect.left() + tick / ticks_per_point;
+    let painter = crate::playhead::foreground_painter(ui).with_clip_rect(timeline_rect);
+    painter.vline(painter.round_to_pixel_center(x), y_range, stroke);
+
+    let tick = info.timeline_start().unwrap_or(0.0) + tick;
+    if !dropped_files.is_empty() {
+        on_drop(track_id, tick, DropPayload::Files(dropped_files));
+    } else if ui.input(|i| i.pointer.any_released())
+        && let Some(payload) = egui::DragAndDrop::take_payload::<P>(ui.ctx())
+    {
+        on_drop(track_id, tick, DropPayload::Item(payload));
+    }
+}
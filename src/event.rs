@@ -0,0 +1,606 @@
+use crate::{
+    interaction::TrackSelectionApi,
+    loop_region::LoopApi,
+    playhead::{Info, Interaction},
+    ruler::{MusicalInfo, MusicalInteract, MusicalRuler},
+    time_selection::TimeSelectionApi,
+    types::{Bar, TempoMap, TrackId},
+    TimelineApi,
+};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+};
+
+/// Something that happened on the timeline during a frame.
+///
+/// Produced by an `EventCollector` for hosts that would rather apply changes to their state with
+/// plain `&mut` access after the frame than implement the `&self` setters of the trait APIs. All
+/// ticks are absolute.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TimelineEvent {
+    /// The playhead was moved to the given tick.
+    PlayheadSet(f32),
+    /// The selected ranges on the given track changed. An empty list clears the track.
+    SelectionChanged { track: TrackId, ranges: Vec<(f32, f32)> },
+    /// The selections on all tracks were cleared.
+    SelectionCleared,
+    /// The timeline start was shifted by the given number of ticks.
+    Scrolled(f32),
+    /// The timeline was zoomed by the given scroll delta, keeping the `anchor` tick in place.
+    ///
+    /// See `TimelineApi::zoom_at`.
+    Zoomed { anchor: f32, y_delta: f32 },
+    /// The zoom was set to the given number of ticks per point.
+    ///
+    /// See `TimelineApi::set_ticks_per_point`.
+    ZoomSet(f32),
+    /// The loop region was set to the given `(start, end)`, or removed if `None`.
+    LoopRangeSet(Option<(f32, f32)>),
+    /// Looping over the loop region was turned on or off.
+    LoopEnabledSet(bool),
+    /// The global time selection was set to the given `(start, end)`, or cleared if `None`.
+    TimeSelectionSet(Option<(f32, f32)>),
+    /// The user pressed on the playhead to begin scrubbing.
+    ScrubStarted,
+    /// The playhead was scrubbed to the given tick, following a `PlayheadSet` to the same tick.
+    Scrubbed(f32),
+    /// The user released the playhead at the given tick.
+    ScrubEnded(f32),
+}
+
+/// The selection being dragged out, stored in egui memory as the collector only lives for a frame.
+type SelectionDrag = (TrackId, f32, usize);
+
+/// Implements the timeline's trait APIs on behalf of the host by collecting `TimelineEvent`s.
+///
+/// Create a collector each frame from a snapshot of the host's state and pass it wherever the
+/// timeline expects a `TimelineApi`, `MusicalRuler`, `PlayheadApi`, `TrackSelectionApi`,
+/// `LoopApi` or `TimeSelectionApi`. Once the timeline has been shown, take the events with
+/// `into_events` and apply them to the host's state.
+///
+/// The collector keeps its own copy of the playhead, scroll position, zoom, selections, loop
+/// region and time selection up to date with the events it records, so widgets later in the
+/// frame see their effects. Ranges on tracks not passed to `selections` are assumed to be empty.
+pub struct EventCollector<'a> {
+    ctx: egui::Context,
+    id: egui::Id,
+    info: &'a dyn MusicalInfo,
+    start_timeline_start: f32,
+    timeline_start: Cell<f32>,
+    timeline_end: Option<f32>,
+    ticks_per_point: Cell<f32>,
+    playhead: Cell<f32>,
+    selections: RefCell<HashMap<TrackId, Vec<(f32, f32)>>>,
+    loop_range: Option<(f32, f32)>,
+    loop_enabled: bool,
+    time_selection: Option<(f32, f32)>,
+    events: RefCell<Vec<TimelineEvent>>,
+}
+
+impl<'a> EventCollector<'a> {
+    /// Create a collector for the current frame.
+    ///
+    /// `timeline_id` is the id of the timeline the collector is shown with, as given by
+    /// `Timeline::id`, and keeps the selection drag of each timeline apart between frames. `info`
+    /// describes the host's timeline, while `timeline_start` is its current absolute scroll
+    /// position.
+    pub fn new(ctx: &egui::Context, timeline_id: egui::Id, info: &'a dyn MusicalInfo, timeline_start: f32) -> Self {
+        Self {
+            ctx: ctx.clone(),
+            id: timeline_id,
+            info,
+            start_timeline_start: timeline_start,
+            timeline_start: Cell::new(timeline_start),
+            timeline_end: None,
+            ticks_per_point: Cell::new(info.ticks_per_point()),
+            playhead: Cell::new(0.0),
+            selections: RefCell::new(HashMap::new()),
+            loop_range: None,
+            loop_enabled: false,
+            time_selection: None,
+            events: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// The end of the timeline in absolute ticks. See `TimelineApi::timeline_end`.
+    pub fn timeline_end(mut self, end: Option<f32>) -> Self {
+        self.timeline_end = end;
+        self
+    }
+
+    /// The current absolute position of the playhead.
+    pub fn playhead(self, tick: f32) -> Self {
+        self.playhead.set(tick);
+        self
+    }
+
+    /// The currently selected ranges of each track.
+    pub fn selections(self, selections: impl IntoIterator<Item = (TrackId, Vec<(f32, f32)>)>) -> Self {
+        self.selections.borrow_mut().extend(selections);
+        self
+    }
+
+    /// The current loop region and whether looping over it is enabled. See `LoopApi`.
+    pub fn loop_region(mut self, range: Option<(f32, f32)>, enabled: bool) -> Self {
+        self.loop_range = range;
+        self.loop_enabled = enabled;
+        self
+    }
+
+    /// The current global time selection. See `TimeSelectionApi`.
+    pub fn time_selection(mut self, range: Option<(f32, f32)>) -> Self {
+        self.time_selection = range;
+        self
+    }
+
+    /// The events collected so far this frame, in the order they occurred.
+    pub fn into_events(self) -> Vec<TimelineEvent> {
+        self.events.into_inner()
+    }
+
+    /// The number of ticks scrolled since the start of the frame.
+    fn scrolled(&self) -> f32 {
+        self.timeline_start.get() - self.start_timeline_start
+    }
+
+    fn drag_id(&self) -> egui::Id {
+        self.id.with("event_selection_drag")
+    }
+
+    fn push(&self, event: TimelineEvent) {
+        let mut events = self.events.borrow_mut();
+        // Only keep the latest of consecutive changes to the same thing.
+        match (events.last_mut(), &event) {
+            (Some(TimelineEvent::PlayheadSet(last)), TimelineEvent::PlayheadSet(tick))
+            | (Some(TimelineEvent::Scrubbed(last)), TimelineEvent::Scrubbed(tick))
+            | (Some(TimelineEvent::ZoomSet(last)), TimelineEvent::ZoomSet(tick)) => *last = *tick,
+            (Some(TimelineEvent::LoopRangeSet(last)), TimelineEvent::LoopRangeSet(range))
+            | (Some(TimelineEvent::TimeSelectionSet(last)), TimelineEvent::TimeSelectionSet(range)) => {
+                *last = *range
+            }
+            (
+                Some(TimelineEvent::SelectionChanged { track: last, ranges: last_ranges }),
+                TimelineEvent::SelectionChanged { track, ranges },
+            ) if last == track => *last_ranges = ranges.clone(),
+            _ => events.push(event),
+        }
+    }
+
+    /// Record a change to the ranges of the given track.
+    fn change_selection(&self, track: TrackId, change: impl FnOnce(&mut Vec<(f32, f32)>)) {
+        let ranges = {
+            let mut selections = self.selections.borrow_mut();
+            let ranges = selections.entry(track).or_default();
+            change(ranges);
+            ranges.clone()
+        };
+        self.push(TimelineEvent::SelectionChanged { track, ranges });
+    }
+}
+
+impl TimelineApi for EventCollector<'_> {
+    fn musical_ruler_info(&self) -> &dyn MusicalInfo {
+        self
+    }
+
+    fn timeline_start(&self) -> f32 {
+        self.timeline_start.get()
+    }
+
+    fn timeline_end(&self) -> Option<f32> {
+        self.timeline_end
+    }
+
+    fn shift_timeline_start(&mut self, ticks: f32) {
+        self.timeline_start.set(self.timeline_start.get() + ticks);
+        self.push(TimelineEvent::Scrolled(ticks));
+    }
+
+    fn zoom(&mut self, y_delta: f32) {
+        let anchor = self.timeline_start.get();
+        self.push(TimelineEvent::Zoomed { anchor, y_delta });
+    }
+
+    fn zoom_at(&mut self, anchor_ticks: f32, y_delta: f32) {
+        // The new zoom level is unknown until the host applies the event.
+        self.push(TimelineEvent::Zoomed { anchor: anchor_ticks, y_delta });
+    }
+
+    fn set_ticks_per_point(&mut self, ticks_per_point: f32) {
+        self.ticks_per_point.set(ticks_per_point);
+        self.push(TimelineEvent::ZoomSet(ticks_per_point));
+    }
+}
+
+impl MusicalInfo for EventCollector<'_> {
+    fn ticks_per_beat(&self) -> u32 {
+        self.info.ticks_per_beat()
+    }
+
+    fn bar_at_ticks(&self, tick: f32) -> Bar {
+        // The host's info is relative to the start of the view before any scrolling this frame.
+        let scrolled = self.scrolled();
+        let mut bar = self.info.bar_at_ticks(tick + scrolled);
+        bar.tick_range = bar.tick_range.start - scrolled..bar.tick_range.end - scrolled;
+        bar
+    }
+
+    fn ticks_per_point(&self) -> f32 {
+        self.ticks_per_point.get()
+    }
+
+    fn tempo_bpm(&self) -> f32 {
+        self.info.tempo_bpm()
+    }
+
+    fn tempo_map(&self) -> Option<&TempoMap> {
+        self.info.tempo_map()
+    }
+
+    fn ticks_to_seconds(&self, tick: f32) -> f32 {
+        self.info.ticks_to_seconds(tick)
+    }
+
+    fn seconds_to_ticks(&self, seconds: f32) -> f32 {
+        self.info.seconds_to_ticks(seconds)
+    }
+
+    fn timeline_start(&self) -> Option<f32> {
+        Some(self.timeline_start.get())
+    }
+
+    fn bar_number_at_ticks(&self, tick: f32) -> u32 {
+        self.info.bar_number_at_ticks(tick + self.scrolled())
+    }
+}
+
+impl MusicalInteract for EventCollector<'_> {
+    fn click_at_tick(&mut self, tick: f32) {
+        self.set_playhead_ticks(tick);
+    }
+}
+
+impl MusicalRuler for EventCollector<'_> {
+    fn info(&self) -> &dyn MusicalInfo {
+        self
+    }
+
+    fn interact(&mut self) -> &mut dyn MusicalInteract {
+        self
+    }
+
+    fn loop_api(&mut self) -> Option<&mut dyn LoopApi> {
+        Some(self)
+    }
+
+    fn time_selection_api(&mut self) -> Option<&mut dyn TimeSelectionApi> {
+        Some(self)
+    }
+
+    fn timeline_end(&self) -> Option<f32> {
+        self.timeline_end
+    }
+}
+
+impl Info for EventCollector<'_> {
+    fn playhead_ticks(&self) -> f32 {
+        self.playhead.get() - self.timeline_start.get()
+    }
+}
+
+impl Interaction for EventCollector<'_> {
+    fn set_playhead_ticks(&self, ticks: f32) {
+        let tick = self.timeline_start.get() + ticks;
+        self.playhead.set(tick);
+        self.push(TimelineEvent::PlayheadSet(tick));
+    }
+
+    fn on_scrub_start(&self) {
+        self.push(TimelineEvent::ScrubStarted);
+    }
+
+    fn on_scrub(&self, tick: f32) {
+        self.push(TimelineEvent::Scrubbed(self.timeline_start.get() + tick));
+    }
+
+    fn on_scrub_end(&self, tick: f32) {
+        self.push(TimelineEvent::ScrubEnded(self.timeline_start.get() + tick));
+    }
+}
+
+impl TrackSelectionApi for EventCollector<'_> {
+    fn ticks_per_point(&self) -> f32 {
+        self.ticks_per_point.get()
+    }
+
+    fn timeline_start(&self) -> f32 {
+        self.timeline_start.get()
+    }
+
+    fn start_selection_drag(&self, track_id: TrackId, start_tick: f32) {
+        let mut index = 0;
+        self.change_selection(track_id, |ranges| {
+            ranges.push((start_tick, start_tick));
+            index = ranges.len() - 1;
+        });
+        let drag: SelectionDrag = (track_id, start_tick, index);
+        self.ctx.data_mut(|d| d.insert_temp(self.drag_id(), drag));
+    }
+
+    fn update_selection_drag(&self, track_id: TrackId, end_tick: f32) {
+        let drag = self.ctx.data(|d| d.get_temp::<SelectionDrag>(self.drag_id()));
+        if let Some((drag_track_id, start_tick, index)) = drag
+            && drag_track_id == track_id
+        {
+            self.change_selection(track_id, |ranges| {
+                if let Some(range) = ranges.get_mut(index) {
+                    *range = (start_tick.min(end_tick), start_tick.max(end_tick));
+                }
+            });
+        }
+    }
+
+    fn get_drag_start(&self) -> Option<(TrackId, f32)> {
+        let drag = self.ctx.data(|d| d.get_temp::<SelectionDrag>(self.drag_id()));
+        drag.map(|(track_id, start_tick, _)| (track_id, start_tick))
+    }
+
+    fn end_selection_drag(&self) {
+        self.ctx.data_mut(|d| d.remove::<SelectionDrag>(self.drag_id()));
+    }
+
+    fn set_selection(&self, track_id: TrackId, start_tick: f32, end_tick: f32) {
+        self.change_selection(track_id, |ranges| *ranges = vec![(start_tick, end_tick)]);
+    }
+
+    fn clear_selection(&self, track_id: TrackId) {
+        self.change_selection(track_id, |ranges| ranges.clear());
+    }
+
+    fn clear_all_selections(&self) {
+        self.selections.borrow_mut().clear();
+        self.push(TimelineEvent::SelectionCleared);
+    }
+
+    fn get_selection(&self, track_id: TrackId) -> Option<(f32, f32)> {
+        self.selections.borrow().get(&track_id).and_then(|ranges| ranges.first().copied())
+    }
+
+    fn get_selected_track_id(&self) -> Option<TrackId> {
+        self.selections
+            .borrow()
+            .iter()
+            .find(|(_, ranges)| !ranges.is_empty())
+            .map(|(&track_id, _)| track_id)
+    }
+
+    fn all_selections(&self) -> Vec<(TrackId, Vec<(f32, f32)>)> {
+        self.selections
+            .borrow()
+            .iter()
+            .filter(|(_, ranges)| !ranges.is_empty())
+            .map(|(&track_id, ranges)| (track_id, ranges.clone()))
+            .collect()
+    }
+
+    fn add_selection(&self, track_id: TrackId, start_tick: f32, end_tick: f32) {
+        self.change_selection(track_id, |ranges| ranges.push((start_tick, end_tick)));
+    }
+
+    fn selections(&self, track_id: TrackId) -> Vec<(f32, f32)> {
+        self.selections.borrow().get(&track_id).cloned().unwrap_or_default()
+    }
+
+    fn for_each_selection(&self, track_id: TrackId, visit: &mut dyn FnMut(f32, f32)) {
+        for &(start, end) in self.selections.borrow().get(&track_id).into_iter().flatten() {
+            visit(start, end);
+        }
+    }
+
+    fn remove_selection(&self, track_id: TrackId, index: usize) {
+        self.change_selection(track_id, |ranges| {
+            if index < ranges.len() {
+                ranges.remove(index);
+            }
+        });
+    }
+}
+
+impl LoopApi for EventCollector<'_> {
+    fn loop_range(&self) -> Option<(f32, f32)> {
+        self.loop_range
+    }
+
+    fn set_loop_range(&mut self, range: Option<(f32, f32)>) {
+        self.loop_range = range;
+        self.push(TimelineEvent::LoopRangeSet(range));
+    }
+
+    fn loop_enabled(&self) -> bool {
+        self.loop_enabled
+    }
+
+    fn set_loop_enabled(&mut self, enabled: bool) {
+        self.loop_enabled = enabled;
+        self.push(TimelineEvent::LoopEnabledSet(enabled));
+    }
+}
+
+impl TimeSelectionApi for EventCollector<'_> {
+    fn time_selection(&self) -> Option<(f32, f32)> {
+        self.time_selection
+    }
+
+    fn set_time_selection(&mut self, range: Option<(f32, f32)>) {
+        self.time_selection = range;
+        self.push(TimelineEvent::TimeSelectionSet(range));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        context::TimelineCtx, interaction::handle_track_interaction, state::SimpleTimelineState,
+        style::TimelineStyle, SnapMode, ToolMode,
+    };
+
+    fn collector<'a>(ctx: &egui::Context, id: &str, state: &'a SimpleTimelineState) -> EventCollector<'a> {
+        EventCollector::new(ctx, egui::Id::new(id), state, state.timeline_start as f32)
+            .playhead(state.playhead.get() as f32)
+            .selections(state.selections.borrow().clone())
+    }
+
+    #[test]
+    fn keeps_latest_of_consecutive_changes() {
+        let ctx = egui::Context::default();
+        let state = SimpleTimelineState::new();
+        let mut events = collector(&ctx, "timeline", &state);
+        let track = TrackId::new("drums");
+        events.set_playhead_ticks(100.0);
+        events.set_playhead_ticks(200.0);
+        events.set_selection(track, 0.0, 10.0);
+        events.set_selection(track, 0.0, 20.0);
+        events.set_loop_range(Some((0.0, 960.0)));
+        events.set_loop_range(Some((0.0, 1920.0)));
+        events.set_playhead_ticks(300.0);
+        assert_eq!(
+            events.into_events(),
+            vec![
+                TimelineEvent::PlayheadSet(200.0),
+                TimelineEvent::SelectionChanged { track, ranges: vec![(0.0, 20.0)] },
+                TimelineEvent::LoopRangeSet(Some((0.0, 1920.0))),
+                TimelineEvent::PlayheadSet(300.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn later_widgets_see_earlier_events() {
+        let ctx = egui::Context::default();
+        let state = SimpleTimelineState::new();
+        let mut events = collector(&ctx, "timeline", &state);
+        let bar = state.ticks_per_bar();
+        events.set_playhead_ticks(bar);
+        events.shift_timeline_start(bar);
+        events.set_ticks_per_point(2.0);
+        // Relative ticks follow the scroll, while absolute ticks are unchanged
+        assert_eq!(events.playhead_ticks(), 0.0);
+        assert_eq!(events.bar_at_ticks(0.0).tick_range, 0.0..bar);
+        assert_eq!(events.bar_number_at_ticks(0.0), 1);
+        assert_eq!(MusicalInfo::ticks_per_point(&events), 2.0);
+        events.on_scrub(bar);
+        assert_eq!(
+            events.into_events(),
+            vec![
+                TimelineEvent::PlayheadSet(bar),
+                TimelineEvent::Scrolled(bar),
+                TimelineEvent::ZoomSet(2.0),
+                TimelineEvent::Scrubbed(2.0 * bar),
+            ]
+        );
+    }
+
+    #[test]
+    fn selection_drag_is_kept_per_timeline() {
+        let ctx = egui::Context::default();
+        let state = SimpleTimelineState::new();
+        let track = TrackId::new("drums");
+        collector(&ctx, "a", &state).start_selection_drag(track, 100.0);
+        // The drag outlives the collector, but is only seen by collectors for the same timeline
+        assert_eq!(collector(&ctx, "a", &state).get_drag_start(), Some((track, 100.0)));
+        assert_eq!(collector(&ctx, "b", &state).get_drag_start(), None);
+        collector(&ctx, "b", &state).update_selection_drag(track, 200.0);
+        collector(&ctx, "a", &state).end_selection_drag();
+        assert_eq!(collector(&ctx, "a", &state).get_drag_start(), None);
+    }
+
+    #[test]
+    fn applied_events_update_state() {
+        let ctx = egui::Context::default();
+        let mut state = SimpleTimelineState::new();
+        let track = TrackId::new("drums");
+        let mut events = collector(&ctx, "timeline", &state).loop_region(None, true);
+        events.add_selection(track, 0.0, 10.0);
+        events.add_selection(track, 20.0, 30.0);
+        events.set_loop_range(Some((0.0, 960.0)));
+        events.set_loop_enabled(false);
+        events.set_time_selection(Some((480.0, 1440.0)));
+        events.set_ticks_per_point(4.0);
+        events.on_scrub_start();
+        events.set_playhead_ticks(240.0);
+        events.on_scrub_end(240.0);
+        let events = events.into_events();
+        state.apply_events(events);
+        assert_eq!(state.selections(track), vec![(0.0, 10.0), (20.0, 30.0)]);
+        assert_eq!(state.loop_range, Some((0.0, 960.0)));
+        assert!(!state.loop_enabled);
+        assert_eq!(state.time_selection, Some((480.0, 1440.0)));
+        assert_eq!(MusicalInfo::ticks_per_point(&state), 4.0);
+        assert_eq!(state.playhead.get(), 240.0);
+
+        let events = collector(&ctx, "timeline", &state);
+        events.clear_all_selections();
+        state.apply_events(events.into_events());
+        assert!(state.selections(track).is_empty());
+    }
+
+    /// Run a frame showing a single track across the screen with the given pointer events,
+    /// collecting the events and then applying them to the state.
+    fn track_frame(
+        ctx: &egui::Context,
+        time: &mut f64,
+        state: &mut SimpleTimelineState,
+        track_id: TrackId,
+        events: Vec<egui::Event>,
+    ) -> Vec<TimelineEvent> {
+        let screen_rect = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(800.0, 100.0));
+        *time += 0.1;
+        let input = egui::RawInput { screen_rect: Some(screen_rect), time: Some(*time), events, ..Default::default() };
+        let mut collected = vec![];
+        let _ = ctx.run(input, |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                let style = TimelineStyle::from_visuals(ui.visuals());
+                let visible_ticks = 60.0 * screen_rect.width();
+                let id = egui::Id::new("timeline");
+                let timeline =
+                    TimelineCtx::new(id, screen_rect, visible_ticks, 0.0, style, SnapMode::Off, ToolMode::Select);
+                let events = collector(ctx, "timeline", state);
+                handle_track_interaction(ui, screen_rect, &timeline, track_id, None, Some(&events));
+                collected = events.into_events();
+            });
+        });
+        state.apply_events(collected.clone());
+        collected
+    }
+
+    fn pointer_moved(x: f32) -> egui::Event {
+        egui::Event::PointerMoved(egui::pos2(x, 50.0))
+    }
+
+    fn pointer_button(x: f32, pressed: bool) -> egui::Event {
+        let pos = egui::pos2(x, 50.0);
+        egui::Event::PointerButton { pos, button: egui::PointerButton::Primary, pressed, modifiers: egui::Modifiers::NONE }
+    }
+
+    #[test]
+    fn collects_selection_dragged_over_frames() {
+        let ctx = egui::Context::default();
+        let mut time = 0.0;
+        let mut state = SimpleTimelineState::new();
+        let track = TrackId::new("drums");
+        let mut frame = |events| track_frame(&ctx, &mut time, &mut state, track, events);
+
+        assert_eq!(frame(vec![pointer_moved(100.0)]), vec![]);
+        frame(vec![pointer_button(100.0, true)]);
+        let mut last = vec![];
+        for x in [120.0, 160.0, 200.0] {
+            last = frame(vec![pointer_moved(x)]);
+        }
+        assert_eq!(last, vec![TimelineEvent::SelectionChanged { track, ranges: vec![(6000.0, 12_000.0)] }]);
+        frame(vec![pointer_button(200.0, false)]);
+        frame(vec![]);
+        assert_eq!(state.selections(track), vec![(6000.0, 12_000.0)]);
+    }
+}
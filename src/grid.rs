@@ -1,5 +1,147 @@
 use crate::{context::TimelineCtx, ruler, types::MIN_STEP_GAP};
 
+/// The kind of grid painted over the timeline.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GridMode {
+    /// Lines on whole seconds with up to ten subdivisions per second.
+    Seconds,
+    /// Lines aligned to bars, beats and the given beat subdivision.
+    Musical { subdivision: Subdivision },
+    /// Musical lines with the subdivision chosen automatically from the zoom level.
+    #[default]
+    MusicalAuto,
+}
+
+/// The note value used for the faintest lines of a musical grid.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Subdivision {
+    /// Quarter notes.
+    Quarter,
+    /// Eighth notes.
+    Eighth,
+    /// Sixteenth notes.
+    Sixteenth,
+    /// Eighth note triplets, three per quarter note.
+    EighthTriplet,
+    /// Sixteenth note triplets, six per quarter note.
+    SixteenthTriplet,
+}
+
+impl Subdivision {
+    /// All subdivisions, from coarsest to finest.
+    pub const ALL: [Self; 5] = [
+        Self::Quarter,
+        Self::Eighth,
+        Self::EighthTriplet,
+        Self::Sixteenth,
+        Self::SixteenthTriplet,
+    ];
+
+    /// The number of lines per quarter note.
+    pub fn per_quarter_note(&self) -> u32 {
+        match self {
+            Self::Quarter => 1,
+            Self::Eighth => 2,
+            Self::Sixteenth => 4,
+            Self::EighthTriplet => 3,
+            Self::SixteenthTriplet => 6,
+        }
+    }
+
+    /// The distance between lines of this subdivision in ticks.
+    pub fn ticks(&self, ticks_per_beat: u32) -> f32 {
+        ticks_per_beat as f32 / self.per_quarter_note() as f32
+    }
+}
+
+/// Shading of every other group of bars, painted beneath the grid via `Show::paint_bar_shading`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BarShadingStyle {
+    /// The number of bars in each group, with every other group shaded.
+    pub every: u32,
+    /// Fill painted over each shaded group of bars.
+    pub fill: egui::Color32,
+}
+
+impl BarShadingStyle {
+    /// Shade every other bar with a subtle fill derived from the given visuals.
+    pub fn from_visuals(visuals: &egui::Visuals) -> Self {
+        Self { every: 1, fill: visuals.faint_bg_color }
+    }
+
+    /// Set the number of bars in each group, with every other group shaded.
+    pub fn every(mut self, bars: u32) -> Self {
+        self.every = bars;
+        self
+    }
+
+    /// Set the fill painted over each shaded group of bars.
+    pub fn fill(mut self, fill: egui::Color32) -> Self {
+        self.fill = fill;
+        self
+    }
+}
+
+impl Default for BarShadingStyle {
+    fn default() -> Self {
+        Self::from_visuals(&egui::Visuals::dark())
+    }
+}
+
+/// Shade every other group of `style.every` bars across the timeline `Rect`.
+///
+/// Groups are counted from the first bar of the timeline via `MusicalInfo::bar_number_at_ticks`,
+/// so the shading stays in place while scrolling, and bars are visited one at a time via
+/// `MusicalInfo::bar_at_ticks` so that time signature changes are respected. Shaded groups
+/// separated by less than a pixel are merged into a single rect.
+pub fn paint_bar_shading(
+    ui: &mut egui::Ui,
+    timeline: &TimelineCtx,
+    info: &dyn ruler::MusicalInfo,
+    style: BarShadingStyle,
+) {
+    let every = style.every.max(1);
+    let tl_rect = timeline.full_rect;
+    let visible_ticks = timeline.visible_ticks;
+
+    let mut rects = vec![];
+    let mut shaded: Option<(f32, f32)> = None;
+    let mut bar = info.bar_at_ticks(0.0);
+    let mut number = ruler::bar_index_at(info, bar.tick_range.start);
+    while bar.tick_range.start <= visible_ticks {
+        if number.div_euclid(every as i64) % 2 != 0 {
+            let (start_x, end_x) = (timeline.tick_to_x(bar.tick_range.start), timeline.tick_to_x(bar.tick_range.end));
+            shaded = match shaded {
+                // Extend the previous run if this bar follows it or the gap is under a pixel.
+                Some((run_start, run_end)) if start_x - run_end < 1.0 => Some((run_start, end_x)),
+                Some(run) => {
+                    rects.push(run);
+                    Some((start_x, end_x))
+                }
+                None => Some((start_x, end_x)),
+            };
+        }
+
+        // Move on to the next bar, stopping if the host has no more bars to offer.
+        let next = info.bar_at_ticks(bar.tick_range.end + 0.5);
+        if next.tick_range.start <= bar.tick_range.start {
+            break;
+        }
+        bar = next;
+        number += 1;
+    }
+    rects.extend(shaded);
+
+    let painter = ui.painter();
+    let shapes = rects.into_iter().filter_map(|(start_x, end_x)| {
+        let rect = egui::Rect::from_x_y_ranges(start_x..=end_x, tl_rect.y_range()).intersect(tl_rect);
+        rect.is_positive().then(|| egui::Shape::rect_filled(rect, 0.0, style.fill))
+    });
+    painter.extend(shapes.collect::<Vec<_>>());
+}
+
 /// Paints the grid over the timeline `Rect`.
 ///
 /// If using a custom `background`, you may wish to call this after.
@@ -7,79 +149,290 @@ use crate::{context::TimelineCtx, ruler, types::MIN_STEP_GAP};
 /// The grid is positioned so that tick 0 always aligns with the left edge of the timeline area
 /// (where the header ends), keeping it "glued" to the left edge.
 ///
-/// Uses time-based grid (seconds) instead of musical subdivisions:
-/// - Maximum 10 lines per second (0.1 second intervals)
-/// - Automatically hides lines that are too close (less than MIN_STEP_GAP pixels apart)
+/// Bars are visited one at a time via `MusicalInfo::bar_at_ticks`, so time signature changes
+/// within the visible area are respected:
+/// - Bar lines are drawn at the start of each bar, or of every few bars once they are closer than
+///   `MIN_STEP_GAP` points, as on the ruler
+/// - Beat lines are drawn on each beat of the bar's time signature
+/// - Subdivisions of the beat are added while lines remain at least `MIN_STEP_GAP` points apart
 pub fn paint_grid(ui: &mut egui::Ui, timeline: &TimelineCtx, info: &dyn ruler::MusicalInfo) {
-    let vis = ui.style().noninteractive();
-    let mut stroke = vis.bg_stroke;
-    let second_color = stroke.color.linear_multiply(0.5); // Whole seconds - darker
-    let subdivision_color = stroke.color.linear_multiply(0.25); // 0.1 second subdivisions - lighter
-    
+    paint_grid_with(ui, timeline, info, GridMode::MusicalAuto);
+}
+
+/// Paints the grid over the timeline `Rect` using the given `GridMode`.
+///
+/// In all modes, lines closer than `MIN_STEP_GAP` points to the previously drawn line are culled.
+pub fn paint_grid_with(
+    ui: &mut egui::Ui,
+    timeline: &TimelineCtx,
+    info: &dyn ruler::MusicalInfo,
+    mode: GridMode,
+) {
+    match mode {
+        GridMode::Seconds => paint_seconds(ui, timeline, info),
+        GridMode::Musical { subdivision } => {
+            let step_ticks = subdivision.ticks(info.ticks_per_beat());
+            paint_musical(ui, timeline, info, step_ticks);
+        }
+        GridMode::MusicalAuto => paint_steps(ui, timeline, info),
+    }
+}
+
+/// Paint lines on each of the `ruler::Steps` within the visible area.
+fn paint_steps(ui: &mut egui::Ui, timeline: &TimelineCtx, info: &dyn ruler::MusicalInfo) {
+    let mut stroke = ui.style().noninteractive().bg_stroke;
+    let tl_rect = timeline.full_rect;
+    let painter = ui.painter();
+    let mut lines = vec![];
+    let line_stride = ruler::bar_line_stride(info, MIN_STEP_GAP) as i64;
+    let mut number = None;
+    for step in ruler::Steps::new(info, tl_rect.width(), MIN_STEP_GAP) {
+        if step.kind == ruler::StepKind::Bar {
+            let n = number.map_or_else(|| ruler::bar_index_at(info, step.ticks), |n: i64| n + 1);
+            number = Some(n);
+            if n.rem_euclid(line_stride) != 0 {
+                continue;
+            }
+        }
+        stroke.color = match step.kind {
+            ruler::StepKind::Bar => timeline.style.grid_major_color,
+            ruler::StepKind::Beat { .. } => timeline.style.grid_beat_color,
+            ruler::StepKind::Subdivision { .. } => timeline.style.grid_minor_color,
+        };
+        lines.push(vline(painter, timeline.tick_to_x(step.ticks), tl_rect.y_range(), stroke));
+    }
+    painter.extend(lines);
+}
+
+/// Paint lines on bars, beats and the given subdivision.
+fn paint_musical(
+    ui: &mut egui::Ui,
+    timeline: &TimelineCtx,
+    info: &dyn ruler::MusicalInfo,
+    subdivision_ticks: f32,
+) {
+    let mut stroke = ui.style().noninteractive().bg_stroke;
+    let bar_color = timeline.style.grid_major_color;
+    let beat_color = timeline.style.grid_beat_color;
+    let subdivision_color = timeline.style.grid_minor_color;
+
     let tl_rect = timeline.full_rect;
-    let visible_len = tl_rect.width();
-    let ticks_per_point = info.ticks_per_point();
-    let visible_ticks = ticks_per_point * visible_len;
-    
-    // Calculate ticks per second (1 bar = 1 second)
+    let visible_ticks = timeline.visible_ticks;
     let ticks_per_beat = info.ticks_per_beat() as f32;
-    const BEATS_PER_BAR: f32 = 4.0; // 4/4 time signature
-    let ticks_per_bar = ticks_per_beat * BEATS_PER_BAR;
-    let ticks_per_second = ticks_per_bar; // 1 bar = 1 second
-    
+
+    let painter = ui.painter();
+    let mut lines = vec![];
+    let mut bar = info.bar_at_ticks(0.0);
+    let mut last_x = f32::NEG_INFINITY;
+    // Once bars are thinned out, so is everything within them.
+    let line_stride = ruler::bar_line_stride(info, MIN_STEP_GAP) as i64;
+    let mut number = ruler::bar_index_at(info, bar.tick_range.start);
+    while bar.tick_range.start <= visible_ticks {
+        let bar_ticks = bar.tick_range.end - bar.tick_range.start;
+        if bar_ticks <= 0.0 {
+            break;
+        }
+
+        // The length of one beat of this bar's time signature, e.g. an eighth note in 7/8.
+        let beat_ticks = ticks_per_beat * 4.0 / bar.time_sig.bottom.max(1) as f32;
+        let step_ticks = subdivision_ticks;
+
+        // Visit the bar start, each beat and each subdivision in order.
+        let mut next_beat = bar.tick_range.start;
+        let mut next_step = bar.tick_range.start;
+        loop {
+            let tick = next_beat.min(next_step);
+            if tick >= bar.tick_range.end || tick > visible_ticks {
+                break;
+            }
+            let is_bar = tick == bar.tick_range.start;
+            let is_beat = tick == next_beat;
+            if is_beat {
+                next_beat += beat_ticks;
+            }
+            if tick == next_step {
+                next_step += step_ticks;
+            }
+            if tick < 0.0 {
+                continue;
+            }
+
+            // Skip if line is too close to the previous one (less than MIN_STEP_GAP pixels)
+            let x = timeline.tick_to_x(tick);
+            let skip = if is_bar {
+                number.rem_euclid(line_stride) != 0
+            } else {
+                line_stride > 1 || x - last_x < MIN_STEP_GAP
+            };
+            if skip {
+                continue;
+            }
+
+            stroke.color = if is_bar {
+                bar_color
+            } else if is_beat {
+                beat_color
+            } else {
+                subdivision_color
+            };
+            lines.push(vline(painter, x, tl_rect.y_range(), stroke));
+            last_x = x;
+        }
+
+        // Move on to the next bar, stopping if the host has no more bars to offer.
+        let next = info.bar_at_ticks(bar.tick_range.end + 0.5);
+        if next.tick_range.start <= bar.tick_range.start {
+            break;
+        }
+        bar = next;
+        number += 1;
+    }
+    painter.extend(lines);
+}
+
+/// Paint lines on whole seconds with up to ten subdivisions per second.
+///
+/// Automatically hides lines that are too close (less than MIN_STEP_GAP pixels apart).
+fn paint_seconds(ui: &mut egui::Ui, timeline: &TimelineCtx, info: &dyn ruler::MusicalInfo) {
+    let mut stroke = ui.style().noninteractive().bg_stroke;
+    let second_color = timeline.style.grid_major_color; // Whole seconds - darker
+    let subdivision_color = timeline.style.grid_minor_color; // 0.1 second subdivisions - lighter
+
+    let tl_rect = timeline.full_rect;
+    let visible_ticks = timeline.visible_ticks;
+
     // Maximum 10 lines per second = 0.1 second intervals
     const MAX_LINES_PER_SECOND: f32 = 10.0;
-    let ticks_per_line = ticks_per_second / MAX_LINES_PER_SECOND; // ticks per 0.1 second
-    
+
     // Get timeline start to calculate absolute positions
     let timeline_start = info.timeline_start().unwrap_or(0.0);
-    
-    // Calculate the starting tick for the visible area (relative to timeline start)
-    // The visible area starts at tick 0 relative to timeline_start
-    let start_tick_relative = 0.0;
-    
-    // Find the first line position (snap to 0.1 second intervals)
-    // We need to find the first 0.1 second interval that's visible
-    // Convert relative start to absolute, then find the first interval
-    let absolute_start_tick = timeline_start + start_tick_relative;
-    let absolute_start_seconds = absolute_start_tick / ticks_per_second;
-    // Find the first 0.1 second interval that's >= absolute_start_seconds
-    let first_line_seconds = (absolute_start_seconds * MAX_LINES_PER_SECOND).floor() / MAX_LINES_PER_SECOND;
-    let first_line_absolute_tick = first_line_seconds * ticks_per_second;
-    // Convert back to relative tick
-    let first_line_tick_relative = first_line_absolute_tick - timeline_start;
-    
-    // Draw grid lines
-    let mut current_tick_relative = first_line_tick_relative;
+
+    // Find the first 0.1 second interval at or before the left edge of the view
+    let absolute_start_seconds = info.ticks_to_seconds(timeline_start);
+    let mut line_index = (absolute_start_seconds * MAX_LINES_PER_SECOND).floor() as i64;
+    let painter = ui.painter();
+    let mut lines = vec![];
     let mut last_x = f32::NEG_INFINITY;
-    
-    while current_tick_relative <= visible_ticks {
-        // Convert relative tick to x position
-        let x = tl_rect.left() + (current_tick_relative / ticks_per_point);
-        
+
+    loop {
+        // Convert the line's time to a tick relative to the start of the view
+        let seconds = line_index as f32 / MAX_LINES_PER_SECOND;
+        let current_tick_relative = info.seconds_to_ticks(seconds) - timeline_start;
+        if current_tick_relative > visible_ticks {
+            break;
+        }
+        line_index += 1;
+        let x = timeline.tick_to_x(current_tick_relative);
+
         // Skip if line is too close to the previous one (less than MIN_STEP_GAP pixels)
-        if (x - last_x).abs() < MIN_STEP_GAP && last_x != f32::NEG_INFINITY {
-            current_tick_relative += ticks_per_line;
+        if x - last_x < MIN_STEP_GAP {
             continue;
         }
-        
+
         // Determine if this is a whole second (darker) or subdivision (lighter)
-        let absolute_tick = timeline_start + current_tick_relative;
-        let seconds = absolute_tick / ticks_per_second;
-        let is_whole_second = (seconds % 1.0).abs() < 0.001; // Check if it's a whole second
-        
+        let is_whole_second = (line_index - 1).rem_euclid(MAX_LINES_PER_SECOND as i64) == 0;
+
         stroke.color = if is_whole_second {
             second_color
         } else {
             subdivision_color
         };
-        
-        // Draw the line
-        let a = egui::Pos2::new(x, tl_rect.top());
-        let b = egui::Pos2::new(x, tl_rect.bottom());
-        ui.painter().line_segment([a, b], stroke);
-        
+
+        lines.push(vline(painter, x, tl_rect.y_range(), stroke));
         last_x = x;
-        current_tick_relative += ticks_per_line;
+    }
+    painter.extend(lines);
+}
+
+/// A vertical line at `x`, centred on a physical pixel so that thin lines are not blurred.
+///
+/// Lines are collected and submitted together via `egui::Painter::extend`, as there may be
+/// thousands of them when zoomed out.
+pub(crate) fn vline(
+    painter: &egui::Painter,
+    x: f32,
+    y_range: impl Into<egui::Rangef>,
+    stroke: egui::Stroke,
+) -> egui::Shape {
+    egui::Shape::vline(painter.round_to_pixel_center(x), y_range, stroke)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ruler::{Ruler, RulerMode},
+        state::SimpleTimelineState,
+        style::TimelineStyle,
+        SnapMode, ToolMode,
+    };
+
+    /// Wide enough to show 500 bars of 4/4 at 960 PPQN when fully zoomed out.
+    const WIDTH: f32 = 500.0 * 3840.0 / (960.0 / 16.0 * SimpleTimelineState::MAX_ZOOM);
+
+    /// Run one frame painting via `paint` and count the lines and labels it submitted.
+    fn count_shapes(paint: impl Fn(&mut egui::Ui, &mut SimpleTimelineState)) -> (usize, usize) {
+        fn count(shape: &egui::Shape, counts: &mut (usize, usize)) {
+            match shape {
+                egui::Shape::Vec(shapes) => shapes.iter().for_each(|shape| count(shape, counts)),
+                egui::Shape::Text(_) => counts.1 += 1,
+                egui::Shape::Noop => (),
+                _ => counts.0 += 1,
+            }
+        }
+        let ctx = egui::Context::default();
+        let mut state = SimpleTimelineState::new();
+        state.zoom = SimpleTimelineState::MAX_ZOOM;
+        let screen_rect = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(WIDTH, 400.0));
+        let input = egui::RawInput { screen_rect: Some(screen_rect), ..Default::default() };
+        let output = ctx.run(input, |ctx| {
+            egui::CentralPanel::default()
+                .frame(egui::Frame::none())
+                .show(ctx, |ui| paint(ui, &mut state));
+        });
+        let mut counts = (0, 0);
+        for clipped in &output.shapes {
+            count(&clipped.shape, &mut counts);
+        }
+        counts
+    }
+
+    /// The most lines that fit across the view at least `MIN_STEP_GAP` apart.
+    fn max_lines() -> usize {
+        (WIDTH / MIN_STEP_GAP) as usize + 1
+    }
+
+    #[test]
+    fn grid_lines_are_bounded_when_zoomed_out() {
+        let modes = [
+            GridMode::MusicalAuto,
+            GridMode::Musical { subdivision: Subdivision::Sixteenth },
+            GridMode::Seconds,
+        ];
+        for mode in modes {
+            let (lines, _) = count_shapes(|ui, state| {
+                let rect = ui.max_rect();
+                let visible_ticks = rect.width() * ruler::MusicalInfo::ticks_per_point(state);
+                let style = TimelineStyle::from_visuals(ui.visuals());
+                let timeline =
+                    TimelineCtx::new(ui.id(), rect, visible_ticks, 0.0, style, SnapMode::Off, ToolMode::Select);
+                paint_grid_with(ui, &timeline, state, mode);
+            });
+            assert!(lines > 0, "{mode:?}");
+            assert!(lines <= max_lines(), "{mode:?} painted {lines} lines");
+        }
+    }
+
+    #[test]
+    fn ruler_lines_and_labels_are_bounded_when_zoomed_out() {
+        for mode in [RulerMode::BarsBeats, RulerMode::Seconds] {
+            let (lines, labels) = count_shapes(|ui, state| {
+                ruler::musical_with(ui, state, Ruler::new().mode(mode));
+            });
+            assert!(lines > 0 && labels > 0, "{mode:?}");
+            assert!(lines <= max_lines(), "{mode:?} painted {lines} lines");
+            // Every label is at least a digit plus the gap between labels wide.
+            assert!(labels <= (WIDTH / 10.0) as usize, "{mode:?} painted {labels} labels");
+        }
     }
 }
@@ -0,0 +1,232 @@
+//! Compact widgets for track headers, e.g. mute, solo and record-arm buttons, and for the top
+//! panel, e.g. tempo and time signature editors.
+//!
+//! These only depend on `egui` and are sized to fit a single row of a track's header or the top
+//! panel, so they may be used directly within the `TrackCtx::header` closure or `TopPanel::extra`.
+
+use crate::types::TimeSig;
+use egui::{Color32, Vec2};
+use std::ops::RangeInclusive;
+
+/// The width and height of each button shown by `track_buttons` and of the `color_swatch`.
+pub const BUTTON_SIZE: f32 = 16.0;
+/// The fill of the mute button while muted.
+pub const MUTE_COLOR: Color32 = Color32::from_rgb(90, 160, 230);
+/// The fill of the solo button while soloed.
+pub const SOLO_COLOR: Color32 = Color32::from_rgb(230, 200, 60);
+/// The fill of the record-arm button while armed.
+pub const REC_ARM_COLOR: Color32 = Color32::from_rgb(220, 60, 60);
+
+/// The tempos in beats per minute accepted by `bpm_drag_value`.
+pub const BPM_RANGE: RangeInclusive<f32> = 20.0..=999.0;
+/// The change in tempo per point dragged by `bpm_drag_value`.
+pub const BPM_DRAG_SPEED: f32 = 0.1;
+/// The time signatures listed by `time_sig_selector`.
+pub const COMMON_TIME_SIGS: [TimeSig; 8] = [
+    TimeSig { top: 2, bottom: 4 },
+    TimeSig { top: 3, bottom: 4 },
+    TimeSig { top: 4, bottom: 4 },
+    TimeSig { top: 5, bottom: 4 },
+    TimeSig { top: 6, bottom: 8 },
+    TimeSig { top: 7, bottom: 8 },
+    TimeSig { top: 9, bottom: 8 },
+    TimeSig { top: 12, bottom: 8 },
+];
+/// The largest numerator and denominator accepted by `time_sig_selector`.
+const MAX_TIME_SIG: u16 = 32;
+
+/// The per-track state toggled by `track_buttons`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrackFlags {
+    /// Whether the track is muted.
+    pub mute: bool,
+    /// Whether the track is soloed.
+    pub solo: bool,
+    /// Whether the track is armed for recording.
+    pub rec_arm: bool,
+}
+
+/// The result of showing `track_buttons`.
+#[derive(Clone, Debug)]
+pub struct TrackButtonsResponse {
+    /// The response of the mute button.
+    pub mute: egui::Response,
+    /// The response of the solo button.
+    pub solo: egui::Response,
+    /// The response of the record-arm button.
+    pub rec_arm: egui::Response,
+}
+
+impl TrackButtonsResponse {
+    /// Whether any of the flags were toggled this frame.
+    pub fn changed(&self) -> bool {
+        self.mute.changed() || self.solo.changed() || self.rec_arm.changed()
+    }
+}
+
+/// A row of small square toggle buttons for the track's mute, solo and record-arm flags.
+///
+/// Each button toggles its flag when clicked, or when activated with `Space` or `Enter` while
+/// focused. Active buttons are filled with `MUTE_COLOR`, `SOLO_COLOR` and `REC_ARM_COLOR`.
+pub fn track_buttons(ui: &mut egui::Ui, flags: &mut TrackFlags) -> TrackButtonsResponse {
+    ui.horizontal(|ui| {
+        ui.spacing_mut().item_spacing.x = 2.0;
+        TrackButtonsResponse {
+            mute: toggle_button(ui, &mut flags.mute, "M", MUTE_COLOR, "Mute"),
+            solo: toggle_button(ui, &mut flags.solo, "S", SOLO_COLOR, "Solo"),
+            rec_arm: toggle_button(ui, &mut flags.rec_arm, "R", REC_ARM_COLOR, "Record arm"),
+        }
+    })
+    .inner
+}
+
+/// A small square swatch of the given colour that opens egui's colour picker when clicked, e.g.
+/// for choosing a track's colour.
+pub fn color_swatch(ui: &mut egui::Ui, color: &mut Color32) -> egui::Response {
+    ui.scope(|ui| {
+        ui.spacing_mut().interact_size = Vec2::splat(BUTTON_SIZE);
+        egui::color_picker::color_edit_button_srgba(ui, color, egui::color_picker::Alpha::Opaque)
+    })
+    .inner
+    .on_hover_text("Track colour")
+}
+
+/// A square button labelled with a single letter that toggles `on` when clicked.
+fn toggle_button(ui: &mut egui::Ui, on: &mut bool, label: &str, color: Color32, hint: &str) -> egui::Response {
+    let (rect, mut response) = ui.allocate_exact_size(Vec2::splat(BUTTON_SIZE), egui::Sense::click());
+    if response.clicked() {
+        *on = !*on;
+        response.mark_changed();
+    }
+    response.widget_info(|| egui::WidgetInfo::selected(egui::WidgetType::Checkbox, ui.is_enabled(), *on, hint));
+
+    if ui.is_rect_visible(rect) {
+        let visuals = ui.style().interact_selectable(&response, *on);
+        let (fill, text_color) = if *on {
+            (color, Color32::BLACK)
+        } else {
+            (visuals.bg_fill, visuals.text_color())
+        };
+        let stroke = if response.has_focus() {
+            ui.visuals().selection.stroke
+        } else {
+            visuals.bg_stroke
+        };
+        ui.painter().rect(rect, 2.0, fill, stroke);
+        let font_id = egui::FontId::proportional(BUTTON_SIZE * 0.7);
+        ui.painter().text(rect.center(), egui::Align2::CENTER_CENTER, label, font_id, text_color);
+    }
+    response.on_hover_text(hint)
+}
+
+/// A tempo readout such as "120.00 BPM", dragged horizontally or vertically to change the tempo.
+///
+/// Double-clicking switches to a text field for typing the tempo, applied on `Enter` or when the
+/// field loses focus and discarded on `Escape`. The tempo is clamped to `BPM_RANGE`. The returned
+/// response is marked as changed when the tempo changes.
+pub fn bpm_drag_value(ui: &mut egui::Ui, bpm: &mut f32) -> egui::Response {
+    let id = ui.next_auto_id();
+    let text_id = id.with("bpm_text");
+
+    // Typing a tempo after double-clicking
+    if let Some(mut text) = ui.data(|d| d.get_temp::<String>(text_id)) {
+        let width = ui.spacing().interact_size.x;
+        let mut response = ui.add(egui::TextEdit::singleline(&mut text).id(id).desired_width(width));
+        response.changed = false;
+        if response.has_focus() {
+            ui.data_mut(|d| d.insert_temp(text_id, text));
+            return response;
+        }
+        ui.data_mut(|d| d.remove::<String>(text_id));
+        let cancelled = ui.input(|i| i.key_pressed(egui::Key::Escape));
+        if !cancelled
+            && let Ok(value) = text.trim().trim_end_matches("BPM").trim().parse::<f32>()
+            && value.is_finite()
+        {
+            let value = value.clamp(*BPM_RANGE.start(), *BPM_RANGE.end());
+            if value != *bpm {
+                *bpm = value;
+                response.mark_changed();
+            }
+        }
+        return response;
+    }
+
+    let text = egui::WidgetText::from(format!("{:.2} BPM", *bpm));
+    let galley = text.into_galley(ui, Some(egui::TextWrapMode::Extend), f32::INFINITY, egui::TextStyle::Button);
+    let padding = ui.spacing().button_padding;
+    let size = (galley.size() + 2.0 * padding).max(ui.spacing().interact_size);
+    let (rect, mut response) = ui.allocate_exact_size(size, egui::Sense::click_and_drag());
+    if response.dragged() {
+        let delta = ui.input(|i| i.pointer.delta());
+        let value = (*bpm + (delta.x - delta.y) * BPM_DRAG_SPEED).clamp(*BPM_RANGE.start(), *BPM_RANGE.end());
+        if value != *bpm {
+            *bpm = value;
+            response.mark_changed();
+        }
+    }
+    if response.double_clicked() {
+        ui.data_mut(|d| d.insert_temp(text_id, format!("{:.2}", *bpm)));
+        ui.memory_mut(|m| m.request_focus(id));
+    }
+    response.widget_info(|| egui::WidgetInfo::drag_value(ui.is_enabled(), *bpm as f64));
+
+    if ui.is_rect_visible(rect) {
+        let visuals = ui.style().interact(&response);
+        ui.painter().rect(rect, visuals.rounding, visuals.weak_bg_fill, visuals.bg_stroke);
+        let pos = rect.center() - galley.size() * 0.5;
+        ui.painter().galley(pos, galley, visuals.text_color());
+    }
+    response
+        .on_hover_cursor(egui::CursorIcon::ResizeHorizontal)
+        .on_hover_text("Drag to change the tempo, or double-click to type it")
+}
+
+/// A button showing a time signature such as "4/4" that opens a popup for choosing another.
+///
+/// The popup lists `COMMON_TIME_SIGS` along with fields for entering the numerator and the
+/// denominator, which is restricted to powers of two. The returned response is marked as changed
+/// when the time signature changes.
+pub fn time_sig_selector(ui: &mut egui::Ui, time_sig: &mut TimeSig) -> egui::Response {
+    let before = *time_sig;
+    let popup_id = ui.next_auto_id().with("time_sig_popup");
+    let mut response = ui
+        .button(format!("{}/{}", time_sig.top, time_sig.bottom))
+        .on_hover_text("Time signature");
+    if response.clicked() {
+        ui.memory_mut(|m| m.toggle_popup(popup_id));
+    }
+    egui::popup_below_widget(ui, popup_id, &response, egui::PopupCloseBehavior::CloseOnClickOutside, |ui| {
+        ui.set_min_width(80.0);
+        for sig in COMMON_TIME_SIGS {
+            if ui.selectable_label(*time_sig == sig, format!("{}/{}", sig.top, sig.bottom)).clicked() {
+                *time_sig = sig;
+                ui.memory_mut(|m| m.close_popup());
+            }
+        }
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.spacing_mut().item_spacing.x = 2.0;
+            ui.add(egui::DragValue::new(&mut time_sig.top).range(1..=MAX_TIME_SIG).speed(0.05));
+            ui.label("/");
+            // Edit the exponent so that only powers of two can be entered
+            let mut exponent = time_sig.bottom.max(1).ilog2();
+            ui.add(
+                egui::DragValue::new(&mut exponent)
+                    .range(0..=MAX_TIME_SIG.ilog2())
+                    .speed(0.02)
+                    .custom_formatter(|exponent, _| (1u32 << exponent as u32).to_string())
+                    .custom_parser(|text| {
+                        let bottom = text.trim().parse::<u16>().ok()?;
+                        (bottom.is_power_of_two() && bottom <= MAX_TIME_SIG).then(|| bottom.ilog2() as f64)
+                    }),
+            );
+            time_sig.bottom = 1 << exponent;
+        });
+    });
+    if *time_sig != before {
+        response.mark_changed();
+    }
+    response
+}
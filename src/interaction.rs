@@ -1,16 +1,144 @@
-use crate::{context::TracksCtx, playhead::PlayheadApi};
+use crate::{
+    context::{TimelineCtx, TracksCtx},
+    playhead::PlayheadApi,
+    ruler::MusicalInfo,
+    snap,
+    time_display::TimeDisplay,
+    tool::ToolMode,
+    types::TrackId,
+};
+use std::sync::Arc;
+
+/// How scrolling over the timeline pans and zooms it, set via `Timeline::scroll_settings`.
+///
+/// The default pans horizontally only for mostly horizontal scrolls, leaving vertical scrolls to
+/// the tracks' scroll area.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScrollSettings {
+    /// Whether a plain vertical scroll, e.g. of a mouse wheel, pans the timeline horizontally
+    /// rather than scrolling the tracks, as is common in DAWs.
+    ///
+    /// The tracks may still be scrolled via their scroll bar or while hovering their headers.
+    pub wheel_pans_horizontally: bool,
+    /// Whether horizontal panning is inverted, e.g. for a natural scroll direction.
+    pub invert_horizontal: bool,
+    /// Whether zooming with `Ctrl` + scroll is inverted. Pinch gestures are unaffected.
+    pub invert_zoom: bool,
+    /// The points panned per point scrolled.
+    pub horizontal_speed: f32,
+    /// Whether the timeline keeps scrolling with decaying velocity after a fast scroll or
+    /// middle-button drag is released. Pressing a key or pointer button stops it.
+    pub kinetic: bool,
+}
+
+impl ScrollSettings {
+    /// Set whether a plain vertical scroll pans the timeline horizontally.
+    pub fn wheel_pans_horizontally(mut self, enabled: bool) -> Self {
+        self.wheel_pans_horizontally = enabled;
+        self
+    }
+
+    /// Set whether horizontal panning is inverted.
+    pub fn invert_horizontal(mut self, invert: bool) -> Self {
+        self.invert_horizontal = invert;
+        self
+    }
+
+    /// Set whether zooming with `Ctrl` + scroll is inverted.
+    pub fn invert_zoom(mut self, invert: bool) -> Self {
+        self.invert_zoom = invert;
+        self
+    }
+
+    /// Set the points panned per point scrolled.
+    pub fn horizontal_speed(mut self, speed: f32) -> Self {
+        self.horizontal_speed = speed;
+        self
+    }
+
+    /// Set whether the timeline keeps scrolling after a fast pan is released.
+    pub fn kinetic(mut self, enabled: bool) -> Self {
+        self.kinetic = enabled;
+        self
+    }
+}
+
+impl Default for ScrollSettings {
+    fn default() -> Self {
+        Self {
+            wheel_pans_horizontally: false,
+            invert_horizontal: false,
+            invert_zoom: false,
+            horizontal_speed: 1.0,
+            kinetic: false,
+        }
+    }
+}
 
 /// Handle scroll and zoom interactions for the timeline.
+///
+/// Dragging with the middle mouse button pans the timeline horizontally. Returns the vertical
+/// distance panned this frame, which should be forwarded to the scroll area containing the tracks.
+///
+/// Pinch gestures on touch screens and trackpads zoom around where the gesture started, while
+/// two-finger scrolls pan the timeline when mostly horizontal. Scrolling and zooming are ignored
+/// while the pointer is over a popup or window above the timeline, or while any widget is being
+/// dragged.
 pub fn handle_scroll_and_zoom(
     ui: &mut egui::Ui,
     timeline_rect: egui::Rect,
     timeline_api: &mut dyn crate::TimelineApi,
-) {
-    if ui.rect_contains_pointer(timeline_rect) {
+) -> f32 {
+    handle_scroll_and_zoom_with_settings(ui, timeline_rect, timeline_api, &ScrollSettings::default())
+}
+
+/// The same as `handle_scroll_and_zoom`, but scrolling as described by the given `settings`.
+///
+/// When `ScrollSettings::wheel_pans_horizontally` is set, the vertical scroll used to pan is
+/// consumed so that scroll areas shown afterwards ignore it.
+pub fn handle_scroll_and_zoom_with_settings(
+    ui: &mut egui::Ui,
+    timeline_rect: egui::Rect,
+    timeline_api: &mut dyn crate::TimelineApi,
+    settings: &ScrollSettings,
+) -> f32 {
+    scroll_and_zoom(ui, ui.id(), timeline_rect, timeline_api, 0.0, settings).0
+}
+
+/// The same as `handle_scroll_and_zoom`, but also returns the points scrolled beyond either end of
+/// the timeline this frame, which the timeline start was clamped to.
+///
+/// The timeline start may be scrolled down to `-pre_roll`.
+pub(crate) fn scroll_and_zoom(
+    ui: &mut egui::Ui,
+    id: egui::Id,
+    timeline_rect: egui::Rect,
+    timeline_api: &mut dyn crate::TimelineApi,
+    pre_roll: f32,
+    settings: &ScrollSettings,
+) -> (f32, f32) {
+    let mut pan_y = 0.0;
+    let mut overscroll = 0.0;
+    // The points panned horizontally by the user this frame, if panning, and whether a zoom
+    // interrupted any kinetic scroll.
+    let mut panned = None;
+    let mut interrupted = false;
+    if let Some(delta) = handle_pan(ui, timeline_rect) {
+        // Dragging right reveals earlier ticks, so the content follows the pointer.
+        if delta.x != 0.0 {
+            overscroll = scroll_by_points(timeline_api, timeline_rect.width(), -delta.x, pre_roll);
+        }
+        pan_y = delta.y;
+        panned = Some(-delta.x);
+    }
+    // Leave the scroll to widgets being dragged, e.g. a slider within a track header.
+    if timeline_hovered(ui, timeline_rect) && ui.ctx().dragged_id().is_none() {
         let ctrl_pressed = ui.input(|i| i.modifiers.ctrl);
-        let shift_pressed = ui.input(|i| i.modifiers.shift);
         let smooth_delta = ui.input(|i| i.smooth_scroll_delta);
         let raw_delta = ui.input(|i| i.raw_scroll_delta);
+        // Pinch gestures report a zoom factor without `Ctrl`, which is handled via the deltas.
+        let pinch_factor = if ctrl_pressed { 1.0 } else { ui.input(|i| i.zoom_delta()) };
         // When Ctrl is pressed, prefer raw_delta for more immediate response
         // Otherwise, prefer smooth_delta for better UX
         let delta = if ctrl_pressed {
@@ -19,195 +147,1388 @@ pub fn handle_scroll_and_zoom(
             } else {
                 smooth_delta
             }
+        } else if smooth_delta != egui::Vec2::ZERO {
+            smooth_delta
         } else {
-            if smooth_delta != egui::Vec2::ZERO {
-                smooth_delta
-            } else {
-                raw_delta
-            }
+            raw_delta
         };
-        if ctrl_pressed {
+        if pinch_factor != 1.0 {
+            // Zoom around where the gesture started. Any scroll delta arriving alongside the
+            // pinch is ignored to avoid panning while zooming.
+            let centre = ui.input(|i| i.multi_touch().map(|t| t.start_pos).or(i.pointer.hover_pos()));
+            let y_delta = zoom_delta_for_scale(1.0 / pinch_factor);
+            zoom_at_pos(timeline_api, timeline_rect, centre, y_delta, pre_roll);
+            interrupted = true;
+        } else if ctrl_pressed {
             if delta.x != 0.0 || delta.y != 0.0 {
-                timeline_api.zoom(delta.y - delta.x);
-            }
-        } else if shift_pressed || delta.x != 0.0 {
-            // Handle horizontal scrolling (with or without shift modifier)
-            if delta.x != 0.0 {
-                let ticks_per_point = timeline_api.musical_ruler_info().ticks_per_point();
-                let timeline_width = timeline_rect.width();
-                let visible_ticks = ticks_per_point * timeline_width;
-                
-                // Calculate the maximum timeline_start so that bar 500 is glued to the right edge
-                // Total bars: 501 (0-500 inclusive)
-                // ticks_per_bar = ticks_per_beat * 4 (for 4/4 time signature)
-                let ticks_per_bar = timeline_api.musical_ruler_info().ticks_per_beat() as f32 * 4.0;
-                let total_ticks = 501.0 * ticks_per_bar; // 501 bars (0-500 inclusive)
-                let max_timeline_start = (total_ticks - visible_ticks).max(0.0);
-                
-                let shift_amount = delta.x * ticks_per_point;
-                let current_start = timeline_api.timeline_start();
-                let mut new_start = current_start + shift_amount;
-                
-                // Clamp to prevent scrolling past boundaries
-                new_start = new_start.max(0.0);
-                if new_start > max_timeline_start {
-                    new_start = max_timeline_start;
-                }
-                
-                if (new_start - current_start).abs() > 0.001 {
-                    timeline_api.shift_timeline_start(new_start - current_start);
-                }
+                // Anchor the zoom at the tick under the pointer so that it stays in place.
+                let pointer = ui.input(|i| i.pointer.hover_pos());
+                let y_delta = if settings.invert_zoom { delta.x - delta.y } else { delta.y - delta.x };
+                zoom_at_pos(timeline_api, timeline_rect, pointer, y_delta, pre_roll);
+                interrupted = true;
             }
+        } else if delta.x != 0.0 && delta.x.abs() >= delta.y.abs() {
+            // Handle horizontal scrolling (with or without shift modifier), ignoring the small
+            // horizontal drift of mostly vertical two-finger scrolls
+            let points = horizontal_scroll_points(delta.x, settings);
+            overscroll += scroll_by_points(timeline_api, timeline_rect.width(), points, pre_roll);
+            panned = Some(panned.unwrap_or(0.0) + points);
+        } else if delta.y != 0.0 && settings.wheel_pans_horizontally {
+            // Pan with the vertical scroll, consuming it so that the tracks don't scroll too
+            let points = horizontal_scroll_points(delta.y, settings);
+            overscroll += scroll_by_points(timeline_api, timeline_rect.width(), points, pre_roll);
+            panned = Some(panned.unwrap_or(0.0) + points);
+            ui.ctx().input_mut(|i| {
+                i.smooth_scroll_delta.y = 0.0;
+                i.raw_scroll_delta.y = 0.0;
+            });
         }
     }
+    if settings.kinetic {
+        let id = id.with("kinetic_scroll");
+        let width = timeline_rect.width();
+        overscroll += kinetic_scroll(ui, id, width, timeline_api, pre_roll, panned, interrupted);
+    }
+    (pan_y, overscroll)
+}
+
+/// The points to pan the timeline for the given scroll delta.
+fn horizontal_scroll_points(delta: f32, settings: &ScrollSettings) -> f32 {
+    let points = delta * settings.horizontal_speed;
+    if settings.invert_horizontal { -points } else { points }
+}
+
+/// Whether the pointer is over the given area of the timeline, rather than over a popup, window
+/// or other layer above it.
+fn timeline_hovered(ui: &egui::Ui, timeline_rect: egui::Rect) -> bool {
+    let Some(pos) = ui.input(|i| i.pointer.hover_pos()) else {
+        return false;
+    };
+    // Panels are not areas, so the layer is unknown when over the background.
+    timeline_rect.contains(pos) && ui.ctx().layer_id_at(pos).is_none_or(|layer| layer == ui.layer_id())
+}
+
+/// The furthest in points that the contents may be rubber-banded past either end of the timeline.
+const MAX_OVERSCROLL: f32 = 64.0;
+/// The fraction of the points scrolled beyond either end that initially stretch the view.
+const OVERSCROLL_RESISTANCE: f32 = 0.4;
+/// The time in seconds taken for the view to spring back once no longer scrolled beyond the end.
+const OVERSCROLL_RELEASE_SECS: f64 = 0.15;
+
+/// The state of the rubber-banded view, stored in egui memory between frames.
+#[derive(Copy, Clone, Debug, Default)]
+struct Overscroll {
+    /// The distance in points that the contents are shifted.
+    offset: f32,
+    /// The time and offset at which the view began springing back.
+    release: Option<(f64, f32)>,
+}
+
+/// Update the rubber-banding of the view given the points scrolled beyond either end of the
+/// timeline this frame, returning the distance in points to shift the contents by.
+///
+/// Scrolling beyond the end stretches the view against increasing resistance. Once the scrolling
+/// stops the view eases back over `OVERSCROLL_RELEASE_SECS`.
+pub(crate) fn elastic_overscroll(ui: &egui::Ui, id: egui::Id, overscroll: f32) -> f32 {
+    let now = ui.input(|i| i.time);
+    let mut state = ui.data(|d| d.get_temp::<Overscroll>(id)).unwrap_or_default();
+    if overscroll.abs() > 0.5 {
+        // Scrolling towards later ticks past the end shifts the contents left, and vice versa.
+        let resistance = OVERSCROLL_RESISTANCE * (1.0 - state.offset.abs() / MAX_OVERSCROLL);
+        state.offset = (state.offset - overscroll * resistance).clamp(-MAX_OVERSCROLL, MAX_OVERSCROLL);
+        state.release = None;
+    } else if state.offset != 0.0 {
+        let (start_time, start_offset) = *state.release.get_or_insert((now, state.offset));
+        let t = ((now - start_time) / OVERSCROLL_RELEASE_SECS).clamp(0.0, 1.0) as f32;
+        let eased = 1.0 - (1.0 - t).powi(3);
+        state.offset = start_offset * (1.0 - eased);
+        if t >= 1.0 {
+            state = Overscroll::default();
+        }
+    }
+    if state.offset != 0.0 {
+        ui.ctx().request_repaint();
+    }
+    ui.data_mut(|d| d.insert_temp(id, state));
+    state.offset
+}
+
+/// Zoom keeping the tick at the given position in place, or the start of the timeline if `None`.
+fn zoom_at_pos(
+    timeline_api: &mut dyn crate::TimelineApi,
+    timeline_rect: egui::Rect,
+    pos: Option<egui::Pos2>,
+    y_delta: f32,
+    pre_roll: f32,
+) {
+    let timeline_start = timeline_api.timeline_start();
+    let ticks_per_point = timeline_api.musical_ruler_info().ticks_per_point();
+    let anchor_ticks = pos
+        .map(|pos| timeline_start + (pos.x - timeline_rect.left()).max(0.0) * ticks_per_point)
+        .unwrap_or(timeline_start);
+    zoom_clamped(timeline_api, anchor_ticks, y_delta, timeline_rect.width(), pre_roll);
+}
+
+/// The `TimelineApi::zoom` delta that scales the visible ticks by the given factor.
+///
+/// Follows egui's conversion between `Ctrl` + scroll and zoom factors, so that hosts which zoom
+/// via `apply_zoom` at `ZoomSettings::DEFAULT_SPEED` are scaled by exactly `scale`.
+pub(crate) fn zoom_delta_for_scale(scale: f32) -> f32 {
+    scale.ln() / crate::ZoomSettings::DEFAULT_SPEED
+}
+
+/// Zoom via `TimelineApi::zoom_at`, then clamp the timeline start to the extent of the timeline.
+pub(crate) fn zoom_clamped(
+    timeline_api: &mut dyn crate::TimelineApi,
+    anchor_ticks: f32,
+    y_delta: f32,
+    timeline_width: f32,
+    pre_roll: f32,
+) {
+    timeline_api.zoom_at(anchor_ticks, y_delta);
+
+    // Zooming out may have revealed ticks beyond either end of the timeline.
+    let current_start = timeline_api.timeline_start_f64();
+    let visible_ticks = timeline_api.musical_ruler_info().ticks_per_point() * timeline_width;
+    scroll_to_start(timeline_api, current_start, visible_ticks, pre_roll);
+}
+
+/// The pointer delta while the middle mouse button is dragged from within the timeline area, for
+/// panning the timeline, or `None` if not panning.
+fn handle_pan(ui: &egui::Ui, timeline_rect: egui::Rect) -> Option<egui::Vec2> {
+    let (panning, delta) = ui.input(|i| {
+        let panning = i.pointer.middle_down()
+            && i.pointer.press_origin().is_some_and(|pos| timeline_rect.contains(pos));
+        (panning, i.pointer.delta())
+    });
+    if !panning {
+        return None;
+    }
+    ui.ctx().set_cursor_icon(egui::CursorIcon::Grabbing);
+    Some(delta)
+}
+
+/// The fraction of the latest velocity blended into the tracked velocity each frame while panning.
+const KINETIC_SMOOTHING: f32 = 0.4;
+/// The rate per second at which the velocity of a kinetic scroll decays.
+const KINETIC_FRICTION: f32 = 4.0;
+/// The velocity in points per second below which a kinetic scroll stops, or doesn't start.
+const KINETIC_MIN_VELOCITY: f32 = 20.0;
+
+/// The state of a kinetic scroll, stored in egui memory between frames.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+struct Kinetic {
+    /// The velocity in points per second, in the direction passed to `scroll_by_points`.
+    velocity: f32,
+    /// Whether the velocity is being tracked while the user pans, rather than coasting.
+    tracking: bool,
+}
+
+/// Continue scrolling the timeline after the user stops panning, with exponentially decaying
+/// velocity, as enabled via `ScrollSettings::kinetic`.
+///
+/// `panned` is the points panned by the user this frame, if panning. Pressing a key or pointer
+/// button, or `interrupted`, e.g. by zooming, stops the scroll. Returns the points scrolled beyond
+/// either end of the timeline, at which point the scroll stops.
+fn kinetic_scroll(
+    ui: &egui::Ui,
+    id: egui::Id,
+    timeline_width: f32,
+    timeline_api: &mut dyn crate::TimelineApi,
+    pre_roll: f32,
+    panned: Option<f32>,
+    interrupted: bool,
+) -> f32 {
+    let (dt, pressed) = ui.input(|i| {
+        let key_pressed = i.events.iter().any(|e| matches!(e, egui::Event::Key { pressed: true, .. }));
+        (i.stable_dt.clamp(1e-3, 0.1), key_pressed || i.pointer.any_pressed())
+    });
+    let mut kinetic = ui.data(|d| d.get_temp::<Kinetic>(id)).unwrap_or_default();
+    let mut overscroll = 0.0;
+    if interrupted || pressed {
+        kinetic = Kinetic::default();
+    } else if let Some(points) = panned {
+        // Smooth over the jitter between frames of an ongoing pan.
+        let velocity = points / dt;
+        kinetic.velocity = if kinetic.tracking {
+            kinetic.velocity + (velocity - kinetic.velocity) * KINETIC_SMOOTHING
+        } else {
+            velocity
+        };
+        kinetic.tracking = true;
+    } else if kinetic.velocity != 0.0 {
+        // Coast once the pan is released, starting from the tracked velocity.
+        if !kinetic.tracking {
+            kinetic.velocity *= (-KINETIC_FRICTION * dt).exp();
+            overscroll = scroll_by_points(timeline_api, timeline_width, kinetic.velocity * dt, pre_roll);
+        }
+        kinetic.tracking = false;
+        if overscroll != 0.0 || kinetic.velocity.abs() < KINETIC_MIN_VELOCITY {
+            kinetic = Kinetic::default();
+        } else {
+            ui.ctx().request_repaint();
+        }
+    } else {
+        kinetic.tracking = false;
+    }
+    if kinetic == Kinetic::default() {
+        ui.data_mut(|d| d.remove::<Kinetic>(id));
+    } else {
+        ui.data_mut(|d| d.insert_temp(id, kinetic));
+    }
+    overscroll
+}
+
+/// The distance from either edge of the timeline within which drags scroll the view.
+const EDGE_SCROLL_ZONE: f32 = 20.0;
+/// The points scrolled per frame for each point that the pointer is beyond `EDGE_SCROLL_ZONE`.
+const EDGE_SCROLL_SPEED: f32 = 0.5;
+
+/// Request that the view scrolls while the pointer is near the edge of the timeline.
+///
+/// Called each pass during drags that may continue into off-screen content, such as drawing out a
+/// selection or scrubbing the playhead. The scroll itself is applied by `edge_scroll` when the
+/// timeline is next shown, and the dragged content is extended to the revealed ticks as the drag
+/// continues.
+pub(crate) fn request_edge_scroll(ui: &egui::Ui, timeline_id: egui::Id) {
+    let pass_nr = ui.ctx().cumulative_pass_nr();
+    ui.data_mut(|d| d.insert_temp(timeline_id.with("edge_scroll"), pass_nr));
+}
+
+/// Scroll the view while a drag requested by `request_edge_scroll` nears an edge of the timeline.
+///
+/// The speed is proportional to how far the pointer is within `EDGE_SCROLL_ZONE` of the edge, or
+/// beyond it. Scrolling stops as soon as the drag ends.
+pub(crate) fn edge_scroll(
+    ui: &egui::Ui,
+    timeline_id: egui::Id,
+    timeline_rect: egui::Rect,
+    timeline_api: &mut dyn crate::TimelineApi,
+    pre_roll: f32,
+) {
+    let pass_nr = ui.ctx().cumulative_pass_nr();
+    let requested = ui
+        .data(|d| d.get_temp::<u64>(timeline_id.with("edge_scroll")))
+        .is_some_and(|nr| nr + 1 >= pass_nr);
+    let (dragging, pointer_pos) = ui.input(|i| (i.pointer.primary_down(), i.pointer.interact_pos()));
+    let Some(pos) = pointer_pos.filter(|_| requested && dragging) else {
+        return;
+    };
+    let left = (timeline_rect.left() + EDGE_SCROLL_ZONE - pos.x).max(0.0);
+    let right = (pos.x - (timeline_rect.right() - EDGE_SCROLL_ZONE)).max(0.0);
+    let points = (right - left) * EDGE_SCROLL_SPEED;
+    if points != 0.0 {
+        scroll_by_points(timeline_api, timeline_rect.width(), points, pre_roll);
+        // Keep scrolling while the pointer is held still.
+        ui.ctx().request_repaint();
+    }
+}
+
+/// Shift the timeline start by the given number of points, clamped to the timeline's extent.
+///
+/// Returns the points that could not be scrolled due to the clamp.
+fn scroll_by_points(
+    timeline_api: &mut dyn crate::TimelineApi,
+    timeline_width: f32,
+    points: f32,
+    pre_roll: f32,
+) -> f32 {
+    let ticks_per_point = timeline_api.musical_ruler_info().ticks_per_point();
+    let visible_ticks = ticks_per_point * timeline_width;
+    
+    let shift_amount = points as f64 * ticks_per_point as f64;
+    let current_start = timeline_api.timeline_start_f64();
+    scroll_to_start(timeline_api, current_start + shift_amount, visible_ticks, pre_roll);
+    let shifted = timeline_api.timeline_start_f64() - current_start;
+    ((shift_amount - shifted) / ticks_per_point as f64) as f32
+}
+
+/// Shift the timeline start to the given absolute tick, clamped to the timeline's extent.
+///
+/// The shift is computed at double precision so that small scrolls are not lost far along the
+/// timeline. The start may be scrolled down to `-pre_roll`.
+pub(crate) fn scroll_to_start(
+    timeline_api: &mut dyn crate::TimelineApi,
+    target_start: f64,
+    visible_ticks: f32,
+    pre_roll: f32,
+) {
+    let current_start = timeline_api.timeline_start_f64();
+    // Clamp to prevent scrolling past boundaries
+    let new_start = clamp_start_with_pre_roll(
+        target_start,
+        visible_ticks as f64,
+        timeline_api.timeline_end().map(f64::from),
+        pre_roll as f64,
+    );
+    if (new_start - current_start).abs() > 0.001 {
+        timeline_api.shift_timeline_start_f64(new_start - current_start);
+    }
+}
+
+/// Clamp a proposed timeline start so that the view never scrolls before tick `0.0`.
+///
+/// If `timeline_end` is `Some`, the start is also clamped so that the end tick can at most be
+/// glued to the right edge of the `visible_ticks` wide view. Timelines shorter than the view
+/// always start at `0.0`.
+pub fn clamp_timeline_start(timeline_start: f32, visible_ticks: f32, timeline_end: Option<f32>) -> f32 {
+    let max_timeline_start = timeline_end
+        .map(|end| (end - visible_ticks).max(0.0))
+        .unwrap_or(f32::INFINITY);
+    timeline_start.min(max_timeline_start).max(0.0)
+}
+
+/// The same as `clamp_timeline_start`, but at double precision.
+pub fn clamp_timeline_start_f64(timeline_start: f64, visible_ticks: f64, timeline_end: Option<f64>) -> f64 {
+    clamp_start_with_pre_roll(timeline_start, visible_ticks, timeline_end, 0.0)
+}
+
+/// The same as `clamp_timeline_start_f64`, but allowing the view to start as early as
+/// `-pre_roll`, as set via `Timeline::pre_roll`.
+pub(crate) fn clamp_start_with_pre_roll(
+    timeline_start: f64,
+    visible_ticks: f64,
+    timeline_end: Option<f64>,
+    pre_roll: f64,
+) -> f64 {
+    let min_timeline_start = -pre_roll.max(0.0);
+    let max_timeline_start = timeline_end
+        .map(|end| (end - visible_ticks).max(min_timeline_start))
+        .unwrap_or(f64::INFINITY);
+    timeline_start.min(max_timeline_start).max(min_timeline_start)
 }
 
 /// Handle clicks and drags on timeline area to set playhead.
+///
+/// The timeline area is allocated with `Sense::click_and_drag`, so call this before showing the
+/// tracks in order for widgets within them to keep their own clicks and drags.
 pub fn handle_track_playhead_interaction(
     ui: &mut egui::Ui,
     tracks: &TracksCtx,
     playhead_api: Option<&dyn PlayheadApi>,
 ) {
     if let Some(api) = playhead_api {
-        let timeline_rect = tracks.timeline.full_rect;
-        let timeline_w = timeline_rect.width();
-        let ticks_per_point = api.ticks_per_point();
-        let visible_ticks = ticks_per_point * timeline_w;
-
-        // Check input state without allocating space (to avoid layout issues)
-        let pointer_pressed = ui.input(|i| i.pointer.primary_pressed());
-        let pointer_down = ui.input(|i| i.pointer.primary_down());
-        let pointer_pos = ui.input(|i| i.pointer.interact_pos());
-        let pointer_over = pointer_pos
-            .map(|pos| timeline_rect.contains(pos))
-            .unwrap_or(false);
-
-        // Handle both initial click and drag
-        if (pointer_pressed && pointer_over) || (pointer_down && pointer_over) {
-            if let Some(pt) = pointer_pos {
-                let tick = (((pt.x - timeline_rect.min.x) / timeline_w) * visible_ticks).max(0.0);
-                api.set_playhead_ticks(tick);
-            }
+        let timeline = &tracks.timeline;
+        let id = timeline.id.with("track_playhead_interaction");
+        let response = ui.interact(timeline.full_rect, id, egui::Sense::click_and_drag());
+
+        // Handle both the initial press and the following drag
+        if (primary_pressed_on(ui, &response) || response.dragged_by(egui::PointerButton::Primary))
+            && let Some(pt) = response.interact_pointer_pos()
+        {
+            let tick = timeline.x_to_tick(pt.x).max(0.0);
+            let snap = timeline.effective_snap(ui);
+            crate::playhead::seek(api, snap::snap_tick(tick, api, snap), timeline.relative_end());
         }
     }
 }
 
+/// Paint a faint line and position readout at the pointer while it hovers over the timeline.
+///
+/// The position is snapped to the timeline's `SnapMode`. Nothing is painted while any pointer button is held.
+pub(crate) fn paint_hover_indicator(
+    ui: &egui::Ui,
+    timeline: &TimelineCtx,
+    tracks_bottom: f32,
+    playhead_api: Option<&dyn PlayheadApi>,
+) {
+    let timeline_rect = timeline.full_rect;
+    if ui.input(|i| i.pointer.any_down()) || !timeline_hovered(ui, timeline_rect) {
+        return;
+    }
+    let Some(pos) = ui.input(|i| i.pointer.hover_pos()) else {
+        return;
+    };
+    let tick = timeline.x_to_tick(pos.x).max(0.0);
+    let tick = timeline.snap_tick(ui, tick);
+    let x = ui.painter().round_to_pixel_center(timeline.tick_to_x(tick));
+    let color = ui.visuals().weak_text_color().linear_multiply(0.5);
+    let top = egui::Pos2::new(x, timeline_rect.top());
+    let bottom = egui::Pos2::new(x, tracks_bottom.min(timeline_rect.bottom()));
+    let painter = ui.painter().with_clip_rect(timeline_rect);
+    painter.line_segment([top, bottom], egui::Stroke::new(1.0, color));
+
+    // Show the position in the format chosen for the top panel's time readout.
+    let absolute_tick = timeline.timeline_start + tick;
+    let text = match playhead_api {
+        Some(info) => {
+            let display_id = timeline.id.with("time_display");
+            let display = ui.data(|d| d.get_temp::<TimeDisplay>(display_id)).unwrap_or_default();
+            display.format(absolute_tick, info)
+        }
+        None => format!("{}", absolute_tick.round()),
+    };
+    let anchor = egui::Pos2::new(x, timeline_rect.top() + HOVER_READOUT_OFFSET);
+    crate::time_display::paint_position_tooltip(ui, timeline_rect, anchor, text);
+}
+
+/// The distance of the bottom of the hover readout from the top of the timeline.
+const HOVER_READOUT_OFFSET: f32 = 18.0;
+
+/// Show a context menu when the given `response` is right-clicked.
+///
+/// The tick under the pointer at the time of the click is found via `x_to_tick` and stored in
+/// egui memory under `id`, so that the menu keeps acting on it while open.
+pub(crate) fn context_menu(
+    ui: &egui::Ui,
+    response: &egui::Response,
+    id: egui::Id,
+    x_to_tick: impl FnOnce(f32) -> f32,
+    menu: impl FnOnce(&mut egui::Ui, f32),
+) {
+    if response.secondary_clicked()
+        && let Some(pos) = response.interact_pointer_pos()
+    {
+        let tick = x_to_tick(pos.x);
+        ui.data_mut(|d| d.insert_temp(id, tick));
+    }
+    let tick = ui.data(|d| d.get_temp::<f32>(id));
+    response.context_menu(|ui| menu(ui, tick.unwrap_or(0.0)));
+}
+
+/// Widgets drawn within a track (e.g. clips) call this while hovered or dragged so that the
+/// track's own playhead and selection interaction does not also respond to the pointer.
+pub fn claim_pointer(ui: &egui::Ui, timeline_id: egui::Id) {
+    let pass_nr = ui.ctx().cumulative_pass_nr();
+    ui.data_mut(|d| d.insert_temp(timeline_id.with("pointer_claimed"), pass_nr));
+}
+
+/// Store the area of each track with an id shown this pass, for widgets within the tracks to look
+/// up the other tracks via `track_at` on the next pass.
+pub(crate) fn remember_track_rects(ui: &egui::Ui, timeline_id: egui::Id, track_rects: &[(TrackId, egui::Rect)]) {
+    let track_rects: Arc<[(TrackId, egui::Rect)]> = track_rects.into();
+    ui.data_mut(|d| d.insert_temp(timeline_id.with("track_rects"), track_rects));
+}
+
+/// The id and area of the track at the given y position on the previous pass, if any.
+///
+/// Pinned tracks are shown first, so take precedence over the tracks scrolled beneath them.
+pub(crate) fn track_at(ui: &egui::Ui, timeline_id: egui::Id, y: f32) -> Option<(TrackId, egui::Rect)> {
+    let track_rects = ui.data(|d| d.get_temp::<Arc<[(TrackId, egui::Rect)]>>(timeline_id.with("track_rects")))?;
+    track_rects.iter().copied().find(|(_, rect)| rect.y_range().contains(y))
+}
+
+/// Whether a widget within the timeline has claimed the pointer during the current pass.
+pub(crate) fn pointer_claimed(ui: &egui::Ui, timeline_id: egui::Id) -> bool {
+    let pass_nr = ui.ctx().cumulative_pass_nr();
+    ui.data(|d| d.get_temp::<u64>(timeline_id.with("pointer_claimed"))) == Some(pass_nr)
+}
+
 /// Handle clicks and drags on a specific track for selection and playhead.
+///
+/// Dragging either edge of an existing selection resizes it, and dragging within a selection
+/// while holding `Shift` moves it. Drags either create selections or move the playhead depending
+/// on `TimelineCtx::tool_mode`. Double-clicking selects the bar under the pointer, while clicking
+/// with `Shift` held extends the selection to the pointer (see `extend_selection`). Pressing
+/// `Escape` during a selection drag cancels it, restoring the selections from before the drag
+/// began.
+///
+/// The track's area is allocated with `Sense::click_and_drag`, so call this before showing the
+/// track's contents in order for widgets within the track to keep their own clicks and drags.
 pub fn handle_track_interaction(
     ui: &mut egui::Ui,
     track_rect: egui::Rect, // The actual track area (for pointer detection)
-    timeline_rect: egui::Rect, // The full timeline area (for tick calculation)
+    timeline: &TimelineCtx, // The full timeline area (for tick calculation)
+    track_id: TrackId,
+    playhead_api: Option<&dyn PlayheadApi>,
+    selection_api: Option<&dyn TrackSelectionApi>,
+) {
+    let id = timeline.id.with("track_interaction").with(track_id);
+    let response = ui.interact(track_rect, id, egui::Sense::click_and_drag());
+    track_interaction(ui, &response, timeline, track_id, playhead_api, selection_api, true, true);
+}
+
+/// The same as `handle_track_interaction`, but identifies the track by name.
+#[deprecated(note = "use `handle_track_interaction` with a `TrackId` instead")]
+pub fn handle_track_interaction_str(
+    ui: &mut egui::Ui,
+    track_rect: egui::Rect,
+    timeline: &TimelineCtx,
     track_id: &str,
     playhead_api: Option<&dyn PlayheadApi>,
     selection_api: Option<&dyn TrackSelectionApi>,
 ) {
-    let timeline_w = timeline_rect.width();
-    
-    let ticks_per_point = if let Some(ref api) = playhead_api {
-        api.ticks_per_point()
-    } else if let Some(ref api) = selection_api {
-        api.ticks_per_point()
-    } else {
+    let track_id = TrackId::from(track_id);
+    handle_track_interaction(ui, track_rect, timeline, track_id, playhead_api, selection_api);
+}
+
+/// Whether the primary button was pressed on the given `response` this frame.
+///
+/// Unlike checking the pointer directly, this respects egui's hit testing, so presses on widgets
+/// drawn over the top of the response's area are left to those widgets.
+pub(crate) fn primary_pressed_on(ui: &egui::Ui, response: &egui::Response) -> bool {
+    response.is_pointer_button_down_on() && ui.input(|i| i.pointer.primary_pressed())
+}
+
+/// The implementation of `handle_track_interaction`, given the `response` of the track's area.
+///
+/// New selections are only dragged out on the track if `create_selections` is `true`. Otherwise
+/// they are created by `handle_rect_selection`, while existing selections may still be edited and
+/// cleared via the track. Right-clicking clears all selections only if `clear_on_secondary` is
+/// `true`, e.g. when the track has no context menu.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn track_interaction(
+    ui: &egui::Ui,
+    response: &egui::Response,
+    timeline: &TimelineCtx,
+    track_id: TrackId,
+    playhead_api: Option<&dyn PlayheadApi>,
+    selection_api: Option<&dyn TrackSelectionApi>,
+    create_selections: bool,
+    clear_on_secondary: bool,
+) {
+    if playhead_api.is_none() && selection_api.is_none() {
         return;
+    }
+
+    // Leave the pointer to any widget within the track that has claimed it, and to multi-touch
+    // gestures such as pinch-to-zoom.
+    if pointer_claimed(ui, timeline.id) || ui.input(|i| i.multi_touch().is_some()) {
+        return;
+    }
+
+    // The host handles the pointer itself in the passthrough modes.
+    let tool_mode = timeline.tool_mode;
+    if tool_mode.is_passthrough() {
+        return;
+    }
+
+    // The tick at the given x position relative to the start of the view, snapped to the grid.
+    // Shift extends and moves selections when selecting, so it only coarsens the grid otherwise.
+    let modifiers = ui.input(|i| i.modifiers);
+    let snap_mode = match tool_mode {
+        ToolMode::Select => timeline.snap.with_bypass(modifiers),
+        _ => timeline.snap.with_modifiers(modifiers),
     };
-    
-    let visible_ticks = ticks_per_point * timeline_w;
-
-    let pointer_pressed = ui.input(|i| i.pointer.primary_pressed());
-    let pointer_released = ui.input(|i| i.pointer.primary_released());
-    let pointer_down = ui.input(|i| i.pointer.primary_down());
-    let secondary_pressed = ui.input(|i| i.pointer.secondary_pressed());
-    let pointer_pos = ui.input(|i| i.pointer.interact_pos());
-    // Check if pointer is over the actual track area (not the full timeline)
-    let pointer_over_track = pointer_pos
-        .map(|pos| track_rect.contains(pos))
-        .unwrap_or(false);
-    // Check if pointer is over the timeline area (for right-click deselection)
-    let pointer_over_timeline = pointer_pos
-        .map(|pos| timeline_rect.contains(pos))
-        .unwrap_or(false);
-
-    // Check if we're currently dragging on this track
-    let is_dragging_this_track = if let Some(api) = selection_api {
-        if let Some((drag_track_id, _)) = api.get_drag_start() {
-            drag_track_id == track_id
+    let snap = |tick: f32| snap::snap_tick(tick, &timeline.snap_grid, snap_mode);
+    let visible_ticks = timeline.visible_ticks;
+    let x_to_tick = |x: f32| timeline.clamp_to_end(snap(timeline.x_to_tick(x).clamp(0.0, visible_ticks)));
+
+    // Resizing or moving an existing selection takes priority over the playhead and new
+    // selections.
+    if let Some(api) = selection_api
+        && tool_mode == ToolMode::Select
+        && edit_selection(ui, response, timeline, track_id, api, &snap)
+    {
+        return;
+    }
+
+    // Scrubbing moves the playhead while the primary button is held on the track, while
+    // selecting only moves it on click.
+    let move_playhead = match tool_mode {
+        ToolMode::Scrub => response.is_pointer_button_down_on() && ui.input(|i| i.pointer.primary_down()),
+        _ => response.clicked(),
+    };
+    if let Some(api) = playhead_api
+        && move_playhead
+        && let Some(pos) = response.interact_pointer_pos()
+    {
+        crate::playhead::seek(api, x_to_tick(pos.x), timeline.relative_end());
+    }
+
+    let Some(api) = selection_api else {
+        return;
+    };
+    let is_dragging_this_track = || api.drag_track() == Some(track_id);
+    let snapshot_id = timeline.id.with("selection_snapshot");
+    // The ranges on the dragged track that the new range is added to.
+    let base_id = timeline.id.with("selection_base");
+    let ctrl = ui.input(|i| i.modifiers.ctrl);
+
+    if response.secondary_clicked() {
+        if clear_on_secondary {
+            clear_all_selections(api);
+        }
+    } else if tool_mode != ToolMode::Select {
+        // Selections are neither created nor cleared by the pointer while scrubbing.
+    } else if response.double_clicked()
+        && let Some(pos) = response.interact_pointer_pos()
+        && let tick = timeline.x_to_tick(pos.x).clamp(0.0, visible_ticks)
+        && let bar = timeline.snap_grid.bar_at_ticks(tick)
+        && bar.tick_range.end > bar.tick_range.start
+    {
+        // Double-clicking selects the bar under the pointer, replacing the selections unless
+        // holding Ctrl. The bar is found via the grid captured from the timeline's `MusicalInfo`.
+        let timeline_start = api.timeline_start();
+        let range = (timeline_start + bar.tick_range.start, timeline_start + bar.tick_range.end);
+        snapshot_selections(ui, snapshot_id, api);
+        let mut ranges = if ctrl { api.selections(track_id) } else { vec![] };
+        if !ctrl {
+            api.clear_all_selections();
+        }
+        ranges.push(range);
+        set_track_selections(api, track_id, merge_ranges(ranges));
+        commit_selections(ui, snapshot_id, api, &[(track_id, range)]);
+    } else if response.clicked()
+        && ui.input(|i| i.modifiers.shift)
+        && let Some(pos) = response.interact_pointer_pos()
+    {
+        // Shift-clicking extends the selection to the pointer rather than deselecting.
+        let tick = api.timeline_start() + x_to_tick(pos.x);
+        extend_selection(ui, snapshot_id, api, track_id, tick);
+    } else if response.clicked() {
+        // A click without a drag deselects all, unless holding Ctrl to add to the selections.
+        if !ctrl {
+            clear_all_selections(api);
+        }
+    } else if response.drag_started_by(egui::PointerButton::Primary)
+        && let Some(origin) = ui.input(|i| i.pointer.press_origin())
+    {
+        // Start the selection where the drag began. Holding Ctrl adds a range to this track's
+        // selections, otherwise all previous selections are cleared first. egui only reports the
+        // start of a drag once the pointer has moved beyond its click threshold, so a slight
+        // movement while clicking is still treated as a click.
+        let absolute_start_tick = api.timeline_start() + x_to_tick(origin.x);
+        snapshot_selections(ui, snapshot_id, api);
+        if create_selections {
+            let base = if ctrl {
+                api.selections(track_id)
+            } else {
+                api.clear_all_selections();
+                vec![]
+            };
+            ui.data_mut(|d| d.insert_temp(base_id, base));
+            api.start_selection_drag(track_id, absolute_start_tick);
+        } else {
+            begin_rect_selection(ui, timeline, api, absolute_start_tick, origin.y, ctrl);
+        }
+    }
+
+    if !create_selections || !is_dragging_this_track() {
+        return;
+    }
+    if cancel_pressed(ui) {
+        // Abandon the drag, restoring the selections from before it began.
+        api.end_selection_drag();
+        restore_selections(ui, snapshot_id, api);
+        ui.data_mut(|d| d.remove::<Vec<(f32, f32)>>(base_id));
+        return;
+    }
+    let pointer_pos = response.interact_pointer_pos().or(ui.input(|i| i.pointer.latest_pos()));
+    if response.dragged_by(egui::PointerButton::Primary)
+        && let Some(pos) = pointer_pos
+    {
+        // Continue the drag, even if the pointer leaves the track.
+        api.update_selection_drag(track_id, api.timeline_start() + x_to_tick(pos.x));
+        request_edge_scroll(ui, timeline.id);
+    } else if let Some(absolute_start_tick) = api.drag_start_tick() {
+        // The drag has ended, so add the range (absolute ticks) to this track's selections.
+        let absolute_end_tick = match pointer_pos {
+            Some(pos) => api.timeline_start() + x_to_tick(pos.x),
+            None => absolute_start_tick,
+        };
+        let mut ranges = ui
+            .data_mut(|d| d.remove_temp::<Vec<(f32, f32)>>(base_id))
+            .unwrap_or_default();
+        api.end_selection_drag();
+        if (absolute_end_tick - absolute_start_tick).abs() >= 1.0 {
+            let range = (absolute_start_tick.min(absolute_end_tick), absolute_start_tick.max(absolute_end_tick));
+            ranges.push(range);
+            set_track_selections(api, track_id, merge_ranges(ranges));
+            commit_selections(ui, snapshot_id, api, &[(track_id, range)]);
         } else {
-            false
+            // A drag that selects nothing leaves the selections as they were.
+            restore_selections(ui, snapshot_id, api);
+        }
+    }
+}
+
+/// A rectangular selection being dragged out across tracks, stored in egui memory.
+#[derive(Copy, Clone, Debug)]
+struct RectSelectDrag {
+    /// The absolute tick at which the drag started.
+    start_tick: f32,
+    /// The y position at which the drag started.
+    start_y: f32,
+    /// Whether the ranges are added to the existing selections, due to `Ctrl` being held.
+    additive: bool,
+}
+
+/// Begin a rectangular selection from the given absolute tick and y position, once a drag has
+/// started on one of the tracks. Existing selections are cleared unless `additive`.
+fn begin_rect_selection(
+    ui: &egui::Ui,
+    timeline: &TimelineCtx,
+    api: &dyn TrackSelectionApi,
+    start_tick: f32,
+    start_y: f32,
+    additive: bool,
+) {
+    if !additive {
+        // Tracks are only notified of the cleared selections once the drag is committed.
+        api.clear_all_selections();
+    }
+    let drag = RectSelectDrag { start_tick, start_y, additive };
+    ui.data_mut(|d| d.insert_temp(timeline.id.with("rect_select_drag"), drag));
+}
+
+/// Handle a rectangular selection dragged out across all of the given tracks.
+///
+/// Dragging on a track begins the selection, which spans every track that the rectangle between the
+/// press origin and the pointer touches vertically. A translucent rectangle is painted across
+/// those tracks during the drag, and on release each of them is given the same tick range. As
+/// with selections on a single track, holding `Ctrl` adds to the existing selections and pressing
+/// `Escape` cancels the drag.
+///
+/// Must be called after all tracks have been shown so that their areas are known.
+pub(crate) fn handle_rect_selection(
+    ui: &mut egui::Ui,
+    tracks: &TracksCtx,
+    selection_api: Option<&dyn TrackSelectionApi>,
+) {
+    let Some(api) = selection_api else {
+        return;
+    };
+    let timeline = &tracks.timeline;
+    let track_rects = tracks.track_rects.borrow();
+    let drag_id = timeline.id.with("rect_select_drag");
+    let Some(pointer_pos) = ui.input(|i| i.pointer.interact_pos()) else {
+        return;
+    };
+
+    // The absolute tick under the pointer, snapped to the grid. As on a single track, Shift doesn't
+    // coarsen the grid while selecting.
+    let timeline_start = api.timeline_start();
+    let tick = timeline.x_to_tick(pointer_pos.x).clamp(0.0, timeline.visible_ticks);
+    let snap_mode = timeline.snap.with_bypass(ui.input(|i| i.modifiers));
+    let tick = timeline_start + timeline.clamp_to_end(snap::snap_tick(tick, &timeline.snap_grid, snap_mode));
+
+    let Some(drag) = ui.data(|d| d.get_temp::<RectSelectDrag>(drag_id)) else {
+        return;
+    };
+    let snapshot_id = timeline.id.with("selection_snapshot");
+    if cancel_pressed(ui) {
+        restore_selections(ui, snapshot_id, api);
+        ui.data_mut(|d| d.remove::<RectSelectDrag>(drag_id));
+        return;
+    }
+
+    // The tracks touched by the rectangle.
+    let (top, bottom) = (drag.start_y.min(pointer_pos.y), drag.start_y.max(pointer_pos.y));
+    let touched = track_rects
+        .iter()
+        .filter(|(_, rect)| rect.top() <= bottom && top <= rect.bottom());
+    let (start, end) = (drag.start_tick.min(tick), drag.start_tick.max(tick));
+
+    if ui.input(|i| i.pointer.primary_down()) {
+        // Paint the rectangle across the full height of the touched tracks.
+        let y_range = touched.fold(None, |y: Option<(f32, f32)>, (_, rect)| match y {
+            Some((t, b)) => Some((t.min(rect.top()), b.max(rect.bottom()))),
+            None => Some((rect.top(), rect.bottom())),
+        });
+        if let Some((top, bottom)) = y_range {
+            let rect = egui::Rect::from_min_max(
+                egui::Pos2::new(timeline.tick_to_x(start - timeline_start), top),
+                egui::Pos2::new(timeline.tick_to_x(end - timeline_start), bottom),
+            );
+            ui.painter().rect_filled(rect, 0.0, timeline.style.selection_fill);
         }
+        request_edge_scroll(ui, timeline.id);
+        return;
+    }
+
+    // Released - select the range on each touched track, or leave the selections as they were if
+    // the range is empty.
+    if end - start >= 1.0 {
+        let mut committed = vec![];
+        for &(track_id, _) in touched {
+            let mut ranges = if drag.additive { api.selections(track_id) } else { vec![] };
+            ranges.push((start, end));
+            set_track_selections(api, track_id, merge_ranges(ranges));
+            committed.push((track_id, (start, end)));
+        }
+        commit_selections(ui, snapshot_id, api, &committed);
     } else {
-        false
+        restore_selections(ui, snapshot_id, api);
+    }
+    ui.data_mut(|d| d.remove::<RectSelectDrag>(drag_id));
+}
+
+/// The selected ranges on every track from before a drag began, stored in egui memory so that
+/// they may be restored if the drag is cancelled.
+type SelectionSnapshot = Vec<(TrackId, Vec<(f32, f32)>)>;
+
+/// Remember the current selections under `id` so that `restore_selections` may restore them.
+fn snapshot_selections(ui: &egui::Ui, id: egui::Id, api: &dyn TrackSelectionApi) {
+    let snapshot: SelectionSnapshot = api.all_selections();
+    ui.data_mut(|d| d.insert_temp(id, snapshot));
+}
+
+/// Replace all selections with those remembered under `id` by `snapshot_selections`.
+fn restore_selections(ui: &egui::Ui, id: egui::Id, api: &dyn TrackSelectionApi) {
+    let Some(snapshot) = ui.data_mut(|d| d.remove_temp::<SelectionSnapshot>(id)) else {
+        return;
     };
+    api.clear_all_selections();
+    for (track_id, ranges) in snapshot {
+        set_track_selections(api, track_id, ranges);
+    }
+}
 
-    if let Some(pt) = pointer_pos {
-        // Calculate tick based on position in timeline (not track)
-        let tick = (((pt.x - timeline_rect.min.x) / timeline_w) * visible_ticks).max(0.0);
+/// Extend the nearest of the track's selections so that it reaches the given absolute tick.
+///
+/// A tick before or after the range moves its nearer end out to the tick, while a tick within it
+/// moves its nearer edge in. If the track has no selections but another track does, that track's
+/// ranges are moved to this track unchanged instead.
+fn extend_selection(ui: &egui::Ui, snapshot_id: egui::Id, api: &dyn TrackSelectionApi, track_id: TrackId, tick: f32) {
+    snapshot_selections(ui, snapshot_id, api);
+    let mut ranges = api.selections(track_id);
+    let edge_dist = |&(start, end): &(f32, f32)| (tick - start).abs().min((tick - end).abs());
+    let committed = if let Some(nearest) = ranges.iter_mut().min_by(|a, b| edge_dist(a).total_cmp(&edge_dist(b))) {
+        let (start, end) = *nearest;
+        let (a, b) = if (tick - start).abs() < (tick - end).abs() { (tick, end) } else { (start, tick) };
+        *nearest = (a.min(b), a.max(b));
+        vec![(track_id, *nearest)]
+    } else if let Some(other_id) = api.get_selected_track_id() {
+        ranges = api.selections(other_id);
+        api.clear_all_selections();
+        ranges.iter().map(|&range| (track_id, range)).collect()
+    } else {
+        ui.data_mut(|d| d.remove::<SelectionSnapshot>(snapshot_id));
+        return;
+    };
+    set_track_selections(api, track_id, merge_ranges(ranges));
+    commit_selections(ui, snapshot_id, api, &committed);
+}
 
-        // Handle playhead (always update on click/drag, but not on right-click)
-        if let Some(api) = playhead_api {
-            if ((pointer_pressed && pointer_over_track) || (pointer_down && pointer_over_track)) && !secondary_pressed {
-                api.set_playhead_ticks(tick);
-            }
+/// Notify the host of the selections committed at the end of a drag.
+///
+/// Each of the `committed` ranges is reported via `TrackSelectionApi::on_selection_committed` as
+/// the stored range that contains it, since it may have been merged with overlapping ranges. Any
+/// track that had selections when the drag began, as remembered under `snapshot_id`, but now has
+/// none is reported as cleared.
+fn commit_selections(
+    ui: &egui::Ui,
+    snapshot_id: egui::Id,
+    api: &dyn TrackSelectionApi,
+    committed: &[(TrackId, (f32, f32))],
+) {
+    let snapshot = ui.data_mut(|d| d.remove_temp::<SelectionSnapshot>(snapshot_id)).unwrap_or_default();
+    for (track_id, _) in snapshot {
+        if !committed.iter().any(|&(id, _)| id == track_id) && api.selections(track_id).is_empty() {
+            api.on_selection_committed(track_id, None);
+        }
+    }
+    for &(track_id, range) in committed {
+        let range = api
+            .selections(track_id)
+            .into_iter()
+            .find(|&(start, end)| start <= range.0 && range.1 <= end)
+            .unwrap_or(range);
+        api.on_selection_committed(track_id, Some(range));
+    }
+}
+
+/// Clear all selections, notifying the host of each track whose selections were cleared.
+fn clear_all_selections(api: &dyn TrackSelectionApi) {
+    let cleared = api.all_selections();
+    api.clear_all_selections();
+    for (track_id, _) in cleared {
+        api.on_selection_committed(track_id, None);
+    }
+}
+
+/// Whether `Escape` was pressed to cancel the drag in progress, consuming the key press so that
+/// it isn't handled elsewhere.
+pub(crate) fn cancel_pressed(ui: &egui::Ui) -> bool {
+    ui.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Escape))
+}
+
+/// Sort the given ranges and merge any that overlap.
+fn merge_ranges(mut ranges: Vec<(f32, f32)>) -> Vec<(f32, f32)> {
+    ranges.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut merged: Vec<(f32, f32)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
         }
+    }
+    merged
+}
 
-        // Handle selection
-        if let Some(api) = selection_api {
-            // Right mouse button click - deselect all tracks (works anywhere in timeline area)
-            if secondary_pressed && pointer_over_timeline {
-                api.clear_all_selections();
-            } else if pointer_pressed && pointer_over_track && !secondary_pressed {
-                // Start drag - ONLY if click is inside the track area
-                // Clear all previous selections first, then store absolute start position
-                api.clear_all_selections();
-                let timeline_start = api.timeline_start();
-                let absolute_start_tick = timeline_start + tick;
-                api.start_selection_drag(track_id, absolute_start_tick);
-            } else if pointer_down && is_dragging_this_track && !secondary_pressed {
-                // Continue drag - allow dragging even if pointer goes outside track
-                // Update end position (absolute) - clamp tick to valid range
-                let timeline_start = api.timeline_start();
-                let clamped_tick = tick.max(0.0).min(visible_ticks);
-                let absolute_end_tick = timeline_start + clamped_tick;
-                api.update_selection_drag(track_id, absolute_end_tick);
-            } else if pointer_released {
-                // End drag - check if it was a click or drag
-                if is_dragging_this_track {
-                    if let Some((_, absolute_start_tick)) = api.get_drag_start() {
-                        let timeline_start = api.timeline_start();
-                        // Use current tick position, clamped to valid range
-                        let clamped_tick = if pointer_over_timeline { tick } else {
-                            // If released outside timeline, use the last valid position
-                            (absolute_start_tick - timeline_start).max(0.0).min(visible_ticks)
-                        };
-                        let absolute_end_tick = timeline_start + clamped_tick.max(0.0).min(visible_ticks);
-                        let drag_distance = (absolute_end_tick - absolute_start_tick).abs();
-                        if drag_distance < 1.0 {
-                            // Click (no significant drag) - clear all selections
-                            api.clear_all_selections();
-                        } else {
-                            // Drag - set selection (absolute ticks) on this track
-                            // Clear all first to ensure only one selection exists
-                            api.clear_all_selections();
-                            api.set_selection(track_id, absolute_start_tick.min(absolute_end_tick), absolute_start_tick.max(absolute_end_tick));
-                        }
-                        api.end_selection_drag();
-                    }
+/// Replace all selections on the given track with `ranges`.
+fn set_track_selections(api: &dyn TrackSelectionApi, track_id: TrackId, ranges: Vec<(f32, f32)>) {
+    api.clear_selection(track_id);
+    for (start, end) in ranges {
+        api.add_selection(track_id, start, end);
+    }
+}
+
+/// The distance in points from a selection edge within which the pointer grabs that edge.
+const SELECTION_EDGE_GRAB: f32 = 4.0;
+
+/// An edit of an existing selection, stored in egui memory between frames.
+#[derive(Clone, Debug)]
+struct SelectionEdit {
+    /// The track whose selection is being edited.
+    track_id: TrackId,
+    /// The index of the edited range within the track's selections.
+    index: usize,
+    kind: SelectionEditKind,
+}
+
+#[derive(Copy, Clone, Debug)]
+enum SelectionEditKind {
+    /// Move one edge, keeping the other at the given absolute `anchor` tick.
+    Resize { anchor: f32 },
+    /// Move the whole selection, grabbed at the given offset in ticks from its start.
+    Move { grab_offset: f32, len: f32 },
+}
+
+/// Resize or move one of the existing selections on the given track.
+///
+/// Dragging from within a few points of either edge of a selection drags that edge, while
+/// dragging from within a selection with `Shift` held drags the whole range, preserving its
+/// length. Dragging an edge past the opposite edge swaps them. Ranges that overlap once the edit
+/// ends are merged, while pressing `Escape` during the edit restores the selections.
+///
+/// Returns `true` if the pointer is being used to edit the selection, in which case the caller
+/// should neither move the playhead nor start a new selection.
+fn edit_selection(
+    ui: &egui::Ui,
+    response: &egui::Response,
+    timeline: &TimelineCtx,
+    track_id: TrackId,
+    api: &dyn TrackSelectionApi,
+    snap: &dyn Fn(f32) -> f32,
+) -> bool {
+    let edit_id = timeline.id.with("selection_edit");
+    let snapshot_id = timeline.id.with("selection_snapshot");
+    let timeline_start = api.timeline_start();
+
+    // Continue an edit that is already in progress.
+    if let Some(edit) = ui.data(|d| d.get_temp::<SelectionEdit>(edit_id)) {
+        if edit.track_id != track_id {
+            return false;
+        }
+        if cancel_pressed(ui) {
+            // Abandon the edit, restoring the selections from before it began.
+            restore_selections(ui, snapshot_id, api);
+            ui.data_mut(|d| d.remove::<SelectionEdit>(edit_id));
+        } else if response.dragged_by(egui::PointerButton::Primary)
+            && let Some(pos) = response.interact_pointer_pos()
+        {
+            let pointer_tick = timeline.x_to_tick(pos.x);
+            let (start, end, icon) = match edit.kind {
+                SelectionEditKind::Resize { anchor } => {
+                    let tick = pointer_tick.clamp(-timeline_start, timeline.visible_ticks);
+                    let tick = timeline_start + timeline.clamp_to_end(snap(tick));
+                    (anchor.min(tick), anchor.max(tick), egui::CursorIcon::ResizeHorizontal)
+                }
+                SelectionEditKind::Move { grab_offset, len } => {
+                    let start = (pointer_tick - grab_offset).max(-timeline_start);
+                    let start = (timeline_start + timeline.clamp_to_end(snap(start) + len) - len).max(0.0);
+                    (start, start + len, egui::CursorIcon::Grabbing)
                 }
+            };
+            let mut ranges = api.selections(track_id);
+            if let Some(range) = ranges.get_mut(edit.index) {
+                *range = (start, end);
+                set_track_selections(api, track_id, ranges);
             }
+            ui.ctx().set_cursor_icon(icon);
+            request_edge_scroll(ui, timeline.id);
+        } else {
+            // Merge overlapping ranges and remove any that were shrunk to nothing.
+            let mut ranges = api.selections(track_id);
+            let edited = ranges.get(edit.index).copied().filter(|(start, end)| end - start >= 1.0);
+            ranges.retain(|(start, end)| end - start >= 1.0);
+            set_track_selections(api, track_id, merge_ranges(ranges));
+            let committed: Vec<_> = edited.map(|range| (track_id, range)).into_iter().collect();
+            commit_selections(ui, snapshot_id, api, &committed);
+            ui.data_mut(|d| d.remove::<SelectionEdit>(edit_id));
+        }
+        return true;
+    }
+
+    // Otherwise, check whether the pointer is over part of one of this track's selections.
+    if api.drag_track().is_some() {
+        return false;
+    }
+    // Only query the ranges while the pointer is over this track, to avoid allocating for every
+    // track on every frame.
+    let drag_started = response.drag_started_by(egui::PointerButton::Primary);
+    if !response.hovered() && !drag_started {
+        return false;
+    }
+    let ranges = api.selections(track_id);
+    let edge_x = |tick: f32| timeline.tick_to_x(tick - timeline_start);
+    let shift = ui.input(|i| i.modifiers.shift);
+    let edit_at = |pos: egui::Pos2| {
+        let edge_dist = |&(start, end): &(f32, f32)| {
+            (pos.x - edge_x(start)).abs().min((pos.x - edge_x(end)).abs())
+        };
+        let nearest_edge = ranges
+            .iter()
+            .enumerate()
+            .filter(|(_, range)| edge_dist(range) <= SELECTION_EDGE_GRAB)
+            .min_by(|a, b| edge_dist(a.1).total_cmp(&edge_dist(b.1)));
+        if let Some((index, &(start, end))) = nearest_edge {
+            let start_dist = (pos.x - edge_x(start)).abs();
+            let end_dist = (pos.x - edge_x(end)).abs();
+            let anchor = if start_dist < end_dist { end } else { start };
+            Some((index, SelectionEditKind::Resize { anchor }, egui::CursorIcon::ResizeHorizontal))
+        } else if shift
+            && let Some((index, &(start, end))) = ranges
+                .iter()
+                .enumerate()
+                .find(|&(_, &(start, end))| edge_x(start) < pos.x && pos.x < edge_x(end))
+        {
+            let grab_offset = timeline.x_to_tick(pos.x) - (start - timeline_start);
+            let len = end - start;
+            Some((index, SelectionEditKind::Move { grab_offset, len }, egui::CursorIcon::Grab))
+        } else {
+            None
         }
+    };
+    if let Some((_, _, icon)) = response.hover_pos().and_then(edit_at) {
+        ui.ctx().set_cursor_icon(icon);
+    }
+
+    // Begin an edit when a drag starts on part of a selection.
+    if drag_started
+        && let Some((index, kind, _)) = ui.input(|i| i.pointer.press_origin()).and_then(edit_at)
+    {
+        let edit = SelectionEdit { track_id, index, kind };
+        snapshot_selections(ui, snapshot_id, api);
+        ui.data_mut(|d| d.insert_temp(edit_id, edit));
+        return true;
     }
+    false
 }
 
 /// API for track selection functionality.
 pub trait TrackSelectionApi {
     fn ticks_per_point(&self) -> f32;
     fn timeline_start(&self) -> f32;
-    fn start_selection_drag(&self, track_id: &str, start_tick: f32);
-    fn update_selection_drag(&self, track_id: &str, end_tick: f32);
-    fn get_drag_start(&self) -> Option<(String, f32)>;
+    fn start_selection_drag(&self, track_id: TrackId, start_tick: f32);
+    fn update_selection_drag(&self, track_id: TrackId, end_tick: f32);
+    fn get_drag_start(&self) -> Option<(TrackId, f32)>;
     fn end_selection_drag(&self);
-    fn set_selection(&self, track_id: &str, start_tick: f32, end_tick: f32);
-    fn clear_selection(&self, track_id: &str);
+    fn set_selection(&self, track_id: TrackId, start_tick: f32, end_tick: f32);
+    fn clear_selection(&self, track_id: TrackId);
     fn clear_all_selections(&self);
-    fn get_selection(&self, track_id: &str) -> Option<(f32, f32)>;
-    fn get_selected_track_id(&self) -> Option<String>;
+    fn get_selection(&self, track_id: TrackId) -> Option<(f32, f32)>;
+    fn get_selected_track_id(&self) -> Option<TrackId>;
+
+    /// The track on which a selection is being dragged out, if any.
+    ///
+    /// This is queried for every track on every frame. The default implementation calls
+    /// `get_drag_start`.
+    fn drag_track(&self) -> Option<TrackId> {
+        self.get_drag_start().map(|(track_id, _)| track_id)
+    }
+
+    /// The absolute tick at which the current selection drag started, if any.
+    ///
+    /// The default implementation calls `get_drag_start`.
+    fn drag_start_tick(&self) -> Option<f32> {
+        self.get_drag_start().map(|(_, start_tick)| start_tick)
+    }
+
+    /// Call `visit` with each selected `(start, end)` range on the given track in absolute ticks.
+    ///
+    /// This is used to draw the selections on every frame, so implementations that store their
+    /// ranges should override it to visit them in place. The default implementation iterates
+    /// over `selections`, which allocates for tracks with any selections.
+    fn for_each_selection(&self, track_id: TrackId, visit: &mut dyn FnMut(f32, f32)) {
+        for (start, end) in self.selections(track_id) {
+            visit(start, end);
+        }
+    }
+
+    /// The selected ranges on every track that has any, in absolute ticks.
+    ///
+    /// Used to restore the selections when a selection drag is cancelled with `Escape`. The
+    /// default implementation returns the ranges on `get_selected_track_id` only.
+    fn all_selections(&self) -> Vec<(TrackId, Vec<(f32, f32)>)> {
+        self.get_selected_track_id()
+            .map(|track_id| (track_id, self.selections(track_id)))
+            .into_iter()
+            .collect()
+    }
+
+    /// Add a range to the selections on the given track.
+    ///
+    /// Implement this along with `selections` and `remove_selection` to support multiple ranges
+    /// per track, which are added by dragging with `Ctrl` held. The default implementation
+    /// replaces the track's selection via `set_selection`.
+    fn add_selection(&self, track_id: TrackId, start_tick: f32, end_tick: f32) {
+        self.set_selection(track_id, start_tick, end_tick);
+    }
+
+    /// All selected `(start, end)` ranges on the given track in absolute ticks.
+    ///
+    /// The default implementation returns the single range from `get_selection`.
+    fn selections(&self, track_id: TrackId) -> Vec<(f32, f32)> {
+        self.get_selection(track_id).into_iter().collect()
+    }
+
+    /// Remove the range at the given index within `selections` from the given track.
+    ///
+    /// The default implementation clears the track's selection when `index` is `0`.
+    fn remove_selection(&self, track_id: TrackId, index: usize) {
+        if index == 0 {
+            self.clear_selection(track_id);
+        }
+    }
+
+    /// Called once a selection created or edited with the pointer is released, with its final
+    /// `range` in absolute ticks, or with `None` once the track's selections are cleared.
+    ///
+    /// Unlike the storage methods above, this is not called during the drag, so it suits work
+    /// such as updating an inspector or preparing a loop. Drags that are cancelled or select
+    /// nothing are not reported. The default implementation does nothing.
+    fn on_selection_committed(&self, _track_id: TrackId, _range: Option<(f32, f32)>) {}
+
+    // Forwarding methods for callers still passing track names, from before tracks were
+    // identified by `TrackId`.
+
+    #[deprecated(note = "pass a `TrackId` to `start_selection_drag`, e.g. via `TrackId::from`")]
+    fn start_selection_drag_str(&self, track_id: &str, start_tick: f32) {
+        self.start_selection_drag(TrackId::from(track_id), start_tick);
+    }
+
+    #[deprecated(note = "pass a `TrackId` to `update_selection_drag`, e.g. via `TrackId::from`")]
+    fn update_selection_drag_str(&self, track_id: &str, end_tick: f32) {
+        self.update_selection_drag(TrackId::from(track_id), end_tick);
+    }
+
+    #[deprecated(note = "pass a `TrackId` to `set_selection`, e.g. via `TrackId::from`")]
+    fn set_selection_str(&self, track_id: &str, start_tick: f32, end_tick: f32) {
+        self.set_selection(TrackId::from(track_id), start_tick, end_tick);
+    }
+
+    #[deprecated(note = "pass a `TrackId` to `clear_selection`, e.g. via `TrackId::from`")]
+    fn clear_selection_str(&self, track_id: &str) {
+        self.clear_selection(TrackId::from(track_id));
+    }
+
+    #[deprecated(note = "pass a `TrackId` to `get_selection`, e.g. via `TrackId::from`")]
+    fn get_selection_str(&self, track_id: &str) -> Option<(f32, f32)> {
+        self.get_selection(TrackId::from(track_id))
+    }
+
+    #[deprecated(note = "pass a `TrackId` to `add_selection`, e.g. via `TrackId::from`")]
+    fn add_selection_str(&self, track_id: &str, start_tick: f32, end_tick: f32) {
+        self.add_selection(TrackId::from(track_id), start_tick, end_tick);
+    }
+
+    #[deprecated(note = "pass a `TrackId` to `selections`, e.g. via `TrackId::from`")]
+    fn selections_str(&self, track_id: &str) -> Vec<(f32, f32)> {
+        self.selections(TrackId::from(track_id))
+    }
+
+    #[deprecated(note = "pass a `TrackId` to `remove_selection`, e.g. via `TrackId::from`")]
+    fn remove_selection_str(&self, track_id: &str, index: usize) {
+        self.remove_selection(TrackId::from(track_id), index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{snap::SnapGrid, state::SimpleTimelineState, style::TimelineStyle, SnapMode};
+    use std::cell::RefCell;
+
+    /// The ticks visible across an 800 point wide view at a few zoom levels, from zoomed far in
+    /// to zoomed far out.
+    const VISIBLE_TICKS: [f32; 3] = [800.0 * 0.5, 800.0 * 60.0, 800.0 * 1200.0];
+
+    #[test]
+    fn clamp_without_end() {
+        for visible_ticks in VISIBLE_TICKS {
+            assert_eq!(clamp_timeline_start(-100.0, visible_ticks, None), 0.0);
+            assert_eq!(clamp_timeline_start(1e9, visible_ticks, None), 1e9);
+            assert_eq!(clamp_timeline_start_f64(-100.0, visible_ticks as f64, None), 0.0);
+            assert_eq!(clamp_timeline_start_f64(1e12, visible_ticks as f64, None), 1e12);
+        }
+    }
+
+    #[test]
+    fn clamp_end_shorter_than_view() {
+        for visible_ticks in VISIBLE_TICKS {
+            let end = visible_ticks * 0.5;
+            for start in [-100.0, 0.0, end * 0.5, end, end * 2.0] {
+                assert_eq!(clamp_timeline_start(start, visible_ticks, Some(end)), 0.0);
+                assert_eq!(
+                    clamp_timeline_start_f64(start as f64, visible_ticks as f64, Some(end as f64)),
+                    0.0
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn clamp_glues_end_to_right_edge() {
+        for visible_ticks in VISIBLE_TICKS {
+            let end = visible_ticks * 4.0;
+            let glued = end - visible_ticks;
+            // Starts within range are kept, while later starts glue the end to the right edge
+            assert_eq!(clamp_timeline_start(glued * 0.5, visible_ticks, Some(end)), glued * 0.5);
+            assert_eq!(clamp_timeline_start(glued, visible_ticks, Some(end)), glued);
+            assert_eq!(clamp_timeline_start(end, visible_ticks, Some(end)), glued);
+            assert_eq!(
+                clamp_timeline_start_f64(end as f64 * 2.0, visible_ticks as f64, Some(end as f64)),
+                glued as f64
+            );
+        }
+    }
+
+    #[test]
+    fn clamp_with_pre_roll() {
+        for visible_ticks in VISIBLE_TICKS.map(f64::from) {
+            assert_eq!(clamp_start_with_pre_roll(-1e9, visible_ticks, None, 960.0), -960.0);
+            assert_eq!(clamp_start_with_pre_roll(-480.0, visible_ticks, None, 960.0), -480.0);
+            // A timeline shorter than the view glues its end to the right edge, as far as the
+            // pre-roll allows
+            let end = visible_ticks * 0.5;
+            let expected = (end - visible_ticks).max(-960.0);
+            assert_eq!(clamp_start_with_pre_roll(0.0, visible_ticks, Some(end), 960.0), expected);
+        }
+    }
+
+    /// A call to `on_selection_committed`.
+    type Commit = (TrackId, Option<(f32, f32)>);
+
+    /// Selection storage that records each call to `on_selection_committed`.
+    #[derive(Default)]
+    struct CommitLog {
+        state: SimpleTimelineState,
+        committed: RefCell<Vec<Commit>>,
+    }
+
+    impl TrackSelectionApi for CommitLog {
+        fn ticks_per_point(&self) -> f32 {
+            TrackSelectionApi::ticks_per_point(&self.state)
+        }
+        fn timeline_start(&self) -> f32 {
+            TrackSelectionApi::timeline_start(&self.state)
+        }
+        fn start_selection_drag(&self, track_id: TrackId, start_tick: f32) {
+            self.state.start_selection_drag(track_id, start_tick);
+        }
+        fn update_selection_drag(&self, track_id: TrackId, end_tick: f32) {
+            self.state.update_selection_drag(track_id, end_tick);
+        }
+        fn get_drag_start(&self) -> Option<(TrackId, f32)> {
+            self.state.get_drag_start()
+        }
+        fn end_selection_drag(&self) {
+            self.state.end_selection_drag();
+        }
+        fn set_selection(&self, track_id: TrackId, start_tick: f32, end_tick: f32) {
+            self.state.set_selection(track_id, start_tick, end_tick);
+        }
+        fn clear_selection(&self, track_id: TrackId) {
+            self.state.clear_selection(track_id);
+        }
+        fn clear_all_selections(&self) {
+            self.state.clear_all_selections();
+        }
+        fn get_selection(&self, track_id: TrackId) -> Option<(f32, f32)> {
+            self.state.get_selection(track_id)
+        }
+        fn get_selected_track_id(&self) -> Option<TrackId> {
+            self.state.get_selected_track_id()
+        }
+        fn all_selections(&self) -> Vec<(TrackId, Vec<(f32, f32)>)> {
+            self.state.all_selections()
+        }
+        fn add_selection(&self, track_id: TrackId, start_tick: f32, end_tick: f32) {
+            self.state.add_selection(track_id, start_tick, end_tick);
+        }
+        fn selections(&self, track_id: TrackId) -> Vec<(f32, f32)> {
+            self.state.selections(track_id)
+        }
+        fn remove_selection(&self, track_id: TrackId, index: usize) {
+            self.state.remove_selection(track_id, index);
+        }
+        fn on_selection_committed(&self, track_id: TrackId, range: Option<(f32, f32)>) {
+            self.committed.borrow_mut().push((track_id, range));
+        }
+    }
+
+    /// Run a frame showing a single track across the screen with the given pointer events.
+    fn track_frame(
+        ctx: &egui::Context,
+        time: &mut f64,
+        api: &CommitLog,
+        track_id: TrackId,
+        events: Vec<egui::Event>,
+    ) {
+        let screen_rect = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(800.0, 100.0));
+        *time += 0.1;
+        let input = egui::RawInput { screen_rect: Some(screen_rect), time: Some(*time), events, ..Default::default() };
+        let _ = ctx.run(input, |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                let style = TimelineStyle::from_visuals(ui.visuals());
+                let visible_ticks = 60.0 * screen_rect.width();
+                let id = egui::Id::new("timeline");
+                let mut timeline =
+                    TimelineCtx::new(id, screen_rect, visible_ticks, 0.0, style, SnapMode::Off, ToolMode::Select);
+                timeline.snap_grid = SnapGrid::capture(&api.state, visible_ticks);
+                handle_track_interaction(ui, screen_rect, &timeline, track_id, None, Some(api));
+            });
+        });
+    }
+
+    fn pointer_moved(x: f32) -> egui::Event {
+        egui::Event::PointerMoved(egui::pos2(x, 50.0))
+    }
+
+    fn pointer_button(x: f32, pressed: bool) -> egui::Event {
+        let pos = egui::pos2(x, 50.0);
+        egui::Event::PointerButton { pos, button: egui::PointerButton::Primary, pressed, modifiers: egui::Modifiers::NONE }
+    }
+
+    #[test]
+    fn selection_committed_once_on_release_and_clear() {
+        let ctx = egui::Context::default();
+        let mut time = 0.0;
+        let api = CommitLog::default();
+        let track_id = TrackId::new("drums");
+        let mut frame = |events| track_frame(&ctx, &mut time, &api, track_id, events);
+
+        // Drag out a selection over several frames, which is only committed on release
+        frame(vec![pointer_moved(100.0)]);
+        frame(vec![pointer_button(100.0, true)]);
+        for x in [120.0, 160.0, 200.0] {
+            frame(vec![pointer_moved(x)]);
+        }
+        assert!(api.committed.borrow().is_empty());
+        assert_eq!(api.get_drag_start(), Some((track_id, 6000.0)));
+        frame(vec![pointer_button(200.0, false)]);
+        frame(vec![]);
+        assert_eq!(api.committed.take(), vec![(track_id, Some((6000.0, 12_000.0)))]);
+        assert_eq!(api.selections(track_id), vec![(6000.0, 12_000.0)]);
+
+        // A click without a drag clears the selection, committing `None` once
+        frame(vec![pointer_moved(400.0)]);
+        frame(vec![pointer_button(400.0, true)]);
+        frame(vec![pointer_button(400.0, false)]);
+        frame(vec![]);
+        assert_eq!(api.committed.take(), vec![(track_id, None)]);
+        assert!(api.selections(track_id).is_empty());
+    }
+
+    #[test]
+    fn double_click_selects_bar_without_playhead_api() {
+        let ctx = egui::Context::default();
+        let mut time = 0.0;
+        let api = CommitLog::default();
+        let track_id = TrackId::new("drums");
+        let mut frame = |events| track_frame(&ctx, &mut time, &api, track_id, events);
+
+        frame(vec![pointer_moved(100.0)]);
+        for _ in 0..2 {
+            frame(vec![pointer_button(100.0, true)]);
+            frame(vec![pointer_button(100.0, false)]);
+        }
+        frame(vec![]);
+        // Tick 6000 lies within the second bar of 3840 ticks
+        assert_eq!(api.selections(track_id), vec![(3840.0, 7680.0)]);
+        assert_eq!(api.committed.take().last(), Some(&(track_id, Some((3840.0, 7680.0)))));
+    }
 }
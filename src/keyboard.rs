@@ -0,0 +1,167 @@
+use crate::{
+    context::TimelineCtx,
+    interaction,
+    playhead::FollowApi,
+    ruler::MusicalInfo,
+    snap::{self, SnapMode},
+    view,
+};
+use egui::{Key, KeyboardShortcut, Modifiers};
+use std::ops::Range;
+
+/// The keyboard shortcuts handled by `Show::keyboard_shortcuts`.
+///
+/// Each action may be bound to any number of shortcuts. Shortcuts are matched as with
+/// `egui::InputState::consume_shortcut`, so `Shift` and `Alt` are ignored unless required.
+#[derive(Clone, Debug)]
+pub struct KeyBindings {
+    /// Toggle playback.
+    pub play_pause: Vec<KeyboardShortcut>,
+    /// Move the playhead to the start of the timeline.
+    pub seek_start: Vec<KeyboardShortcut>,
+    /// Move the playhead to the end of the timeline, if it has one.
+    pub seek_end: Vec<KeyboardShortcut>,
+    /// Move the playhead back by one bar.
+    pub nudge_bar_left: Vec<KeyboardShortcut>,
+    /// Move the playhead forward by one bar.
+    pub nudge_bar_right: Vec<KeyboardShortcut>,
+    /// Move the playhead back by one step of the timeline's `SnapMode`, or a beat if it is `Off`.
+    pub nudge_left: Vec<KeyboardShortcut>,
+    /// Move the playhead forward by one step of the timeline's `SnapMode`, or a beat if it is
+    /// `Off`.
+    pub nudge_right: Vec<KeyboardShortcut>,
+    /// Zoom in around the playhead.
+    pub zoom_in: Vec<KeyboardShortcut>,
+    /// Zoom out around the playhead.
+    pub zoom_out: Vec<KeyboardShortcut>,
+    /// Fit the selection given to `Show::keyboard_shortcuts` to the view.
+    pub zoom_to_selection: Vec<KeyboardShortcut>,
+}
+
+impl KeyBindings {
+    /// The scroll delta applied per zoom shortcut, as passed to `TimelineApi::zoom_at`.
+    pub const ZOOM_STEP: f32 = 40.0;
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        let key = |key| KeyboardShortcut::new(Modifiers::NONE, key);
+        let shift = |key| KeyboardShortcut::new(Modifiers::SHIFT, key);
+        let command = |key| KeyboardShortcut::new(Modifiers::COMMAND, key);
+        Self {
+            play_pause: vec![key(Key::Space)],
+            seek_start: vec![key(Key::Home)],
+            seek_end: vec![key(Key::End)],
+            nudge_bar_left: vec![shift(Key::ArrowLeft)],
+            nudge_bar_right: vec![shift(Key::ArrowRight)],
+            nudge_left: vec![key(Key::ArrowLeft)],
+            nudge_right: vec![key(Key::ArrowRight)],
+            zoom_in: vec![key(Key::Plus), key(Key::Equals), command(Key::Equals), command(Key::Plus)],
+            zoom_out: vec![key(Key::Minus), command(Key::Minus)],
+            zoom_to_selection: vec![key(Key::Z)],
+        }
+    }
+}
+
+/// Whether the timeline should respond to keyboard shortcuts.
+///
+/// The timeline has focus until the user clicks elsewhere, and never while another widget (e.g. a
+/// `TextEdit` in a track header) has keyboard focus.
+fn has_focus(ui: &egui::Ui, widget_rect: egui::Rect, timeline_id: egui::Id) -> bool {
+    let focus_id = timeline_id.with("keyboard_focus");
+    if ui.input(|i| i.pointer.any_pressed())
+        && let Some(pos) = ui.input(|i| i.pointer.press_origin())
+    {
+        ui.data_mut(|d| d.insert_temp(focus_id, widget_rect.contains(pos)));
+    }
+    let focused = ui.data(|d| d.get_temp::<bool>(focus_id)).unwrap_or(true);
+    focused && ui.memory(|m| m.focused().is_none())
+}
+
+/// Handle the given keyboard shortcuts while the timeline has focus.
+///
+/// All actions are applied via the `PlayheadApi` and `TimelineApi`, except for toggling playback
+/// which calls `play_pause`. Zooming to the selection fits the given `selection` range, if any.
+pub(crate) fn handle(
+    ui: &egui::Ui,
+    widget_rect: egui::Rect,
+    timeline: &TimelineCtx,
+    bindings: &KeyBindings,
+    api: &mut dyn FollowApi,
+    selection: Option<Range<f32>>,
+    play_pause: impl FnOnce(),
+) {
+    if !has_focus(ui, widget_rect, timeline.id) {
+        return;
+    }
+    let pressed = |shortcuts: &[KeyboardShortcut]| {
+        ui.input_mut(|i| shortcuts.iter().any(|s| i.consume_shortcut(s)))
+    };
+
+    if pressed(&bindings.play_pause) {
+        play_pause();
+    }
+
+    // Seeking, in ticks relative to the start of the view.
+    let timeline_start = crate::TimelineApi::timeline_start_f64(api);
+    let playhead_ticks = api.playhead_ticks();
+    if pressed(&bindings.seek_start) {
+        api.set_playhead_ticks_f64(-timeline_start);
+    }
+    if pressed(&bindings.seek_end)
+        && let Some(end) = api.timeline_end()
+    {
+        api.set_playhead_ticks_f64(end as f64 - timeline_start);
+    }
+
+    // Check the bar nudges first, as the step nudges also match while `Shift` is held.
+    let bar_step = || step_ticks(playhead_ticks, api, SnapMode::Bar);
+    let step = || step_ticks(playhead_ticks, api, timeline.snap);
+    let nudge = if pressed(&bindings.nudge_bar_left) {
+        -bar_step()
+    } else if pressed(&bindings.nudge_bar_right) {
+        bar_step()
+    } else if pressed(&bindings.nudge_left) {
+        -step()
+    } else if pressed(&bindings.nudge_right) {
+        step()
+    } else {
+        0.0
+    };
+    if nudge != 0.0 {
+        let end = api.timeline_end().map(|end| (end as f64 - timeline_start) as f32);
+        let start = (-timeline.pre_roll as f64 - timeline_start) as f32;
+        crate::playhead::seek(api, (playhead_ticks + nudge).max(start), end);
+    }
+
+    let zoom = if pressed(&bindings.zoom_in) {
+        -KeyBindings::ZOOM_STEP
+    } else if pressed(&bindings.zoom_out) {
+        KeyBindings::ZOOM_STEP
+    } else {
+        0.0
+    };
+    if zoom != 0.0 {
+        // Keep the playhead in place if visible, otherwise the centre of the view.
+        let visible_ticks = timeline.visible_ticks;
+        let anchor = if (0.0..=visible_ticks).contains(&playhead_ticks) {
+            playhead_ticks
+        } else {
+            visible_ticks * 0.5
+        };
+        let width = timeline.full_rect.width();
+        interaction::zoom_clamped(api, (timeline_start + anchor as f64) as f32, zoom, width, timeline.pre_roll);
+    }
+
+    if pressed(&bindings.zoom_to_selection)
+        && let Some(range) = selection
+    {
+        view::fit_range_with_pre_roll(api, range, timeline.full_rect.width(), timeline.pre_roll);
+    }
+}
+
+/// The length of one step of the given mode at the given tick, or of a beat if it is `Off`.
+fn step_ticks(tick: f32, info: &dyn MusicalInfo, mode: SnapMode) -> f32 {
+    let mode = if mode == SnapMode::Off { SnapMode::Beat } else { mode };
+    snap::step_ticks_at(tick, info, mode).unwrap_or(0.0)
+}
@@ -1,21 +1,58 @@
 //! egui_timeline - A timeline widget for egui with musical ruler support
 
+pub mod automation;
+pub mod clip;
 pub mod context;
+pub mod drop;
+pub mod event;
 pub mod grid;
+pub mod header_widgets;
 pub mod interaction;
+pub mod keyboard;
+pub mod loop_region;
+pub mod marker;
+pub mod minimap;
 pub mod playhead;
 pub mod plot;
 pub mod ruler;
+pub mod scrollbar;
+pub mod snap;
+pub mod state;
+pub mod style;
+pub mod time_display;
+pub mod time_selection;
 pub mod timeline;
+pub mod tool;
+pub mod transport;
 pub mod types;
+pub mod view;
+pub mod zoom;
 
 // Re-export public API
-pub use playhead::{Playhead, PlayheadApi};
-pub use ruler::MusicalRuler;
+pub use playhead::{FollowMode, GhostPlayhead, Playhead, PlayheadApi, PlayheadHandle};
+pub use ruler::{MusicalRuler, Ruler, RulerMode, RulerStyle, TimeRulerStyle};
+pub use snap::SnapMode;
+pub use state::{DefaultPlayheadApi, DefaultPlayheadState, DefaultSelectionState, SimpleTimelineState};
 pub use context::SetPlayhead;
-pub use timeline::{Show, Timeline};
-pub use types::{Bar, TimeSig};
-pub use interaction::TrackSelectionApi;
+pub use drop::DropPayload;
+pub use event::{EventCollector, TimelineEvent};
+pub use grid::{BarShadingStyle, GridMode, Subdivision};
+pub use header_widgets::{TrackButtonsResponse, TrackFlags};
+pub use style::{DropShadow, TimelineStyle, TracksStyle};
+pub use time_display::TimeDisplay;
+pub use time_selection::TimeSelectionApi;
+pub use timeline::{ScrollTo, Show, Timeline, TracksOptions};
+pub use tool::ToolMode;
+pub use transport::{TopPanel, TransportButton, TransportState};
+pub use view::{ViewLink, ViewState};
+pub use zoom::{apply_zoom, zoom_slider, ZoomSettings};
+pub use types::{Bar, TempoMap, TimeSig, TrackId};
+pub use interaction::{ScrollSettings, TrackSelectionApi};
+pub use keyboard::KeyBindings;
+pub use clip::{ClipResponse, ClipStyle};
+pub use loop_region::LoopApi;
+pub use marker::{Marker, MarkerApi};
+pub use minimap::MinimapCtx;
 
 // Re-export TimelineApi trait
 pub use timeline_api::TimelineApi;
@@ -29,28 +66,127 @@ mod timeline_api {
         /// Access to the ruler info.
         fn musical_ruler_info(&self) -> &dyn ruler::MusicalInfo;
         /// Get the current timeline start position in ticks.
-        /// This should return 0.0 or greater - negative values are not allowed.
+        /// This should return 0.0 or greater - negative values are not allowed, unless the
+        /// timeline is shown with `Timeline::pre_roll`, in which case the start may go down to
+        /// minus the pre-roll.
         fn timeline_start(&self) -> f32;
+        /// The same as `timeline_start`, but at double precision.
+        ///
+        /// `f32` ticks lose sub-tick precision past roughly tick one million, so hosts with long
+        /// timelines should store the start as `f64` and implement this along with
+        /// `shift_timeline_start_f64`. The timeline widgets scroll via these methods.
+        fn timeline_start_f64(&self) -> f64 {
+            self.timeline_start() as f64
+        }
+        /// The end of the timeline in absolute ticks, or `None` if the timeline is unbounded.
+        ///
+        /// Horizontal scrolling is clamped so that this tick can be glued to the right edge of
+        /// the timeline area but never scrolled beyond it.
+        /// The area past the end is dimmed via `TimelineStyle::past_end_fill`, and playhead
+        /// seeks and selection edges on the tracks are clamped to it.
+        fn timeline_end(&self) -> Option<f32> {
+            None
+        }
         /// Shift the timeline start by the given number of ticks due to a scroll event.
-        /// The implementation should clamp the result to ensure it never goes below 0.0.
+        /// The implementation should clamp the result to ensure it never goes below 0.0, or below
+        /// minus the pre-roll given to `Timeline::pre_roll`.
         fn shift_timeline_start(&mut self, ticks: f32);
+        /// The same as `shift_timeline_start`, but at double precision.
+        fn shift_timeline_start_f64(&mut self, ticks: f64) {
+            self.shift_timeline_start(ticks as f32);
+        }
         /// The timeline was scrolled with with `Ctrl` held down to zoom in/out.
         fn zoom(&mut self, y_delta: f32);
+        /// Set the zoom directly, e.g. to fit a range to the view via `view::fit_range`.
+        ///
+        /// The default implementation does nothing, in which case the zoom is approximated via
+        /// `zoom`.
+        fn set_ticks_per_point(&mut self, _ticks_per_point: f32) {}
+        /// Zoom in/out while keeping the given absolute tick at the same x position.
+        ///
+        /// `anchor_ticks` is usually the tick under the mouse cursor. The default implementation
+        /// calls `zoom` and then shifts the timeline start so that the anchor stays in place,
+        /// leaving `shift_timeline_start_f64` to clamp the start.
+        fn zoom_at(&mut self, anchor_ticks: f32, y_delta: f32) {
+            let start = self.timeline_start_f64();
+            let anchor_ticks = anchor_ticks as f64;
+            let old_ticks_per_point = self.musical_ruler_info().ticks_per_point() as f64;
+            let anchor_offset_points = (anchor_ticks - start) / old_ticks_per_point;
+            self.zoom(y_delta);
+            let new_ticks_per_point = self.musical_ruler_info().ticks_per_point() as f64;
+            let new_start = anchor_ticks - anchor_offset_points * new_ticks_per_point;
+            if new_start != start {
+                self.shift_timeline_start_f64(new_start - start);
+            }
+        }
     }
 }
 
 // Re-export context types for convenience
-pub use context::{BackgroundCtx, TimelineCtx, TrackCtx, TracksCtx};
+pub use context::{BackgroundCtx, GroupCtx, HeaderSizing, TimelineCtx, TimelineRects, TrackCtx, TrackResponse, TracksCtx};
 
 // Re-export plot helper
-pub use plot::plot_ticks;
+pub use plot::{plot_ticks, plot_ticks_relative, waveform, WaveformStyle};
 
 // Add plot_ticks method to TimelineCtx for backward compatibility
 impl crate::context::TimelineCtx {
     /// Short-hand for drawing a plot within the timeline UI.
     ///
-    /// The same as `egui::plot::Plot::new`, but sets some useful defaults before returning.
+    /// The same as `egui::plot::Plot::new`, but sets some useful defaults before returning. The x
+    /// axis spans the visible range in absolute ticks.
     pub fn plot_ticks(&self, id_source: impl std::hash::Hash, y: std::ops::RangeInclusive<f32>) -> egui_plot::Plot<'_> {
         crate::plot::plot_ticks(self, id_source, y)
     }
+
+    /// Short-hand for drawing a plot within the timeline UI, with an x axis relative to the start
+    /// of the visible area.
+    ///
+    /// See `plot::plot_ticks_relative`.
+    pub fn plot_ticks_relative(&self, id_source: impl std::hash::Hash, y: std::ops::RangeInclusive<f32>) -> egui_plot::Plot<'_> {
+        crate::plot::plot_ticks_relative(self, id_source, y)
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use super::*;
+
+    fn round_trip<T: serde::Serialize + serde::de::DeserializeOwned>(value: &T) -> T {
+        let json = serde_json::to_string(value).unwrap();
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn view_state() {
+        let view = ViewState { timeline_start: 2_000_000.25, ticks_per_point: 7.5, vertical_scroll: 120.0 };
+        assert_eq!(round_trip(&view), view);
+    }
+
+    #[test]
+    fn tempo_map() {
+        let map = TempoMap::new(960, [(3840.0, 60.0), (0.0, 120.0), (5760.0, 240.0)]);
+        // Only the events are serialized, so the times of the events are recomputed
+        let json = serde_json::to_value(&map).unwrap();
+        let events = [[0.0, 120.0], [3840.0, 60.0], [5760.0, 240.0]];
+        assert_eq!(json, serde_json::json!({ "ticks_per_beat": 960, "events": events }));
+        let restored: TempoMap = serde_json::from_value(json).unwrap();
+        assert_eq!(restored.events(), map.events());
+        for tick in [-960.0, 0.0, 3840.0, 5000.0, 6720.0] {
+            assert_eq!(restored.tick_to_seconds(tick), map.tick_to_seconds(tick));
+        }
+    }
+
+    #[test]
+    fn marker() {
+        let marker = Marker::new(7680.0, "Chorus").color(egui::Color32::from_rgb(80, 160, 240));
+        assert_eq!(round_trip(&marker), marker);
+    }
+
+    #[test]
+    fn snap_mode() {
+        let modes = [SnapMode::Off, SnapMode::Bar, SnapMode::Beat, SnapMode::Division(3), SnapMode::Note(12)];
+        for mode in modes {
+            assert_eq!(round_trip(&mode), mode);
+        }
+    }
 }
@@ -0,0 +1,205 @@
+use crate::{
+    ruler::{MusicalInfo, MusicalRuler},
+    snap::{self, SnapMode},
+};
+
+/// Access to the host's loop region.
+///
+/// All ticks are absolute, i.e. measured from the start of the timeline rather than the start of
+/// the visible area.
+pub trait LoopApi {
+    /// The `(start, end)` of the loop region, or `None` if no region has been set.
+    fn loop_range(&self) -> Option<(f32, f32)>;
+    /// Set the `(start, end)` of the loop region, or `None` to clear it.
+    fn set_loop_range(&mut self, range: Option<(f32, f32)>);
+    /// Whether or not playback should loop over the region.
+    fn loop_enabled(&self) -> bool;
+    /// Enable or disable looping over the region.
+    fn set_loop_enabled(&mut self, enabled: bool);
+}
+
+/// The width in points of the grab handles at either edge of the loop band.
+const HANDLE_WIDTH: f32 = 4.0;
+
+/// The part of the loop region being dragged, stored in egui memory between frames.
+#[derive(Copy, Clone, Debug)]
+enum LoopDrag {
+    /// Drag out a new region from the given absolute tick.
+    Create { anchor: f32 },
+    /// Resize the region by moving its start edge.
+    Start,
+    /// Resize the region by moving its end edge.
+    End,
+    /// Move the whole region, grabbed at the given offset in ticks from its start.
+    Move { grab_offset: f32 },
+}
+
+/// The area of the ruler in which the loop region is edited.
+pub(crate) fn band_rect(ruler_rect: egui::Rect) -> egui::Rect {
+    let mut rect = ruler_rect;
+    rect.max.y = ruler_rect.center().y;
+    rect
+}
+
+/// Handle dragging out, resizing, moving and toggling the loop region within the upper half of
+/// the ruler.
+///
+/// Does nothing if the `MusicalRuler` does not provide a `LoopApi`.
+///
+/// Returns `true` if the pointer interaction belongs to the loop region, in which case the ruler
+/// should not move the playhead.
+pub(crate) fn ruler_interaction(
+    ui: &egui::Ui,
+    ruler_rect: egui::Rect,
+    response: &egui::Response,
+    api: &mut dyn MusicalRuler,
+    snap: SnapMode,
+) -> bool {
+    let Some(loop_api) = api.loop_api() else {
+        return false;
+    };
+    let range = loop_api.loop_range();
+    let enabled = loop_api.loop_enabled();
+    let (handled, new_range, toggle) = edit(ui, ruler_rect, response, api.info(), snap, range);
+    if let Some(loop_api) = api.loop_api() {
+        if new_range != range {
+            loop_api.set_loop_range(new_range);
+        }
+        if toggle {
+            loop_api.set_loop_enabled(!enabled);
+        }
+    }
+    handled
+}
+
+/// Determine the edit to make to the given loop `range` in response to the pointer.
+///
+/// Returns whether the interaction belongs to the loop region, the new range, and whether the
+/// enabled flag should be toggled.
+fn edit(
+    ui: &egui::Ui,
+    ruler_rect: egui::Rect,
+    response: &egui::Response,
+    info: &dyn MusicalInfo,
+    snap: SnapMode,
+    range: Option<(f32, f32)>,
+) -> (bool, Option<(f32, f32)>, bool) {
+    let band = band_rect(ruler_rect);
+    let ticks_per_point = info.ticks_per_point();
+    let timeline_start = info.timeline_start().unwrap_or(0.0);
+    let snap = snap.with_modifiers(ui.input(|i| i.modifiers));
+    // Absolute ticks at the given x position, before and after snapping.
+    let x_to_raw_tick = |x: f32| timeline_start + (x - ruler_rect.left()) * ticks_per_point;
+    let snap_abs = |tick: f32| {
+        let tick = (tick - timeline_start).max(-timeline_start);
+        timeline_start + snap::snap_tick(tick, info, snap)
+    };
+    let x_to_tick = |x: f32| snap_abs(x_to_raw_tick(x));
+    let tick_to_x = |tick: f32| ruler_rect.left() + (tick - timeline_start) / ticks_per_point;
+
+    let drag_id = response.id.with("loop_drag");
+    let press_origin = ui.input(|i| i.pointer.press_origin());
+    let pressed_in_band = press_origin.is_some_and(|pos| band.contains(pos));
+    let hover_pos = response.hover_pos().filter(|pos| band.contains(*pos));
+
+    // Determine which part of the region lies under the given x position.
+    let drag_at = |x: f32, range: Option<(f32, f32)>| match range {
+        Some((start, end)) => {
+            let (start_x, end_x) = (tick_to_x(start), tick_to_x(end));
+            if (x - start_x).abs() <= HANDLE_WIDTH {
+                LoopDrag::Start
+            } else if (x - end_x).abs() <= HANDLE_WIDTH {
+                LoopDrag::End
+            } else if x > start_x && x < end_x {
+                LoopDrag::Move { grab_offset: x_to_raw_tick(x) - start }
+            } else {
+                LoopDrag::Create { anchor: x_to_tick(x) }
+            }
+        }
+        None => LoopDrag::Create { anchor: x_to_tick(x) },
+    };
+
+    // Indicate the available interaction with the cursor.
+    if let Some(pos) = hover_pos {
+        let icon = match drag_at(pos.x, range) {
+            LoopDrag::Start | LoopDrag::End => egui::CursorIcon::ResizeHorizontal,
+            LoopDrag::Move { .. } => egui::CursorIcon::Grab,
+            LoopDrag::Create { .. } => egui::CursorIcon::Default,
+        };
+        ui.ctx().set_cursor_icon(icon);
+    }
+
+    // Double-clicking the band toggles looping.
+    let toggle = response.double_clicked()
+        && response.interact_pointer_pos().zip(range).is_some_and(|(pos, (start, end))| {
+            band.contains(pos) && (tick_to_x(start)..=tick_to_x(end)).contains(&pos.x)
+        });
+
+    if response.drag_started_by(egui::PointerButton::Primary)
+        && pressed_in_band
+        && let Some(origin) = press_origin
+    {
+        let drag = drag_at(origin.x, range);
+        ui.data_mut(|d| d.insert_temp(drag_id, drag));
+    }
+
+    let drag = ui.data(|d| d.get_temp::<LoopDrag>(drag_id));
+    let mut new_range = range;
+    if let Some(drag) = drag
+        && response.dragged_by(egui::PointerButton::Primary)
+        && let Some(pos) = response.interact_pointer_pos()
+    {
+        let tick = x_to_tick(pos.x);
+        new_range = match (drag, range) {
+            (LoopDrag::Create { anchor }, _) => Some((anchor.min(tick), anchor.max(tick))),
+            (LoopDrag::Start, Some((_, end))) => Some((tick.min(end), end)),
+            (LoopDrag::End, Some((start, _))) => Some((start, tick.max(start))),
+            (LoopDrag::Move { grab_offset }, Some((start, end))) => {
+                let new_start = snap_abs(x_to_raw_tick(pos.x) - grab_offset);
+                Some((new_start, new_start + end - start))
+            }
+            _ => range,
+        };
+    }
+
+    if response.drag_stopped() {
+        ui.data_mut(|d| d.remove::<LoopDrag>(drag_id));
+    }
+
+    (drag.is_some() || pressed_in_band, new_range, toggle)
+}
+
+/// Paint the loop region as a highlighted band in the upper half of the ruler.
+pub(crate) fn paint_band(
+    ui: &egui::Ui,
+    ruler_rect: egui::Rect,
+    info: &dyn MusicalInfo,
+    range: Option<(f32, f32)>,
+    enabled: bool,
+) {
+    let Some((start, end)) = range else {
+        return;
+    };
+    let band = band_rect(ruler_rect);
+    let ticks_per_point = info.ticks_per_point();
+    let timeline_start = info.timeline_start().unwrap_or(0.0);
+    let start_x = band.left() + (start - timeline_start) / ticks_per_point;
+    let end_x = band.left() + (end - timeline_start) / ticks_per_point;
+    let rect = egui::Rect::from_x_y_ranges(start_x..=end_x, band.y_range()).intersect(band);
+    if !rect.is_positive() {
+        return;
+    }
+    let color = ui.visuals().selection.bg_fill;
+    let fill = if enabled {
+        color.linear_multiply(0.6)
+    } else {
+        color.linear_multiply(0.2)
+    };
+    let painter = ui.painter().with_clip_rect(band);
+    painter.rect_filled(rect, 0.0, fill);
+    let stroke = egui::Stroke::new(1.0, color);
+    for x in [start_x, end_x] {
+        let x = painter.round_to_pixel_center(x);
+        painter.line_segment([egui::Pos2::new(x, band.top()), egui::Pos2::new(x, band.bottom())], stroke);
+    }
+}
@@ -1,11 +1,12 @@
 use egui_timeline::{
-    playhead::{Info, Interaction, Playhead, PlayheadApi},
-    ruler::{musical, MusicalInfo, MusicalInteract, MusicalRuler},
-    Bar, TimeSig, Timeline, TimelineApi, TrackSelectionApi,
+    automation, view,
+    clip::clip,
+    header_widgets::{bpm_drag_value, color_swatch, time_sig_selector, track_buttons},
+    playhead::{FollowMode, GhostPlayhead, Info, Interaction, Playhead, PlayheadApi, PlayheadHandle},
+    ruler::{self, musical_with_menu, MusicalInfo, Ruler, RulerMode, TimeRulerStyle},
+    waveform, zoom_slider, BarShadingStyle, ClipStyle, EventCollector, HeaderSizing, KeyBindings, Marker, MarkerApi, ScrollSettings, SimpleTimelineState, SnapMode, TempoMap, TimeDisplay, Timeline, TimelineApi, TimelineEvent, ToolMode, TopPanel, TrackFlags, TrackId, TrackSelectionApi, TransportButton, TransportState, WaveformStyle,
 };
-use std::ops::Range;
-use std::collections::HashMap;
-use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 
 fn main() -> eframe::Result<()> {
     let options = eframe::NativeOptions {
@@ -20,57 +21,50 @@ fn main() -> eframe::Result<()> {
 }
 
 struct TimelineApp {
-    timeline_start: f32,
-    zoom_level: f32,
-    playhead_pos: RefCell<f32>,
-    ticks_per_beat: u32,
+    state: SimpleTimelineState, // Scroll, zoom, playhead, selections, loop region and markers
     global_panel_visible: bool,
-    track_selections: RefCell<HashMap<String, (f32, f32)>>, // track_id -> (start_tick, end_tick)
-    drag_start_tick: RefCell<Option<(String, f32)>>, // (track_id, start_tick) when dragging
-    track_names: RefCell<HashMap<String, String>>, // track_id -> track_name
-    track_ids: RefCell<Vec<String>>, // Ordered list of track IDs
-    pending_add_track: RefCell<bool>, // Flag to add a track on next frame
-    selected_track_id: RefCell<Option<String>>, // Currently selected track ID
-    is_playing: RefCell<bool>, // true = Play selected, false = Stop selected
-    play_start_time: RefCell<Option<f64>>, // Timestamp when play started (egui time)
-    play_start_playhead_pos: RefCell<f32>, // Playhead position (absolute ticks) when play started
+    clips: HashMap<String, Vec<(f32, f32)>>, // track_id -> clips (absolute start_tick, end_tick)
+    waveform_peaks: Vec<(f32, f32)>, // (min, max) per peak, shown on the first track
+    track_heights: HashMap<String, f32>, // track_id -> height chosen by resizing
+    collapsed_tracks: HashSet<String>, // IDs of collapsed tracks
+    automation_points: Vec<(f32, f32)>, // (absolute tick, value) automation shown on the second track
+    track_names: HashMap<String, String>, // track_id -> track_name
+    track_flags: HashMap<String, TrackFlags>, // track_id -> mute/solo/record-arm
+    track_colors: HashMap<String, egui::Color32>, // track_id -> colour of the track's clips
+    track_ids: Vec<String>, // Ordered list of track IDs
+    pending_add_track: bool, // Flag to add a track on next frame
+    selected_track_id: Option<TrackId>, // Currently selected track ID
+    is_playing: bool, // true = Play selected, false = Stop selected
+    last_stop_pos: Option<f32>, // Absolute playhead position where playback last stopped
+    play_start: Option<(f64, f64)>, // (egui time, absolute playhead position) when play started
+    last_playhead_pos: f64, // Playhead position set by playback on the previous frame
+    tool_mode: ToolMode, // What dragging on the tracks does, chosen in the top panel
+    scrub: Scrub, // Where the playhead is being or was last scrubbed, shown in the bottom bar
 }
 
 impl TimelineApp {
-    /// Total number of bars (0-500 inclusive = 501 bars)
+    /// Total number of bars
     const TOTAL_BARS: u32 = 501;
     
     /// Target frame rate for smooth playhead animation
     const TARGET_FPS: f64 = 60.0;
     
-    /// Calculate ticks per bar
-    fn ticks_per_bar(&self) -> f32 {
-        let beats_per_bar = 4.0; // 4/4 time signature
-        self.ticks_per_beat as f32 * beats_per_bar
-    }
-    
-    /// Calculate ticks per second (1 bar = 1 second)
-    fn ticks_per_second(&self) -> f32 {
-        self.ticks_per_bar()
-    }
-    
-    /// Get maximum playhead position (end of bar 500)
-    fn max_playhead_pos(&self) -> f32 {
-        Self::TOTAL_BARS as f32 * self.ticks_per_bar()
-    }
+    /// The colour of a track's clips until another is chosen via its header swatch
+    const DEFAULT_TRACK_COLOR: egui::Color32 = egui::Color32::from_rgb(0, 92, 128);
     
-    /// Request to add a new track (will be processed on next frame)
-    fn request_add_track(&self) {
-        *self.pending_add_track.borrow_mut() = true;
+    /// The clips placed on a track when it is first shown
+    fn default_clips(state: &SimpleTimelineState) -> Vec<(f32, f32)> {
+        let bar = state.ticks_per_bar();
+        vec![(0.0, 2.0 * bar), (4.0 * bar, 7.0 * bar)]
     }
     
     /// Process pending track addition (called at start of frame)
-    fn process_pending_add_track(&self) {
-        if *self.pending_add_track.borrow() {
-            *self.pending_add_track.borrow_mut() = false;
+    fn process_pending_add_track(&mut self) {
+        if self.pending_add_track {
+            self.pending_add_track = false;
             
-            let mut track_ids = self.track_ids.borrow_mut();
-            let mut track_names = self.track_names.borrow_mut();
+            let track_ids = &mut self.track_ids;
+            let track_names = &mut self.track_names;
             
             // Find the next available track number that doesn't have a duplicate name
             let mut track_num = track_ids.len() + 1;
@@ -92,239 +86,208 @@ impl TimelineApp {
     }
     
     /// Remove the currently selected track
-    fn remove_selected_track(&self) {
-        let selected_id = self.selected_track_id.borrow().clone();
-        
-        if let Some(track_id) = selected_id {
+    fn remove_selected_track(&mut self) {
+        if let Some(track_id) = self.selected_track_id {
             // Remove from track_ids (ordered list)
-            let mut track_ids = self.track_ids.borrow_mut();
-            track_ids.retain(|id| id != &track_id);
+            self.track_ids.retain(|id| TrackId::from(id) != track_id);
             
-            // Remove from track_names
-            self.track_names.borrow_mut().remove(&track_id);
+            // Remove from track_names, track_flags and track_colors
+            self.track_names.retain(|id, _| TrackId::from(id) != track_id);
+            self.track_flags.retain(|id, _| TrackId::from(id) != track_id);
+            self.track_colors.retain(|id, _| TrackId::from(id) != track_id);
             
             // Remove from track_selections
-            self.track_selections.borrow_mut().remove(&track_id);
+            self.state.clear_selection(track_id);
             
             // Clear selection if the removed track was selected
-            *self.selected_track_id.borrow_mut() = None;
+            self.selected_track_id = None;
+        }
+    }
+    
+    /// The tempo in effect at the given absolute tick
+    fn tempo_at(&self, tick: f32) -> f32 {
+        self.state.tempo_map.as_ref().map_or(self.state.tempo_bpm, |tempo_map| tempo_map.bpm_at_tick(tick))
+    }
+    
+    /// Change the tempo of the tempo map event in effect at the given absolute tick
+    fn set_tempo_at(&mut self, tick: f32, bpm: f32) {
+        let Some(tempo_map) = &self.state.tempo_map else {
+            self.state.tempo_bpm = bpm;
+            return;
+        };
+        let mut events = tempo_map.events().to_vec();
+        let ix = events.partition_point(|e| e.0 <= tick).saturating_sub(1);
+        match events.get_mut(ix) {
+            Some(event) => event.1 = bpm,
+            None => events.push((0.0, bpm)),
         }
+        self.state.tempo_map = Some(TempoMap::new(self.state.ppqn, events));
     }
     
     /// Update playhead position based on playback state
     /// Called at the start of each frame to update playhead if playing
     /// Uses time-based calculation for frame-rate independent, smooth animation
-    fn update_playhead_position(&self, ctx: &egui::Context) {
-        let is_playing = *self.is_playing.borrow();
+    fn update_playhead_position(&mut self, ctx: &egui::Context) {
+        let play_start = &mut self.play_start;
         
-        if is_playing {
+        if self.is_playing {
             let current_time = ctx.input(|i| i.time);
-            let mut play_start_time = self.play_start_time.borrow_mut();
-            let mut play_start_playhead_pos = self.play_start_playhead_pos.borrow_mut();
+            // Positions are kept at double precision so that the playhead doesn't jitter on long timelines
+            let playhead_pos = self.state.playhead.get();
             
-            // Initialize play start time and position if not set
-            if play_start_time.is_none() {
-                *play_start_time = Some(current_time);
-                *play_start_playhead_pos = *self.playhead_pos.borrow();
+            // (Re)start from the current position when play is pressed or the user moves the playhead
+            if play_start.is_none() || playhead_pos != self.last_playhead_pos {
+                *play_start = Some((current_time, playhead_pos));
             }
             
-            // Calculate elapsed time since play started
-            if let Some(start_time) = *play_start_time {
-                let elapsed_seconds = (current_time - start_time) as f32;
+            if let Some((start_time, start_pos)) = *play_start {
+                let elapsed_seconds = current_time - start_time;
                 
-                // Calculate new playhead position: start position + elapsed time in ticks
-                let ticks_per_second = self.ticks_per_second();
-                let new_pos = *play_start_playhead_pos + (elapsed_seconds * ticks_per_second);
+                // Calculate new playhead position: start position + elapsed time, following the tempo map
+                let start_seconds = self.state.ticks_to_seconds_f64(start_pos);
+                let mut new_pos = self.state.seconds_to_ticks_f64(start_seconds + elapsed_seconds);
                 
-                // Clamp to maximum position (end of bar 500)
-                let max_pos = self.max_playhead_pos();
-                let clamped_pos = new_pos.min(max_pos);
+                // Jump back to the start of the loop region when playback reaches its end
+                if self.state.loop_enabled
+                    && let Some((loop_start, loop_end)) = self.state.loop_range
+                    && let (loop_start, loop_end) = (loop_start as f64, loop_end as f64)
+                    && loop_end > loop_start
+                    && start_pos < loop_end
+                    && new_pos >= loop_end
+                {
+                    new_pos = loop_start;
+                    *play_start = Some((current_time, loop_start));
+                }
                 
-                // Update playhead position
-                *self.playhead_pos.borrow_mut() = clamped_pos;
+                // Clamp to the end of the timeline
+                let clamped_pos = self.state.clamp_tick_f64(new_pos);
+                self.state.playhead.set(clamped_pos);
+                self.last_playhead_pos = clamped_pos;
                 
                 // Request continuous repaints for smooth animation at target FPS
                 // This creates a continuous animation loop while playing
                 ctx.request_repaint_after(std::time::Duration::from_secs_f64(1.0 / Self::TARGET_FPS));
                 
                 // If we reached the end, stop playback automatically
-                if clamped_pos >= max_pos {
-                    *self.is_playing.borrow_mut() = false;
-                    *play_start_time = None;
+                if self.state.end_tick().is_some_and(|end| clamped_pos >= end as f64) {
+                    self.is_playing = false;
+                    self.last_stop_pos = Some(clamped_pos as f32);
+                    *play_start = None;
                 }
             }
         } else {
-            // Not playing: clear play start time so it reinitializes on next play
-            *self.play_start_time.borrow_mut() = None;
+            // Not playing: clear play start so it reinitializes on next play
+            *play_start = None;
         }
     }
 }
 
+/// The playhead scrub lifecycle, as reported by the timeline's scrub events.
+#[derive(Clone, Copy)]
+enum Scrub {
+    /// The playhead hasn't been scrubbed yet.
+    Idle,
+    /// The playhead is being dragged over the given absolute tick.
+    Scrubbing(f32),
+    /// The playhead was released at the given absolute tick.
+    Ended(f32),
+}
+
+/// The number of waveform peaks per tick in the demo
+const PEAKS_PER_TICK: f32 = 1.0 / 16.0;
+
+/// Generate the min/max peaks of a sine burst that decays over each beat, for 16 bars at 960 PPQN
+fn sine_burst_peaks() -> Vec<(f32, f32)> {
+    let ticks_per_beat = 960.0;
+    let total_ticks = 16.0 * 4.0 * ticks_per_beat;
+    let peak_count = (total_ticks * PEAKS_PER_TICK) as usize;
+    (0..peak_count)
+        .map(|i| {
+            let tick = i as f32 / PEAKS_PER_TICK;
+            let beat_phase = (tick % ticks_per_beat) / ticks_per_beat;
+            let envelope = (-beat_phase * 5.0).exp();
+            let wobble = 0.75 + 0.25 * (tick * 0.01).sin();
+            let amplitude = envelope * wobble;
+            (-amplitude, amplitude)
+        })
+        .collect()
+}
+
 impl Default for TimelineApp {
     fn default() -> Self {
+        let ppqn = 960; // Standard MIDI PPQN
+        let bar = 4.0 * ppqn as f32;
+        let state = SimpleTimelineState::new()
+            .ppqn(ppqn)
+            .total_bars(Self::TOTAL_BARS)
+            // Two bars of count-in before the first bar
+            .pre_roll(2.0 * bar)
+            // 96 BPM, speeding up to 140 BPM at bar 17 and settling on 120 BPM at bar 33
+            .tempo_map(TempoMap::new(ppqn, [(0.0, 96.0), (16.0 * bar, 140.0), (32.0 * bar, 120.0)]));
+        state.add_marker(Marker::new(0.0, "Intro"));
+        state.add_marker(Marker::new(16.0 * bar, "Verse").color(egui::Color32::from_rgb(90, 170, 230)));
+        state.add_marker(Marker::new(32.0 * bar, "Chorus").color(egui::Color32::from_rgb(200, 90, 160)));
+        // The number of tracks to start with, e.g. `cargo run -- --tracks 500` to try culling
+        let track_count = std::env::args()
+            .skip_while(|arg| arg != "--tracks")
+            .nth(1)
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(2);
+        let track_ids: Vec<String> = (1..=track_count).map(|n| format!("track{n}")).collect();
         Self {
-            timeline_start: 0.0,
-            zoom_level: 1.0,
-            playhead_pos: RefCell::new(0.0),
-            ticks_per_beat: 960, // Standard MIDI PPQN
+            state,
             global_panel_visible: false,
-            track_selections: RefCell::new(HashMap::new()),
-            drag_start_tick: RefCell::new(None),
-            track_names: RefCell::new({
+            clips: HashMap::new(),
+            waveform_peaks: sine_burst_peaks(),
+            track_heights: HashMap::new(),
+            collapsed_tracks: HashSet::new(),
+            automation_points: vec![(0.0, 0.8), (4.0 * bar, 0.2), (8.0 * bar, 0.6)],
+            track_names: {
                 let mut names = HashMap::new();
-                names.insert("track1".to_string(), "Track 1".to_string());
-                names.insert("track2".to_string(), "Track 2".to_string());
+                for (i, track_id) in track_ids.iter().enumerate() {
+                    names.insert(track_id.clone(), format!("Track {}", i + 1));
+                }
                 names
-            }),
-            track_ids: RefCell::new(vec!["track1".to_string(), "track2".to_string()]),
-            pending_add_track: RefCell::new(false),
-            selected_track_id: RefCell::new(None),
-            is_playing: RefCell::new(false), // Start with Stop selected
-            play_start_time: RefCell::new(None),
-            play_start_playhead_pos: RefCell::new(0.0),
-        }
-    }
-}
-
-impl TimelineApi for TimelineApp {
-    fn musical_ruler_info(&self) -> &dyn MusicalInfo {
-        self
-    }
-
-    fn timeline_start(&self) -> f32 {
-        self.timeline_start
-    }
-
-    fn shift_timeline_start(&mut self, ticks: f32) {
-        // Apply the shift - clamping is handled in the interaction handler
-        // where we have access to the visible width to calculate proper max
-        self.timeline_start += ticks;
-    }
-
-    fn zoom(&mut self, y_delta: f32) {
-        self.zoom_level = (self.zoom_level * (1.0 + y_delta * 0.01)).max(0.1).min(3.0);
-    }
-}
-
-impl MusicalInfo for TimelineApp {
-    fn ticks_per_beat(&self) -> u32 {
-        self.ticks_per_beat
-    }
-
-    fn timeline_start(&self) -> Option<f32> {
-        Some(self.timeline_start)
-    }
-
-    fn bar_at_ticks(&self, tick: f32) -> Bar {
-        let absolute_tick = self.timeline_start + tick;
-        let ticks_per_bar = self.ticks_per_bar();
-        let mut bar_number = (absolute_tick / ticks_per_bar).floor() as u32;
-        
-        // Clamp bar number to 0-500
-        bar_number = bar_number.min(Self::TOTAL_BARS - 1);
-        
-        let bar_start = bar_number as f32 * ticks_per_bar;
-        let bar_end = bar_start + ticks_per_bar;
-        Bar {
-            tick_range: Range {
-                start: bar_start - self.timeline_start,
-                end: bar_end - self.timeline_start,
             },
-            time_sig: TimeSig { top: 4, bottom: 4 },
-        }
-    }
-
-    fn ticks_per_point(&self) -> f32 {
-        (self.ticks_per_beat as f32 / 16.0) * self.zoom_level
-    }
-}
-
-impl MusicalInteract for TimelineApp {
-    fn click_at_tick(&mut self, tick: f32) {
-        *self.playhead_pos.borrow_mut() = self.timeline_start + tick;
-    }
-}
-
-impl MusicalRuler for TimelineApp {
-    fn info(&self) -> &dyn MusicalInfo {
-        self
-    }
-
-    fn interact(&mut self) -> &mut dyn MusicalInteract {
-        self
-    }
-}
-
-impl Info for TimelineApp {
-    fn playhead_ticks(&self) -> f32 {
-        *self.playhead_pos.borrow() - self.timeline_start
-    }
-}
-
-impl Interaction for TimelineApp {
-    fn set_playhead_ticks(&self, ticks: f32) {
-        let new_pos = self.timeline_start + ticks;
-        *self.playhead_pos.borrow_mut() = new_pos;
-        
-        // If playing and user drags playhead, reset play start to continue from new position
-        // We'll handle this in update_playhead_position by checking if play_start_time is None
-        if *self.is_playing.borrow() {
-            *self.play_start_playhead_pos.borrow_mut() = new_pos;
-            // Reset play start time so it reinitializes with current time on next update
-            *self.play_start_time.borrow_mut() = None;
+            track_flags: HashMap::new(),
+            track_colors: HashMap::new(),
+            track_ids,
+            pending_add_track: false,
+            selected_track_id: None,
+            is_playing: false, // Start with Stop selected
+            last_stop_pos: None,
+            play_start: None,
+            last_playhead_pos: 0.0,
+            tool_mode: ToolMode::Select,
+            scrub: Scrub::Idle,
         }
     }
 }
 
-impl TrackSelectionApi for TimelineApp {
-    fn ticks_per_point(&self) -> f32 {
-        (self.ticks_per_beat as f32 / 16.0) * self.zoom_level
-    }
-
-    fn timeline_start(&self) -> f32 {
-        self.timeline_start
-    }
-
-    fn start_selection_drag(&self, track_id: &str, start_tick: f32) {
-        *self.drag_start_tick.borrow_mut() = Some((track_id.to_string(), start_tick));
-    }
-
-    fn update_selection_drag(&self, track_id: &str, end_tick: f32) {
-        if let Some((drag_track_id, start_tick)) = self.drag_start_tick.borrow().as_ref() {
-            if drag_track_id == track_id {
-                let start = start_tick.min(end_tick);
-                let end = start_tick.max(end_tick);
-                self.track_selections.borrow_mut().insert(track_id.to_string(), (start, end));
-            }
+/// The contents of the pop-up panel above the bottom bar, divided into 6 columns.
+fn global_panel(ui: &mut egui::Ui) {
+    const COLUMNS: usize = 6;
+    let panel_rect = ui.max_rect();
+    let column_width = panel_rect.width() / COLUMNS as f32;
+    ui.horizontal(|ui| {
+        for column in 0..COLUMNS {
+            ui.vertical(|ui| {
+                ui.set_width(column_width);
+                // Column 1: "Global Panel" label, remaining columns are available for widgets
+                if column == 0 {
+                    ui.label("Global Panel");
+                }
+            });
         }
-    }
+    });
 
-    fn get_drag_start(&self) -> Option<(String, f32)> {
-        self.drag_start_tick.borrow().clone()
-    }
-
-    fn end_selection_drag(&self) {
-        *self.drag_start_tick.borrow_mut() = None;
-    }
-
-    fn set_selection(&self, track_id: &str, start_tick: f32, end_tick: f32) {
-        self.track_selections.borrow_mut().insert(track_id.to_string(), (start_tick, end_tick));
-    }
-
-    fn clear_selection(&self, track_id: &str) {
-        self.track_selections.borrow_mut().remove(track_id);
-    }
-
-    fn clear_all_selections(&self) {
-        self.track_selections.borrow_mut().clear();
-    }
-
-    fn get_selection(&self, track_id: &str) -> Option<(f32, f32)> {
-        self.track_selections.borrow().get(track_id).copied()
-    }
-
-    fn get_selected_track_id(&self) -> Option<String> {
-        self.track_selections.borrow().keys().next().cloned()
+    // Draw 1px grey vertical borders between columns (100% height)
+    let grey_border = egui::Stroke::new(1.0, egui::Color32::from_rgb(128, 128, 128));
+    for i in 1..COLUMNS {
+        let x = panel_rect.min.x + column_width * i as f32;
+        let top = egui::Pos2::new(x, panel_rect.min.y);
+        let bottom = egui::Pos2::new(x, panel_rect.max.y);
+        ui.painter().line_segment([top, bottom], grey_border);
     }
 }
 
@@ -344,48 +307,160 @@ impl eframe::App for TimelineApp {
 
             ui.add_space(10.0);
 
-            // Create and show the timeline
-            let timeline = Timeline::new().header(150.0);
-            let show = timeline.show(ui, self);
+            // Show where playback last stopped as a ghost playhead
+            let ghost_playheads: Vec<GhostPlayhead> = self
+                .last_stop_pos
+                .map(|tick| GhostPlayhead::new(tick, egui::Color32::from_gray(140)).label("Last Stop"))
+                .into_iter()
+                .collect();
+
+            // Only follow the playhead while playing so the view can be scrolled freely when stopped
+            let follow_mode = if self.is_playing {
+                FollowMode::Page
+            } else {
+                FollowMode::Off
+            };
 
-            show.paint_grid(self)
+            // Create and show the timeline
+            let timeline = Timeline::new()
+                .header(150.0)
+                .header_resizable(100.0, 300.0)
+                .top_panel(40.0)
+                .bottom_bar(20.0)
+                .snap(SnapMode::Beat)
+                .tool_mode(self.tool_mode)
+                .hover_indicator(true)
+                .elastic_overscroll(true)
+                // Keep scrolling briefly after a fast fling or middle-button drag
+                .scroll_settings(ScrollSettings::default().kinetic(true))
+                .pre_roll(self.state.pre_roll);
+            // The widgets report their changes as events, applied to the state once the frame is done
+            let mut events = EventCollector::new(ui.ctx(), timeline.id(ui), &self.state, self.state.timeline_start as f32)
+                .timeline_end(self.state.end_tick())
+                .playhead(self.state.playhead.get() as f32)
+                .selections(self.state.all_selections())
+                .loop_region(self.state.loop_range, self.state.loop_enabled)
+                .time_selection(self.state.time_selection);
+            let show = timeline.show(ui, &mut events);
+
+            // Tint the intro and outro sections, which should line up with the bar lines.
+            let ticks_per_bar = self.state.ticks_per_bar();
+            let end_tick = self.state.end_tick().unwrap_or(0.0);
+            let tint = ui.visuals().selection.bg_fill.linear_multiply(0.05);
+            // The transport bar toggles playback and looping
+            let mut transport = TransportState {
+                playing: self.is_playing,
+                looping: self.state.loop_enabled,
+                ..Default::default()
+            };
+            let transport_before = transport;
+            // The tempo at the playhead and the time signature, edited in the top panel
+            let playhead_tick = self.state.playhead.get() as f32;
+            let mut bpm = self.tempo_at(playhead_tick);
+            let mut time_sig = self.state.time_sig;
+            // Pressing Z fits the selections to the view.
+            let selection = view::selection_range(&events);
+            // Tracks are added and removed once the frame is done
+            let mut remove_track = false;
+            let (set_playhead, double_clicked_clip) = show.keyboard_shortcuts(&KeyBindings::default(), &mut events, selection, || {
+                    self.is_playing = !self.is_playing;
+                })
+                .background(|bg, ui| {
+                    bg.paint_absolute_tick_range(ui, 0.0..ticks_per_bar * 4.0, tint);
+                    bg.paint_absolute_tick_range(ui, end_tick - ticks_per_bar * 4.0..end_tick, tint);
+                })
+                .paint_bar_shading(&events, BarShadingStyle::from_visuals(ui.visuals()).every(4))
+                .paint_grid(&events)
                 .pinned_tracks(|tracks, ui| {
-                    // Ruler track
+                    // Musical ruler track
                     tracks.next(ui).header(|ui| {
-                        ui.label("Ruler");
+                        ui.label("Bars");
                     }).show(
-                        |_timeline, ui| {
-                            musical(ui, self);
+                        |timeline, ui| {
+                            let ruler = Ruler::new().mode(RulerMode::BarsBeats).snap(timeline.snap);
+                            // The ruler borrows the events, so apply the chosen action afterwards
+                            let mut add_marker_at = None;
+                            musical_with_menu(ui, &mut events, ruler, |ui, tick| {
+                                if ui.button("Add marker").clicked() {
+                                    add_marker_at = Some(tick);
+                                    ui.close_menu();
+                                }
+                            });
+                            if let Some(tick) = add_marker_at {
+                                self.state.add_marker(Marker::new(tick, "Marker"));
+                            }
                         },
                         None,
                         None,
-                        None::<fn(String)>, // No track click handler for ruler
+                        None::<fn(TrackId)>, // No track click handler for ruler
                         false, // Ruler is never selected
                     );
+                    // Time ruler track, aligned with the musical ruler above
+                    tracks.next(ui).header(|ui| {
+                        ui.label("Time");
+                    }).show(
+                        |timeline, ui| {
+                            ruler::time(ui, &mut events, TimeRulerStyle::new().snap(timeline.snap));
+                        },
+                        None,
+                        None,
+                        None::<fn(TrackId)>,
+                        false,
+                    );
+                })
+                .pinned_tracks_bottom(|tracks, ui| {
+                    // Master track pinned beneath the scrollable tracks
+                    tracks.next(ui).fixed_height(24.0).header(|ui| {
+                        ui.label("Master");
+                    }).show(
+                        |_timeline, _ui| {},
+                        None,
+                        None,
+                        None::<fn(TrackId)>,
+                        false,
+                    );
                 })
                 .tracks(
                     |tracks, _viewport, ui, playhead_api, selection_api| {
-                    // Collect track data into local Vecs to drop RefCell borrows early
-                    // This prevents borrow conflicts when the "Add Track" button is clicked
-                    let track_ids_vec: Vec<String> = {
-                        let track_ids = self.track_ids.borrow();
-                        track_ids.clone()
-                    };
-                    let track_names_map: HashMap<String, String> = {
-                        let track_names = self.track_names.borrow();
-                        track_names.clone()
-                    };
+                    let track_ids_vec = &self.track_ids;
                     
                     // Get selected track ID before the loop
-                    let selected_track_id = self.selected_track_id.borrow().clone();
+                    let selected_track_id = self.selected_track_id;
+                    
+                    // The absolute start tick of a clip double-clicked on any track
+                    let mut double_clicked_clip = None;
                     
                     for track_id in track_ids_vec.iter() {
-                        let track_name = track_names_map.get(track_id).cloned().unwrap_or_else(|| format!("Track {}", track_id));
                         let track_id_clone = track_id.clone();
-                        let is_selected = selected_track_id.as_ref() == Some(track_id);
+                        let is_selected = selected_track_id == Some(TrackId::from(track_id));
                         
-                        tracks.next(ui)
+                        let track_height = self.track_heights.get(track_id).copied().unwrap_or(40.0);
+                        let is_collapsed = self.collapsed_tracks.contains(track_id);
+                        let track_color = self.track_colors.get(track_id).copied().unwrap_or(Self::DEFAULT_TRACK_COLOR);
+                        let track = tracks.next(ui)
                             .with_id(track_id_clone.as_str())
+                            .color(track_color)
+                            .resizable(24.0, 160.0)
+                            .collapsible(is_collapsed)
+                            .header_height(HeaderSizing::MatchContent)
+                            .context_menu(|ui, _tick, _track_id| {
+                                // Right-click no longer clears selections on tracks with a menu
+                                if ui.button("Clear selections").clicked() {
+                                    if let Some(api) = selection_api {
+                                        api.clear_all_selections();
+                                    }
+                                    ui.close_menu();
+                                }
+                            });
+
+                        // Skip the header and contents of tracks scrolled out of view
+                        if !track.is_visible() {
+                            track.skip();
+                            continue;
+                        }
+                        let track_name = self.track_names.get(track_id).cloned().unwrap_or_else(|| format!("Track {}", track_id));
+
+                        let track_response = track
                             .header(|ui| {
                                 ui.add_space(2.0); // Top padding
                                 let available_width = ui.available_width();
@@ -409,51 +484,250 @@ impl eframe::App for TimelineApp {
                                 let text_response = ui.put(rect, text_edit);
                                 
                                 if text_response.changed() {
-                                    self.track_names.borrow_mut().insert(track_id_clone.clone(), name);
+                                    self.track_names.insert(track_id_clone.clone(), name);
                                 }
+
+                                // Colour swatch and mute/solo/record-arm buttons
+                                ui.horizontal(|ui| {
+                                    let color = self.track_colors.entry(track_id_clone.clone()).or_insert(Self::DEFAULT_TRACK_COLOR);
+                                    color_swatch(ui, color);
+                                    track_buttons(ui, self.track_flags.entry(track_id_clone.clone()).or_default());
+                                });
                             })
                             .show(
-                                |_timeline, ui| {
-                                    // Track content area - ready for custom track data rendering
-                                    // Allocate 40px height to ensure track is interactive for selection
-                                    ui.add_space(40.0);
+                                |timeline, ui| {
+                                    // Track content area - allocate the track height to ensure track is interactive for selection
+                                    let (track_rect, _) = ui.allocate_exact_size(
+                                        egui::Vec2::new(ui.available_width(), track_height),
+                                        egui::Sense::hover(),
+                                    );
+                                    
+                                    // Show a waveform on the first track, behind its clips
+                                    if track_ids_vec.first() == Some(track_id) {
+                                        let style = WaveformStyle::from_visuals(ui.visuals());
+                                        waveform(ui, timeline, track_rect, &self.waveform_peaks, PEAKS_PER_TICK, &style);
+                                    }
+                                    
+                                    // Show an editable automation lane on the second track instead of clips
+                                    if track_ids_vec.get(1) == Some(track_id) {
+                                        automation::lane(ui, track_id, timeline, &events, track_rect, &mut self.automation_points, 0.0..=1.0);
+                                        return None;
+                                    }
+                                    
+                                    // Show the track's visible clips, applying any moves or resizes
+                                    let all_clips = &mut self.clips;
+                                    let clips = all_clips.entry(track_id.clone()).or_insert_with(|| Self::default_clips(&self.state));
+                                    let clip_style = ClipStyle::from_visuals(ui.visuals())
+                                        .fill(track_color.linear_multiply(0.5))
+                                        .stroke(egui::Stroke::new(1.0, track_color));
+                                    let visible = timeline.absolute_visible_range();
+                                    let mut moved_to_tracks = vec![];
+                                    let mut double_clicked = None;
+                                    for (ix, (start, end)) in clips.iter_mut().enumerate() {
+                                        if *end < visible.start || *start > visible.end {
+                                            continue;
+                                        }
+                                        let clip_rect = track_rect.shrink2(egui::Vec2::new(0.0, 4.0));
+                                        let res = clip(ui, (track_id, ix), timeline, &events, clip_rect, *start, *end, &clip_style);
+                                        if let Some(to_track) = res.moved_to_track {
+                                            moved_to_tracks.push((ix, to_track));
+                                        }
+                                        if res.double_clicked {
+                                            double_clicked = Some(*start);
+                                        }
+                                        if let Some(delta) = res.moved_by_ticks {
+                                            *start += delta;
+                                            *end += delta;
+                                        }
+                                        if let Some(new_start) = res.resized_start_to {
+                                            *start = new_start;
+                                        }
+                                        if let Some(new_end) = res.resized_end_to {
+                                            *end = new_end;
+                                        }
+                                    }
+                                    
+                                    // Move clips dropped on other tracks, last first so that indices stay valid
+                                    let moved: Vec<_> = moved_to_tracks
+                                        .into_iter()
+                                        .rev()
+                                        .map(|(ix, to_track)| (to_track, clips.remove(ix)))
+                                        .collect();
+                                    for (to_track, moved_clip) in moved {
+                                        if let Some(to_id) = track_ids_vec.iter().find(|id| TrackId::from(*id) == to_track) {
+                                            all_clips.entry(to_id.clone()).or_insert_with(|| Self::default_clips(&self.state)).push(moved_clip);
+                                        }
+                                    }
+                                    double_clicked
                                 },
                                 playhead_api,
                                 selection_api,
-                                Some({
-                                    let selected_track_id_ref = &self.selected_track_id;
-                                    move |track_id: String| {
-                                        // Set this track as selected
-                                        *selected_track_id_ref.borrow_mut() = Some(track_id);
-                                    }
+                                Some(|track_id: TrackId| {
+                                    // Set this track as selected
+                                    self.selected_track_id = Some(track_id);
                                 }),
                                 is_selected,
                             );
+                        
+                        // Flip the collapsed state when the header chevron is clicked
+                        if track_response.collapse_toggled && !self.collapsed_tracks.remove(track_id) {
+                            self.collapsed_tracks.insert(track_id.clone());
+                        }
+                        
+                        // Remember the new height so the track content fills the resized track
+                        if let Some(new_height) = track_response.new_height {
+                            self.track_heights.insert(track_id.clone(), new_height);
+                        }
+                        
+                        double_clicked_clip = double_clicked_clip.or(track_response.inner.flatten());
                     }
+                    double_clicked_clip
                     },
-                    Some(self as &dyn PlayheadApi),
-                    Some(self as &dyn TrackSelectionApi),
-                )
-                .playhead(ui, self, Playhead::new())
-                .top_panel_time(
+                    Some(&events as &dyn PlayheadApi),
+                    Some(&events as &dyn TrackSelectionApi),
+                );
+            
+            // Double-clicking a clip moves the playhead to its start
+            if let Some(start) = double_clicked_clip {
+                events.set_playhead_ticks(start - TimelineApi::timeline_start(&events));
+            }
+            set_playhead
+                .loop_region(ui, &events, &events)
+                .time_selection(ui, &events, &events)
+                .markers(ui, &self.state)
+                // Dropping files onto a track adds a one bar clip, or a new track beneath the last
+                .drop_target::<()>(ui, &events, |track_id, tick, _payload| match track_id {
+                    Some(track_id) => {
+                        let bar = self.state.ticks_per_bar();
+                        if let Some(id) = self.track_ids.iter().find(|id| TrackId::from(*id) == track_id) {
+                            self.clips.entry(id.clone()).or_insert_with(|| Self::default_clips(&self.state)).push((tick, tick + bar));
+                        }
+                    }
+                    None => self.pending_add_track = true,
+                })
+                .ghost_playheads(ui, &events, &ghost_playheads)
+                .playhead(ui, &mut events, Playhead::new().handle(PlayheadHandle::Triangle).through_pinned_bottom(true))
+                .follow_playhead(ui, &mut events, follow_mode)
+                .top_panel(
                     ui,
-                    Some(self as &dyn PlayheadApi),
-                    || *self.is_playing.borrow(), // Get is_playing
-                    |val| *self.is_playing.borrow_mut() = val, // Set is_playing
-                    {
-                        // Get track count without holding borrow
-                        let count = self.track_ids.borrow().len();
-                        count
-                    }, // Track count
-                    self.max_playhead_pos(), // Maximum absolute playhead position (bar 500)
-                    || self.request_add_track(), // Add track callback
-                    || self.remove_selected_track(), // Remove track callback
-                    || self.selected_track_id.borrow().is_some(), // Has selected track
+                    TopPanel::new()
+                        .transport(&mut transport)
+                        .playhead(&events)
+                        .time_display(TimeDisplay::BarsBeatsTicks)
+                        // The demo doesn't record
+                        .hide(TransportButton::Record)
+                        .extra(|ui| {
+                            bpm_drag_value(ui, &mut bpm);
+                            time_sig_selector(ui, &mut time_sig);
+                            ui.add_space(4.0);
+                            // Move the playhead to the end of the last bar
+                            if ui.button(">").on_hover_text("Go to end").clicked() {
+                                let timeline_start = TimelineApi::timeline_start(&events);
+                                events.set_playhead_ticks(self.state.end_tick().unwrap_or(0.0) - timeline_start);
+                            }
+                            // A long dropdown over the timeline, which must not scroll while its list scrolls
+                            egui::ComboBox::from_id_salt("go_to_bar")
+                                .selected_text("Go to bar")
+                                .show_ui(ui, |ui| {
+                                    for bar in 1..=Self::TOTAL_BARS {
+                                        if ui.selectable_label(false, bar.to_string()).clicked() {
+                                            let timeline_start = TimelineApi::timeline_start(&events);
+                                            let tick = (bar - 1) as f32 * self.state.ticks_per_bar();
+                                            events.set_playhead_ticks(tick - timeline_start);
+                                        }
+                                    }
+                                });
+                            ui.add_space(4.0);
+                            if ui.button(format!("Add Track ({})", self.track_ids.len())).clicked() {
+                                self.pending_add_track = true;
+                            }
+                            ui.add_space(4.0);
+                            // Only enabled when a track is selected
+                            let has_selection = self.selected_track_id.is_some();
+                            if ui.add_enabled(has_selection, egui::Button::new("Remove Track")).clicked() {
+                                remove_track = true;
+                            }
+                        }),
                 )
-                .bottom_bar(ui, &mut self.global_panel_visible);
-
+                .top_panel_tools(ui, &mut self.tool_mode, &[ToolMode::Select, ToolMode::Scrub])
+                .bottom_bar_with_panel(
+                    ui,
+                    &mut self.global_panel_visible,
+                    |ui, panel_visible| {
+                        // Toggle the global panel
+                        if ui.button("Global").clicked() {
+                            *panel_visible = !*panel_visible;
+                        }
+                        
+                        // Add a marker at the playhead
+                        if ui.button("Add Marker").clicked() {
+                            let label = format!("Marker {}", self.state.markers().len() + 1);
+                            self.state.add_marker(Marker::new(events.playhead_ticks() + TimelineApi::timeline_start(&events), label));
+                        }
+
+                        // Show the scrub lifecycle, which ends even when the playhead is released outside the timeline
+                        match self.scrub {
+                            Scrub::Idle => {}
+                            Scrub::Scrubbing(tick) => {
+                                ui.label(format!("Scrubbing at tick {tick:.0}"));
+                            }
+                            Scrub::Ended(tick) => {
+                                ui.label(format!("Scrub ended at tick {tick:.0}"));
+                            }
+                        }
+
+                        // Choose the snap mode, remembered by the timeline
+                        set_playhead.snap_selector(ui);
+
+                        // Zoom via the slider, which follows Ctrl + scroll
+                        let zoom = self.state.zoom_settings();
+                        zoom_slider(ui, &mut events, zoom.min_ticks_per_point..=zoom.max_ticks_per_point);
+
+                        // Scroll and zoom via the overview scrollbar
+                        set_playhead.scrollbar(ui, &mut events);
+                    },
+                    global_panel,
+                );
+
+            // Apply the changes made via the timeline, following the scrub lifecycle in the bottom bar
+            let events = events.into_events();
+            for event in &events {
+                match *event {
+                    TimelineEvent::ScrubStarted => self.scrub = Scrub::Scrubbing(self.state.playhead.get() as f32),
+                    TimelineEvent::Scrubbed(tick) => self.scrub = Scrub::Scrubbing(tick),
+                    TimelineEvent::ScrubEnded(tick) => self.scrub = Scrub::Ended(tick),
+                    _ => {}
+                }
+            }
+            if !events.is_empty() {
+                // Show the changes straight away rather than on the next input
+                ui.ctx().request_repaint();
+            }
+            self.state.apply_events(events);
+            if remove_track {
+                self.remove_selected_track();
+            }
 
-            
+            // Apply the buttons pressed this frame, leaving changes made via keyboard shortcuts
+            if transport.playing != transport_before.playing {
+                // Remember where playback stopped
+                if !transport.playing {
+                    self.last_stop_pos = Some(self.state.playhead.get() as f32);
+                }
+                self.is_playing = transport.playing;
+            }
+            // Tempo and time signature changes show in the ruler and grid on the next frame
+            if bpm != self.tempo_at(playhead_tick) {
+                self.set_tempo_at(playhead_tick, bpm);
+                ui.ctx().request_repaint();
+            }
+            if time_sig != self.state.time_sig {
+                self.state.time_sig = time_sig;
+                ui.ctx().request_repaint();
+            }
+            if transport.looping != transport_before.looping {
+                self.state.loop_enabled = transport.looping;
+            }
         });
     }
 }
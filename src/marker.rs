@@ -0,0 +1,167 @@
+use crate::{
+    ruler::MusicalInfo,
+    snap::{self, SnapMode},
+};
+use egui::Color32;
+
+/// A named position on the timeline, also known as a cue point.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Marker {
+    /// The position of the marker in absolute ticks.
+    pub tick: f32,
+    /// The name shown on the marker's flag.
+    pub label: String,
+    /// The colour of the marker's flag and line.
+    pub color: Color32,
+}
+
+impl Marker {
+    /// The colour used by `Marker::new`.
+    pub const DEFAULT_COLOR: Color32 = Color32::from_rgb(230, 160, 60);
+
+    /// Create a marker at the given absolute tick with the default colour.
+    pub fn new(tick: f32, label: impl Into<String>) -> Self {
+        Self {
+            tick,
+            label: label.into(),
+            color: Self::DEFAULT_COLOR,
+        }
+    }
+
+    /// Set the colour of the marker's flag and line.
+    pub fn color(mut self, color: Color32) -> Self {
+        self.color = color;
+        self
+    }
+}
+
+/// Access to the host's markers.
+///
+/// Markers are identified by their index within the list returned by `markers`.
+pub trait MarkerApi: MusicalInfo {
+    /// All markers on the timeline.
+    fn markers(&self) -> Vec<Marker>;
+    /// Add the given marker.
+    fn add_marker(&self, marker: Marker);
+    /// Move the marker at the given index to the given absolute tick.
+    fn move_marker(&self, index: usize, tick: f32);
+    /// Remove the marker at the given index.
+    fn remove_marker(&self, index: usize);
+    /// Change the label of the marker at the given index.
+    fn rename_marker(&self, index: usize, label: String);
+}
+
+/// The height of the flag drawn at the top of each marker.
+const FLAG_HEIGHT: f32 = 14.0;
+/// The width of a flag whose label has been culled.
+const FLAG_MIN_WIDTH: f32 = 6.0;
+/// The minimum gap between labels before later labels are hidden.
+const LABEL_GAP: f32 = 4.0;
+
+/// Draw the markers as flags along the top of the timeline with lines extending down through
+/// the tracks, and handle dragging, renaming and removing them.
+pub(crate) fn show(
+    ui: &mut egui::Ui,
+    api: &dyn MarkerApi,
+    id: egui::Id,
+    timeline_rect: egui::Rect,
+    tracks_bottom: f32,
+    snap: SnapMode,
+) {
+    let ticks_per_point = api.ticks_per_point();
+    let timeline_start = api.timeline_start().unwrap_or(0.0);
+    let font = egui::TextStyle::Small.resolve(ui.style());
+
+    // Lay out from left to right so that overlapping labels can be culled.
+    let mut markers: Vec<(usize, Marker)> = api.markers().into_iter().enumerate().collect();
+    markers.sort_by(|a, b| a.1.tick.total_cmp(&b.1.tick));
+
+    let mut last_label_right = f32::NEG_INFINITY;
+    for (index, marker) in markers {
+        let x = timeline_rect.left() + (marker.tick - timeline_start) / ticks_per_point;
+        if x < timeline_rect.left() || x > timeline_rect.right() {
+            continue;
+        }
+
+        // Only show the label if it does not overlap the previous one.
+        let galley = ui.painter().layout_no_wrap(marker.label.clone(), font.clone(), text_color(marker.color));
+        let label_w = galley.size().x + 4.0;
+        let show_label = x >= last_label_right + LABEL_GAP && x + label_w <= timeline_rect.right();
+        let flag_w = if show_label { label_w } else { FLAG_MIN_WIDTH };
+        if show_label {
+            last_label_right = x + label_w;
+        }
+
+        let flag_rect = egui::Rect::from_min_size(
+            egui::Pos2::new(x, timeline_rect.top()),
+            egui::Vec2::new(flag_w, FLAG_HEIGHT),
+        );
+        let marker_id = id.with("marker").with(index);
+        let response = ui
+            .interact(flag_rect, marker_id, egui::Sense::click_and_drag())
+            .on_hover_cursor(egui::CursorIcon::ResizeHorizontal)
+            .on_hover_text(&marker.label);
+
+        // Drag the marker horizontally.
+        if response.dragged_by(egui::PointerButton::Primary)
+            && let Some(pos) = response.interact_pointer_pos()
+        {
+            let tick = ((pos.x - timeline_rect.left()) * ticks_per_point).max(-timeline_start);
+            let snap = snap.with_modifiers(ui.input(|i| i.modifiers));
+            let tick = timeline_start + snap::snap_tick(tick, api, snap);
+            if tick != marker.tick {
+                api.move_marker(index, tick);
+            }
+        }
+
+        // Offer rename and delete via a context menu.
+        let rename_id = marker_id.with("rename");
+        response.context_menu(|ui| {
+            let mut label = ui
+                .data(|d| d.get_temp::<String>(rename_id))
+                .unwrap_or_else(|| marker.label.clone());
+            let edit = ui.text_edit_singleline(&mut label);
+            let submitted = edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if submitted || ui.button("Rename").clicked() {
+                api.rename_marker(index, label);
+                ui.data_mut(|d| d.remove::<String>(rename_id));
+                ui.close_menu();
+            } else {
+                ui.data_mut(|d| d.insert_temp(rename_id, label));
+            }
+            if ui.button("Delete").clicked() {
+                api.remove_marker(index);
+                ui.data_mut(|d| d.remove::<String>(rename_id));
+                ui.close_menu();
+            }
+        });
+
+        // Paint the line through the tracks and the flag on top, above the tracks' contents.
+        let painter = crate::playhead::foreground_painter(ui);
+        let line_x = painter.round_to_pixel_center(x);
+        let line_top = egui::Pos2::new(line_x, timeline_rect.top());
+        let line_bottom = egui::Pos2::new(line_x, tracks_bottom);
+        painter.line_segment([line_top, line_bottom], egui::Stroke::new(1.0, marker.color));
+        let fill = if response.hovered() || response.dragged() {
+            marker.color
+        } else {
+            marker.color.linear_multiply(0.8)
+        };
+        painter.rect_filled(flag_rect, 0.0, fill);
+        if show_label {
+            let text_pos = egui::Pos2::new(x + 2.0, flag_rect.center().y - galley.size().y * 0.5);
+            painter.galley(text_pos, galley, Color32::PLACEHOLDER);
+        }
+    }
+}
+
+/// A text colour that is legible on top of the given fill.
+fn text_color(fill: Color32) -> Color32 {
+    let luminance = 0.299 * fill.r() as f32 + 0.587 * fill.g() as f32 + 0.114 * fill.b() as f32;
+    if luminance > 128.0 {
+        Color32::BLACK
+    } else {
+        Color32::WHITE
+    }
+}
@@ -0,0 +1,63 @@
+use crate::{playhead::FollowApi, scrollbar, style::TimelineStyle};
+
+/// Relevant information for drawing a summary of the whole timeline within the minimap.
+pub struct MinimapCtx {
+    /// The area of the minimap.
+    pub rect: egui::Rect,
+    /// The absolute range of ticks spanned by the minimap.
+    pub tick_range: std::ops::Range<f32>,
+}
+
+impl MinimapCtx {
+    /// The x position of the given absolute tick within the minimap.
+    pub fn tick_to_x(&self, tick: f32) -> f32 {
+        let len = self.tick_range.end - self.tick_range.start;
+        self.rect.left() + (tick - self.tick_range.start) / len * self.rect.width()
+    }
+}
+
+/// Show a minimap of the whole timeline, overlaid with the visible range and the playhead.
+///
+/// `draw` paints a summary of the tracks within the given `MinimapCtx`. The visible range of a
+/// timeline `timeline_width` points wide may be dragged to scroll and its ends dragged to zoom,
+/// as with the scrollbar. The minimap starts at `-pre_roll`.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn show(
+    ui: &mut egui::Ui,
+    id: egui::Id,
+    height: f32,
+    timeline_width: f32,
+    pre_roll: f32,
+    api: &mut dyn FollowApi,
+    style: &TimelineStyle,
+    draw: impl FnOnce(&MinimapCtx, &mut egui::Ui),
+) -> egui::Response {
+    let size = egui::Vec2::new(ui.available_width(), height);
+    let (rect, response) = ui.allocate_exact_size(size, egui::Sense::click_and_drag());
+    // The playhead is relative to the view, so look it up before scrolling.
+    let playhead_ticks = (crate::TimelineApi::timeline_start_f64(api) + api.playhead_ticks_f64()) as f32;
+    let drag_id = id.with("minimap_drag");
+    let Some(viewport) = scrollbar::viewport(ui, drag_id, rect, &response, timeline_width, pre_roll, api) else {
+        return response;
+    };
+
+    // The host's summary of the tracks.
+    let visuals = ui.visuals();
+    ui.painter().rect(rect, 0.0, visuals.extreme_bg_color, visuals.widgets.noninteractive.bg_stroke);
+    let viewport_stroke = if viewport.dragged || viewport.hovered {
+        visuals.widgets.active.fg_stroke
+    } else {
+        visuals.widgets.inactive.fg_stroke
+    };
+    let ctx = MinimapCtx { rect, tick_range: viewport.tick_range.clone() };
+    let mut minimap_ui = ui.new_child(egui::UiBuilder::new().max_rect(rect));
+    minimap_ui.set_clip_rect(rect.intersect(ui.clip_rect()));
+    draw(&ctx, &mut minimap_ui);
+
+    // The visible range and the playhead.
+    let painter = ui.painter_at(rect);
+    painter.rect(viewport.rect, 0.0, style.selection_fill, viewport_stroke);
+    let playhead_x = ctx.tick_to_x(playhead_ticks);
+    painter.vline(playhead_x, rect.y_range(), style.playhead_stroke);
+    response
+}
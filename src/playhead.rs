@@ -1,31 +1,115 @@
 use super::ruler::MusicalInfo;
+use crate::{
+    snap::{self, SnapMode},
+    time_display::{self, TimeDisplay},
+};
 
 /// For retrieving information about the playhead.
 pub trait Info: MusicalInfo {
     /// The location of the playhead in ticks relative to the start of the timeline.
     fn playhead_ticks(&self) -> f32;
+    /// The same as `playhead_ticks`, but at double precision.
+    ///
+    /// Hosts storing the playhead as `f64`, e.g. alongside `TimelineApi::timeline_start_f64`,
+    /// should implement this along with `Interaction::set_playhead_ticks_f64` so that the
+    /// playhead does not jitter far from tick zero.
+    fn playhead_ticks_f64(&self) -> f64 {
+        self.playhead_ticks() as f64
+    }
 }
 
 /// For handling interaction with the playhead.
 pub trait Interaction {
     /// Set the location of the playhead in ticks.
     fn set_playhead_ticks(&self, ticks: f32);
+    /// The same as `set_playhead_ticks`, but at double precision.
+    fn set_playhead_ticks_f64(&self, ticks: f64) {
+        self.set_playhead_ticks(ticks as f32);
+    }
+    /// The user pressed on the playhead to begin scrubbing, e.g. to pause the transport.
+    fn on_scrub_start(&self) {}
+    /// The playhead was dragged to the given tick while scrubbing.
+    ///
+    /// Called after `set_playhead_ticks`, with ticks relative to the start of the timeline view.
+    fn on_scrub(&self, _tick: f32) {}
+    /// The user released the playhead at the given tick, relative to the start of the view.
+    ///
+    /// Called even if the pointer was released outside of the timeline.
+    fn on_scrub_end(&self, _tick: f32) {}
+    /// A seek was clamped to the end of the timeline given by `TimelineApi::timeline_end`, or the
+    /// playhead passed the end while following it via `follow`.
+    ///
+    /// Called after `set_playhead_ticks`, e.g. for the host to stop its transport.
+    fn on_reached_end(&self) {}
 }
 
 /// For both providing info and handling interaction.
 pub trait PlayheadApi: Info + Interaction {}
 
+/// For following the playhead by scrolling the timeline.
+pub trait FollowApi: PlayheadApi + crate::TimelineApi {}
+
+/// How the view follows the playhead as it moves.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum FollowMode {
+    /// The view never moves on its own.
+    #[default]
+    Off,
+    /// When the playhead leaves the visible range, jump by whole pages to bring it back.
+    Page,
+    /// Scroll every frame so that the playhead stays at the given fraction of the width.
+    Continuous(f32),
+}
+
+impl FollowMode {
+    /// Continuously scroll so that the playhead stays at the center of the view.
+    pub const CENTERED: Self = Self::Continuous(0.5);
+}
+
+/// The shape of the handle drawn at the top of the playhead line.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum PlayheadHandle {
+    /// No handle, only the line.
+    #[default]
+    None,
+    /// A downward pointing triangle.
+    Triangle,
+    /// A diamond centred on the line.
+    Diamond,
+}
+
 /// Playhead configuration for a timeline widget.
 pub struct Playhead {
     extend_beyond_last_track: f32,
     extend_to_available_height: bool,
-    width: f32,
+    pub(crate) through_pinned_bottom: bool,
+    color: Option<egui::Color32>,
+    width: Option<f32>,
+    handle: PlayheadHandle,
+    grab_width: f32,
+    pub(crate) time_display: Option<TimeDisplay>,
+    foreground: bool,
+    interpolate: bool,
+    max_extrapolation: Option<f32>,
+    velocity: Option<f32>,
+    /// Set from `TimelineStyle::pixel_snap_playhead` by `SetPlayhead::playhead`.
+    pub(crate) pixel_snap: bool,
+    /// Set from `TimelineApi::timeline_end` by `SetPlayhead::playhead`.
+    pub(crate) timeline_end: Option<f32>,
 }
 
 impl Playhead {
     pub const DEFAULT_EXTEND_BEYOND_LAST_TRACK: f32 = 0.0;
     pub const DEFAULT_EXTEND_TO_AVAILABLE_HEIGHT: bool = false;
-    pub const DEFAULT_WIDTH: f32 = 1.0;
+    pub const DEFAULT_GRAB_WIDTH: f32 = 8.0;
+    /// The width and height of the handle drawn at the top of the line.
+    pub const HANDLE_SIZE: f32 = 10.0;
+    /// The weight given to each new measurement of the playhead's velocity and update interval
+    /// when `interpolate` is enabled.
+    const INTERPOLATION_SMOOTHING: f32 = 0.3;
+    /// The number of update intervals without a new position after which the playhead is
+    /// considered stopped and drawn at the reported position.
+    const STALL_INTERVALS: f32 = 2.0;
 
     /// Create a new default playhead.
     pub fn new() -> Self {
@@ -44,6 +128,15 @@ impl Playhead {
         self
     }
 
+    /// Whether or not to extend the playhead through the tracks set via
+    /// `Show::pinned_tracks_bottom`, rather than stopping at the scrollable tracks.
+    ///
+    /// Default: `false`
+    pub fn through_pinned_bottom(mut self, b: bool) -> Self {
+        self.through_pinned_bottom = b;
+        self
+    }
+
     /// Extend the playhead beyond the last track by the given amount.
     ///
     /// Only applies if `extend_to_available_height` is `false`.
@@ -54,13 +147,123 @@ impl Playhead {
         self
     }
 
-    /// Specify the width of the playhead rect.
+    /// Specify the colour of the playhead line and handle.
+    ///
+    /// Default: the colour of `TimelineStyle::playhead_stroke`
+    pub fn color(mut self, color: egui::Color32) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    /// Specify the width of the playhead line.
     ///
-    /// Default: `1.0`
+    /// Default: the width of `TimelineStyle::playhead_stroke`
     pub fn width(mut self, width: f32) -> Self {
-        self.width = width;
+        self.width = Some(width);
         self
     }
+
+    /// Specify the shape of the handle drawn at the top of the timeline.
+    ///
+    /// The handle may be dragged just like the line, and is drawn over the top of any other
+    /// content, e.g. the ruler.
+    ///
+    /// Default: `PlayheadHandle::None`
+    pub fn handle(mut self, handle: PlayheadHandle) -> Self {
+        self.handle = handle;
+        self
+    }
+
+    /// Specify the width in points of the area around the line that responds to the pointer.
+    ///
+    /// Default: `8.0`
+    pub fn grab_width(mut self, width: f32) -> Self {
+        self.grab_width = width;
+        self
+    }
+
+    /// Specify the format of the position shown beside the pointer while dragging the playhead.
+    ///
+    /// Default: the format chosen for the time readout of `SetPlayhead::top_panel_time`
+    pub fn time_display(mut self, time_display: TimeDisplay) -> Self {
+        self.time_display = Some(time_display);
+        self
+    }
+
+    /// Whether to paint the playhead on a layer above the tracks, so that track contents painted
+    /// afterwards never cover it. It is still painted beneath popups, menus and other windows.
+    ///
+    /// Disable this to paint the playhead along with the tracks, e.g. so that clips drawn after
+    /// the playhead cover the line.
+    ///
+    /// Default: `true`
+    pub fn foreground(mut self, foreground: bool) -> Self {
+        self.foreground = foreground;
+        self
+    }
+
+    /// Smooth the motion of the playhead when the host only updates its position every so often,
+    /// e.g. once per audio callback.
+    ///
+    /// The velocity is estimated from successive values of `Info::playhead_ticks`, unless given
+    /// via `velocity`, and the drawn position is extrapolated to the current frame. It never leads
+    /// the reported position by more than `max_extrapolation`, and snaps to it when the reported
+    /// position jumps backwards, e.g. on loop or seek, or stops changing.
+    ///
+    /// Default: `false`
+    pub fn interpolate(mut self, interpolate: bool) -> Self {
+        self.interpolate = interpolate;
+        self
+    }
+
+    /// The furthest in ticks that an interpolated playhead is drawn ahead of the position
+    /// reported by the host.
+    ///
+    /// Default: one beat
+    pub fn max_extrapolation(mut self, ticks: f32) -> Self {
+        self.max_extrapolation = Some(ticks);
+        self
+    }
+
+    /// The velocity of the playhead in ticks per second during playback, used by `interpolate` in
+    /// place of an estimate, e.g. as derived from the tempo.
+    ///
+    /// Default: estimated from the reported positions
+    pub fn velocity(mut self, ticks_per_second: f32) -> Self {
+        self.velocity = Some(ticks_per_second);
+        self
+    }
+}
+
+/// A painter for a layer directly above the `ui`'s layer, for cursors that should be painted over
+/// the contents of the tracks but beneath popups and any windows above the `ui`.
+pub(crate) fn foreground_painter(ui: &egui::Ui) -> egui::Painter {
+    let parent = ui.layer_id();
+    let layer = egui::LayerId::new(parent.order, egui::Id::new("egui_timeline_foreground").with(parent.id));
+    ui.ctx().move_to_top(layer);
+    ui.ctx().set_sublayer(parent, layer);
+    ui.painter().clone().with_layer_id(layer)
+}
+
+/// Move the playhead to the given tick relative to the start of the view, clamped to `end` if
+/// given, also relative to the start of the view.
+///
+/// Calls `Interaction::on_reached_end` if the tick reaches the end.
+pub(crate) fn seek(api: &dyn Interaction, tick: f32, end: Option<f32>) {
+    match end {
+        Some(end) if tick >= end => {
+            api.set_playhead_ticks_f64(end as f64);
+            api.on_reached_end();
+        }
+        _ => api.set_playhead_ticks_f64(tick as f64),
+    }
+}
+
+/// The x position of the playhead at the given ticks relative to the start of the view.
+///
+/// Computed at double precision, so that the playhead moves smoothly far along the timeline.
+pub(crate) fn x_at_ticks(timeline_rect: egui::Rect, ticks: f64, ticks_per_point: f32) -> f32 {
+    timeline_rect.left() + (ticks / ticks_per_point as f64) as f32
 }
 
 impl Default for Playhead {
@@ -68,25 +271,133 @@ impl Default for Playhead {
         Self {
             extend_beyond_last_track: Self::DEFAULT_EXTEND_BEYOND_LAST_TRACK,
             extend_to_available_height: Self::DEFAULT_EXTEND_TO_AVAILABLE_HEIGHT,
-            width: Self::DEFAULT_WIDTH,
+            through_pinned_bottom: false,
+            color: None,
+            width: None,
+            handle: PlayheadHandle::default(),
+            grab_width: Self::DEFAULT_GRAB_WIDTH,
+            time_display: None,
+            foreground: true,
+            interpolate: false,
+            max_extrapolation: None,
+            velocity: None,
+            pixel_snap: true,
+            timeline_end: None,
         }
     }
 }
 
+/// A read-only cursor drawn alongside the playhead, e.g. a punch-in point, the position where
+/// playback last stopped or a collaborator's playhead.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GhostPlayhead {
+    /// The position of the cursor in absolute ticks.
+    pub tick: f32,
+    /// The colour of the line and label.
+    pub color: egui::Color32,
+    /// An optional label shown at the top of the timeline beside the line.
+    pub label: Option<String>,
+}
+
+impl GhostPlayhead {
+    /// A ghost playhead at the given absolute tick with no label.
+    pub fn new(tick: f32, color: egui::Color32) -> Self {
+        Self { tick, color, label: None }
+    }
+
+    /// Label the ghost playhead.
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+}
+
 impl<T> PlayheadApi for T where T: Info + Interaction {}
 
+impl<T> FollowApi for T where T: PlayheadApi + crate::TimelineApi {}
+
+/// Scroll the timeline according to the `FollowMode` so that the playhead remains visible.
+///
+/// Following is suspended while the user is dragging within or scrolling over the timeline, and
+/// re-engages as soon as they let go. A playhead that passes `TimelineApi::timeline_end` is
+/// moved back to the end, calling `Interaction::on_reached_end`.
+pub fn follow(ui: &egui::Ui, api: &mut dyn FollowApi, timeline_rect: egui::Rect, mode: FollowMode) {
+    follow_with_pre_roll(ui, api, timeline_rect, mode, 0.0);
+}
+
+/// The same as `follow`, but allowing the view to start as early as `-pre_roll`, as set via
+/// `Timeline::pre_roll`.
+pub(crate) fn follow_with_pre_roll(
+    ui: &egui::Ui,
+    api: &mut dyn FollowApi,
+    timeline_rect: egui::Rect,
+    mode: FollowMode,
+    pre_roll: f32,
+) {
+    if mode == FollowMode::Off {
+        return;
+    }
+    // Stop the playhead at the end of the timeline.
+    let timeline_start = crate::TimelineApi::timeline_start_f64(api);
+    if let Some(end) = api.timeline_end()
+        && timeline_start + api.playhead_ticks_f64() > end as f64
+    {
+        api.set_playhead_ticks_f64(end as f64 - timeline_start);
+        api.on_reached_end();
+    }
+    let (dragging, scrolling) = ui.input(|i| {
+        let dragging = i.pointer.any_down()
+            && i.pointer.press_origin().is_some_and(|pos| timeline_rect.contains(pos));
+        let scrolling = i.raw_scroll_delta != egui::Vec2::ZERO || i.smooth_scroll_delta != egui::Vec2::ZERO;
+        (dragging, scrolling)
+    });
+    if dragging || scrolling {
+        return;
+    }
+
+    let visible_ticks = api.ticks_per_point() * timeline_rect.width();
+    if visible_ticks <= 0.0 {
+        return;
+    }
+    let playhead_ticks = api.playhead_ticks_f64();
+    let page_ticks = visible_ticks as f64;
+    let target_start = timeline_start + match mode {
+        FollowMode::Off => return,
+        FollowMode::Page => {
+            if (0.0..page_ticks).contains(&playhead_ticks) {
+                return;
+            }
+            (playhead_ticks / page_ticks).floor() * page_ticks
+        }
+        FollowMode::Continuous(fraction) => playhead_ticks - fraction.clamp(0.0, 1.0) as f64 * page_ticks,
+    };
+    crate::interaction::scroll_to_start(api, target_start, visible_ticks, pre_roll);
+}
+
 /// Set the playhead widget - a thin line for indicating progress through the timeline.
+///
+/// Dragging the playhead snaps it to the given `snap` grid unless `Alt` is held, and clamps it to
+/// the end of the timeline set by `SetPlayhead::playhead`. The `stroke` is used unless overridden
+/// by the `Playhead`'s colour or width.
 pub fn set(
     ui: &mut egui::Ui,
     api: &dyn PlayheadApi,
     timeline_rect: egui::Rect,
     tracks_bottom: f32,
     playhead: Playhead,
+    stroke: egui::Stroke,
+    snap: SnapMode,
 ) -> egui::Response {
     // Allocate a thin `Rect` over the timeline at the playhead.
-    let playhead_ticks = api.playhead_ticks();
-    let playhead_x = timeline_rect.left() + playhead_ticks / api.ticks_per_point();
-    let half_w = playhead.width * 0.5;
+    let scrub_id = ui.id().with("playhead_scrub");
+    let playhead_ticks = if playhead.interpolate {
+        let scrubbing = ui.data(|d| d.get_temp::<bool>(scrub_id)).unwrap_or(false);
+        interpolated_ticks(ui, ui.id().with("playhead_interpolation"), api, &playhead, scrubbing)
+    } else {
+        api.playhead_ticks_f64()
+    };
+    let playhead_x = x_at_ticks(timeline_rect, playhead_ticks, api.ticks_per_point());
+    let half_w = playhead.grab_width * 0.5;
     let top = timeline_rect.top();
     let bottom = if playhead.extend_to_available_height {
         timeline_rect.bottom()
@@ -98,40 +409,283 @@ pub fn set(
     let rect = egui::Rect::from_min_max(min, max);
     let mut response = ui.allocate_rect(rect, egui::Sense::click_and_drag());
 
+    // The handle is allocated after the line so that it takes priority where they overlap.
+    let handle_rect = (playhead.handle != PlayheadHandle::None).then(|| {
+        let size = Playhead::HANDLE_SIZE;
+        egui::Rect::from_min_size(egui::Pos2::new(playhead_x - size * 0.5, top), egui::Vec2::splat(size))
+    });
+    if let Some(handle_rect) = handle_rect {
+        let handle_response = ui.allocate_rect(handle_rect, egui::Sense::click_and_drag());
+        response = response.union(handle_response);
+    }
+
     let timeline_w = timeline_rect.width();
     let ticks_per_point = api.ticks_per_point();
     let visible_ticks = ticks_per_point * timeline_w;
 
     // Handle interactions (on mouse down).
-    let pointer_pressed = ui.input(|i| i.pointer.primary_pressed());
-    let pointer_over = ui.input(|i| {
-        i.pointer.hover_pos()
-            .map(|pos| rect.contains(pos))
-            .unwrap_or(false)
-    });
-    if (pointer_pressed && pointer_over) || response.dragged() {
-        if let Some(pt) = response.interact_pointer_pos() {
-            let tick = (((pt.x - timeline_rect.min.x) / timeline_w) * visible_ticks).max(0.0);
-            api.set_playhead_ticks(tick);
-            response.mark_changed();
+    let pressed = crate::interaction::primary_pressed_on(ui, &response);
+    // Whether the playhead is being scrubbed, from press until release.
+    let mut scrubbing = ui.data(|d| d.get_temp::<bool>(scrub_id)).unwrap_or(false);
+    if pressed && !scrubbing {
+        scrubbing = true;
+        ui.data_mut(|d| d.insert_temp(scrub_id, true));
+        api.on_scrub_start();
+    }
+    if (pressed || response.dragged_by(egui::PointerButton::Primary))
+        && let Some(pt) = response.interact_pointer_pos()
+    {
+        let tick = (((pt.x - timeline_rect.min.x) / timeline_w) * visible_ticks).max(0.0);
+        let snap = snap.with_modifiers(ui.input(|i| i.modifiers));
+        let tick = snap::snap_tick(tick, api, snap);
+        let timeline_start = api.timeline_start_f64().unwrap_or(0.0);
+        let end = playhead.timeline_end.map(|end| (end as f64 - timeline_start) as f32);
+        seek(api, tick, end);
+        if scrubbing {
+            api.on_scrub(end.map_or(tick, |end| tick.min(end)));
         }
+        response.mark_changed();
+    }
+    if scrubbing && !ui.input(|i| i.pointer.primary_down()) {
+        scrubbing = false;
+        ui.data_mut(|d| d.remove::<bool>(scrub_id));
+        api.on_scrub_end(api.playhead_ticks());
+    }
+
+    // Show where the playhead will land beside the pointer while scrubbing.
+    if scrubbing
+        && let Some(pos) = ui.input(|i| i.pointer.interact_pos())
+    {
+        let playhead_ticks = api.playhead_ticks_f64();
+        let absolute_ticks = api.timeline_start_f64().unwrap_or(0.0) + playhead_ticks;
+        let text = playhead.time_display.unwrap_or_default().format(absolute_ticks as f32, api);
+        let anchor = egui::Pos2::new(x_at_ticks(timeline_rect, playhead_ticks, ticks_per_point), pos.y);
+        time_display::paint_position_tooltip(ui, timeline_rect, anchor, text);
     }
 
     // Draw a thin vertical line (not a rect with stroke to avoid double lines at edges).
+    let stroke = egui::Stroke::new(
+        playhead.width.unwrap_or(stroke.width),
+        playhead.color.unwrap_or(stroke.color),
+    );
     if timeline_rect.x_range().contains(playhead_x) {
-        // Use a specific color for the playhead instead of the default interactive color (which is red)
-        // Using a blue color that's visible but distinct from other UI elements
-        let playhead_color = egui::Color32::from_rgb(150, 150, 150); // Light blue
-        let stroke = egui::Stroke {
-            width: 1.0,
-            color: playhead_color,
+        let painter = if playhead.foreground {
+            foreground_painter(ui)
+        } else {
+            ui.painter().clone()
+        };
+        let playhead_x = if playhead.pixel_snap {
+            painter.round_to_pixel_center(playhead_x)
+        } else {
+            playhead_x
         };
         // Draw only a vertical line instead of a rect to avoid double vertical lines at edges
         let top_pos = egui::Pos2::new(playhead_x, top);
         let bottom_pos = egui::Pos2::new(playhead_x, bottom);
-        ui.painter().line_segment([top_pos, bottom_pos], stroke);
+        painter.line_segment([top_pos, bottom_pos], stroke);
+
+        // Draw the handle over the top of the line.
+        let (x, s) = (playhead_x, Playhead::HANDLE_SIZE);
+        let points = match playhead.handle {
+            PlayheadHandle::None => vec![],
+            PlayheadHandle::Triangle => vec![
+                egui::Pos2::new(x - s * 0.5, top),
+                egui::Pos2::new(x + s * 0.5, top),
+                egui::Pos2::new(x, top + s),
+            ],
+            PlayheadHandle::Diamond => vec![
+                egui::Pos2::new(x, top),
+                egui::Pos2::new(x + s * 0.5, top + s * 0.5),
+                egui::Pos2::new(x, top + s),
+                egui::Pos2::new(x - s * 0.5, top + s * 0.5),
+            ],
+        };
+        if !points.is_empty() {
+            painter.add(egui::Shape::convex_polygon(points, stroke.color, egui::Stroke::NONE));
+        }
     }
 
     response
 }
 
+/// The most recent playhead position reported by the host, for interpolating between updates.
+#[derive(Copy, Clone, Debug)]
+struct Interpolation {
+    /// The reported position in absolute ticks.
+    reported: f64,
+    /// The input time in seconds at which the position was reported.
+    time: f64,
+    /// The smoothed velocity in ticks per second.
+    velocity: f32,
+    /// The smoothed interval in seconds between updates of the reported position.
+    interval: f32,
+}
+
+/// The position at which to draw the playhead in ticks relative to the start of the timeline,
+/// extrapolated from the positions reported on previous frames.
+///
+/// Snaps to the reported position while `reset` is set, e.g. while scrubbing.
+fn interpolated_ticks(ui: &egui::Ui, id: egui::Id, api: &dyn PlayheadApi, playhead: &Playhead, reset: bool) -> f64 {
+    let playhead_ticks = api.playhead_ticks_f64();
+    let timeline_start = api.timeline_start_f64().unwrap_or(0.0);
+    let reported = timeline_start + playhead_ticks;
+    let time = ui.input(|i| i.time);
+    let max_extrapolation = playhead.max_extrapolation.unwrap_or(api.ticks_per_beat() as f32);
+    let snapped = Interpolation { reported, time, velocity: 0.0, interval: 0.0 };
+    let blend = |old: f32, new: f32| {
+        if old == 0.0 {
+            new
+        } else {
+            old + (new - old) * Playhead::INTERPOLATION_SMOOTHING
+        }
+    };
+
+    let state = match ui.data(|d| d.get_temp::<Interpolation>(id)) {
+        Some(prev) if !reset && reported == prev.reported => prev,
+        // Only follow forward motion close to where the playhead was expected to be, so that
+        // loops and seeks snap rather than glide.
+        Some(prev) if !reset && reported > prev.reported => {
+            let dt = (time - prev.time) as f32;
+            let expected = prev.velocity * dt.min(prev.interval);
+            let jump = (reported - prev.reported) as f32;
+            if dt > 0.0 && (jump - expected).abs() <= max_extrapolation {
+                let velocity = playhead.velocity.unwrap_or_else(|| blend(prev.velocity, jump / dt));
+                Interpolation { reported, time, velocity, interval: blend(prev.interval, dt) }
+            } else {
+                snapped
+            }
+        }
+        _ => snapped,
+    };
+
+    // Stop extrapolating once the host stops updating the position, e.g. when paused.
+    let elapsed = (time - state.time) as f32;
+    let state = if elapsed > state.interval * Playhead::STALL_INTERVALS {
+        Interpolation { velocity: 0.0, interval: 0.0, ..state }
+    } else {
+        state
+    };
+    ui.data_mut(|d| d.insert_temp(id, state));
+    if state.velocity <= 0.0 {
+        return playhead_ticks;
+    }
+    ui.ctx().request_repaint();
+    let lead = (state.velocity * elapsed.min(state.interval)).clamp(0.0, max_extrapolation);
+    playhead_ticks + lead as f64
+}
+
+/// Paint non-interactive dashed lines for each of the given ghost playheads.
+///
+/// Lines are clipped to the timeline area above `tracks_bottom`. Labels are drawn at the top of
+/// the timeline to the right of their line, unless they would overlap the main playhead.
+pub(crate) fn paint_ghosts(
+    ui: &egui::Ui,
+    api: &dyn PlayheadApi,
+    timeline_rect: egui::Rect,
+    tracks_bottom: f32,
+    ghosts: &[GhostPlayhead],
+) {
+    const DASH: f32 = 4.0;
+    const GAP: f32 = 3.0;
+    const LABEL_PAD: f32 = 3.0;
+    let mut clip_rect = timeline_rect;
+    clip_rect.max.y = clip_rect.max.y.min(tracks_bottom);
+    let painter = foreground_painter(ui).with_clip_rect(clip_rect);
+    let ticks_per_point = api.ticks_per_point();
+    let timeline_start = api.timeline_start_f64().unwrap_or(0.0);
+    let x_at = |tick: f32| x_at_ticks(timeline_rect, tick as f64 - timeline_start, ticks_per_point);
+
+    // The area occupied by the main playhead's handle, which labels must not obscure.
+    let playhead_x = x_at_ticks(timeline_rect, api.playhead_ticks_f64(), ticks_per_point);
+    let half_handle = Playhead::HANDLE_SIZE * 0.5;
+    let playhead_x_range = playhead_x - half_handle..=playhead_x + half_handle;
+
+    let font_id = egui::TextStyle::Small.resolve(ui.style());
+    for ghost in ghosts {
+        let x = painter.round_to_pixel_center(x_at(ghost.tick));
+        if !clip_rect.x_range().contains(x) {
+            continue;
+        }
+        let top = egui::Pos2::new(x, clip_rect.top());
+        let bottom = egui::Pos2::new(x, clip_rect.bottom());
+        let stroke = egui::Stroke::new(1.0, ghost.color);
+        painter.extend(egui::Shape::dashed_line(&[top, bottom], stroke, DASH, GAP));
+
+        if let Some(label) = &ghost.label {
+            let galley = painter.layout_no_wrap(label.clone(), font_id.clone(), ghost.color);
+            let pos = egui::Pos2::new(x + LABEL_PAD, clip_rect.top());
+            let label_rect = egui::Rect::from_min_size(pos, galley.size());
+            let overlaps_playhead = label_rect.left() <= *playhead_x_range.end()
+                && *playhead_x_range.start() <= label_rect.right();
+            if !overlaps_playhead {
+                painter.galley(pos, galley, ghost.color);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::SimpleTimelineState;
+
+    /// The x position of the playhead shown by `set` over a 2000 point wide timeline in a frame
+    /// at the given time.
+    fn playhead_x(ctx: &egui::Context, time: f64, api: &dyn PlayheadApi, interpolate: bool) -> f32 {
+        let screen_rect = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(2000.0, 100.0));
+        let input = egui::RawInput { screen_rect: Some(screen_rect), time: Some(time), ..Default::default() };
+        let mut x = f32::NAN;
+        let _ = ctx.run(input, |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                let stroke = ui.visuals().widgets.noninteractive.fg_stroke;
+                let response = set(ui, api, screen_rect, 100.0, Playhead::new().interpolate(interpolate), stroke, SnapMode::Off);
+                x = response.rect.center().x;
+            });
+        });
+        x
+    }
+
+    /// A timeline zoomed all the way in, with the view two million ticks along.
+    fn far_along() -> SimpleTimelineState {
+        let mut state = SimpleTimelineState::new();
+        state.zoom = SimpleTimelineState::MIN_ZOOM;
+        state.timeline_start = 2_000_000.0;
+        state
+    }
+
+    #[test]
+    fn playhead_x_far_along_the_timeline() {
+        let state = far_along();
+        let ticks_per_point = MusicalInfo::ticks_per_point(&state);
+        state.playhead.set(2_000_000.0 + 300.0);
+        let x = playhead_x(&egui::Context::default(), 0.0, &state, false);
+        assert_eq!(x, 300.0 / ticks_per_point);
+    }
+
+    #[test]
+    fn playhead_does_not_jitter_far_along_the_timeline() {
+        for interpolate in [false, true] {
+            let ctx = egui::Context::default();
+            let state = far_along();
+
+            // Advance the playhead as the demo does, from the time elapsed since playback started
+            let start_seconds = state.ticks_to_seconds_f64(state.timeline_start + 100.0);
+            let frame_seconds = 1.0 / 60.0;
+            let mut last_x: Option<f32> = None;
+            let mut last_dx: Option<f32> = None;
+            for frame in 0..120 {
+                let seconds = frame as f64 * frame_seconds;
+                state.playhead.set(state.seconds_to_ticks_f64(start_seconds + seconds));
+                let x = playhead_x(&ctx, seconds, &state, interpolate);
+                if let Some(last_x) = last_x {
+                    let dx = x - last_x;
+                    if let Some(last_dx) = last_dx {
+                        assert!((dx - last_dx).abs() < 1e-3, "frame {frame}: moved {last_dx} points, then {dx}");
+                    }
+                    last_dx = Some(dx);
+                }
+                last_x = Some(x);
+            }
+        }
+    }
+}
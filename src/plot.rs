@@ -1,11 +1,69 @@
 use crate::context::TimelineCtx;
+use egui::{Color32, Stroke};
 use egui_plot as plot;
 use std::{hash::Hash, ops::RangeInclusive};
 
+/// The colours and strokes used to paint a waveform.
+#[derive(Clone, Debug)]
+pub struct WaveformStyle {
+    /// Fill painted between the minimum and maximum of the envelope.
+    pub fill: Color32,
+    /// Stroke painted along the zero line. `None` disables the line.
+    pub center_line: Option<Stroke>,
+}
+
+impl WaveformStyle {
+    /// Derive a waveform style from the given visuals.
+    pub fn from_visuals(visuals: &egui::Visuals) -> Self {
+        let color = visuals.widgets.inactive.fg_stroke.color;
+        Self {
+            fill: color.linear_multiply(0.6),
+            center_line: Some(Stroke::new(1.0, color.linear_multiply(0.2))),
+        }
+    }
+
+    /// Set the fill painted between the minimum and maximum of the envelope.
+    pub fn fill(mut self, fill: Color32) -> Self {
+        self.fill = fill;
+        self
+    }
+
+    /// Set the stroke painted along the zero line, or `None` for no line.
+    pub fn center_line(mut self, stroke: Option<Stroke>) -> Self {
+        self.center_line = stroke;
+        self
+    }
+}
+
+impl Default for WaveformStyle {
+    fn default() -> Self {
+        Self::from_visuals(&egui::Visuals::dark())
+    }
+}
+
 /// Short-hand for drawing a plot within the timeline UI.
 ///
 /// The same as `egui::plot::Plot::new`, but sets some useful defaults before returning.
+///
+/// The x axis spans the absolute ticks currently visible on the timeline, so data expressed in
+/// absolute ticks stays aligned with the timeline while scrolling. See `plot_ticks_relative` for
+/// data expressed relative to the start of the visible area.
 pub fn plot_ticks(timeline: &TimelineCtx, id_source: impl Hash, y: RangeInclusive<f32>) -> plot::Plot<'_> {
+    let start = timeline.timeline_start;
+    plot_ticks_in(timeline.id.with(id_source), start..=start + timeline.visible_ticks, y)
+}
+
+/// Short-hand for drawing a plot within the timeline UI, with an x axis relative to the start of
+/// the visible area.
+///
+/// The x axis spans `0.0` to `visible_ticks`, so data must be offset by the timeline start in
+/// order to stay aligned with the timeline while scrolling.
+pub fn plot_ticks_relative(timeline: &TimelineCtx, id_source: impl Hash, y: RangeInclusive<f32>) -> plot::Plot<'_> {
+    plot_ticks_in(timeline.id.with(id_source), 0.0..=timeline.visible_ticks, y)
+}
+
+/// A plot with some useful defaults spanning the given x and y ranges.
+fn plot_ticks_in<'a>(id_source: impl Hash, x: RangeInclusive<f32>, y: RangeInclusive<f32>) -> plot::Plot<'a> {
     let h = 72.0;
     plot::Plot::new(id_source)
         .set_margin_fraction(egui::Vec2::ZERO)
@@ -15,8 +73,8 @@ pub fn plot_ticks(timeline: &TimelineCtx, id_source: impl Hash, y: RangeInclusiv
         .allow_drag(false)
         .allow_scroll(false)
         .allow_boxed_zoom(false)
-        .include_x(0.0)
-        .include_x(timeline.visible_ticks)
+        .include_x(*x.start())
+        .include_x(*x.end())
         .include_y(*y.start())
         .include_y(*y.end())
         .show_x(false)
@@ -26,3 +84,70 @@ pub fn plot_ticks(timeline: &TimelineCtx, id_source: impl Hash, y: RangeInclusiv
         .show_axes([false; 2])
         .height(h)
 }
+
+/// Draw a min/max waveform envelope aligned with the timeline within the given track area.
+///
+/// Each entry of `peaks` is the `(min, max)` of the signal, in the range `-1.0..=1.0`, for one
+/// peak period. Peak `0` starts at absolute tick `0.0` and `samples_per_tick` is the number of
+/// peaks per tick.
+///
+/// Only the visible range of peaks is drawn. Peaks are decimated to a single min/max pair per
+/// pixel column, so the cost of drawing is bounded by the width of the track rather than the
+/// number of peaks.
+pub fn waveform(
+    ui: &mut egui::Ui,
+    timeline: &TimelineCtx,
+    track_rect: egui::Rect,
+    peaks: &[(f32, f32)],
+    samples_per_tick: f32,
+    style: &WaveformStyle,
+) {
+    if peaks.is_empty() || samples_per_tick <= 0.0 {
+        return;
+    }
+    let painter = ui.painter().with_clip_rect(ui.clip_rect().intersect(track_rect));
+    let center_y = track_rect.center().y;
+    let half_h = track_rect.height() * 0.5;
+    if let Some(stroke) = style.center_line {
+        let a = egui::Pos2::new(track_rect.left(), center_y);
+        let b = egui::Pos2::new(track_rect.right(), center_y);
+        painter.line_segment([a, b], stroke);
+    }
+
+    // The visible x range occupied by the peaks.
+    let peak_to_x = |ix: f32| timeline.tick_to_x(ix / samples_per_tick - timeline.timeline_start);
+    let left = track_rect.left().max(peak_to_x(0.0));
+    let right = track_rect.right().min(peak_to_x(peaks.len() as f32));
+    if left >= right {
+        return;
+    }
+
+    // Step one physical pixel at a time, merging all peaks that fall within each column.
+    let column_w = 1.0 / ui.ctx().pixels_per_point();
+    let x_to_peak = |x: f32| (timeline.timeline_start + timeline.x_to_tick(x)) * samples_per_tick;
+    let last = peaks.len() - 1;
+    let mut mesh = egui::Mesh::default();
+    let mut x = left;
+    while x < right {
+        let start = (x_to_peak(x).floor().max(0.0) as usize).min(last);
+        let end = (x_to_peak(x + column_w).ceil().max(0.0) as usize).clamp(start + 1, last + 1);
+        let (min, max) = peaks[start..end]
+            .iter()
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), &(min, max)| {
+                (lo.min(min), hi.max(max))
+            });
+        let top = center_y - max.clamp(-1.0, 1.0) * half_h;
+        let bottom = center_y - min.clamp(-1.0, 1.0) * half_h;
+
+        // Two vertices per column, joined to the previous column by a pair of triangles.
+        let ix = mesh.vertices.len() as u32;
+        mesh.colored_vertex(egui::Pos2::new(x, top), style.fill);
+        mesh.colored_vertex(egui::Pos2::new(x, bottom), style.fill);
+        if ix >= 2 {
+            mesh.add_triangle(ix - 2, ix - 1, ix);
+            mesh.add_triangle(ix - 1, ix, ix + 1);
+        }
+        x += column_w;
+    }
+    painter.add(mesh);
+}
@@ -1,4 +1,11 @@
-use crate::types::Bar;
+use crate::{
+    grid, interaction,
+    loop_region::{self, LoopApi},
+    time_selection::{self, TimeSelectionApi},
+    snap::{self, SnapMode},
+    time_display::{self, TimeDisplay},
+    types::{Bar, TempoMap, TimeSig},
+};
 
 pub trait MusicalInfo {
     /// The number of ticks per beat, also known as PPQN (parts per quarter note).
@@ -9,174 +16,821 @@ pub trait MusicalInfo {
     fn ticks_per_point(&self) -> f32 {
         self.ticks_per_beat() as f32 / 16.0
     }
+    /// The tempo in beats per minute, used to convert between ticks and seconds.
+    ///
+    /// Default: `120.0`
+    fn tempo_bpm(&self) -> f32 {
+        120.0
+    }
+    /// The tempo map used to convert between ticks and seconds when the tempo changes over time.
+    ///
+    /// When `None`, the constant `tempo_bpm` is used instead.
+    fn tempo_map(&self) -> Option<&TempoMap> {
+        None
+    }
+    /// The time in seconds at the given absolute tick (measured from the start of the timeline).
+    ///
+    /// The default implementation consults `tempo_map` when present, and otherwise assumes a
+    /// constant `tempo_bpm`.
+    fn ticks_to_seconds(&self, tick: f32) -> f32 {
+        match self.tempo_map() {
+            Some(map) => map.tick_to_seconds(tick),
+            None => tick / self.ticks_per_beat() as f32 * 60.0 / self.tempo_bpm(),
+        }
+    }
+    /// The absolute tick at the given time in seconds, the inverse of `ticks_to_seconds`.
+    fn seconds_to_ticks(&self, seconds: f32) -> f32 {
+        match self.tempo_map() {
+            Some(map) => map.seconds_to_tick(seconds),
+            None => seconds * self.tempo_bpm() / 60.0 * self.ticks_per_beat() as f32,
+        }
+    }
+    /// The same as `ticks_to_seconds`, but at double precision.
+    fn ticks_to_seconds_f64(&self, tick: f64) -> f64 {
+        match self.tempo_map() {
+            Some(map) => map.tick_to_seconds_f64(tick),
+            None => tick / self.ticks_per_beat() as f64 * 60.0 / self.tempo_bpm() as f64,
+        }
+    }
+    /// The same as `seconds_to_ticks`, but at double precision.
+    fn seconds_to_ticks_f64(&self, seconds: f64) -> f64 {
+        match self.tempo_map() {
+            Some(map) => map.seconds_to_tick_f64(seconds),
+            None => seconds * self.tempo_bpm() as f64 / 60.0 * self.ticks_per_beat() as f64,
+        }
+    }
     /// Get the current timeline start position in ticks (for calculating absolute bar numbers).
     /// Returns None if not available.
     fn timeline_start(&self) -> Option<f32> {
         None
     }
+    /// The same as `timeline_start`, but at double precision.
+    fn timeline_start_f64(&self) -> Option<f64> {
+        self.timeline_start().map(f64::from)
+    }
+    /// The zero-based number of the bar at the given tick offset starting from the beginning
+    /// (left) of the timeline view.
+    ///
+    /// The default implementation counts bars via `bar_at_ticks` from the absolute start of the
+    /// timeline, so hosts with many bars may wish to provide a direct lookup.
+    fn bar_number_at_ticks(&self, tick: f32) -> u32 {
+        let mut bar = self.bar_at_ticks(-self.timeline_start().unwrap_or(0.0));
+        let mut number = 0;
+        while bar.tick_range.end <= tick {
+            let next = self.bar_at_ticks(bar.tick_range.end + 0.5);
+            if next.tick_range.start <= bar.tick_range.start {
+                break;
+            }
+            bar = next;
+            number += 1;
+        }
+        number
+    }
 }
 
 /// Respond to when the user clicks on the ruler.
 pub trait MusicalInteract {
     /// The given tick location was clicked
     fn click_at_tick(&mut self, tick: f32);
+    /// A click was clamped to `MusicalRuler::timeline_end`.
+    ///
+    /// Called after `click_at_tick`, e.g. for the host to stop its transport.
+    fn on_reached_end(&mut self) {}
 }
 
 /// The required API for the musical ruler widget.
 pub trait MusicalRuler {
     fn info(&self) -> &dyn MusicalInfo;
     fn interact(&mut self) -> &mut dyn MusicalInteract;
+    /// Access to the loop region, if the ruler should allow editing one.
+    ///
+    /// When provided, dragging in the upper half of the ruler creates a new loop region,
+    /// dragging either edge of the region resizes it and dragging the middle moves it.
+    /// Double-clicking the region toggles whether looping is enabled. The lower half of the
+    /// ruler continues to move the playhead.
+    fn loop_api(&mut self) -> Option<&mut dyn LoopApi> {
+        None
+    }
+    /// Access to the time selection, if the ruler should allow editing one.
+    ///
+    /// When provided, dragging in the lower half of the ruler with `Shift` held drags out a new
+    /// selection and dragging either edge of the selection resizes it. Clicking with `Shift` held
+    /// clears the selection. Other presses continue to move the playhead.
+    fn time_selection_api(&mut self) -> Option<&mut dyn TimeSelectionApi> {
+        None
+    }
+    /// The end of the timeline in absolute ticks, usually the same as `TimelineApi::timeline_end`.
+    ///
+    /// Clicks on the ruler are clamped to the end. The default implementation returns `None`, in
+    /// which case clicks are not clamped.
+    fn timeline_end(&self) -> Option<f32> {
+        None
+    }
+}
+
+/// The units in which positions along the ruler are marked and labelled.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum RulerMode {
+    /// Lines on bars, beats and their subdivisions, labelled with bar numbers counted from one.
+    ///
+    /// Bars before tick `0.0`, e.g. within a `Timeline::pre_roll`, are counted back from `-1`.
+    #[default]
+    BarsBeats,
+    /// Lines spaced by powers of ten seconds, labelled in seconds.
+    Seconds,
+    /// Lines aligned to frames at the given frame rate, labelled with `hh:mm:ss:ff` timecode.
+    Timecode { fps: f32 },
+    /// Lines spaced by powers of ten samples at the given sample rate, labelled in samples.
+    Samples { sample_rate: u32 },
+}
+
+impl RulerMode {
+    /// The length of the whole unit in which time based modes are counted, or `None` for
+    /// `BarsBeats` or an empty rate.
+    ///
+    /// `Seconds` are counted in milliseconds so that fractions of a second can be labelled.
+    fn unit_seconds(&self) -> Option<f64> {
+        match *self {
+            Self::BarsBeats => None,
+            Self::Seconds => Some(0.001),
+            Self::Timecode { fps } => (fps > 0.0).then(|| 1.0 / fps as f64),
+            Self::Samples { sample_rate } => (sample_rate > 0).then(|| 1.0 / sample_rate as f64),
+        }
+    }
+
+    /// The candidate intervals in units between lines of time based modes, from finest to coarsest.
+    fn line_intervals(&self) -> Vec<u64> {
+        match *self {
+            Self::Timecode { .. } => self.label_intervals(),
+            _ => (0..16).map(|exp| 10u64.pow(exp)).collect(),
+        }
+    }
+
+    /// The candidate intervals in units between labels of time based modes, from finest to
+    /// coarsest.
+    fn label_intervals(&self) -> Vec<u64> {
+        let one_two_five = |exp| [1, 2, 5].map(|n| n * 10u64.pow(exp));
+        match *self {
+            Self::Timecode { fps } => {
+                // Frames within a second, then whole seconds and minutes.
+                let frames = (fps.round() as u64).max(1);
+                let within_second = [1, 2, 5, 10].into_iter().filter(|&n| n < frames);
+                let seconds = [1, 2, 5, 10, 15, 30, 60, 120, 300, 600, 900, 1800];
+                let hours = (0..12).flat_map(one_two_five).map(|n| n * 3600);
+                within_second.chain(seconds.into_iter().chain(hours).map(|n| n * frames)).collect()
+            }
+            _ => (0..16).flat_map(one_two_five).collect(),
+        }
+    }
+
+    /// The label for the given position in units, where labels are `interval` units apart.
+    fn label(&self, unit: i64, interval: u64) -> String {
+        match *self {
+            // There is no bar zero, so bars before the start count back from `-1`.
+            Self::BarsBeats if unit < 0 => format!("{}", unit),
+            Self::BarsBeats => format!("{}", unit + 1),
+            Self::Seconds => {
+                let decimals = match interval {
+                    i if i % 1000 == 0 => 0,
+                    i if i % 100 == 0 => 1,
+                    i if i % 10 == 0 => 2,
+                    _ => 3,
+                };
+                format!("{:.*}", decimals, unit as f64 / 1000.0)
+            }
+            Self::Timecode { fps } => {
+                let frames = (fps.round() as i64).max(1);
+                let (seconds, frame) = (unit.div_euclid(frames), unit.rem_euclid(frames));
+                let (hours, minutes, seconds) = (seconds / 3600, (seconds / 60) % 60, seconds % 60);
+                format!("{:02}:{:02}:{:02}:{:02}", hours, minutes, seconds, frame)
+            }
+            Self::Samples { .. } => format!("{}", unit),
+        }
+    }
+}
+
+/// Configuration for the musical ruler widget.
+#[derive(Clone, Debug)]
+pub struct Ruler {
+    snap: SnapMode,
+    time_display: TimeDisplay,
+    mode: RulerMode,
+    /// The `(left, right)` distances from the ends of the ruler within which no labels are drawn.
+    label_margins: (f32, f32),
+    /// The size and appearance of the ruler, derived from the `Ui` when `None`.
+    style: Option<RulerStyle>,
+}
+
+impl Default for Ruler {
+    fn default() -> Self {
+        Self {
+            snap: SnapMode::Off,
+            time_display: TimeDisplay::default(),
+            mode: RulerMode::default(),
+            label_margins: (20.0, 30.0),
+            style: None,
+        }
+    }
 }
 
+impl Ruler {
+    /// Create a new default ruler configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Snap clicks on the ruler to the given grid.
+    ///
+    /// Snapping can be bypassed temporarily by holding `Alt`.
+    ///
+    /// Default: `SnapMode::Off`
+    pub fn snap(mut self, snap: SnapMode) -> Self {
+        self.snap = snap;
+        self
+    }
+
+    /// The format of the position shown beside the pointer while seeking via the ruler.
+    ///
+    /// Default: `TimeDisplay::BarsBeatsTicks`
+    pub fn time_display(mut self, time_display: TimeDisplay) -> Self {
+        self.time_display = time_display;
+        self
+    }
+
+    /// The units in which positions along the ruler are marked and labelled.
+    ///
+    /// Default: `RulerMode::BarsBeats`
+    pub fn mode(mut self, mode: RulerMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Don't draw labels that would start within `left` points of the left end of the ruler, or
+    /// end within `right` points of its right end.
+    ///
+    /// Default: `(20.0, 30.0)`
+    pub fn label_margins(mut self, left: f32, right: f32) -> Self {
+        self.label_margins = (left, right);
+        self
+    }
+
+    /// The size and appearance of the ruler, e.g. a taller ruler with larger labels for touch.
+    ///
+    /// Default: derived from the `egui::Style` of the `Ui` via `RulerStyle::from_style`.
+    pub fn style(mut self, style: RulerStyle) -> Self {
+        self.style = Some(style);
+        self
+    }
+}
+
+/// The size, fonts and colours used to paint a ruler.
+#[derive(Clone, Debug)]
+pub struct RulerStyle {
+    /// The height of the ruler, including the area that responds to clicks.
+    pub height: f32,
+    /// The length of bar lines, and other labelled lines, as a fraction of the height.
+    pub bar_line_frac: f32,
+    /// The length of beat lines as a fraction of the height. Lines between beats are half as long.
+    pub beat_line_frac: f32,
+    /// The font of the labels.
+    pub label_font: egui::FontId,
+    /// The colour of the labels.
+    pub label_color: egui::Color32,
+    /// The `(bar, beat)` line colours. Lines between beats share the beat colour.
+    pub line_colors: (egui::Color32, egui::Color32),
+    /// Fill painted over the ruler before tick `0.0`, e.g. within a `Timeline::pre_roll`.
+    pub pre_roll_fill: egui::Color32,
+}
+
+impl RulerStyle {
+    /// Derive a ruler style from the given `egui::Style`, with labels at three quarters of the
+    /// `Body` text size.
+    pub fn from_style(style: &egui::Style) -> Self {
+        let body_size = style
+            .text_styles
+            .get(&egui::TextStyle::Body)
+            .map(|f| f.size)
+            .unwrap_or(14.0);
+        let color = style.visuals.widgets.noninteractive.fg_stroke.color;
+        Self {
+            height: 20.0,
+            bar_line_frac: 0.5,
+            beat_line_frac: 0.25,
+            label_font: egui::FontId::proportional(body_size * 0.75),
+            label_color: color,
+            line_colors: (color.linear_multiply(0.5), color.linear_multiply(0.125)),
+            pre_roll_fill: egui::Color32::from_black_alpha(40),
+        }
+    }
+
+    /// Set the height of the ruler.
+    pub fn height(mut self, height: f32) -> Self {
+        self.height = height;
+        self
+    }
+
+    /// Set the lengths of bar and beat lines as fractions of the height.
+    pub fn line_fracs(mut self, bar: f32, beat: f32) -> Self {
+        self.bar_line_frac = bar;
+        self.beat_line_frac = beat;
+        self
+    }
+
+    /// Set the font of the labels.
+    pub fn label_font(mut self, font: egui::FontId) -> Self {
+        self.label_font = font;
+        self
+    }
+
+    /// Set the colour of the labels.
+    pub fn label_color(mut self, color: egui::Color32) -> Self {
+        self.label_color = color;
+        self
+    }
+
+    /// Set the colours of bar and beat lines.
+    pub fn line_colors(mut self, bar: egui::Color32, beat: egui::Color32) -> Self {
+        self.line_colors = (bar, beat);
+        self
+    }
+
+    /// Set the fill painted over the ruler before tick `0.0`.
+    pub fn pre_roll_fill(mut self, fill: egui::Color32) -> Self {
+        self.pre_roll_fill = fill;
+        self
+    }
+}
+
+impl Default for RulerStyle {
+    fn default() -> Self {
+        Self::from_style(&egui::Style::default())
+    }
+}
+
+/// The musical ruler widget with the default `Ruler` configuration.
 pub fn musical(ui: &mut egui::Ui, api: &mut dyn MusicalRuler) -> egui::Response {
+    musical_with(ui, api, Ruler::new())
+}
+
+/// The musical ruler widget with the given configuration.
+pub fn musical_with(ui: &mut egui::Ui, api: &mut dyn MusicalRuler, ruler: Ruler) -> egui::Response {
+    show(ui, api, ruler, true, None::<fn(&mut egui::Ui, f32)>)
+}
+
+/// The musical ruler widget with the given configuration and a context menu shown on right-click.
+///
+/// The menu is given the absolute tick that was clicked, snapped in the same manner as clicks
+/// that move the playhead, e.g. for actions such as "Add marker" or "Set loop start here".
+pub fn musical_with_menu(
+    ui: &mut egui::Ui,
+    api: &mut dyn MusicalRuler,
+    ruler: Ruler,
+    menu: impl FnOnce(&mut egui::Ui, f32),
+) -> egui::Response {
+    show(ui, api, ruler, true, Some(menu))
+}
+
+/// Configuration for the time ruler widget.
+#[derive(Clone, Debug)]
+pub struct TimeRulerStyle {
+    fps: f32,
+    snap: SnapMode,
+    style: Option<RulerStyle>,
+}
+
+impl Default for TimeRulerStyle {
+    fn default() -> Self {
+        Self { fps: 30.0, snap: SnapMode::Off, style: None }
+    }
+}
+
+impl TimeRulerStyle {
+    /// Create a new default time ruler configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The frame rate of the SMPTE timecode labels.
+    ///
+    /// Default: `30.0`
+    pub fn fps(mut self, fps: f32) -> Self {
+        self.fps = fps;
+        self
+    }
+
+    /// Snap clicks on the ruler to the given grid.
+    ///
+    /// Default: `SnapMode::Off`
+    pub fn snap(mut self, snap: SnapMode) -> Self {
+        self.snap = snap;
+        self
+    }
+
+    /// The size and appearance of the ruler, usually the same as that of the musical ruler.
+    ///
+    /// Default: derived from the `egui::Style` of the `Ui` via `RulerStyle::from_style`.
+    pub fn style(mut self, style: RulerStyle) -> Self {
+        self.style = Some(style);
+        self
+    }
+}
+
+/// A wall-clock ruler with lines on frames, seconds and minutes and SMPTE timecode labels.
+///
+/// Shares the `ticks_per_point` and `timeline_start` of the given `api` with the musical ruler,
+/// so that the two remain aligned when stacked, e.g. as two of the `pinned_tracks`. Clicking
+/// seeks via `MusicalInteract::click_at_tick`. The loop region is only shown and edited by the
+/// musical ruler.
+pub fn time(ui: &mut egui::Ui, api: &mut dyn MusicalRuler, style: TimeRulerStyle) -> egui::Response {
+    let mut ruler = Ruler::new()
+        .mode(RulerMode::Timecode { fps: style.fps })
+        .time_display(TimeDisplay::Smpte { fps: style.fps })
+        .snap(style.snap);
+    ruler.style = style.style;
+    show(ui, api, ruler, false, None::<fn(&mut egui::Ui, f32)>)
+}
+
+/// Show a ruler with the given configuration, showing and editing the loop region if `with_loop`.
+fn show(
+    ui: &mut egui::Ui,
+    api: &mut dyn MusicalRuler,
+    ruler: Ruler,
+    with_loop: bool,
+    menu: Option<impl FnOnce(&mut egui::Ui, f32)>,
+) -> egui::Response {
     // Use fixed height to match track height and prevent overflow
-    const RULER_HEIGHT: f32 = 20.0;
+    let style = ruler.style.clone().unwrap_or_else(|| RulerStyle::from_style(ui.style()));
     let w = ui.available_rect_before_wrap().width();
-    let desired_size = egui::Vec2::new(w, RULER_HEIGHT);
+    let desired_size = egui::Vec2::new(w, style.height);
     let (rect, mut response) = ui.allocate_exact_size(desired_size, egui::Sense::click_and_drag());
 
     let w = rect.width();
     let ticks_per_point = api.info().ticks_per_point();
     let visible_ticks = w * ticks_per_point;
-    let pointer_pressed = ui.input(|i| i.pointer.primary_pressed());
-    let pointer_over = ui.input(|i| {
-        i.pointer.hover_pos()
-            .map(|pos| rect.contains(pos))
-            .unwrap_or(false)
-    });
-    if (pointer_pressed && pointer_over) || response.dragged() {
-        if let Some(pt) = response.interact_pointer_pos() {
-            let tick = (((pt.x - rect.min.x) / w) * visible_ticks).max(0.0);
-            api.interact().click_at_tick(tick);
-            response.mark_changed();
-        }
+    let pressed = interaction::primary_pressed_on(ui, &response);
+    let loop_interaction = with_loop && loop_region::ruler_interaction(ui, rect, &response, api, ruler.snap);
+    let selection_interaction = time_selection::ruler_interaction(ui, rect, &response, api, ruler.snap);
+    if (pressed || response.dragged_by(egui::PointerButton::Primary))
+        && !loop_interaction
+        && !selection_interaction
+        && let Some(pt) = response.interact_pointer_pos()
+    {
+        let tick = (((pt.x - rect.min.x) / w) * visible_ticks).max(0.0);
+        let snap = ruler.snap.with_modifiers(ui.input(|i| i.modifiers));
+        let tick = snap::snap_tick(tick, api.info(), snap);
+        let end = api.timeline_end().map(|end| end - api.info().timeline_start().unwrap_or(0.0));
+        let tick = match end {
+            Some(end) if tick >= end => {
+                api.interact().click_at_tick(end);
+                api.interact().on_reached_end();
+                end
+            }
+            _ => {
+                api.interact().click_at_tick(tick);
+                tick
+            }
+        };
+        response.mark_changed();
+
+        // Show where the playhead will land beside the pointer.
+        let info = api.info();
+        let text = ruler.time_display.format(info.timeline_start().unwrap_or(0.0) + tick, info);
+        let anchor = egui::Pos2::new(rect.min.x + tick / ticks_per_point, pt.y);
+        time_display::paint_position_tooltip(ui, rect, anchor, text);
+    }
+
+    if let Some(menu) = menu {
+        let info = api.info();
+        let x_to_tick = |x: f32| {
+            let tick = ((x - rect.min.x) * ticks_per_point).max(0.0);
+            let snap = ruler.snap.with_modifiers(ui.input(|i| i.modifiers));
+            info.timeline_start().unwrap_or(0.0) + snap::snap_tick(tick, info, snap)
+        };
+        interaction::context_menu(ui, &response, response.id.with("menu_tick"), x_to_tick, menu);
+    }
+
+    // Dim any pre-roll before tick `0.0`.
+    let timeline_start = api.info().timeline_start().unwrap_or(0.0);
+    if timeline_start < 0.0 {
+        let x = (rect.left() - timeline_start / ticks_per_point).min(rect.right());
+        let pre_roll = egui::Rect::from_x_y_ranges(rect.left()..=x, rect.y_range());
+        ui.painter().rect_filled(pre_roll, 0.0, style.pre_roll_fill);
+    }
+
+    // Draw the loop region behind the ruler lines
+    if with_loop
+        && let Some((range, enabled)) = api.loop_api().map(|l| (l.loop_range(), l.loop_enabled()))
+    {
+        loop_region::paint_band(ui, rect, api.info(), range, enabled);
+    }
+    if let Some(range) = api.time_selection_api().map(|s| s.time_selection()) {
+        time_selection::paint_band(ui, rect, api.info(), range);
     }
 
-    let vis = ui.style().noninteractive();
-    // Note: Pink border is drawn by the track's show() method to include header + timeline
+    // Note: Any track border is drawn by the track's show() method to include header + timeline
     // No need to draw border here as it would only cover the timeline area
+    let (left_margin, right_margin) = ruler.label_margins;
+    let labels = Labels {
+        rect,
+        font: style.label_font.clone(),
+        color: style.label_color,
+        left_margin,
+        right_margin,
+    };
+    let lines = Lines { rect, width: ui.style().noninteractive().fg_stroke.width, style: &style };
 
-    let mut stroke = vis.fg_stroke;
-    let bar_color = stroke.color.linear_multiply(0.5);
-    let step_color = stroke.color.linear_multiply(0.125);
-    let bar_y = rect.center().y;
-    let step_even_y = rect.top() + rect.height() * 0.25;
-    let step_odd_y = rect.top() + rect.height() * 0.125;
+    // Draw the lines together beneath the labels.
+    let lines_idx = ui.painter().add(egui::Shape::Noop);
+    let shapes = match ruler.mode {
+        RulerMode::BarsBeats => paint_bars_beats(ui, api.info(), &lines, &labels),
+        mode => paint_time(ui, api.info(), mode, &lines, &labels),
+    };
+    ui.painter().set(lines_idx, egui::Shape::Vec(shapes));
+
+    response
+}
+
+/// The style and placement of the lines drawn along the ruler.
+struct Lines<'a> {
+    rect: egui::Rect,
+    width: f32,
+    style: &'a RulerStyle,
+}
+
+impl Lines<'_> {
+    /// A bar line at `x`, also used for other labelled positions.
+    fn major(&self, painter: &egui::Painter, x: f32) -> egui::Shape {
+        let stroke = egui::Stroke::new(self.width, self.style.line_colors.0);
+        let y = self.rect.top() + self.rect.height() * self.style.bar_line_frac;
+        grid::vline(painter, x, self.rect.top()..=y, stroke)
+    }
+
+    /// A bar line at `x` drawn in the label colour to mark a change of time signature.
+    fn meter(&self, painter: &egui::Painter, x: f32) -> egui::Shape {
+        let stroke = egui::Stroke::new(self.width, self.style.label_color);
+        let y = self.rect.top() + self.rect.height() * self.style.bar_line_frac;
+        grid::vline(painter, x, self.rect.top()..=y, stroke)
+    }
+
+    /// A beat line at `x` if `even`, or a line half as long otherwise to distinguish neighbours.
+    fn minor(&self, painter: &egui::Painter, x: f32, even: bool) -> egui::Shape {
+        let stroke = egui::Stroke::new(self.width, self.style.line_colors.1);
+        let fraction = self.style.beat_line_frac * if even { 1.0 } else { 0.5 };
+        let y = self.rect.top() + self.rect.height() * fraction;
+        grid::vline(painter, x, self.rect.top()..=y, stroke)
+    }
+}
+
+/// The style and placement of the labels drawn beside major lines.
+struct Labels {
+    rect: egui::Rect,
+    font: egui::FontId,
+    color: egui::Color32,
+    left_margin: f32,
+    right_margin: f32,
+}
+
+impl Labels {
+    fn layout(&self, ui: &egui::Ui, text: String) -> std::sync::Arc<egui::Galley> {
+        ui.painter().layout_no_wrap(text, self.font.clone(), self.color)
+    }
+
+    /// The width of the given label, plus its offset from its line.
+    fn width(&self, ui: &egui::Ui, text: String) -> f32 {
+        self.layout(ui, text).size().x + LABEL_OFFSET
+    }
+
+    /// Draw the label beside the line at `x`, only if it fits within the margins in full and
+    /// starts at least `LABEL_GAP` beyond `after`, the end of the previous label.
+    ///
+    /// Moves `after` to the end of the label if it was drawn.
+    fn paint(&self, ui: &egui::Ui, x: f32, text: String, after: &mut f32) {
+        let galley = self.layout(ui, text);
+        let pos = egui::Pos2::new(x + LABEL_OFFSET, self.rect.center().y - galley.size().y * 0.5);
+        let fits_left = x >= self.rect.left() + self.left_margin && pos.x >= *after + LABEL_GAP;
+        let fits_right = pos.x + galley.size().x <= self.rect.right() - self.right_margin;
+        if fits_left && fits_right {
+            *after = pos.x + galley.size().x;
+            ui.painter().galley(pos, galley, self.color);
+        }
+    }
+}
+
+/// The index of the bar starting at the given tick relative to the start of the view, counted
+/// from `0` at tick `0.0` and negative for bars before it, e.g. within a `Timeline::pre_roll`.
+///
+/// Bars before tick `0.0` are assumed to share the length of the bar at the given tick.
+pub(crate) fn bar_index_at(info: &dyn MusicalInfo, tick: f32) -> i64 {
+    let absolute_tick = info.timeline_start().unwrap_or(0.0) + tick;
+    if absolute_tick < 0.0 {
+        let bar = info.bar_at_ticks(tick);
+        let ticks_per_bar = bar.tick_range.end - bar.tick_range.start;
+        (absolute_tick / ticks_per_bar).round() as i64
+    } else {
+        // The bar at the start of the view may begin before it.
+        info.bar_number_at_ticks(tick.max(0.0)) as i64
+    }
+}
+
+/// Paint a line on each of the `Steps` across the ruler, labelling every `label_stride` bars.
+///
+/// Bars whose time signature differs from that of the previous bar are always labelled, with the
+/// new time signature beside the bar number, unless the label would overlap the previous one.
+/// Once bars are narrower than `MIN_STEP_GAP`, only the labelled bars and every `bar_line_stride`
+/// bars are lined.
+fn paint_bars_beats(
+    ui: &egui::Ui,
+    info: &dyn MusicalInfo,
+    lines: &Lines,
+    labels: &Labels,
+) -> Vec<egui::Shape> {
+    let rect = lines.rect;
+    let steps: Vec<Step> = Steps::new(info, rect.width(), crate::types::MIN_STEP_GAP).collect();
+
+    // Number the bars from the first visible bar line onwards, counting bars within any pre-roll
+    // back from tick `0.0`.
+    let first_bar = steps.iter().find(|step| step.kind == StepKind::Bar);
+    let first_number = first_bar.map(|step| bar_index_at(info, step.ticks)).unwrap_or(0);
+    let bar_count = steps.iter().filter(|step| step.kind == StepKind::Bar).count() as i64;
+    let last_number = first_number + (bar_count - 1).max(0);
+
+    // Choose the stride from the width of the bar at the start of the view.
+    let bar = info.bar_at_ticks(0.0);
+    let points_per_bar = (bar.tick_range.end - bar.tick_range.start) / info.ticks_per_point();
+    let label_width = [first_number, last_number]
+        .map(|number| labels.width(ui, RulerMode::BarsBeats.label(number, 1)))
+        .into_iter()
+        .fold(0.0, f32::max);
+    let label_stride = label_stride(points_per_bar, label_width, LABEL_GAP);
+    let line_stride = bar_line_stride(info, crate::types::MIN_STEP_GAP);
+
+    // The time signature of the bar before the first step, if any.
+    let mut prev_time_sig = steps.first().and_then(|step| {
+        let start = step.ticks - step.index_in_bar as f32 * step.step_ticks;
+        let prev = info.bar_at_ticks(start - 0.5);
+        (prev.tick_range.start < start).then_some(prev.time_sig)
+    });
+
+    let painter = ui.painter();
+    let mut shapes = vec![];
+    let mut number = first_number;
+    let mut after = f32::NEG_INFINITY;
+    for step in steps {
+        let x = rect.left() + step.x;
+        match step.kind {
+            StepKind::Bar => {
+                let label = RulerMode::BarsBeats.label(number, 1);
+                let meter_change = prev_time_sig.is_some_and(|sig| sig != step.time_sig);
+                if meter_change {
+                    shapes.push(lines.meter(painter, x));
+                    let sig = step.time_sig;
+                    labels.paint(ui, x, format!("{} {}/{}", label, sig.top, sig.bottom), &mut after);
+                } else {
+                    let labelled = number.rem_euclid(label_stride as i64) == 0;
+                    if labelled || number.rem_euclid(line_stride as i64) == 0 {
+                        shapes.push(lines.major(painter, x));
+                    }
+                    if labelled {
+                        labels.paint(ui, x, label, &mut after);
+                    }
+                }
+                number += 1;
+            }
+            StepKind::Beat { .. } => shapes.push(lines.minor(painter, x, true)),
+            StepKind::Subdivision { .. } => shapes.push(lines.minor(painter, x, false)),
+        }
+        prev_time_sig = Some(step.time_sig);
+    }
+    shapes
+}
 
-    let visible_len = w;
-    let info = api.info();
+/// Paint lines and labels across the ruler for one of the time based modes.
+///
+/// Time is counted in whole units of the mode, e.g. frames for `Timecode`, and converted to ticks
+/// via `MusicalInfo::seconds_to_ticks` so that the lines follow any tempo changes. Spacing is
+/// chosen at the slowest tempo so that it doesn't change while scrolling.
+fn paint_time(
+    ui: &egui::Ui,
+    info: &dyn MusicalInfo,
+    mode: RulerMode,
+    lines: &Lines,
+    labels: &Labels,
+) -> Vec<egui::Shape> {
+    let rect = lines.rect;
     let ticks_per_point = info.ticks_per_point();
-    let visible_ticks = ticks_per_point * visible_len;
-    
-    // Calculate ticks per second (1 bar = 1 second) - same logic as grid
-    let ticks_per_beat = info.ticks_per_beat() as f32;
-    const BEATS_PER_BAR: f32 = 4.0; // 4/4 time signature
-    let ticks_per_bar = ticks_per_beat * BEATS_PER_BAR;
-    let ticks_per_second = ticks_per_bar; // 1 bar = 1 second
-    
-    // Maximum 10 lines per second = 0.1 second intervals - same as grid
-    const MAX_LINES_PER_SECOND: f32 = 10.0;
-    let ticks_per_line = ticks_per_second / MAX_LINES_PER_SECOND; // ticks per 0.1 second
-    
-    // Get timeline start to calculate absolute positions
     let timeline_start = info.timeline_start().unwrap_or(0.0);
-    
-    // Calculate the starting tick for the visible area (relative to timeline start)
-    // The visible area starts at tick 0 relative to timeline_start
-    let start_tick_relative = 0.0;
-    
-    // Find the first line position (snap to 0.1 second intervals) - same as grid
-    // We need to find the first 0.1 second interval that's visible
-    // Convert relative start to absolute, then find the first interval
-    let absolute_start_tick = timeline_start + start_tick_relative;
-    let absolute_start_seconds = absolute_start_tick / ticks_per_second;
-    // Find the first 0.1 second interval that's >= absolute_start_seconds
-    let first_line_seconds = (absolute_start_seconds * MAX_LINES_PER_SECOND).floor() / MAX_LINES_PER_SECOND;
-    let first_line_absolute_tick = first_line_seconds * ticks_per_second;
-    // Convert back to relative tick
-    let first_line_tick_relative = first_line_absolute_tick - timeline_start;
-    
-    // Draw ruler lines using same logic as grid
-    let mut current_tick_relative = first_line_tick_relative;
-    let mut last_x = f32::NEG_INFINITY;
-    let mut last_bar_number_at_x: Option<(u32, f32)> = None; // Track (bar_number, x_position)
-    
-    while current_tick_relative <= visible_ticks {
-        // Convert relative tick to x position - same calculation as grid
-        let x = rect.left() + (current_tick_relative / ticks_per_point);
-        
-        // Determine if this is a whole second (bar) or subdivision
-        let absolute_tick = timeline_start + current_tick_relative;
-        let seconds = absolute_tick / ticks_per_second;
-        let is_whole_second = (seconds % 1.0).abs() < 0.001;
-        
-        // Check if line is too close to the previous one (less than MIN_STEP_GAP pixels)
-        let line_too_close = (x - last_x).abs() < crate::types::MIN_STEP_GAP && last_x != f32::NEG_INFINITY;
-        
-        // Draw the line with appropriate style (skip subdivisions if too close, but always draw whole seconds)
-        if is_whole_second {
-            // Whole second (bar) - always draw the line, even if close (but might be shorter)
-            stroke.color = bar_color;
-            let a = egui::Pos2::new(x, rect.top());
-            let b = egui::Pos2::new(x, bar_y);
-            ui.painter().line_segment([a, b], stroke);
-            
-            // Draw bar number - always try to draw if it's a different bar or at a different x position
-            let bar_number = seconds.floor() as u32;
-            let bar_number = bar_number.min(500);
-            
-            // Draw if it's a new bar number OR if it's at a significantly different x position
-            // This ensures numbers show up even when zooming changes the spacing
-            let should_draw_number = match last_bar_number_at_x {
-                None => true, // First bar number
-                Some((last_bar, last_x_pos)) => {
-                    // Draw if different bar number OR if x position changed significantly (more than 5px)
-                    bar_number != last_bar || (x - last_x_pos).abs() > 5.0
-                }
-            };
-            
-            if should_draw_number {
-                const MIN_LEFT_MARGIN: f32 = 20.0;
-                const MIN_RIGHT_MARGIN: f32 = 30.0;
-                let text = format!("{}", bar_number);
-                let estimated_text_width = text.len() as f32 * 6.0;
-                let fits_left = x >= rect.left() + MIN_LEFT_MARGIN;
-                let fits_right = x + estimated_text_width <= rect.right() - MIN_RIGHT_MARGIN;
-                
-                if fits_left && fits_right {
-                    let text_color = vis.fg_stroke.color;
-                    let text_pos = egui::Pos2::new(x + 2.0, rect.center().y);
-                    let default_font_size = ui.style().text_styles.get(&egui::TextStyle::Body)
-                        .map(|f| f.size)
-                        .unwrap_or(14.0);
-                    let small_font = egui::FontId::new(default_font_size * 0.75, egui::FontFamily::Proportional);
-                    ui.painter().text(text_pos, egui::Align2::LEFT_CENTER, text, small_font, text_color);
-                    last_bar_number_at_x = Some((bar_number, x));
-                }
+    let visible_ticks = rect.width() * ticks_per_point;
+    let Some(unit_seconds) = mode.unit_seconds() else {
+        return vec![];
+    };
+    let slowest_bpm = info
+        .tempo_map()
+        .and_then(|map| map.events().iter().map(|&(_, bpm)| bpm).reduce(f32::min))
+        .unwrap_or_else(|| info.tempo_bpm());
+    let points_per_second = slowest_bpm / 60.0 * info.ticks_per_beat() as f32 / ticks_per_point;
+    let points_per_unit = points_per_second as f64 * unit_seconds;
+    if points_per_unit <= 0.0 || !points_per_unit.is_finite() {
+        return vec![];
+    }
+
+    // The visible range in units.
+    let start_seconds = info.ticks_to_seconds(timeline_start) as f64;
+    let end_seconds = info.ticks_to_seconds(timeline_start + visible_ticks) as f64;
+    let first_unit = (start_seconds / unit_seconds).floor() as i64;
+    let last_unit = (end_seconds / unit_seconds).ceil() as i64;
+
+    // The closest spacing of lines, and the closest spacing of labels that don't overlap.
+    let fits = |interval: u64, spacing: f32| interval as f64 * points_per_unit >= spacing as f64;
+    let Some(minor) = mode
+        .line_intervals()
+        .into_iter()
+        .find(|&i| fits(i, crate::types::MIN_STEP_GAP))
+    else {
+        return vec![];
+    };
+    let label_width = labels.width(ui, mode.label(last_unit.max(0), minor));
+    let major = mode
+        .label_intervals()
+        .into_iter()
+        .find(|&i| i % minor == 0 && fits(i, label_width + LABEL_GAP))
+        .unwrap_or(u64::MAX);
+    let (minor, major) = (minor as i64, major.min(i64::MAX as u64) as i64);
+
+    let painter = ui.painter();
+    let mut shapes = vec![];
+    let mut line = first_unit.div_euclid(minor);
+    let mut after = f32::NEG_INFINITY;
+    loop {
+        let unit = line * minor;
+        let seconds = (unit as f64 * unit_seconds) as f32;
+        let tick = info.seconds_to_ticks(seconds) - timeline_start;
+        if tick > visible_ticks || unit > last_unit {
+            break;
+        }
+        line += 1;
+        if tick < 0.0 {
+            continue;
+        }
+        let x = rect.left() + tick / ticks_per_point;
+        if unit.rem_euclid(major) == 0 {
+            shapes.push(lines.major(painter, x));
+            labels.paint(ui, x, mode.label(unit, major as u64), &mut after);
+        } else {
+            shapes.push(lines.minor(painter, x, (unit / minor).rem_euclid(2) == 0));
+        }
+    }
+    shapes
+}
+
+/// The distance from a line to the start of its label.
+const LABEL_OFFSET: f32 = 2.0;
+/// The minimum gap between consecutive labels.
+const LABEL_GAP: f32 = 6.0;
+
+/// The interval between labels, in units of e.g. bars or seconds, such that they never overlap.
+///
+/// Returns the smallest of 1, 2, 5, 10, 20, 50, 100 and so on for which consecutive labels at
+/// most `label_width` points wide and placed every `points_per_unit` points are at least `gap`
+/// points apart. The stride depends only on its arguments, so it remains stable while scrolling.
+/// Grids may use the same stride to emphasise the labelled lines.
+pub fn label_stride(points_per_unit: f32, label_width: f32, gap: f32) -> u32 {
+    let min_spacing = label_width + gap;
+    let mut magnitude: u32 = 1;
+    loop {
+        for stride in [magnitude, magnitude * 2, magnitude * 5] {
+            if stride as f32 * points_per_unit >= min_spacing {
+                return stride;
             }
-        } else if !line_too_close {
-            // Subdivision (0.1 second) - only draw if not too close
-            stroke.color = step_color;
-            // Alternate between step_even_y and step_odd_y for visual distinction
-            let subdivision_index = ((seconds * MAX_LINES_PER_SECOND) % MAX_LINES_PER_SECOND).floor() as usize;
-            let y = if subdivision_index % 2 == 0 {
-                step_even_y
-            } else {
-                step_odd_y
-            };
-            let a = egui::Pos2::new(x, rect.top());
-            let b = egui::Pos2::new(x, y);
-            ui.painter().line_segment([a, b], stroke);
         }
-        
-        // Update last_x only if we actually drew a line (or it's a whole second)
-        if !line_too_close || is_whole_second {
-            last_x = x;
+        match magnitude.checked_mul(10) {
+            Some(next) => magnitude = next,
+            None => return u32::MAX,
         }
-        current_tick_relative += ticks_per_line;
     }
+}
 
-    response
+/// The interval in bars between bar lines, e.g. for grids and rulers.
+///
+/// Returns `1` unless the bar at the start of the view is narrower than `min_step_gap` points, in
+/// which case bars are thinned to the smallest `label_stride` that spaces them far enough apart.
+pub(crate) fn bar_line_stride(info: &dyn MusicalInfo, min_step_gap: f32) -> u32 {
+    let bar = info.bar_at_ticks(0.0);
+    let points_per_bar = (bar.tick_range.end - bar.tick_range.start) / info.ticks_per_point();
+    label_stride(points_per_bar, 0.0, min_step_gap)
+}
+
+/// Where a `Step` lies within its bar.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StepKind {
+    /// The start of the bar.
+    Bar,
+    /// The start of the beat with the given index within the bar, other than the first.
+    Beat { index: u32 },
+    /// A subdivision of the beat with the given index within the bar.
+    ///
+    /// `index` counts the subdivisions within the beat, starting from `1` for the first
+    /// subdivision after the start of the beat.
+    Subdivision { of_beat: u32, index: u32 },
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -185,14 +839,28 @@ pub struct Step {
     ///
     /// The first step always indicates the start of the bar.
     pub index_in_bar: usize,
+    /// Whether the step is the start of a bar, a beat or a subdivision of a beat.
+    pub kind: StepKind,
+    /// The time signature of the step's bar.
+    pub time_sig: TimeSig,
+    /// The interval in ticks between the steps of this step's bar.
+    ///
+    /// Either a subdivision of the bar's beat, a whole beat, or the whole bar if even a beat is
+    /// narrower than the minimum gap between steps.
+    pub step_ticks: f32,
     /// The position of the step in ticks from the beginning of the start of the visible area.
     pub ticks: f32,
     /// The location of the step along the x axis from the start of the ruler.
     pub x: f32,
 }
 
-#[derive(Clone, Debug)]
-pub struct Steps {
+/// An iterator over the steps of the bars within the visible area, e.g. for drawing a ruler.
+///
+/// Each bar is divided into steps of its beat, halved while the steps remain at least
+/// `min_step_gap` points apart. If even a single beat is narrower than that, the bar is a single
+/// step. Steps before the start of the visible area are skipped without being visited.
+pub struct Steps<'a> {
+    info: &'a dyn MusicalInfo,
     ticks_per_beat: f32,
     ticks_per_point: f32,
     visible_ticks: f32,
@@ -203,72 +871,159 @@ pub struct Steps {
     ticks: f32,
 }
 
-impl Steps {
-    /// Create a new `Steps`.
-    pub fn new(api: &dyn MusicalInfo, visible_len: f32, min_step_gap: f32) -> Self {
-        let ticks_per_beat = api.ticks_per_beat() as f32;
-        let ticks_per_point = api.ticks_per_point();
+impl<'a> Steps<'a> {
+    /// Create a new `Steps` over a visible area `visible_len` points wide.
+    pub fn new(info: &'a dyn MusicalInfo, visible_len: f32, min_step_gap: f32) -> Self {
+        let ticks_per_beat = info.ticks_per_beat() as f32;
+        let ticks_per_point = info.ticks_per_point();
         let visible_ticks = ticks_per_point * visible_len;
         let min_step_ticks = ticks_per_point * min_step_gap;
-        Self {
+        let mut steps = Self {
+            info,
             ticks_per_beat,
             ticks_per_point,
             visible_ticks,
             min_step_ticks,
             index_in_bar: 0,
             step_ticks: 0.0,
-            bar: api.bar_at_ticks(0.0),
+            bar: info.bar_at_ticks(0.0),
             ticks: 0.0,
-        }
+        };
+        steps.begin_bar();
+        steps
     }
 
-    /// Produce the next `Step`.
-    pub fn next(&mut self, api: &dyn MusicalInfo) -> Option<Step> {
-        'bars: loop {
-            // If this is the first step of the bar, update step interval.
-            if self.index_in_bar == 0 {
-                self.ticks = self.bar.tick_range.start;
-                let mut beat_subdivs = self.bar.time_sig.bottom / 4;
-                self.step_ticks = self.ticks_per_beat as f32 / beat_subdivs as f32;
-                if self.step_ticks >= self.min_step_ticks {
-                    loop {
-                        let new_beat_subdivs = beat_subdivs * 2;
-                        let new_step_ticks = self.ticks_per_beat as f32 / new_beat_subdivs as f32;
-                        if new_step_ticks <= self.min_step_ticks {
-                            break;
-                        }
-                        beat_subdivs = new_beat_subdivs;
-                        self.step_ticks = new_step_ticks;
-                    }
-                } else {
-                    self.step_ticks = self.bar.tick_range.end - self.bar.tick_range.start;
+    /// Choose the step interval for the current bar and move to its first visible step.
+    fn begin_bar(&mut self) {
+        let mut beat_subdivs = (self.bar.time_sig.bottom / 4).max(1);
+        self.step_ticks = self.ticks_per_beat / beat_subdivs as f32;
+        if self.step_ticks >= self.min_step_ticks {
+            while let Some(new_beat_subdivs) = beat_subdivs.checked_mul(2) {
+                let new_step_ticks = self.ticks_per_beat / new_beat_subdivs as f32;
+                // Steps are never finer than a tick, which also bounds the loop when the view is
+                // zoomed in so far that `min_step_ticks` is zero.
+                if new_step_ticks <= self.min_step_ticks || new_step_ticks < 1.0 {
+                    break;
                 }
+                beat_subdivs = new_beat_subdivs;
+                self.step_ticks = new_step_ticks;
             }
+        } else {
+            self.step_ticks = self.bar.tick_range.end - self.bar.tick_range.start;
+        }
+
+        // Jump straight to the first step at or after the start of the visible area.
+        let start = self.bar.tick_range.start;
+        self.index_in_bar = if start < 0.0 && self.step_ticks > 0.0 {
+            (-start / self.step_ticks).ceil() as usize
+        } else {
+            0
+        };
+        self.ticks = start + self.index_in_bar as f32 * self.step_ticks;
+    }
+
+    /// The interval in ticks between the steps of the current bar.
+    pub fn step_ticks(&self) -> f32 {
+        self.step_ticks
+    }
 
-            'ticks: loop {
-                if self.ticks > self.visible_ticks {
+    /// The kind of the step at the given index and tick within the current bar.
+    fn kind_at(&self, index_in_bar: usize, ticks: f32) -> StepKind {
+        if index_in_bar == 0 {
+            return StepKind::Bar;
+        }
+        // The length of one beat of this bar's time signature, e.g. an eighth note in 7/8.
+        let beat_ticks = self.ticks_per_beat * 4.0 / self.bar.time_sig.bottom.max(1) as f32;
+        let offset = ticks - self.bar.tick_range.start;
+        // Steps either divide a beat or span whole beats, so round away float error.
+        let beats = offset / beat_ticks;
+        let beat = (beats + 1e-3).floor();
+        let index = ((beats - beat) * beat_ticks / self.step_ticks).round() as u32;
+        match index {
+            0 => StepKind::Beat { index: beat as u32 },
+            index => StepKind::Subdivision { of_beat: beat as u32, index },
+        }
+    }
+}
+
+impl Iterator for Steps<'_> {
+    type Item = Step;
+
+    fn next(&mut self) -> Option<Step> {
+        loop {
+            if self.ticks > self.visible_ticks || self.step_ticks <= 0.0 {
+                return None;
+            }
+            if self.ticks >= self.bar.tick_range.end {
+                // Move on to the next bar, stopping if the host has no more bars to offer.
+                let next = self.info.bar_at_ticks(self.bar.tick_range.end + 0.5);
+                if next.tick_range.start <= self.bar.tick_range.start {
                     return None;
                 }
-                if self.ticks >= self.bar.tick_range.end {
-                    self.index_in_bar = 0;
-                    self.bar = api.bar_at_ticks(self.bar.tick_range.end + 0.5);
-                    continue 'bars;
-                }
-                let index_in_bar = self.index_in_bar;
-                let ticks = self.ticks;
-                self.index_in_bar += 1;
-                self.ticks += self.step_ticks;
-                if ticks < 0.0 {
-                    continue 'ticks;
-                }
-                let x = ticks / self.ticks_per_point;
-                let step = Step {
-                    index_in_bar,
-                    ticks,
-                    x,
-                };
-                return Some(step);
+                self.bar = next;
+                self.begin_bar();
+                continue;
             }
+            let index_in_bar = self.index_in_bar;
+            let ticks = self.ticks;
+            self.index_in_bar += 1;
+            self.ticks = self.bar.tick_range.start + self.index_in_bar as f32 * self.step_ticks;
+            let x = ticks / self.ticks_per_point;
+            let kind = self.kind_at(index_in_bar, ticks);
+            let step_ticks = self.step_ticks;
+            let time_sig = self.bar.time_sig;
+            return Some(Step { index_in_bar, kind, time_sig, step_ticks, ticks, x });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::SimpleTimelineState;
+
+    /// A 4/4 timeline at 960 PPQN whose view starts at the given absolute tick.
+    fn state(timeline_start: f64, zoom: f32) -> SimpleTimelineState {
+        let mut state = SimpleTimelineState::new();
+        state.timeline_start = timeline_start;
+        state.zoom = zoom;
+        state
+    }
+
+    #[test]
+    fn steps_from_bar_boundary() {
+        // 60 ticks per point, so beats are 16 points apart and eighths would be 8
+        let state = state(2.0 * 3840.0, 1.0);
+        let steps: Vec<_> = Steps::new(&state, 800.0, 10.0).collect();
+        assert_eq!(steps[0].index_in_bar, 0);
+        assert_eq!(steps[0].ticks, 0.0);
+        assert!(matches!(steps[0].kind, StepKind::Bar));
+        assert!(steps.iter().all(|step| step.step_ticks == 960.0));
+        assert!(matches!(steps[1].kind, StepKind::Beat { index: 1 }));
+        assert!(matches!(steps[4].kind, StepKind::Bar));
+        assert_eq!(steps.last().unwrap().ticks, 48_000.0);
+    }
+
+    #[test]
+    fn steps_from_mid_beat() {
+        // The view starts half way through the first beat of the second bar
+        let state = state(3840.0 + 480.0, 1.0);
+        let steps: Vec<_> = Steps::new(&state, 800.0, 10.0).collect();
+        assert_eq!(steps[0].index_in_bar, 1);
+        assert_eq!(steps[0].ticks, 480.0);
+        assert_eq!(steps[0].x, 8.0);
+        assert!(matches!(steps[0].kind, StepKind::Beat { index: 1 }));
+        assert!(steps.iter().all(|step| step.ticks >= 0.0));
+    }
+
+    #[test]
+    fn steps_with_tiny_ticks_per_point() {
+        for zoom in [1e-9, 0.0] {
+            let state = state(3840.0, zoom);
+            let steps = Steps::new(&state, 800.0, 10.0);
+            // 960 ticks per beat halves down to 1.875 ticks, the finest step of at least a tick
+            assert_eq!(steps.step_ticks(), 1.875);
+            assert_eq!(steps.count(), 1);
         }
     }
 }
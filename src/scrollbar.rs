@@ -0,0 +1,188 @@
+use crate::interaction;
+
+/// The height of the scrollbar, which fits within a 20px bottom bar.
+const HEIGHT: f32 = 12.0;
+/// The distance from either end of the thumb within which dragging zooms rather than scrolls.
+const EDGE_GRAB: f32 = 4.0;
+/// The minimum width of the thumb so that it remains easy to grab.
+const MIN_THUMB_WIDTH: f32 = 3.0 * EDGE_GRAB;
+
+/// The part of the viewport being dragged, stored in egui memory.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum ViewportDrag {
+    /// The body of the viewport, scrolling the view.
+    Thumb,
+    /// The start of the viewport, zooming while keeping the end of the view in place.
+    Start,
+    /// The end of the viewport, zooming while keeping the start of the view in place.
+    End,
+}
+
+/// The visible range of the timeline within an overview of the whole timeline.
+pub(crate) struct Viewport {
+    /// The area spanned by the visible range, at least `MIN_THUMB_WIDTH` wide.
+    pub(crate) rect: egui::Rect,
+    /// The absolute range of ticks spanned by the whole overview, starting before tick `0.0` by
+    /// any pre-roll.
+    pub(crate) tick_range: std::ops::Range<f32>,
+    /// Whether the viewport is hovered.
+    pub(crate) hovered: bool,
+    /// Whether the viewport is being dragged to scroll or zoom.
+    pub(crate) dragged: bool,
+}
+
+/// Show a horizontal scrollbar visualising the view relative to the whole timeline.
+///
+/// The thumb spans the visible ticks of a timeline `timeline_width` points wide. See `viewport`
+/// for how it is interacted with.
+pub(crate) fn show(
+    ui: &mut egui::Ui,
+    id: egui::Id,
+    timeline_width: f32,
+    pre_roll: f32,
+    api: &mut dyn crate::TimelineApi,
+) -> egui::Response {
+    let size = egui::Vec2::new(ui.available_width(), HEIGHT.min(ui.available_height()));
+    let (rect, response) = ui.allocate_exact_size(size, egui::Sense::click_and_drag());
+    let drag_id = id.with("scrollbar_drag");
+    let Some(viewport) = viewport(ui, drag_id, rect, &response, timeline_width, pre_roll, api) else {
+        return response;
+    };
+
+    // Paint the trough and thumb.
+    let visuals = ui.visuals();
+    let rounding = rect.height() * 0.5;
+    ui.painter().rect_filled(rect, rounding, visuals.extreme_bg_color);
+    let widget = if viewport.dragged {
+        &visuals.widgets.active
+    } else if viewport.hovered {
+        &visuals.widgets.hovered
+    } else {
+        &visuals.widgets.inactive
+    };
+    ui.painter().rect_filled(viewport.rect.intersect(rect), rounding, widget.bg_fill);
+    response
+}
+
+/// Handle interaction with the visible range of the timeline drawn across the given `rect`.
+///
+/// Dragging the viewport scrolls the view, dragging either end of the viewport zooms, and
+/// clicking either side of the viewport pages towards the pointer. Unbounded timelines are
+/// treated as ending one view beyond the visible area, so that there is always room to scroll
+/// further. The overview starts at `-pre_roll`. The drag in progress is stored in egui memory
+/// under `drag_id`.
+///
+/// Returns the viewport prior to any scroll or zoom, or `None` if `rect` has no width.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn viewport(
+    ui: &egui::Ui,
+    drag_id: egui::Id,
+    rect: egui::Rect,
+    response: &egui::Response,
+    timeline_width: f32,
+    pre_roll: f32,
+    api: &mut dyn crate::TimelineApi,
+) -> Option<Viewport> {
+    let trough_w = rect.width();
+    if trough_w <= 0.0 || timeline_width <= 0.0 {
+        return None;
+    }
+
+    // The view and timeline extents in ticks.
+    let timeline_start = api.timeline_start();
+    let visible_ticks = api.musical_ruler_info().ticks_per_point() * timeline_width;
+    let first_tick = -pre_roll.max(0.0);
+    let last_tick = api
+        .timeline_end()
+        .unwrap_or(timeline_start + visible_ticks * 2.0)
+        .max(timeline_start + visible_ticks);
+    let total_ticks = last_tick - first_tick;
+    let tick_to_x = |tick: f32| rect.left() + (tick - first_tick) / total_ticks * trough_w;
+    let x_to_tick = |x: f32| first_tick + (x - rect.left()) / trough_w * total_ticks;
+
+    // The thumb, widened about its centre if it would be too narrow to grab.
+    let thumb_x = {
+        let (left, right) = (tick_to_x(timeline_start), tick_to_x(timeline_start + visible_ticks));
+        let pad = ((MIN_THUMB_WIDTH - (right - left)) * 0.5).max(0.0);
+        (left - pad)..=(right + pad)
+    };
+    let thumb = egui::Rect::from_x_y_ranges(thumb_x, rect.y_range());
+
+    // The part of the thumb under the pointer, if any.
+    let part_at = |pos: egui::Pos2| {
+        if (pos.x - thumb.left()).abs() <= EDGE_GRAB {
+            Some(ViewportDrag::Start)
+        } else if (pos.x - thumb.right()).abs() <= EDGE_GRAB {
+            Some(ViewportDrag::End)
+        } else if thumb.contains(pos) {
+            Some(ViewportDrag::Thumb)
+        } else {
+            None
+        }
+    };
+    let hovered_part = response.hover_pos().and_then(part_at);
+    if matches!(hovered_part, Some(ViewportDrag::Start | ViewportDrag::End)) {
+        ui.ctx().set_cursor_icon(egui::CursorIcon::ResizeHorizontal);
+    }
+
+    if response.drag_started_by(egui::PointerButton::Primary)
+        && let Some(part) = response.interact_pointer_pos().and_then(part_at)
+    {
+        ui.data_mut(|d| d.insert_temp(drag_id, part));
+    }
+    let drag = ui.data(|d| d.get_temp::<ViewportDrag>(drag_id));
+    if response.dragged_by(egui::PointerButton::Primary)
+        && let Some(drag) = drag
+        && let Some(pos) = response.interact_pointer_pos()
+    {
+        let pointer_tick = x_to_tick(pos.x).max(first_tick);
+        let view_end = timeline_start + visible_ticks;
+        match drag {
+            ViewportDrag::Thumb => {
+                let ticks = response.drag_delta().x / trough_w * total_ticks;
+                let target_start = api.timeline_start_f64() + ticks as f64;
+                interaction::scroll_to_start(api, target_start, visible_ticks, pre_roll);
+            }
+            ViewportDrag::Start => {
+                let new_visible = view_end - pointer_tick.min(view_end - 1.0);
+                zoom_to(api, view_end, visible_ticks, new_visible, timeline_width, pre_roll);
+            }
+            ViewportDrag::End => {
+                let new_visible = pointer_tick.max(timeline_start + 1.0) - timeline_start;
+                zoom_to(api, timeline_start, visible_ticks, new_visible, timeline_width, pre_roll);
+            }
+        }
+    } else if drag.is_some() && !response.dragged() {
+        ui.data_mut(|d| d.remove::<ViewportDrag>(drag_id));
+    }
+
+    // Clicking the trough either side of the thumb pages towards the pointer.
+    if response.clicked()
+        && let Some(pos) = response.interact_pointer_pos()
+        && part_at(pos).is_none()
+    {
+        let pages = if pos.x < thumb.left() { -1.0 } else { 1.0 };
+        let target_start = api.timeline_start_f64() + (pages * visible_ticks) as f64;
+        interaction::scroll_to_start(api, target_start, visible_ticks, pre_roll);
+    }
+
+    Some(Viewport { rect: thumb, tick_range: first_tick..last_tick, hovered: hovered_part.is_some(), dragged: drag.is_some() })
+}
+
+/// Zoom so that `new_visible` ticks are visible, keeping the tick `anchor_ticks` in place.
+fn zoom_to(
+    api: &mut dyn crate::TimelineApi,
+    anchor_ticks: f32,
+    visible_ticks: f32,
+    new_visible: f32,
+    timeline_width: f32,
+    pre_roll: f32,
+) {
+    if visible_ticks <= 0.0 || new_visible <= 0.0 {
+        return;
+    }
+    let y_delta = interaction::zoom_delta_for_scale(new_visible / visible_ticks);
+    if y_delta.abs() > 0.001 {
+        interaction::zoom_clamped(api, anchor_ticks, y_delta, timeline_width, pre_roll);
+    }
+}
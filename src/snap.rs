@@ -0,0 +1,289 @@
+use crate::{
+    ruler::MusicalInfo,
+    types::{Bar, TimeSig},
+};
+
+/// The grid that interactive tick positions are quantized to.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SnapMode {
+    /// Positions are not quantized.
+    #[default]
+    Off,
+    /// Snap to the nearest bar line.
+    Bar,
+    /// Snap to the nearest beat of the bar's time signature.
+    Beat,
+    /// Snap to the nearest of the given number of equal divisions of a beat.
+    Division(u32),
+    /// Snap to the nearest note of the given value, i.e. the given number of equal divisions of
+    /// a whole note, e.g. `Note(8)` for eighth notes or `Note(12)` for eighth-note triplets.
+    Note(u32),
+}
+
+impl SnapMode {
+    /// The modes offered by `snap_selector`, in the order they are listed.
+    pub const SELECTOR: [Self; 7] = [
+        Self::Off,
+        Self::Bar,
+        Self::Note(2),
+        Self::Note(4),
+        Self::Note(8),
+        Self::Note(16),
+        Self::Note(12),
+    ];
+
+    /// The mode to apply given the currently held modifiers.
+    ///
+    /// Holding `Alt` temporarily bypasses snapping, while holding `Shift` temporarily snaps to the
+    /// next `coarser` unit. Gestures that use `Shift` themselves apply `with_bypass` instead.
+    pub fn with_modifiers(self, modifiers: egui::Modifiers) -> Self {
+        if modifiers.shift {
+            self.with_bypass(modifiers).coarser()
+        } else {
+            self.with_bypass(modifiers)
+        }
+    }
+
+    /// The mode to apply given the currently held modifiers, for gestures where `Shift` already
+    /// has a meaning, e.g. Shift-dragging a time selection or Shift-clicking to extend a
+    /// selection.
+    ///
+    /// Holding `Alt` temporarily bypasses snapping, while `Shift` is ignored.
+    pub fn with_bypass(self, modifiers: egui::Modifiers) -> Self {
+        if modifiers.alt { Self::Off } else { self }
+    }
+
+    /// The next coarser snap unit, e.g. quarter notes for eighth notes or beats for half beats.
+    ///
+    /// Divisions and notes are halved while they divide evenly, after which beats and notes
+    /// promote to `Bar`. `Off` and `Bar` are unchanged.
+    pub fn coarser(self) -> Self {
+        match self {
+            Self::Off | Self::Bar => self,
+            Self::Beat => Self::Bar,
+            Self::Division(n) if n > 1 && n % 2 == 0 => Self::Division(n / 2),
+            Self::Division(_) => Self::Beat,
+            Self::Note(n) if n > 1 && n % 2 == 0 => Self::Note(n / 2),
+            Self::Note(_) => Self::Bar,
+        }
+    }
+
+    /// A short name for the mode, e.g. "1/8" for eighth notes or "1/8 triplet" for `Note(12)`.
+    pub fn label(&self) -> String {
+        match *self {
+            Self::Off => "Off".to_string(),
+            Self::Bar => "Bar".to_string(),
+            Self::Beat => "Beat".to_string(),
+            Self::Division(n) => format!("1/{n} beat"),
+            Self::Note(n) if n % 3 == 0 => format!("1/{} triplet", n / 3 * 2),
+            Self::Note(n) => format!("1/{n}"),
+        }
+    }
+
+    /// The distance between snap points in ticks within a bar of the given length and beat, given
+    /// the length of a quarter note.
+    ///
+    /// Returns `None` when snapping is `Off`.
+    fn step_ticks(&self, bar_ticks: f32, beat_ticks: f32, quarter_ticks: f32) -> Option<f32> {
+        match *self {
+            Self::Off => None,
+            Self::Bar => Some(bar_ticks),
+            Self::Beat => Some(beat_ticks),
+            Self::Division(n) => Some(beat_ticks / n.max(1) as f32),
+            Self::Note(n) => Some(quarter_ticks * 4.0 / n.max(1) as f32),
+        }
+    }
+}
+
+/// A compact dropdown for choosing between the `SnapMode::SELECTOR` modes, e.g. for the top panel
+/// or the bottom bar.
+///
+/// The returned response is marked as changed when a different mode is chosen. See
+/// `SetPlayhead::snap_selector` for a selector that remembers the timeline's mode.
+pub fn snap_selector(ui: &mut egui::Ui, mode: &mut SnapMode) -> egui::Response {
+    let mut changed = false;
+    let mut response = egui::ComboBox::from_id_salt(ui.next_auto_id().with("snap_selector"))
+        .selected_text(format!("Snap: {}", mode.label()))
+        .width(0.0)
+        .show_ui(ui, |ui| {
+            for m in SnapMode::SELECTOR {
+                changed |= ui.selectable_value(mode, m, m.label()).changed();
+            }
+        })
+        .response;
+    if changed {
+        response.mark_changed();
+    }
+    response.on_hover_text("Snap, bypassed by holding Alt or made coarser by holding Shift")
+}
+
+/// The distance in ticks between snap points of the given `mode` within the bar at `tick`.
+///
+/// `tick` is relative to the start of the visible area. Returns `None` when snapping is `Off`.
+pub fn step_ticks_at(tick: f32, info: &dyn MusicalInfo, mode: SnapMode) -> Option<f32> {
+    let bar = info.bar_at_ticks(tick);
+    let bar_ticks = bar.tick_range.end - bar.tick_range.start;
+    let quarter_ticks = info.ticks_per_beat() as f32;
+    let beat_ticks = quarter_ticks * 4.0 / bar.time_sig.bottom.max(1) as f32;
+    mode.step_ticks(bar_ticks, beat_ticks, quarter_ticks)
+}
+
+/// Quantize the given tick to the nearest snap point of the given `mode`.
+///
+/// Like `MusicalInfo::bar_at_ticks`, `tick` is relative to the start of the visible area. Snap
+/// points are measured from the start of the bar containing `tick`, so bars with differing time
+/// signatures each snap to their own beats. The result never lies beyond the end of that bar.
+pub fn snap_tick(tick: f32, info: &dyn MusicalInfo, mode: SnapMode) -> f32 {
+    if mode == SnapMode::Off {
+        return tick;
+    }
+    let bar = info.bar_at_ticks(tick);
+    let bar_ticks = bar.tick_range.end - bar.tick_range.start;
+    let quarter_ticks = info.ticks_per_beat() as f32;
+    let beat_ticks = quarter_ticks * 4.0 / bar.time_sig.bottom.max(1) as f32;
+    match mode.step_ticks(bar_ticks, beat_ticks, quarter_ticks) {
+        Some(step) if step > 0.0 => {
+            let steps = ((tick - bar.tick_range.start) / step).round();
+            (bar.tick_range.start + steps * step).min(bar.tick_range.end)
+        }
+        _ => tick,
+    }
+}
+
+/// The bars across the visible area, captured from the timeline's `MusicalInfo` each frame.
+///
+/// Lets the widgets that are only given a `TimelineCtx` snap positions without the host's
+/// `PlayheadApi`. Ticks beyond the captured bars continue the nearest bar's length and time
+/// signature.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SnapGrid {
+    ticks_per_beat: u32,
+    bars: Vec<Bar>,
+}
+
+impl SnapGrid {
+    /// The most bars captured, after which the last bar is repeated.
+    const MAX_BARS: usize = 256;
+
+    /// Capture the bars from the start of the visible area to just past `visible_ticks`.
+    pub(crate) fn capture(info: &dyn MusicalInfo, visible_ticks: f32) -> Self {
+        let mut bars = vec![info.bar_at_ticks(0.0)];
+        while let Some(last) = bars.last()
+            && last.tick_range.end <= visible_ticks
+            && bars.len() < Self::MAX_BARS
+        {
+            // Stop if the host has no more bars to offer.
+            let next = info.bar_at_ticks(last.tick_range.end + 0.5);
+            if next.tick_range.start <= last.tick_range.start {
+                break;
+            }
+            bars.push(next);
+        }
+        Self { ticks_per_beat: info.ticks_per_beat(), bars }
+    }
+}
+
+impl MusicalInfo for SnapGrid {
+    fn ticks_per_beat(&self) -> u32 {
+        self.ticks_per_beat
+    }
+
+    fn bar_at_ticks(&self, tick: f32) -> Bar {
+        let (Some(first), Some(last)) = (self.bars.first(), self.bars.last()) else {
+            let time_sig = TimeSig { top: 4, bottom: 4 };
+            return Bar { tick_range: 0.0..0.0, time_sig };
+        };
+        // Repeat the nearest bar outside of the captured range.
+        let repeat = |bar: &Bar| {
+            let len = bar.tick_range.end - bar.tick_range.start;
+            if len <= 0.0 {
+                return bar.clone();
+            }
+            let start = bar.tick_range.start + ((tick - bar.tick_range.start) / len).floor() * len;
+            Bar { tick_range: start..start + len, time_sig: bar.time_sig }
+        };
+        if tick < first.tick_range.start {
+            return repeat(first);
+        }
+        if tick >= last.tick_range.end {
+            return repeat(last);
+        }
+        let index = self.bars.partition_point(|bar| bar.tick_range.end <= tick);
+        self.bars[index].clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 960 PPQN timeline of 4/4 bars before tick 3840, one bar of 7/8, then 3/4 bars onwards.
+    struct Bars;
+
+    impl MusicalInfo for Bars {
+        fn ticks_per_beat(&self) -> u32 {
+            960
+        }
+
+        fn bar_at_ticks(&self, tick: f32) -> Bar {
+            let (time_sig, start, len) = if tick < 3840.0 {
+                (TimeSig { top: 4, bottom: 4 }, (tick / 3840.0).floor() * 3840.0, 3840.0)
+            } else if tick < 7200.0 {
+                (TimeSig { top: 7, bottom: 8 }, 3840.0, 3360.0)
+            } else {
+                (TimeSig { top: 3, bottom: 4 }, 7200.0 + ((tick - 7200.0) / 2880.0).floor() * 2880.0, 2880.0)
+            };
+            Bar { tick_range: start..start + len, time_sig }
+        }
+    }
+
+    #[test]
+    fn snap_near_zero() {
+        assert_eq!(snap_tick(100.0, &Bars, SnapMode::Off), 100.0);
+        assert_eq!(snap_tick(0.0, &Bars, SnapMode::Beat), 0.0);
+        assert_eq!(snap_tick(100.0, &Bars, SnapMode::Beat), 0.0);
+        assert_eq!(snap_tick(479.0, &Bars, SnapMode::Beat), 0.0);
+        assert_eq!(snap_tick(481.0, &Bars, SnapMode::Beat), 960.0);
+        assert_eq!(snap_tick(130.0, &Bars, SnapMode::Note(16)), 240.0);
+        // Just before zero the previous bar's grid ends at zero
+        assert_eq!(snap_tick(-100.0, &Bars, SnapMode::Beat), 0.0);
+        assert_eq!(snap_tick(-100.0, &Bars, SnapMode::Bar), 0.0);
+        assert_eq!(snap_tick(-600.0, &Bars, SnapMode::Beat), -960.0);
+    }
+
+    #[test]
+    fn snap_across_time_signatures() {
+        // Beats of the 7/8 bar are eighth notes measured from its start
+        assert_eq!(snap_tick(3840.0 + 700.0, &Bars, SnapMode::Beat), 4320.0);
+        // Quarter notes restart at each bar and never pass its end
+        assert_eq!(snap_tick(3840.0 + 1000.0, &Bars, SnapMode::Note(4)), 4800.0);
+        assert_eq!(snap_tick(7100.0, &Bars, SnapMode::Note(4)), 6720.0);
+        assert_eq!(snap_tick(7300.0, &Bars, SnapMode::Note(4)), 7200.0);
+        assert_eq!(snap_tick(7000.0, &Bars, SnapMode::Bar), 7200.0);
+        // Beats of the 3/4 bars are quarter notes again
+        assert_eq!(snap_tick(7200.0 + 500.0, &Bars, SnapMode::Beat), 8160.0);
+        assert_eq!(snap_tick(7200.0 + 2880.0 + 100.0, &Bars, SnapMode::Bar), 10080.0);
+    }
+
+    #[test]
+    fn shift_only_coarsens_outside_shift_gestures() {
+        let mode = SnapMode::Note(8);
+        assert_eq!(mode.with_modifiers(egui::Modifiers::NONE), mode);
+        assert_eq!(mode.with_modifiers(egui::Modifiers::SHIFT), SnapMode::Note(4));
+        assert_eq!(mode.with_modifiers(egui::Modifiers::ALT | egui::Modifiers::SHIFT), SnapMode::Off);
+        assert_eq!(mode.with_bypass(egui::Modifiers::SHIFT), mode);
+        assert_eq!(mode.with_bypass(egui::Modifiers::ALT), SnapMode::Off);
+    }
+
+    #[test]
+    fn snap_grid_matches_info() {
+        let grid = SnapGrid::capture(&Bars, 12_000.0);
+        let modes = [SnapMode::Bar, SnapMode::Beat, SnapMode::Division(3), SnapMode::Note(8)];
+        for mode in modes {
+            for tick in (-5000..20_000).step_by(37).map(|tick| tick as f32) {
+                assert_eq!(snap_tick(tick, &grid, mode), snap_tick(tick, &Bars, mode), "{mode:?} at {tick}");
+            }
+        }
+    }
+}
@@ -0,0 +1,891 @@
+use crate::{
+    event::TimelineEvent,
+    interaction::TrackSelectionApi,
+    loop_region::LoopApi,
+    time_selection::TimeSelectionApi,
+    marker::{Marker, MarkerApi},
+    playhead::{Info, Interaction},
+    ruler::{MusicalInfo, MusicalInteract, MusicalRuler},
+    types::{Bar, TempoMap, TimeSig, TrackId},
+    zoom::{self, ZoomSettings},
+    TimelineApi,
+};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, MutexGuard, PoisonError,
+    },
+};
+
+/// A ready-made timeline state implementing all of the traits required by the timeline widgets.
+///
+/// The timeline has a single time signature and either a constant tempo or a `TempoMap`. Traits
+/// whose setters take `&self` are supported via interior mutability, so the state may be shared
+/// between the widgets of a single frame.
+///
+/// All ticks stored within the state are absolute, i.e. measured from the start of the timeline
+/// rather than the start of the visible area.
+#[derive(Debug)]
+pub struct SimpleTimelineState {
+    /// The absolute tick at the left edge of the view.
+    ///
+    /// Stored at double precision, as are the playhead, so that positions far along the timeline
+    /// do not jitter.
+    pub timeline_start: f64,
+    /// The zoom factor applied to the default 16 points per beat. Larger values zoom out.
+    pub zoom: f32,
+    /// The position of the playhead in absolute ticks.
+    pub playhead: Cell<f64>,
+    /// The number of ticks per beat, also known as PPQN.
+    pub ppqn: u32,
+    /// The time signature of every bar.
+    pub time_sig: TimeSig,
+    /// The total number of bars, or `None` for an unbounded timeline.
+    pub total_bars: Option<u32>,
+    /// The number of ticks before tick `0.0` that the view and playhead may move into, matching
+    /// `Timeline::pre_roll`.
+    pub pre_roll: f32,
+    /// The tempo in beats per minute, used when there is no `tempo_map`.
+    pub tempo_bpm: f32,
+    /// The tempo map, if the tempo changes over time.
+    pub tempo_map: Option<TempoMap>,
+    /// The selected `(start, end)` tick ranges of each track, keyed by track id.
+    pub selections: RefCell<HashMap<TrackId, Vec<(f32, f32)>>>,
+    /// The track id, start tick and range index of the selection currently being dragged out.
+    pub selection_drag: RefCell<Option<(TrackId, f32, usize)>>,
+    /// The `(start, end)` of the loop region.
+    pub loop_range: Option<(f32, f32)>,
+    /// Whether or not playback should loop over the loop region.
+    pub loop_enabled: bool,
+    /// The `(start, end)` of the time selection spanning all tracks.
+    pub time_selection: Option<(f32, f32)>,
+    /// The markers on the timeline.
+    pub markers: RefCell<Vec<Marker>>,
+}
+
+impl SimpleTimelineState {
+    pub const DEFAULT_PPQN: u32 = 960;
+    pub const DEFAULT_TEMPO_BPM: f32 = 120.0;
+    /// The smallest zoom factor, i.e. the furthest the view can zoom in.
+    pub const MIN_ZOOM: f32 = 0.05;
+    /// The largest zoom factor, i.e. the furthest the view can zoom out.
+    pub const MAX_ZOOM: f32 = 20.0;
+
+    /// Create a new default state: 960 PPQN, 4/4 at 120 BPM with no end.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The zoom curve, clamped between `MIN_ZOOM` and `MAX_ZOOM`.
+    pub fn zoom_settings(&self) -> ZoomSettings {
+        let ticks_per_point = |zoom: f32| self.ppqn as f32 / 16.0 * zoom;
+        ZoomSettings::default().range(ticks_per_point(Self::MIN_ZOOM), ticks_per_point(Self::MAX_ZOOM))
+    }
+
+    /// Set the number of ticks per beat.
+    pub fn ppqn(mut self, ppqn: u32) -> Self {
+        self.ppqn = ppqn.max(1);
+        self
+    }
+
+    /// Limit the timeline to the given number of bars.
+    ///
+    /// Scrolling and the playhead are clamped to the end of the last bar.
+    pub fn total_bars(mut self, bars: u32) -> Self {
+        self.total_bars = Some(bars.max(1));
+        self
+    }
+
+    /// Allow the view and playhead to move the given number of ticks before tick `0.0`, e.g. for
+    /// a count-in. Pass the same number of ticks to `Timeline::pre_roll`.
+    pub fn pre_roll(mut self, ticks: f32) -> Self {
+        self.pre_roll = ticks.max(0.0);
+        self
+    }
+
+    /// Set the time signature of every bar.
+    pub fn time_sig(mut self, time_sig: TimeSig) -> Self {
+        self.time_sig = time_sig;
+        self
+    }
+
+    /// Set the constant tempo in beats per minute.
+    pub fn tempo_bpm(mut self, bpm: f32) -> Self {
+        self.tempo_bpm = bpm;
+        self
+    }
+
+    /// Set a tempo map, for tempos that change over time.
+    pub fn tempo_map(mut self, tempo_map: TempoMap) -> Self {
+        self.tempo_map = Some(tempo_map);
+        self
+    }
+
+    /// The number of ticks in each bar.
+    pub fn ticks_per_bar(&self) -> f32 {
+        self.ppqn as f32 * self.time_sig.beats_per_bar()
+    }
+
+    /// The absolute tick at the end of the last bar, if the timeline has an end.
+    pub fn end_tick(&self) -> Option<f32> {
+        self.total_bars.map(|bars| bars as f32 * self.ticks_per_bar())
+    }
+
+    /// Clamp the given absolute tick to the extent of the timeline, including any pre-roll.
+    pub fn clamp_tick(&self, tick: f32) -> f32 {
+        tick.max(-self.pre_roll).min(self.end_tick().unwrap_or(f32::INFINITY))
+    }
+
+    /// Clamp the given absolute tick to the extent of the timeline, at double precision.
+    pub fn clamp_tick_f64(&self, tick: f64) -> f64 {
+        tick.max(-self.pre_roll as f64).min(self.end_tick().map_or(f64::INFINITY, f64::from))
+    }
+
+    /// Apply an event collected by an `EventCollector`.
+    pub fn apply(&mut self, event: TimelineEvent) {
+        match event {
+            TimelineEvent::PlayheadSet(tick) => self.playhead.set(self.clamp_tick(tick) as f64),
+            TimelineEvent::SelectionChanged { track, ranges } if ranges.is_empty() => {
+                self.selections.get_mut().remove(&track);
+            }
+            TimelineEvent::SelectionChanged { track, ranges } => {
+                self.selections.get_mut().insert(track, ranges);
+            }
+            TimelineEvent::SelectionCleared => self.selections.get_mut().clear(),
+            TimelineEvent::Scrolled(ticks) => self.shift_timeline_start(ticks),
+            TimelineEvent::Zoomed { anchor, y_delta } => self.zoom_at(anchor, y_delta),
+            TimelineEvent::ZoomSet(ticks_per_point) => self.set_ticks_per_point(ticks_per_point),
+            TimelineEvent::LoopRangeSet(range) => self.set_loop_range(range),
+            TimelineEvent::LoopEnabledSet(enabled) => self.set_loop_enabled(enabled),
+            TimelineEvent::TimeSelectionSet(range) => self.set_time_selection(range),
+            // Scrubbing moves the playhead via the accompanying `PlayheadSet` events
+            TimelineEvent::ScrubStarted | TimelineEvent::Scrubbed(_) | TimelineEvent::ScrubEnded(_) => {}
+        }
+    }
+
+    /// Apply each of the given events in order.
+    pub fn apply_events(&mut self, events: impl IntoIterator<Item = TimelineEvent>) {
+        for event in events {
+            self.apply(event);
+        }
+    }
+}
+
+impl Default for SimpleTimelineState {
+    fn default() -> Self {
+        Self {
+            timeline_start: 0.0,
+            zoom: 1.0,
+            playhead: Cell::new(0.0),
+            ppqn: Self::DEFAULT_PPQN,
+            time_sig: TimeSig { top: 4, bottom: 4 },
+            total_bars: None,
+            pre_roll: 0.0,
+            tempo_bpm: Self::DEFAULT_TEMPO_BPM,
+            tempo_map: None,
+            selections: RefCell::new(HashMap::new()),
+            selection_drag: RefCell::new(None),
+            loop_range: None,
+            loop_enabled: true,
+            time_selection: None,
+            markers: RefCell::new(Vec::new()),
+        }
+    }
+}
+
+impl TimelineApi for SimpleTimelineState {
+    fn musical_ruler_info(&self) -> &dyn MusicalInfo {
+        self
+    }
+
+    fn timeline_start(&self) -> f32 {
+        self.timeline_start as f32
+    }
+
+    fn timeline_start_f64(&self) -> f64 {
+        self.timeline_start
+    }
+
+    fn timeline_end(&self) -> Option<f32> {
+        self.end_tick()
+    }
+
+    fn shift_timeline_start(&mut self, ticks: f32) {
+        self.shift_timeline_start_f64(ticks as f64);
+    }
+
+    fn shift_timeline_start_f64(&mut self, ticks: f64) {
+        // The end is clamped by the interaction handler which knows the visible width
+        self.timeline_start = (self.timeline_start + ticks).max(-self.pre_roll as f64);
+    }
+
+    fn zoom(&mut self, y_delta: f32) {
+        let mut ticks_per_point = MusicalInfo::ticks_per_point(self);
+        zoom::apply_zoom(&mut ticks_per_point, y_delta, &self.zoom_settings());
+        self.set_ticks_per_point(ticks_per_point);
+    }
+
+    fn set_ticks_per_point(&mut self, ticks_per_point: f32) {
+        let ticks_per_point = self.zoom_settings().clamp(ticks_per_point);
+        self.zoom = ticks_per_point * 16.0 / self.ppqn as f32;
+    }
+}
+
+impl MusicalInfo for SimpleTimelineState {
+    fn ticks_per_beat(&self) -> u32 {
+        self.ppqn
+    }
+
+    fn bar_at_ticks(&self, tick: f32) -> Bar {
+        let ticks_per_bar = self.ticks_per_bar();
+        // Bars continue back into any pre-roll with the same time signature.
+        let absolute_tick = (self.timeline_start + tick as f64).max(-self.pre_roll as f64);
+        let mut bar_number = (absolute_tick / ticks_per_bar as f64).floor() as i64;
+        if let Some(total_bars) = self.total_bars {
+            bar_number = bar_number.min(total_bars.saturating_sub(1) as i64);
+        }
+        // Find the start relative to the view before converting to `f32` to retain precision.
+        let bar_start = (bar_number as f64 * ticks_per_bar as f64 - self.timeline_start) as f32;
+        Bar {
+            tick_range: bar_start..bar_start + ticks_per_bar,
+            time_sig: self.time_sig,
+        }
+    }
+
+    fn ticks_per_point(&self) -> f32 {
+        self.ppqn as f32 / 16.0 * self.zoom
+    }
+
+    fn tempo_bpm(&self) -> f32 {
+        self.tempo_bpm
+    }
+
+    fn tempo_map(&self) -> Option<&TempoMap> {
+        self.tempo_map.as_ref()
+    }
+
+    fn timeline_start(&self) -> Option<f32> {
+        Some(self.timeline_start as f32)
+    }
+
+    fn timeline_start_f64(&self) -> Option<f64> {
+        Some(self.timeline_start)
+    }
+
+    fn bar_number_at_ticks(&self, tick: f32) -> u32 {
+        // All bars are the same length, so the bar number can be computed directly. Bars within
+        // the pre-roll precede bar 0 and so can't be numbered, see `ruler::bar_index_at`.
+        let absolute_tick = (self.timeline_start + tick as f64).max(-self.pre_roll as f64);
+        let bar_number = (absolute_tick / self.ticks_per_bar() as f64).floor().max(0.0) as u32;
+        match self.total_bars {
+            Some(total_bars) => bar_number.min(total_bars.saturating_sub(1)),
+            None => bar_number,
+        }
+    }
+}
+
+impl MusicalInteract for SimpleTimelineState {
+    fn click_at_tick(&mut self, tick: f32) {
+        self.playhead.set(self.clamp_tick_f64(self.timeline_start + tick as f64));
+    }
+}
+
+impl MusicalRuler for SimpleTimelineState {
+    fn info(&self) -> &dyn MusicalInfo {
+        self
+    }
+
+    fn interact(&mut self) -> &mut dyn MusicalInteract {
+        self
+    }
+
+    fn loop_api(&mut self) -> Option<&mut dyn LoopApi> {
+        Some(self)
+    }
+
+    fn time_selection_api(&mut self) -> Option<&mut dyn TimeSelectionApi> {
+        Some(self)
+    }
+
+    fn timeline_end(&self) -> Option<f32> {
+        self.end_tick()
+    }
+}
+
+impl Info for SimpleTimelineState {
+    fn playhead_ticks(&self) -> f32 {
+        self.playhead_ticks_f64() as f32
+    }
+
+    fn playhead_ticks_f64(&self) -> f64 {
+        self.playhead.get() - self.timeline_start
+    }
+}
+
+impl Interaction for SimpleTimelineState {
+    fn set_playhead_ticks(&self, ticks: f32) {
+        self.set_playhead_ticks_f64(ticks as f64);
+    }
+
+    fn set_playhead_ticks_f64(&self, ticks: f64) {
+        self.playhead.set(self.clamp_tick_f64(self.timeline_start + ticks));
+    }
+}
+
+impl TrackSelectionApi for SimpleTimelineState {
+    fn ticks_per_point(&self) -> f32 {
+        MusicalInfo::ticks_per_point(self)
+    }
+
+    fn timeline_start(&self) -> f32 {
+        self.timeline_start as f32
+    }
+
+    fn start_selection_drag(&self, track_id: TrackId, start_tick: f32) {
+        // The dragged out range is shown alongside the track's existing ranges.
+        let mut selections = self.selections.borrow_mut();
+        let ranges = selections.entry(track_id).or_default();
+        ranges.push((start_tick, start_tick));
+        *self.selection_drag.borrow_mut() = Some((track_id, start_tick, ranges.len() - 1));
+    }
+
+    fn update_selection_drag(&self, track_id: TrackId, end_tick: f32) {
+        if let Some((drag_track_id, start_tick, index)) = self.selection_drag.borrow().as_ref()
+            && *drag_track_id == track_id
+            && let Some(range) = self.selections.borrow_mut().get_mut(&track_id).and_then(|r| r.get_mut(*index))
+        {
+            *range = (start_tick.min(end_tick), start_tick.max(end_tick));
+        }
+    }
+
+    fn get_drag_start(&self) -> Option<(TrackId, f32)> {
+        self.selection_drag
+            .borrow()
+            .as_ref()
+            .map(|&(track_id, start_tick, _)| (track_id, start_tick))
+    }
+
+    fn end_selection_drag(&self) {
+        *self.selection_drag.borrow_mut() = None;
+    }
+
+    fn set_selection(&self, track_id: TrackId, start_tick: f32, end_tick: f32) {
+        self.selections.borrow_mut().insert(track_id, vec![(start_tick, end_tick)]);
+    }
+
+    fn clear_selection(&self, track_id: TrackId) {
+        self.selections.borrow_mut().remove(&track_id);
+    }
+
+    fn clear_all_selections(&self) {
+        self.selections.borrow_mut().clear();
+    }
+
+    fn get_selection(&self, track_id: TrackId) -> Option<(f32, f32)> {
+        self.selections.borrow().get(&track_id).and_then(|ranges| ranges.first().copied())
+    }
+
+    fn get_selected_track_id(&self) -> Option<TrackId> {
+        self.selections
+            .borrow()
+            .iter()
+            .find(|(_, ranges)| !ranges.is_empty())
+            .map(|(&track_id, _)| track_id)
+    }
+
+    fn all_selections(&self) -> Vec<(TrackId, Vec<(f32, f32)>)> {
+        self.selections
+            .borrow()
+            .iter()
+            .filter(|(_, ranges)| !ranges.is_empty())
+            .map(|(&track_id, ranges)| (track_id, ranges.clone()))
+            .collect()
+    }
+
+    fn add_selection(&self, track_id: TrackId, start_tick: f32, end_tick: f32) {
+        self.selections
+            .borrow_mut()
+            .entry(track_id)
+            .or_default()
+            .push((start_tick, end_tick));
+    }
+
+    fn selections(&self, track_id: TrackId) -> Vec<(f32, f32)> {
+        self.selections.borrow().get(&track_id).cloned().unwrap_or_default()
+    }
+
+    fn for_each_selection(&self, track_id: TrackId, visit: &mut dyn FnMut(f32, f32)) {
+        for &(start, end) in self.selections.borrow().get(&track_id).into_iter().flatten() {
+            visit(start, end);
+        }
+    }
+
+    fn remove_selection(&self, track_id: TrackId, index: usize) {
+        if let Some(ranges) = self.selections.borrow_mut().get_mut(&track_id)
+            && index < ranges.len()
+        {
+            ranges.remove(index);
+        }
+    }
+}
+
+impl LoopApi for SimpleTimelineState {
+    fn loop_range(&self) -> Option<(f32, f32)> {
+        self.loop_range
+    }
+
+    fn set_loop_range(&mut self, range: Option<(f32, f32)>) {
+        self.loop_range = range.map(|(start, end)| (self.clamp_tick(start), self.clamp_tick(end)));
+    }
+
+    fn loop_enabled(&self) -> bool {
+        self.loop_enabled
+    }
+
+    fn set_loop_enabled(&mut self, enabled: bool) {
+        self.loop_enabled = enabled;
+    }
+}
+
+impl TimeSelectionApi for SimpleTimelineState {
+    fn time_selection(&self) -> Option<(f32, f32)> {
+        self.time_selection
+    }
+
+    fn set_time_selection(&mut self, range: Option<(f32, f32)>) {
+        self.time_selection = range.map(|(start, end)| (self.clamp_tick(start), self.clamp_tick(end)));
+    }
+}
+
+impl MarkerApi for SimpleTimelineState {
+    fn markers(&self) -> Vec<Marker> {
+        self.markers.borrow().clone()
+    }
+
+    fn add_marker(&self, marker: Marker) {
+        self.markers.borrow_mut().push(marker);
+    }
+
+    fn move_marker(&self, index: usize, tick: f32) {
+        if let Some(marker) = self.markers.borrow_mut().get_mut(index) {
+            marker.tick = self.clamp_tick(tick);
+        }
+    }
+
+    fn remove_marker(&self, index: usize) {
+        let mut markers = self.markers.borrow_mut();
+        if index < markers.len() {
+            markers.remove(index);
+        }
+    }
+
+    fn rename_marker(&self, index: usize, label: String) {
+        if let Some(marker) = self.markers.borrow_mut().get_mut(index) {
+            marker.label = label;
+        }
+    }
+}
+
+/// Lock the given mutex, ignoring poisoning as the guarded state is always left valid.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Ready-made storage of the selections on each track, implementing `TrackSelectionApi`.
+///
+/// Each track may have several selected ranges, as with `SimpleTimelineState`. The state is
+/// `Send + Sync`, so it may be shared with other threads, e.g. within an `Arc`.
+///
+/// `TrackSelectionApi` also describes the view that selections are made within, so call
+/// `sync_view` with the timeline's `TimelineApi` each frame before showing the tracks.
+#[derive(Debug, Default)]
+pub struct DefaultSelectionState {
+    /// The selected `(start, end)` tick ranges of each track.
+    selections: Mutex<HashMap<TrackId, Vec<(f32, f32)>>>,
+    /// The track id, start tick and range index of the selection currently being dragged out.
+    drag: Mutex<Option<(TrackId, f32, usize)>>,
+    /// The absolute tick at the left edge of the view and the ticks per point.
+    view: Mutex<(f32, f32)>,
+}
+
+impl DefaultSelectionState {
+    /// Create a state with no selections.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Copy the start and zoom of the view from the given timeline.
+    pub fn sync_view(&self, api: &dyn TimelineApi) {
+        *lock(&self.view) = (api.timeline_start(), api.musical_ruler_info().ticks_per_point());
+    }
+
+    /// The selected `(start, end)` ranges of the given track in absolute ticks.
+    pub fn selection_of(&self, track_id: TrackId) -> Vec<(f32, f32)> {
+        self.selections(track_id)
+    }
+}
+
+impl TrackSelectionApi for DefaultSelectionState {
+    fn ticks_per_point(&self) -> f32 {
+        lock(&self.view).1
+    }
+
+    fn timeline_start(&self) -> f32 {
+        lock(&self.view).0
+    }
+
+    fn start_selection_drag(&self, track_id: TrackId, start_tick: f32) {
+        // The dragged out range is shown alongside the track's existing ranges. Only one mutex is
+        // held at a time, so the locks can never be taken in conflicting orders.
+        let index = {
+            let mut selections = lock(&self.selections);
+            let ranges = selections.entry(track_id).or_default();
+            ranges.push((start_tick, start_tick));
+            ranges.len() - 1
+        };
+        *lock(&self.drag) = Some((track_id, start_tick, index));
+    }
+
+    fn update_selection_drag(&self, track_id: TrackId, end_tick: f32) {
+        // Copy the drag out first, releasing its lock before the selections are locked.
+        let drag = *lock(&self.drag);
+        if let Some((drag_track_id, start_tick, index)) = drag
+            && drag_track_id == track_id
+            && let Some(range) = lock(&self.selections).get_mut(&track_id).and_then(|r| r.get_mut(index))
+        {
+            *range = (start_tick.min(end_tick), start_tick.max(end_tick));
+        }
+    }
+
+    fn get_drag_start(&self) -> Option<(TrackId, f32)> {
+        lock(&self.drag).map(|(track_id, start_tick, _)| (track_id, start_tick))
+    }
+
+    fn end_selection_drag(&self) {
+        *lock(&self.drag) = None;
+    }
+
+    fn set_selection(&self, track_id: TrackId, start_tick: f32, end_tick: f32) {
+        lock(&self.selections).insert(track_id, vec![(start_tick, end_tick)]);
+    }
+
+    fn clear_selection(&self, track_id: TrackId) {
+        lock(&self.selections).remove(&track_id);
+    }
+
+    fn clear_all_selections(&self) {
+        lock(&self.selections).clear();
+    }
+
+    fn get_selection(&self, track_id: TrackId) -> Option<(f32, f32)> {
+        lock(&self.selections).get(&track_id).and_then(|ranges| ranges.first().copied())
+    }
+
+    fn get_selected_track_id(&self) -> Option<TrackId> {
+        lock(&self.selections)
+            .iter()
+            .find(|(_, ranges)| !ranges.is_empty())
+            .map(|(&track_id, _)| track_id)
+    }
+
+    fn all_selections(&self) -> Vec<(TrackId, Vec<(f32, f32)>)> {
+        lock(&self.selections)
+            .iter()
+            .filter(|(_, ranges)| !ranges.is_empty())
+            .map(|(&track_id, ranges)| (track_id, ranges.clone()))
+            .collect()
+    }
+
+    fn add_selection(&self, track_id: TrackId, start_tick: f32, end_tick: f32) {
+        lock(&self.selections).entry(track_id).or_default().push((start_tick, end_tick));
+    }
+
+    fn selections(&self, track_id: TrackId) -> Vec<(f32, f32)> {
+        lock(&self.selections).get(&track_id).cloned().unwrap_or_default()
+    }
+
+    fn for_each_selection(&self, track_id: TrackId, visit: &mut dyn FnMut(f32, f32)) {
+        for &(start, end) in lock(&self.selections).get(&track_id).into_iter().flatten() {
+            visit(start, end);
+        }
+    }
+
+    fn remove_selection(&self, track_id: TrackId, index: usize) {
+        if let Some(ranges) = lock(&self.selections).get_mut(&track_id)
+            && index < ranges.len()
+        {
+            ranges.remove(index);
+        }
+    }
+}
+
+/// Ready-made storage of the playhead position in absolute ticks.
+///
+/// The position is stored atomically at double precision, so the state may be shared with an
+/// audio thread that advances the playhead, e.g. within an `Arc`. As the playhead traits also
+/// describe the musical layout of the timeline, the widgets are given the state via `with_info`.
+#[derive(Debug, Default)]
+pub struct DefaultPlayheadState {
+    /// The bits of the `f64` absolute playhead position.
+    playhead: AtomicU64,
+    /// The number of ticks before tick `0.0` that the playhead may move into.
+    pre_roll: f64,
+}
+
+/// A `DefaultPlayheadState` paired with the timeline's musical info, implementing `PlayheadApi`.
+///
+/// Created via `DefaultPlayheadState::with_info`.
+#[derive(Clone, Copy)]
+pub struct DefaultPlayheadApi<'a> {
+    state: &'a DefaultPlayheadState,
+    info: &'a dyn MusicalInfo,
+}
+
+impl DefaultPlayheadState {
+    /// Create a state with the playhead at the start of the timeline.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The position of the playhead in absolute ticks.
+    pub fn playhead(&self) -> f64 {
+        f64::from_bits(self.playhead.load(Ordering::Relaxed))
+    }
+
+    /// Allow the playhead to move the given number of ticks before tick `0.0`, e.g. for a
+    /// count-in. Pass the same number of ticks to `Timeline::pre_roll`.
+    pub fn pre_roll(mut self, ticks: f64) -> Self {
+        self.pre_roll = ticks.max(0.0);
+        self
+    }
+
+    /// Move the playhead to the given absolute tick, clamped so that it never precedes the
+    /// `pre_roll`.
+    pub fn set_playhead(&self, tick: f64) {
+        self.playhead.store(tick.max(-self.pre_roll).to_bits(), Ordering::Relaxed);
+    }
+
+    /// Pair the state with the timeline's musical info, e.g. to pass to `SetPlayhead::playhead`.
+    ///
+    /// Positions relative to the view are converted via `MusicalInfo::timeline_start`.
+    pub fn with_info<'a>(&'a self, info: &'a dyn MusicalInfo) -> DefaultPlayheadApi<'a> {
+        DefaultPlayheadApi { state: self, info }
+    }
+}
+
+impl MusicalInfo for DefaultPlayheadApi<'_> {
+    fn ticks_per_beat(&self) -> u32 {
+        self.info.ticks_per_beat()
+    }
+
+    fn bar_at_ticks(&self, tick: f32) -> Bar {
+        self.info.bar_at_ticks(tick)
+    }
+
+    fn ticks_per_point(&self) -> f32 {
+        self.info.ticks_per_point()
+    }
+
+    fn tempo_bpm(&self) -> f32 {
+        self.info.tempo_bpm()
+    }
+
+    fn tempo_map(&self) -> Option<&TempoMap> {
+        self.info.tempo_map()
+    }
+
+    fn ticks_to_seconds(&self, tick: f32) -> f32 {
+        self.info.ticks_to_seconds(tick)
+    }
+
+    fn seconds_to_ticks(&self, seconds: f32) -> f32 {
+        self.info.seconds_to_ticks(seconds)
+    }
+
+    fn timeline_start(&self) -> Option<f32> {
+        self.info.timeline_start()
+    }
+
+    fn timeline_start_f64(&self) -> Option<f64> {
+        self.info.timeline_start_f64()
+    }
+
+    fn bar_number_at_ticks(&self, tick: f32) -> u32 {
+        self.info.bar_number_at_ticks(tick)
+    }
+}
+
+impl Info for DefaultPlayheadApi<'_> {
+    fn playhead_ticks(&self) -> f32 {
+        self.playhead_ticks_f64() as f32
+    }
+
+    fn playhead_ticks_f64(&self) -> f64 {
+        self.state.playhead() - self.info.timeline_start_f64().unwrap_or(0.0)
+    }
+}
+
+impl Interaction for DefaultPlayheadApi<'_> {
+    fn set_playhead_ticks(&self, ticks: f32) {
+        self.set_playhead_ticks_f64(ticks as f64);
+    }
+
+    fn set_playhead_ticks_f64(&self, ticks: f64) {
+        self.state.set_playhead(self.info.timeline_start_f64().unwrap_or(0.0) + ticks);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time_display::TimeDisplay;
+    use std::{sync::Arc, thread};
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn default_states_are_send_and_sync() {
+        assert_send_sync::<DefaultSelectionState>();
+        assert_send_sync::<DefaultPlayheadState>();
+    }
+
+    #[test]
+    fn selection_drag_lifecycle() {
+        let state = DefaultSelectionState::new();
+        let track = TrackId::new("drums");
+        state.add_selection(track, 0.0, 100.0);
+
+        // Start and update a drag, which is shown alongside the existing range
+        state.start_selection_drag(track, 500.0);
+        assert_eq!(state.get_drag_start(), Some((track, 500.0)));
+        state.update_selection_drag(track, 300.0);
+        assert_eq!(state.selection_of(track), vec![(0.0, 100.0), (300.0, 500.0)]);
+
+        // Updates for other tracks are ignored
+        state.update_selection_drag(TrackId::new("bass"), 900.0);
+        assert_eq!(state.selection_of(track), vec![(0.0, 100.0), (300.0, 500.0)]);
+
+        // Ending the drag keeps the range
+        state.end_selection_drag();
+        assert_eq!(state.get_drag_start(), None);
+        assert_eq!(state.selection_of(track), vec![(0.0, 100.0), (300.0, 500.0)]);
+
+        // Cancelling a drag restores the selections from before it began, as the widgets do
+        let snapshot = state.all_selections();
+        state.start_selection_drag(track, 1000.0);
+        state.update_selection_drag(track, 1200.0);
+        state.end_selection_drag();
+        state.clear_all_selections();
+        for (track_id, ranges) in snapshot {
+            for (start, end) in ranges {
+                state.add_selection(track_id, start, end);
+            }
+        }
+        assert_eq!(state.get_drag_start(), None);
+        assert_eq!(state.selection_of(track), vec![(0.0, 100.0), (300.0, 500.0)]);
+    }
+
+    #[test]
+    fn selection_drag_across_threads() {
+        let state = Arc::new(DefaultSelectionState::new());
+        let track = TrackId::new("drums");
+        state.start_selection_drag(track, 0.0);
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let state = Arc::clone(&state);
+                thread::spawn(move || {
+                    for tick in 0..1000 {
+                        if i % 2 == 0 {
+                            state.update_selection_drag(track, tick as f32);
+                        } else {
+                            state.add_selection(TrackId::new(i), 0.0, tick as f32);
+                            let _ = state.get_drag_start();
+                        }
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        state.end_selection_drag();
+        assert_eq!(state.get_drag_start(), None);
+    }
+
+    #[test]
+    fn playhead_scrub_lifecycle() {
+        let mut info = SimpleTimelineState::new();
+        info.timeline_start = 1000.0;
+        let state = DefaultPlayheadState::new();
+        let api = state.with_info(&info);
+
+        // Start a scrub, move the playhead relative to the view and end it
+        api.on_scrub_start();
+        api.set_playhead_ticks(50.0);
+        api.on_scrub(50.0);
+        assert_eq!(state.playhead(), 1050.0);
+        assert_eq!(api.playhead_ticks(), 50.0);
+        api.on_scrub_end(50.0);
+        assert_eq!(state.playhead(), 1050.0);
+
+        // Cancelling a scrub returns the playhead to where it started
+        api.on_scrub_start();
+        let before = api.playhead_ticks_f64();
+        api.set_playhead_ticks(200.0);
+        assert_eq!(state.playhead(), 1200.0);
+        api.set_playhead_ticks_f64(before);
+        assert_eq!(state.playhead(), 1050.0);
+    }
+
+    #[test]
+    fn playhead_moves_into_pre_roll() {
+        let state = DefaultPlayheadState::new();
+        state.set_playhead(-500.0);
+        assert_eq!(state.playhead(), 0.0);
+        let state = DefaultPlayheadState::new().pre_roll(3840.0);
+        state.set_playhead(-500.0);
+        assert_eq!(state.playhead(), -500.0);
+        state.set_playhead(-5000.0);
+        assert_eq!(state.playhead(), -3840.0);
+    }
+
+    #[test]
+    fn bar_numbers_within_pre_roll() {
+        let mut state = SimpleTimelineState::new().pre_roll(2.0 * 3840.0);
+        state.timeline_start = -2.0 * 3840.0;
+        // Bars within the pre-roll precede bar 0, and are shown counting back from -1
+        assert_eq!(state.bar_at_ticks(100.0).tick_range, 0.0..3840.0);
+        assert_eq!(state.bar_number_at_ticks(100.0), 0);
+        assert_eq!(TimeDisplay::BarsBeatsTicks.format(-7680.0 + 100.0, &state), "-2.1.100");
+        assert_eq!(TimeDisplay::BarsBeatsTicks.format(-3840.0 + 960.0, &state), "-1.2.000");
+        assert_eq!(TimeDisplay::BarsBeatsTicks.format(0.0, &state), "1.1.000");
+        assert_eq!(state.bar_number_at_ticks(7680.0 + 3840.0), 1);
+        assert_eq!(TimeDisplay::BarsBeatsTicks.format(3840.0 + 480.0, &state), "2.1.480");
+    }
+
+    #[test]
+    fn empty_timeline_has_one_bar() {
+        let mut state = SimpleTimelineState::new();
+        state.total_bars = Some(0);
+        assert_eq!(state.bar_number_at_ticks(10_000.0), 0);
+        assert_eq!(state.bar_at_ticks(10_000.0).tick_range, 0.0..3840.0);
+    }
+
+    #[test]
+    fn playhead_shared_with_another_thread() {
+        let state = Arc::new(DefaultPlayheadState::new());
+        let audio = {
+            let state = Arc::clone(&state);
+            thread::spawn(move || {
+                for tick in 0..1000 {
+                    state.set_playhead(tick as f64);
+                }
+            })
+        };
+        audio.join().unwrap();
+        assert_eq!(state.playhead(), 999.0);
+    }
+
+}
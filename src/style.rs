@@ -0,0 +1,239 @@
+use egui::{Color32, Stroke};
+
+/// A shadow fading out beneath an area of the timeline.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DropShadow {
+    /// The height of the shadow in points.
+    pub height: f32,
+    /// The colour at the top of the shadow, which fades to transparent at the bottom.
+    pub color: Color32,
+}
+
+/// The separation between consecutive tracks, set via `TimelineStyle::tracks`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TracksStyle {
+    /// Line drawn along the bottom edge of each track, spanning the header and timeline. `None`
+    /// disables the separator.
+    pub separator: Option<Stroke>,
+    /// Vertical space in points left beneath each track, including pinned tracks.
+    pub spacing: f32,
+    /// Whether the timeline area of a track given a `TrackCtx::color` is tinted with the colour.
+    pub tint_lanes: bool,
+}
+
+impl TracksStyle {
+    /// The opacity of the tint of a track's timeline area relative to its `TrackCtx::color`.
+    pub const LANE_TINT: f32 = 0.08;
+
+    /// Separate tracks with a faint line derived from the given visuals and no extra space.
+    pub fn from_visuals(visuals: &egui::Visuals) -> Self {
+        Self {
+            separator: Some(visuals.widgets.noninteractive.bg_stroke),
+            spacing: 0.0,
+            tint_lanes: true,
+        }
+    }
+
+    /// Set the line drawn along the bottom edge of each track, or `None` for no separator.
+    pub fn separator(mut self, stroke: Option<Stroke>) -> Self {
+        self.separator = stroke;
+        self
+    }
+
+    /// Set the vertical space in points left beneath each track.
+    pub fn spacing(mut self, spacing: f32) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    /// Set whether the timeline area of a track with a colour is tinted with the colour.
+    pub fn tint_lanes(mut self, tint: bool) -> Self {
+        self.tint_lanes = tint;
+        self
+    }
+}
+
+impl Default for TracksStyle {
+    fn default() -> Self {
+        Self::from_visuals(&egui::Visuals::dark())
+    }
+}
+
+/// Colours and strokes used when painting the timeline.
+///
+/// When no style is given to `Timeline::style`, one is derived from the `egui::Visuals` of the
+/// `Ui` that the timeline is shown within so that it adapts to both dark and light themes.
+#[derive(Clone, Debug)]
+pub struct TimelineStyle {
+    /// Fill painted behind the whole widget, including the header column.
+    pub background_fill: Color32,
+    /// Colour of the strongest grid lines, drawn at the start of each bar.
+    pub grid_major_color: Color32,
+    /// Colour of grid lines drawn on each beat within a bar.
+    pub grid_beat_color: Color32,
+    /// Colour of the faintest grid lines, drawn on subdivisions of a beat.
+    pub grid_minor_color: Color32,
+    /// Stroke painted around each track (header + timeline). `None` disables the border.
+    pub track_border: Option<Stroke>,
+    /// Fill painted over the selected tick range of a track.
+    pub selection_fill: Color32,
+    /// Fill painted over the full area of the selected track.
+    pub selected_track_fill: Color32,
+    /// Stroke used to draw the playhead line.
+    pub playhead_stroke: Stroke,
+    /// Whether the playhead line is centred on a physical pixel so that it renders crisply.
+    ///
+    /// Disable this for smooth sub-pixel motion of the playhead during playback, at the cost of
+    /// the line blurring across two pixels between pixel centres.
+    pub pixel_snap_playhead: bool,
+    /// Fill painted over the tracks within the loop region while looping is enabled.
+    pub loop_fill: Color32,
+    /// Fill painted over the tracks within the time selection.
+    pub time_selection_fill: Color32,
+    /// Fill painted over the region recorded so far, set via `TrackCtx::recording`.
+    pub record_fill: Color32,
+    /// Fill of the strip shown in place of a collapsed track's content.
+    pub collapsed_track_fill: Color32,
+    /// Shadow painted beneath the pinned tracks while the tracks are scrolled underneath them.
+    /// `None` disables the shadow.
+    pub pinned_shadow: Option<DropShadow>,
+    /// The separators and spacing between tracks.
+    pub tracks: TracksStyle,
+    /// Fill painted over the timeline area past `TimelineApi::timeline_end`.
+    pub past_end_fill: Color32,
+    /// Stroke of the line marking `TimelineApi::timeline_end`.
+    pub end_stroke: Stroke,
+    /// Fill painted over the timeline area before tick `0.0`, set via `Timeline::pre_roll`.
+    pub pre_roll_fill: Color32,
+}
+
+impl TimelineStyle {
+    /// Derive a style from the given visuals.
+    pub fn from_visuals(visuals: &egui::Visuals) -> Self {
+        let grid_stroke = visuals.widgets.noninteractive.bg_stroke;
+        Self {
+            background_fill: visuals.widgets.noninteractive.bg_fill,
+            grid_major_color: grid_stroke.color.linear_multiply(0.5),
+            grid_beat_color: grid_stroke.color.linear_multiply(0.375),
+            grid_minor_color: grid_stroke.color.linear_multiply(0.25),
+            track_border: None,
+            selection_fill: visuals.selection.bg_fill.linear_multiply(0.4),
+            selected_track_fill: visuals.selection.bg_fill.linear_multiply(0.05),
+            playhead_stroke: Stroke::new(1.0, visuals.widgets.inactive.fg_stroke.color),
+            pixel_snap_playhead: true,
+            loop_fill: visuals.selection.bg_fill.linear_multiply(0.08),
+            time_selection_fill: visuals.selection.bg_fill.linear_multiply(0.15),
+            record_fill: visuals.error_fg_color.linear_multiply(0.2),
+            collapsed_track_fill: visuals.widgets.inactive.bg_fill,
+            pinned_shadow: Some(DropShadow { height: 8.0, color: Color32::from_black_alpha(77) }),
+            tracks: TracksStyle::from_visuals(visuals),
+            past_end_fill: Color32::from_black_alpha(64),
+            end_stroke: Stroke::new(2.0, grid_stroke.color),
+            pre_roll_fill: Color32::from_black_alpha(40),
+        }
+    }
+
+    /// Set the fill painted behind the whole widget.
+    pub fn background_fill(mut self, fill: Color32) -> Self {
+        self.background_fill = fill;
+        self
+    }
+
+    /// Set the colours of the bar, beat and subdivision grid lines.
+    pub fn grid_colors(mut self, major: Color32, beat: Color32, minor: Color32) -> Self {
+        self.grid_major_color = major;
+        self.grid_beat_color = beat;
+        self.grid_minor_color = minor;
+        self
+    }
+
+    /// Set the stroke painted around each track, or `None` for no border.
+    pub fn track_border(mut self, stroke: Option<Stroke>) -> Self {
+        self.track_border = stroke;
+        self
+    }
+
+    /// Set the fill painted over a track's selected tick range.
+    pub fn selection_fill(mut self, fill: Color32) -> Self {
+        self.selection_fill = fill;
+        self
+    }
+
+    /// Set the fill painted over the selected track.
+    pub fn selected_track_fill(mut self, fill: Color32) -> Self {
+        self.selected_track_fill = fill;
+        self
+    }
+
+    /// Set the stroke used to draw the playhead.
+    pub fn playhead_stroke(mut self, stroke: Stroke) -> Self {
+        self.playhead_stroke = stroke;
+        self
+    }
+
+    /// Set whether the playhead line is centred on a physical pixel.
+    pub fn pixel_snap_playhead(mut self, snap: bool) -> Self {
+        self.pixel_snap_playhead = snap;
+        self
+    }
+
+    /// Set the fill painted over the tracks within an enabled loop region.
+    pub fn loop_fill(mut self, fill: Color32) -> Self {
+        self.loop_fill = fill;
+        self
+    }
+
+    /// Set the fill painted over the tracks within the time selection.
+    pub fn time_selection_fill(mut self, fill: Color32) -> Self {
+        self.time_selection_fill = fill;
+        self
+    }
+
+    /// Set the fill painted over the region recorded so far.
+    pub fn record_fill(mut self, fill: Color32) -> Self {
+        self.record_fill = fill;
+        self
+    }
+
+    /// Set the fill of the strip shown in place of a collapsed track's content.
+    pub fn collapsed_track_fill(mut self, fill: Color32) -> Self {
+        self.collapsed_track_fill = fill;
+        self
+    }
+
+    /// Set the shadow painted beneath the pinned tracks, or `None` for no shadow.
+    pub fn pinned_shadow(mut self, shadow: Option<DropShadow>) -> Self {
+        self.pinned_shadow = shadow;
+        self
+    }
+
+    /// Set the separators and spacing between tracks.
+    pub fn tracks(mut self, style: TracksStyle) -> Self {
+        self.tracks = style;
+        self
+    }
+
+    /// Set the fill painted over the timeline area past the end of the timeline.
+    pub fn past_end_fill(mut self, fill: Color32) -> Self {
+        self.past_end_fill = fill;
+        self
+    }
+
+    /// Set the stroke of the line marking the end of the timeline.
+    pub fn end_stroke(mut self, stroke: Stroke) -> Self {
+        self.end_stroke = stroke;
+        self
+    }
+
+    /// Set the fill painted over the pre-roll before tick `0.0`.
+    pub fn pre_roll_fill(mut self, fill: Color32) -> Self {
+        self.pre_roll_fill = fill;
+        self
+    }
+}
+
+impl Default for TimelineStyle {
+    fn default() -> Self {
+        Self::from_visuals(&egui::Visuals::dark())
+    }
+}
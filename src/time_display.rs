@@ -0,0 +1,88 @@
+use crate::ruler::MusicalInfo;
+
+/// How a position on the timeline is presented as text.
+#[derive(Copy, Clone, Debug, Default)]
+pub enum TimeDisplay {
+    /// `minutes:seconds:centiseconds`, e.g. `01:23:45`.
+    MinSecCs,
+    /// `bars.beats.ticks` counted from one, e.g. `12.3.480`.
+    #[default]
+    BarsBeatsTicks,
+    /// SMPTE timecode `hours:minutes:seconds:frames` at the given frame rate.
+    Smpte { fps: f32 },
+    /// A custom formatter given the absolute position in ticks.
+    Custom(fn(f32) -> String),
+}
+
+impl TimeDisplay {
+    /// The next display mode, for cycling through the built-in modes on click.
+    ///
+    /// `Custom` cycles back to `BarsBeatsTicks`.
+    pub fn next(self) -> Self {
+        match self {
+            Self::BarsBeatsTicks => Self::MinSecCs,
+            Self::MinSecCs => Self::Smpte { fps: 30.0 },
+            Self::Smpte { .. } | Self::Custom(_) => Self::BarsBeatsTicks,
+        }
+    }
+
+    /// Format the given absolute tick position.
+    pub fn format(&self, absolute_ticks: f32, info: &dyn MusicalInfo) -> String {
+        match *self {
+            Self::MinSecCs => {
+                let total_seconds = info.ticks_to_seconds(absolute_ticks.max(0.0));
+                let minutes = (total_seconds / 60.0).floor() as u32;
+                let seconds = (total_seconds % 60.0).floor() as u32;
+                let centiseconds = ((total_seconds % 1.0) * 100.0).floor() as u32;
+                format!("{:02}:{:02}:{:02}", minutes, seconds, centiseconds)
+            }
+            Self::BarsBeatsTicks => {
+                // Look up the bar relative to the start of the view.
+                let tick = absolute_ticks - info.timeline_start().unwrap_or(0.0);
+                let bar = info.bar_at_ticks(tick);
+                // There is no bar zero, so bars within any pre-roll count back from `-1`, as on
+                // the ruler.
+                let bar_index = crate::ruler::bar_index_at(info, bar.tick_range.start);
+                let bar_number = if bar_index < 0 { bar_index } else { bar_index + 1 };
+                let beat_ticks = info.ticks_per_beat() as f32 * 4.0 / bar.time_sig.bottom.max(1) as f32;
+                let ticks_in_bar = (tick - bar.tick_range.start).max(0.0);
+                let beat = (ticks_in_bar / beat_ticks).floor();
+                let ticks_in_beat = (ticks_in_bar - beat * beat_ticks).floor();
+                format!("{}.{}.{:03}", bar_number, beat as u32 + 1, ticks_in_beat as u32)
+            }
+            Self::Smpte { fps } => {
+                let total_seconds = info.ticks_to_seconds(absolute_ticks.max(0.0));
+                let hours = (total_seconds / 3600.0).floor() as u32;
+                let minutes = ((total_seconds / 60.0) % 60.0).floor() as u32;
+                let seconds = (total_seconds % 60.0).floor() as u32;
+                let frames = ((total_seconds % 1.0) * fps).floor() as u32;
+                format!("{:02}:{:02}:{:02}:{:02}", hours, minutes, seconds, frames)
+            }
+            Self::Custom(format) => format(absolute_ticks),
+        }
+    }
+}
+
+/// Paint a small label showing a position beside a vertical line, e.g. while seeking.
+///
+/// The label is drawn to the right of `anchor`, or to its left if it would otherwise extend
+/// beyond the right edge of `bounds`. It is painted on the tooltip layer so that it appears over
+/// the top of all other timeline content.
+pub(crate) fn paint_position_tooltip(ui: &egui::Ui, bounds: egui::Rect, anchor: egui::Pos2, text: String) {
+    const PAD: f32 = 3.0;
+    const OFFSET: f32 = 6.0;
+    let layer_id = egui::LayerId::new(egui::Order::Tooltip, ui.id().with("position_tooltip"));
+    let painter = ui.ctx().layer_painter(layer_id);
+    let visuals = ui.visuals();
+    let font_id = egui::TextStyle::Small.resolve(ui.style());
+    let galley = painter.layout_no_wrap(text, font_id, visuals.text_color());
+    let size = galley.size() + egui::Vec2::splat(PAD * 2.0);
+    let left = if anchor.x + OFFSET + size.x > bounds.right() {
+        anchor.x - OFFSET - size.x
+    } else {
+        anchor.x + OFFSET
+    };
+    let rect = egui::Rect::from_min_size(egui::Pos2::new(left, anchor.y - size.y), size);
+    painter.rect(rect, 2.0, visuals.window_fill, visuals.window_stroke);
+    painter.galley(rect.min + egui::Vec2::splat(PAD), galley, visuals.text_color());
+}
@@ -0,0 +1,170 @@
+use crate::{
+    ruler::{MusicalInfo, MusicalRuler},
+    snap::{self, SnapMode},
+};
+
+/// Access to the host's time selection, a single range of time spanning every track.
+///
+/// Unlike the per-track ranges of `TrackSelectionApi`, the time selection is edited via the
+/// ruler. All ticks are absolute, i.e. measured from the start of the timeline rather than the
+/// start of the visible area.
+pub trait TimeSelectionApi {
+    /// The `(start, end)` of the time selection, or `None` if nothing is selected.
+    fn time_selection(&self) -> Option<(f32, f32)>;
+    /// Set the `(start, end)` of the time selection, or `None` to clear it.
+    fn set_time_selection(&mut self, range: Option<(f32, f32)>);
+}
+
+/// The width in points of the grab handles at either edge of the selection band.
+const HANDLE_WIDTH: f32 = 4.0;
+
+/// The part of the time selection being dragged, stored in egui memory between frames.
+#[derive(Copy, Clone, Debug)]
+enum SelectionDrag {
+    /// Drag out a new selection from the given absolute tick.
+    Create { anchor: f32 },
+    /// Resize the selection by moving its start edge.
+    Start,
+    /// Resize the selection by moving its end edge.
+    End,
+}
+
+/// The area of the ruler in which the time selection is edited.
+pub(crate) fn band_rect(ruler_rect: egui::Rect) -> egui::Rect {
+    let mut rect = ruler_rect;
+    rect.min.y = ruler_rect.center().y;
+    rect
+}
+
+/// Handle dragging out and resizing the time selection within the lower half of the ruler.
+///
+/// Dragging with `Shift` held drags out a new selection, dragging either edge of the selection
+/// resizes it, and clicking with `Shift` held clears it. All other presses are left to the
+/// playhead. Does nothing if the `MusicalRuler` does not provide a `TimeSelectionApi`.
+///
+/// Returns `true` if the pointer interaction belongs to the time selection, in which case the
+/// ruler should not move the playhead.
+pub(crate) fn ruler_interaction(
+    ui: &egui::Ui,
+    ruler_rect: egui::Rect,
+    response: &egui::Response,
+    api: &mut dyn MusicalRuler,
+    snap: SnapMode,
+) -> bool {
+    let Some(range) = api.time_selection_api().map(|s| s.time_selection()) else {
+        return false;
+    };
+    let (handled, new_range) = edit(ui, ruler_rect, response, api.info(), snap, range);
+    if new_range != range
+        && let Some(selection_api) = api.time_selection_api()
+    {
+        selection_api.set_time_selection(new_range);
+    }
+    handled
+}
+
+/// Determine the edit to make to the given time selection `range` in response to the pointer.
+///
+/// Returns whether the interaction belongs to the time selection and the new range.
+fn edit(
+    ui: &egui::Ui,
+    ruler_rect: egui::Rect,
+    response: &egui::Response,
+    info: &dyn MusicalInfo,
+    snap: SnapMode,
+    range: Option<(f32, f32)>,
+) -> (bool, Option<(f32, f32)>) {
+    let band = band_rect(ruler_rect);
+    let ticks_per_point = info.ticks_per_point();
+    let timeline_start = info.timeline_start().unwrap_or(0.0);
+    let (modifiers, press_origin) = ui.input(|i| (i.modifiers, i.pointer.press_origin()));
+    // Shift creates selections within the band, so it doesn't also coarsen the grid.
+    let snap = snap.with_bypass(modifiers);
+    let x_to_tick = |x: f32| {
+        let tick = ((x - ruler_rect.left()) * ticks_per_point).max(-timeline_start);
+        timeline_start + snap::snap_tick(tick, info, snap)
+    };
+    let tick_to_x = |tick: f32| ruler_rect.left() + (tick - timeline_start) / ticks_per_point;
+
+    // The edge of the selection under the given x position, if any.
+    let edge_at = |x: f32| {
+        let (start, end) = range?;
+        if (x - tick_to_x(start)).abs() <= HANDLE_WIDTH {
+            Some(SelectionDrag::Start)
+        } else if (x - tick_to_x(end)).abs() <= HANDLE_WIDTH {
+            Some(SelectionDrag::End)
+        } else {
+            None
+        }
+    };
+
+    if response.hover_pos().is_some_and(|pos| band.contains(pos) && edge_at(pos.x).is_some()) {
+        ui.ctx().set_cursor_icon(egui::CursorIcon::ResizeHorizontal);
+    }
+
+    // Presses within the band belong to the selection if they grab an edge or `Shift` is held.
+    // The press origin is forgotten once released, so clicks use the release position instead.
+    let origin = press_origin
+        .or_else(|| response.interact_pointer_pos())
+        .filter(|pos| band.contains(*pos));
+    let claimed = origin.is_some_and(|pos| modifiers.shift || edge_at(pos.x).is_some());
+
+    let drag_id = response.id.with("time_selection_drag");
+    if response.drag_started_by(egui::PointerButton::Primary)
+        && claimed
+        && let Some(origin) = origin
+    {
+        let drag = edge_at(origin.x).unwrap_or(SelectionDrag::Create { anchor: x_to_tick(origin.x) });
+        ui.data_mut(|d| d.insert_temp(drag_id, drag));
+    }
+
+    let drag = ui.data(|d| d.get_temp::<SelectionDrag>(drag_id));
+    let mut new_range = range;
+    if let Some(drag) = drag
+        && response.dragged_by(egui::PointerButton::Primary)
+        && let Some(pos) = response.interact_pointer_pos()
+    {
+        let tick = x_to_tick(pos.x);
+        new_range = match (drag, range) {
+            (SelectionDrag::Create { anchor }, _) => Some((anchor.min(tick), anchor.max(tick))),
+            (SelectionDrag::Start, Some((_, end))) => Some((tick.min(end), end)),
+            (SelectionDrag::End, Some((start, _))) => Some((start, tick.max(start))),
+            _ => range,
+        };
+    }
+
+    if response.drag_stopped() && drag.is_some() {
+        // A selection dragged down to nothing is cleared.
+        new_range = new_range.filter(|(start, end)| end - start >= 1.0);
+        ui.data_mut(|d| d.remove::<SelectionDrag>(drag_id));
+    } else if response.clicked() && claimed && modifiers.shift {
+        new_range = None;
+    }
+
+    let pressed = response.is_pointer_button_down_on() || response.clicked();
+    (drag.is_some() || (pressed && claimed), new_range)
+}
+
+/// Paint the time selection as a highlighted band in the lower half of the ruler.
+pub(crate) fn paint_band(ui: &egui::Ui, ruler_rect: egui::Rect, info: &dyn MusicalInfo, range: Option<(f32, f32)>) {
+    let Some((start, end)) = range else {
+        return;
+    };
+    let band = band_rect(ruler_rect);
+    let ticks_per_point = info.ticks_per_point();
+    let timeline_start = info.timeline_start().unwrap_or(0.0);
+    let start_x = band.left() + (start - timeline_start) / ticks_per_point;
+    let end_x = band.left() + (end - timeline_start) / ticks_per_point;
+    let rect = egui::Rect::from_x_y_ranges(start_x..=end_x, band.y_range()).intersect(band);
+    if !rect.is_positive() {
+        return;
+    }
+    let color = ui.visuals().selection.bg_fill;
+    let painter = ui.painter().with_clip_rect(band);
+    painter.rect_filled(rect, 0.0, color.linear_multiply(0.4));
+    let stroke = egui::Stroke::new(1.0, color);
+    for x in [start_x, end_x] {
+        let x = painter.round_to_pixel_center(x);
+        painter.line_segment([egui::Pos2::new(x, band.top()), egui::Pos2::new(x, band.bottom())], stroke);
+    }
+}
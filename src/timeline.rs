@@ -1,7 +1,24 @@
 use crate::{
-    context::{BackgroundCtx, SetPlayhead, TimelineCtx, TracksCtx},
-    grid, interaction, playhead::PlayheadApi, ruler,
+    context::{BackgroundCtx, SetPlayhead, TimelineCtx, TimelineRects, TracksCtx},
+    drop::DropPayload,
+    grid,
+    interaction::{self, ScrollSettings},
+    keyboard::{self, KeyBindings},
+    loop_region::LoopApi,
+    time_selection::TimeSelectionApi,
+    marker::{self, MarkerApi},
+    minimap::MinimapCtx,
+    playhead::{FollowApi, GhostPlayhead, PlayheadApi},
+    ruler,
+    snap::{self, SnapGrid, SnapMode},
+    tool::{self, ToolMode},
+    transport::{self, TopPanel},
+    style::TimelineStyle,
+    time_display::TimeDisplay,
+    types::TrackId,
+    view::{self, ViewLink},
 };
+use std::ops::Range;
 
 /// The top-level timeline widget.
 pub struct Timeline {
@@ -10,6 +27,35 @@ pub struct Timeline {
     /// Can be useful for labelling tracks or providing convenient volume, mute, solo, etc style
     /// widgets.
     header: Option<f32>,
+    /// The `(min, max)` width of the header if it may be resized by dragging its right edge.
+    header_resizable: Option<(f32, f32)>,
+    /// The colours and strokes used to paint the timeline.
+    ///
+    /// If `None`, a style is derived from the visuals of the `Ui` the timeline is shown in.
+    style: Option<TimelineStyle>,
+    /// The grid that playhead seeks and selection edges are snapped to.
+    snap: SnapMode,
+    /// What pressing and dragging on the tracks does.
+    tool_mode: ToolMode,
+    /// The height of the optional panel reserved above the tracks.
+    top_panel: Option<f32>,
+    /// The height of the optional bar reserved below the tracks.
+    bottom_bar: Option<f32>,
+    /// Whether to indicate the position under the pointer while hovering the timeline.
+    hover_indicator: bool,
+    /// Whether scrolling past either end of the timeline stretches the view before springing back.
+    elastic_overscroll: bool,
+    /// How scrolling over the timeline pans and zooms it.
+    scroll_settings: ScrollSettings,
+    /// Distinguishes the timeline from others shown within the same `Ui`.
+    id_salt: Option<egui::Id>,
+    /// Whether the scroll position and zoom are stored in egui memory and restored when the
+    /// timeline is shown again.
+    remember_view: bool,
+    /// Shares the view with other timelines given the same link.
+    link: Option<ViewLink>,
+    /// The number of ticks before tick `0.0` that may be scrolled into view.
+    pre_roll: f32,
 }
 
 /// The result of setting the timeline, ready to start laying out tracks.
@@ -18,12 +64,75 @@ pub struct Show {
     ui: egui::Ui,
     bottom_bar_rect: Option<egui::Rect>,
     top_panel_rect: Option<egui::Rect>,
+    hover_indicator: bool,
+    /// How the tracks are scrolled, set via `tracks_options`.
+    tracks_options: TracksOptions,
+    /// A one-off request to scroll the tracks, set via `scroll_to`.
+    scroll_to: Option<ScrollTo>,
+    /// The area reserved for the tracks set via `pinned_tracks_bottom`.
+    pinned_bottom_rect: Option<egui::Rect>,
+    /// The y position at the bottom of the tracks set via `pinned_tracks`.
+    pinned_top_bottom: Option<f32>,
+    /// Shares the view with other timelines, set via `Timeline::link`.
+    link: Option<ViewLink>,
+}
+
+/// A request to scroll the tracks vertically, passed to `Show::scroll_to`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ScrollTo {
+    /// Scroll the least amount required to bring the track with the given id fully into view.
+    ///
+    /// Only tracks shown with an id via `TrackCtx::with_id` may be scrolled to.
+    Track(TrackId),
+    /// Scroll to the given offset from the top of the tracks, e.g. to restore a previous session.
+    Offset(f32),
+    /// Scroll to the bottom of the tracks.
+    Bottom,
+}
+
+/// Options for the vertically scrollable area containing the tracks, passed to
+/// `Show::tracks_options`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TracksOptions {
+    /// Keep the tracks scrolled to the bottom once scrolled there, e.g. as new tracks are added.
+    ///
+    /// Disable this if it fights with scrolling the tracks via `Show::scroll_to`.
+    pub stick_to_bottom: bool,
+    /// Animate scrolling, e.g. via `Show::scroll_to`.
+    pub animated: bool,
+    /// When to show the vertical scroll bar.
+    pub scrollbar_visibility: egui::scroll_area::ScrollBarVisibility,
+}
+
+impl Default for TracksOptions {
+    fn default() -> Self {
+        Self {
+            stick_to_bottom: true,
+            animated: true,
+            scrollbar_visibility: egui::scroll_area::ScrollBarVisibility::VisibleWhenNeeded,
+        }
+    }
 }
 
 impl Timeline {
     /// Begin building the timeline widget.
     pub fn new() -> Self {
-        Self { header: None }
+        Self {
+            header: None,
+            header_resizable: None,
+            style: None,
+            snap: SnapMode::Off,
+            tool_mode: ToolMode::Select,
+            top_panel: None,
+            bottom_bar: None,
+            hover_indicator: false,
+            elastic_overscroll: false,
+            scroll_settings: ScrollSettings::default(),
+            id_salt: None,
+            remember_view: false,
+            link: None,
+            pre_roll: 0.0,
+        }
     }
 
     /// A optional track header side panel.
@@ -35,94 +144,395 @@ impl Timeline {
         self
     }
 
+    /// Allow the header to be resized between the given widths by dragging its right edge.
+    ///
+    /// The width given to `header` is used until the header is first resized, after which the
+    /// width is persisted in egui memory. The current width is available via `Show::header_width`.
+    pub fn header_resizable(mut self, min: f32, max: f32) -> Self {
+        self.header_resizable = Some((min, max));
+        self
+    }
+
+    /// Set the colours and strokes used to paint the timeline.
+    ///
+    /// By default, the style is derived from the visuals of the `Ui` the timeline is shown in.
+    pub fn style(mut self, style: TimelineStyle) -> Self {
+        self.style = Some(style);
+        self
+    }
+
+    /// Snap playhead seeks and selection edges on tracks to the given grid.
+    ///
+    /// Snapping can be bypassed temporarily by holding `Alt`, or made coarser by holding `Shift`
+    /// other than while selecting, where `Shift` extends and moves selections instead.
+    ///
+    /// The bars are looked up via the `TimelineApi` given to `show`. Once a mode is chosen via
+    /// `SetPlayhead::snap_selector`, it takes precedence over this one, remembered in egui memory
+    /// keyed by the timeline's id.
+    ///
+    /// Default: `SnapMode::Off`
+    pub fn snap(mut self, snap: SnapMode) -> Self {
+        self.snap = snap;
+        self
+    }
+
+    /// What pressing and dragging on the tracks does, e.g. as chosen via `tool::toolbar`.
+    ///
+    /// In `ToolMode::Select` dragging only creates selections, while in `ToolMode::Scrub` it only
+    /// moves the playhead. The other modes leave interaction on the tracks to the host, which
+    /// may check `TimelineCtx::tool_mode` within its track contents.
+    ///
+    /// Default: `ToolMode::Select`
+    pub fn tool_mode(mut self, tool_mode: ToolMode) -> Self {
+        self.tool_mode = tool_mode;
+        self
+    }
+
+    /// Indicate the position under the pointer while hovering over the timeline.
+    ///
+    /// A faint vertical line is drawn over the tracks at the pointer, along with a readout of the
+    /// position at the top of the timeline. The position is snapped to the `snap` grid so that it
+    /// shows where a click would land. Nothing is drawn while any pointer button is held, e.g.
+    /// while dragging a selection or the playhead.
+    ///
+    /// Default: `false`
+    pub fn hover_indicator(mut self, show: bool) -> Self {
+        self.hover_indicator = show;
+        self
+    }
+
+    /// Rubber-band the view when scrolled hard against tick `0.0` or `TimelineApi::timeline_end`.
+    ///
+    /// The contents of the tracks and the playhead overshoot the edge by a distance proportional
+    /// to the scroll, then spring back. The overshoot is purely visual, so the host's timeline
+    /// start always remains clamped.
+    ///
+    /// Default: `false`
+    pub fn elastic_overscroll(mut self, enabled: bool) -> Self {
+        self.elastic_overscroll = enabled;
+        self
+    }
+
+    /// How scrolling over the timeline pans and zooms it, e.g. to pan with a plain mouse wheel or
+    /// to invert the scroll direction.
+    ///
+    /// Default: `ScrollSettings::default()`
+    pub fn scroll_settings(mut self, settings: ScrollSettings) -> Self {
+        self.scroll_settings = settings;
+        self
+    }
+
+    /// Distinguish this timeline from others shown within the same `Ui`.
+    ///
+    /// All of the ids used internally, e.g. for the tracks' scroll area and for plots, are derived
+    /// from the salt, so two timelines in the same `Ui` must be given different salts.
+    pub fn id_salt(mut self, salt: impl std::hash::Hash) -> Self {
+        self.id_salt = Some(egui::Id::new(salt));
+        self
+    }
+
+    /// Store the scroll position and zoom in egui memory, restoring them when the timeline is
+    /// shown again after at least one pass without it, e.g. when switching between tabs.
+    ///
+    /// The view is restored via `TimelineApi::set_ticks_per_point` and
+    /// `TimelineApi::shift_timeline_start_f64`. Use `id_salt` to keep the views of several
+    /// timelines apart.
+    ///
+    /// Default: `false`
+    pub fn remember_view(mut self, remember: bool) -> Self {
+        self.remember_view = remember;
+        self
+    }
+
+    /// Scroll and zoom together with every other timeline given the same `ViewLink`.
+    ///
+    /// Changes made by the other timelines are applied via the `TimelineApi` at the start of
+    /// `show`. The vertical scroll of the tracks stays independent unless `ViewLink::vertical` is
+    /// set. Give each linked timeline its own `id_salt` if they share a `Ui`.
+    pub fn link(mut self, link: ViewLink) -> Self {
+        self.link = Some(link);
+        self
+    }
+
+    /// Allow the view to scroll up to the given number of ticks before tick `0.0`, e.g. to show
+    /// one or two bars of count-in before the musical start.
+    ///
+    /// The pre-roll is dimmed across the tracks and the ruler, and its bars are numbered
+    /// backwards from `-1`. The host must allow `TimelineApi::shift_timeline_start` to scroll to
+    /// `-ticks`, e.g. via `SimpleTimelineState::pre_roll`, and may allow the playhead to sit
+    /// within the pre-roll.
+    ///
+    /// Default: `0.0`
+    pub fn pre_roll(mut self, ticks: f32) -> Self {
+        self.pre_roll = ticks.max(0.0);
+        self
+    }
+
+    /// Reserve a panel of the given height above the tracks.
+    ///
+    /// The panel is filled in by `SetPlayhead::top_panel_time`, which does nothing unless the
+    /// panel was reserved here.
+    pub fn top_panel(mut self, height: f32) -> Self {
+        self.top_panel = Some(height);
+        self
+    }
+
+    /// Reserve a bar of the given height below the tracks.
+    ///
+    /// The bar is filled in by `SetPlayhead::bottom_bar`, which does nothing unless the bar was
+    /// reserved here.
+    pub fn bottom_bar(mut self, height: f32) -> Self {
+        self.bottom_bar = Some(height);
+        self
+    }
+
+    /// The id of the timeline when shown within the given `Ui`, from which the ids of all of the
+    /// state it keeps in egui memory are derived.
+    pub fn id(&self, ui: &egui::Ui) -> egui::Id {
+        match self.id_salt {
+            Some(salt) => ui.id().with("egui_timeline").with(salt),
+            None => ui.id().with("egui_timeline"),
+        }
+    }
+
     /// Set the timeline within the currently available rect.
     pub fn show(self, ui: &mut egui::Ui, timeline: &mut dyn crate::TimelineApi) -> Show {
         // The full area including both headers and timeline.
         let full_rect = ui.available_rect_before_wrap();
         
-        // Reserve 40px at the top for the top panel and 20px at the bottom for the bottom bar
-        const TOP_PANEL_HEIGHT: f32 = 40.0;
-        const BOTTOM_BAR_HEIGHT: f32 = 20.0;
+        // Carve the optional top panel and bottom bar out of the full area
         let mut content_rect = full_rect;
-        content_rect.min.y += TOP_PANEL_HEIGHT;
-        content_rect.set_height(full_rect.height() - TOP_PANEL_HEIGHT - BOTTOM_BAR_HEIGHT);
-        
-        // Top panel area (40px height, full width)
-        let top_panel_rect = egui::Rect::from_min_max(
-            egui::Pos2::new(full_rect.min.x, full_rect.min.y),
-            egui::Pos2::new(full_rect.max.x, full_rect.min.y + TOP_PANEL_HEIGHT),
-        );
+        let top_panel_rect = self.top_panel.map(|height| {
+            let height = height.min(content_rect.height());
+            content_rect.min.y += height;
+            egui::Rect::from_min_max(full_rect.min, egui::Pos2::new(full_rect.max.x, content_rect.min.y))
+        });
+        let bottom_bar_rect = self.bottom_bar.map(|height| {
+            let height = height.min(content_rect.height());
+            content_rect.max.y -= height;
+            egui::Rect::from_min_max(egui::Pos2::new(full_rect.min.x, content_rect.max.y), full_rect.max)
+        });
         
+        // The width of the header, resized before laying out the timeline so that it follows the
+        // splitter without lagging a frame behind.
+        let id = self.id(ui);
+        let header_w = self.header.map(|width| match self.header_resizable {
+            Some((min, max)) => header_splitter(ui, id.with("header_width"), content_rect, width, min, max),
+            None => width,
+        });
+
         // The area occupied by the timeline (excluding top panel and bottom bar).
         let mut timeline_rect = content_rect;
         // The area occupied by track headers.
-        let header_rect = self.header.map(|header_w| {
+        let header_rect = header_w.map(|header_w| {
             let mut r = content_rect;
             r.set_width(header_w);
             timeline_rect.min.x = r.right();
             r
         });
-        
-        // Bottom bar area (20px height, full width)
-        let bottom_bar_rect = egui::Rect::from_min_max(
-            egui::Pos2::new(full_rect.min.x, content_rect.max.y),
-            egui::Pos2::new(full_rect.max.x, full_rect.max.y),
-        );
+
+        // Restore the view from when the timeline was last shown before handling this pass's input.
+        if self.remember_view {
+            view::restore(ui, id.with("view"), timeline);
+        }
+        if let Some(link) = &self.link {
+            link.sync_view(ui, id, timeline);
+        }
 
         // Handle scroll and zoom interactions
-        interaction::handle_scroll_and_zoom(ui, timeline_rect, timeline);
+        let (pan_y, overscroll) = interaction::scroll_and_zoom(ui, id, timeline_rect, timeline, self.pre_roll, &self.scroll_settings);
+        interaction::edge_scroll(ui, id, timeline_rect, timeline, self.pre_roll);
+        let overscroll = if self.elastic_overscroll {
+            interaction::elastic_overscroll(ui, id.with("overscroll"), overscroll)
+        } else {
+            0.0
+        };
+
+        let style = self
+            .style
+            .unwrap_or_else(|| TimelineStyle::from_visuals(ui.visuals()));
 
         // Draw the background.
-        let vis = ui.style().noninteractive();
-        let bg_stroke = egui::Stroke {
-            width: 0.0,
-            ..vis.bg_stroke
-        };
-        ui.painter().rect(full_rect, 0.0, vis.bg_fill, bg_stroke);
+        ui.painter().rect_filled(full_rect, 0.0, style.background_fill);
 
-        // Draw top panel background (no border)
-        let vis = ui.style().noninteractive();
-        ui.painter().rect(top_panel_rect, 0.0, vis.bg_fill, vis.bg_stroke);
-        
-        // Draw a 1px green border around the entire timeline widget (including header column, top panel, and bottom bar)
-        // to visualize the complete viewport
-        let green_border = egui::Stroke {
-            width: 1.0,
-            color: egui::Color32::from_rgb(0, 255, 0),
-        };
-        // full_rect includes the top panel and bottom bar area, so the border will encompass everything
-        ui.painter().rect_stroke(full_rect, 0.0, green_border);
+        // Draw top panel background
+        if let Some(top_panel_rect) = top_panel_rect {
+            let vis = ui.style().noninteractive();
+            ui.painter().rect(top_panel_rect, 0.0, style.background_fill, vis.bg_stroke);
+        }
 
         // The child widgets (content area, excluding bottom bar).
         let layout = egui::Layout::top_down(egui::Align::Min);
         let info = timeline.musical_ruler_info();
         let visible_ticks = info.ticks_per_point() * timeline_rect.width();
-        let timeline_ctx = TimelineCtx::new(timeline_rect, visible_ticks);
-        let tracks = TracksCtx::new(content_rect, header_rect, timeline_ctx);
+        let timeline_start = timeline.timeline_start();
+        let mut timeline_ctx = TimelineCtx::new(
+            id,
+            timeline_rect,
+            visible_ticks,
+            timeline_start,
+            style,
+            // A mode chosen via `SetPlayhead::snap_selector` takes precedence
+            ui.data(|d| d.get_temp::<SnapMode>(id.with("snap_mode"))).unwrap_or(self.snap),
+            self.tool_mode,
+        );
+        timeline_ctx.timeline_end = timeline.timeline_end();
+        timeline_ctx.pre_roll = self.pre_roll;
+        timeline_ctx.snap_grid = SnapGrid::capture(info, visible_ticks);
+        if self.remember_view {
+            view::remember(ui, id.with("view"), timeline);
+        }
+        if let Some(link) = &self.link {
+            link.share_view(ui, id, timeline);
+        }
+        timeline_ctx.overscroll = overscroll;
+        let mut tracks = TracksCtx::new(content_rect, header_rect, timeline_ctx);
+        tracks.pan_y = pan_y;
         let ui = ui.new_child(egui::UiBuilder::new().max_rect(content_rect).layout(layout));
-        Show { tracks, ui, bottom_bar_rect: Some(bottom_bar_rect), top_panel_rect: Some(top_panel_rect) }
+        let hover_indicator = self.hover_indicator;
+        let tracks_options = TracksOptions::default();
+        Show {
+            tracks,
+            ui,
+            bottom_bar_rect,
+            top_panel_rect,
+            hover_indicator,
+            tracks_options,
+            scroll_to: None,
+            pinned_bottom_rect: None,
+            pinned_top_bottom: None,
+            link: self.link,
+        }
     }
 }
 
+impl Default for Timeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Fill the given `rect` with a vertical gradient from `color` at the top to transparent at the
+/// bottom.
+fn paint_drop_shadow(ui: &egui::Ui, rect: egui::Rect, color: egui::Color32) {
+    if !rect.is_positive() {
+        return;
+    }
+    let mut mesh = egui::Mesh::default();
+    let transparent = egui::Color32::TRANSPARENT;
+    mesh.colored_vertex(rect.left_top(), color);
+    mesh.colored_vertex(rect.right_top(), color);
+    mesh.colored_vertex(rect.left_bottom(), transparent);
+    mesh.colored_vertex(rect.right_bottom(), transparent);
+    mesh.add_triangle(0, 1, 2);
+    mesh.add_triangle(1, 2, 3);
+    ui.painter().add(mesh);
+}
+
+/// Dim the part of the timeline area past the end of the timeline, and mark the end with a line.
+///
+/// Painted over the tracks, including pinned tracks, so the painter ignores the clip that keeps
+/// the scrollable tracks beneath the pinned tracks.
+fn paint_past_end(ui: &egui::Ui, timeline: &TimelineCtx) {
+    let Some(end) = timeline.timeline_end else {
+        return;
+    };
+    let rect = timeline.full_rect;
+    let x = timeline.tick_to_x(end - timeline.timeline_start);
+    if x >= rect.right() {
+        return;
+    }
+    let painter = egui::Painter::new(ui.ctx().clone(), ui.layer_id(), rect);
+    let past_end = egui::Rect::from_x_y_ranges(x.max(rect.left())..=rect.right(), rect.y_range());
+    painter.rect_filled(past_end, 0.0, timeline.style.past_end_fill);
+    if x >= rect.left() {
+        painter.vline(x, rect.y_range(), timeline.style.end_stroke);
+    }
+}
+
+/// Dim the part of the timeline area before tick `0.0`, shown when scrolled into the pre-roll.
+///
+/// Painted over the tracks in the same manner as `paint_past_end`.
+fn paint_pre_roll(ui: &egui::Ui, timeline: &TimelineCtx) {
+    if timeline.timeline_start >= 0.0 {
+        return;
+    }
+    let rect = timeline.full_rect;
+    let x = timeline.tick_to_x(-timeline.timeline_start).min(rect.right());
+    let painter = egui::Painter::new(ui.ctx().clone(), ui.layer_id(), rect);
+    let pre_roll = egui::Rect::from_x_y_ranges(rect.left()..=x, rect.y_range());
+    painter.rect_filled(pre_roll, 0.0, timeline.style.pre_roll_fill);
+}
+
+/// The width of the splitter along the right edge of a resizable header.
+const HEADER_SPLITTER_WIDTH: f32 = 4.0;
+
+/// Handle dragging the splitter along the right edge of a resizable header.
+///
+/// The splitter lies just within the header so that it does not compete with the tracks for the
+/// pointer. Returns the width of the header for this frame, persisted in egui memory under
+/// `width_id` once resized.
+fn header_splitter(
+    ui: &egui::Ui,
+    width_id: egui::Id,
+    content_rect: egui::Rect,
+    width: f32,
+    min: f32,
+    max: f32,
+) -> f32 {
+    let width = ui.data(|d| d.get_temp::<f32>(width_id)).unwrap_or(width).clamp(min, max);
+    let x = content_rect.left() + width;
+    let rect = egui::Rect::from_x_y_ranges(x - HEADER_SPLITTER_WIDTH..=x, content_rect.y_range());
+    let response = ui
+        .interact(rect, width_id.with("splitter"), egui::Sense::drag())
+        .on_hover_cursor(egui::CursorIcon::ResizeHorizontal);
+    if response.dragged()
+        && let Some(pos) = response.interact_pointer_pos()
+    {
+        ui.ctx().set_cursor_icon(egui::CursorIcon::ResizeHorizontal);
+        let new_width = (pos.x - content_rect.left()).clamp(min, max);
+        ui.data_mut(|d| d.insert_temp(width_id, new_width));
+        return new_width;
+    }
+    width
+}
+
 impl Show {
     /// Allows for drawing some widgets in the background before showing the grid.
     ///
-    /// Can be useful for subtly colouring different ranges, etc.
+    /// Can be useful for subtly colouring different ranges, etc. See
+    /// `BackgroundCtx::paint_tick_range` for shading a range of ticks. The `Ui` is clipped to the
+    /// timeline area so that the background cannot spill into the header column.
     pub fn background(mut self, background: impl FnOnce(&BackgroundCtx, &mut egui::Ui)) -> Self {
         let Show {
             ref mut ui,
             ref tracks,
             bottom_bar_rect: _,
             top_panel_rect: _,
+            hover_indicator: _,
+            tracks_options: _,
+            scroll_to: _,
+            pinned_bottom_rect: _,
+            pinned_top_bottom: _,
+            link: _,
         } = self;
         let bg = BackgroundCtx {
             header_full_rect: tracks.header_full_rect,
             timeline: &tracks.timeline,
         };
+        let clip_rect = ui.clip_rect();
+        ui.set_clip_rect(clip_rect.intersect(tracks.timeline.full_rect));
         background(&bg, ui);
+        ui.set_clip_rect(clip_rect);
         self
     }
 
+    /// The width of the header, if any, e.g. to persist a width chosen via
+    /// `Timeline::header_resizable`.
+    pub fn header_width(&self) -> Option<f32> {
+        self.tracks.header_full_rect.map(|rect| rect.width())
+    }
+
     /// Paints the grid over the timeline `Rect`.
     ///
     /// If using a custom `background`, you may wish to call this after.
@@ -131,6 +541,91 @@ impl Show {
         self
     }
 
+    /// Shades every other group of bars over the timeline `Rect`, e.g. to make long timelines
+    /// easier to read.
+    ///
+    /// Call this before `paint_grid` so that the grid is drawn over the top.
+    pub fn paint_bar_shading(mut self, info: &dyn ruler::MusicalInfo, style: grid::BarShadingStyle) -> Self {
+        grid::paint_bar_shading(&mut self.ui, &self.tracks.timeline, info, style);
+        self
+    }
+
+    /// Paints the grid over the timeline `Rect` using the given `GridMode`.
+    ///
+    /// Use `GridMode::Seconds` for a time based grid or `GridMode::Musical` to align the grid to a
+    /// fixed beat subdivision.
+    pub fn paint_grid_with(mut self, info: &dyn ruler::MusicalInfo, mode: grid::GridMode) -> Self {
+        grid::paint_grid_with(&mut self.ui, &self.tracks.timeline, info, mode);
+        self
+    }
+
+    /// Handle keyboard shortcuts for the transport and navigation.
+    ///
+    /// Shortcuts are only handled while the timeline has focus, i.e. until the user clicks
+    /// outside of it, and never while another widget such as a `TextEdit` has keyboard focus.
+    /// Seeking, nudging and zooming are applied via `api`, while `play_pause` is called to toggle
+    /// playback. The absolute tick range `selection` is fitted to the view by the
+    /// `zoom_to_selection` shortcut, e.g. as found via `view::selection_range`. See `KeyBindings`
+    /// for the default shortcuts.
+    pub fn keyboard_shortcuts(
+        self,
+        bindings: &KeyBindings,
+        api: &mut dyn FollowApi,
+        selection: Option<Range<f32>>,
+        play_pause: impl FnOnce(),
+    ) -> Self {
+        let timeline = &self.tracks.timeline;
+        keyboard::handle(&self.ui, self.tracks.full_rect, timeline, bindings, api, selection, play_pause);
+        self
+    }
+
+    /// Zoom and scroll so that the whole timeline, from any pre-roll up to
+    /// `TimelineApi::timeline_end`, fits the view. Does nothing if the timeline is unbounded.
+    ///
+    /// The new view is shown from the next pass. See `view::fit_range`.
+    pub fn zoom_to_fit(self, api: &mut dyn crate::TimelineApi) -> Self {
+        if let Some(end) = api.timeline_end() {
+            let timeline = &self.tracks.timeline;
+            view::fit_range_with_pre_roll(api, -timeline.pre_roll..end, timeline.full_rect.width(), timeline.pre_roll);
+            self.ui.ctx().request_repaint();
+        }
+        self
+    }
+
+    /// Zoom and scroll so that every track's selections fit the view. Does nothing if nothing is
+    /// selected.
+    ///
+    /// The new view is shown from the next pass. See `view::fit_range`.
+    pub fn zoom_to_selection<A>(self, api: &mut A) -> Self
+    where
+        A: crate::TimelineApi + crate::interaction::TrackSelectionApi,
+    {
+        if let Some(range) = view::selection_range(api) {
+            let timeline = &self.tracks.timeline;
+            view::fit_range_with_pre_roll(api, range, timeline.full_rect.width(), timeline.pre_roll);
+            self.ui.ctx().request_repaint();
+        }
+        self
+    }
+
+    /// Set how the tracks shown by `tracks` are scrolled.
+    ///
+    /// See `TracksOptions` for the defaults.
+    pub fn tracks_options(mut self, options: TracksOptions) -> Self {
+        self.tracks_options = options;
+        self
+    }
+
+    /// Scroll the tracks shown by `tracks` during this pass.
+    ///
+    /// This is a one-off request, e.g. to bring a newly selected track into view, so it should
+    /// only be made on the pass that the scroll is wanted. The resulting offset is available via
+    /// `SetPlayhead::scroll_offset`.
+    pub fn scroll_to(mut self, scroll_to: ScrollTo) -> Self {
+        self.scroll_to = Some(scroll_to);
+        self
+    }
+
     /// Set some tracks that should be pinned to the top.
     ///
     /// Often useful for the ruler or other tracks that should always be visible.
@@ -140,14 +635,50 @@ impl Show {
             ref tracks,
             bottom_bar_rect: _,
             top_panel_rect: _,
+            hover_indicator: _,
+            tracks_options: _,
+            scroll_to: _,
+            pinned_bottom_rect: _,
+            pinned_top_bottom: _,
+            link: _,
         } = self;
 
         // Use no spacing by default so we can get exact position for line separator.
         ui.scope(|ui| tracks_fn(tracks, ui));
 
-        // Return to default spacing.
+        // Clip the scrollable tracks that follow so that they pass beneath the pinned tracks.
         let rect = ui.available_rect_before_wrap();
         self.ui.set_clip_rect(rect);
+        self.pinned_top_bottom = Some(rect.top());
+        self
+    }
+
+    /// Set tracks pinned to the bottom of the timeline, beneath the scrollable tracks, e.g. for a
+    /// master track or a tempo lane.
+    ///
+    /// Call this before `tracks`, which are given the remaining height. The space for the pinned
+    /// tracks is reserved using their height when last shown, and the pass is discarded in order
+    /// to lay out the timeline again if their height changes. A separator line is painted above
+    /// them. See `Playhead::through_pinned_bottom` to extend the playhead through these tracks.
+    pub fn pinned_tracks_bottom(mut self, tracks_fn: impl FnOnce(&TracksCtx, &mut egui::Ui)) -> Self {
+        let height_id = self.tracks.timeline.id.with("pinned_bottom_height");
+        let height = self.ui.data(|d| d.get_temp::<f32>(height_id)).unwrap_or(0.0);
+        let available = self.ui.available_rect_before_wrap();
+        let mut rect = available;
+        rect.min.y = (available.max.y - height).max(available.min.y);
+
+        let mut ui = self.ui.new_child(egui::UiBuilder::new().max_rect(rect).layout(*self.ui.layout()));
+        ui.set_clip_rect(rect.intersect(self.ui.clip_rect()));
+        tracks_fn(&self.tracks, &mut ui);
+        let new_height = ui.min_rect().height();
+        if (new_height - height).abs() > 0.5 {
+            self.ui.data_mut(|d| d.insert_temp(height_id, new_height));
+            self.ui.ctx().request_discard("pinned tracks resized");
+        }
+
+        let stroke = self.ui.style().noninteractive().bg_stroke;
+        self.ui.painter().hline(self.tracks.full_rect.x_range(), rect.top(), stroke);
+        self.pinned_bottom_rect = Some(rect);
         self
     }
 
@@ -159,37 +690,134 @@ impl Show {
     ///
     /// If `playhead_api` is provided, clicking and dragging on the timeline area of tracks will set the playhead position.
     /// If `selection_api` is provided, clicking and dragging on tracks will create selections.
-    pub fn tracks(
+    ///
+    /// The value returned by `tracks_fn` is returned alongside the `SetPlayhead`, e.g. to report
+    /// which clip was clicked.
+    pub fn tracks<R>(
+        self,
+        tracks_fn: impl FnOnce(&TracksCtx, egui::Rect, &mut egui::Ui, Option<&dyn PlayheadApi>, Option<&dyn crate::interaction::TrackSelectionApi>) -> R,
+        playhead_api: Option<&dyn PlayheadApi>,
+        selection_api: Option<&dyn crate::interaction::TrackSelectionApi>,
+    ) -> (SetPlayhead, R) {
+        self.tracks_inner(tracks_fn, playhead_api, selection_api, false)
+    }
+
+    /// The same as `tracks`, but selections are created by dragging out a rectangle across tracks.
+    ///
+    /// On release, every track with an id that the rectangle touches vertically is given the same
+    /// tick range, like the time selections of many DAWs. Existing selections may still be
+    /// resized and moved on each track.
+    pub fn tracks_with_rect_select<R>(
+        self,
+        tracks_fn: impl FnOnce(&TracksCtx, egui::Rect, &mut egui::Ui, Option<&dyn PlayheadApi>, Option<&dyn crate::interaction::TrackSelectionApi>) -> R,
+        playhead_api: Option<&dyn PlayheadApi>,
+        selection_api: Option<&dyn crate::interaction::TrackSelectionApi>,
+    ) -> (SetPlayhead, R) {
+        self.tracks_inner(tracks_fn, playhead_api, selection_api, true)
+    }
+
+    fn tracks_inner<R>(
         mut self,
-        tracks_fn: impl FnOnce(&TracksCtx, egui::Rect, &mut egui::Ui, Option<&dyn PlayheadApi>, Option<&dyn crate::interaction::TrackSelectionApi>),
+        tracks_fn: impl FnOnce(&TracksCtx, egui::Rect, &mut egui::Ui, Option<&dyn PlayheadApi>, Option<&dyn crate::interaction::TrackSelectionApi>) -> R,
         playhead_api: Option<&dyn PlayheadApi>,
         selection_api: Option<&dyn crate::interaction::TrackSelectionApi>,
-    ) -> SetPlayhead {
+        rect_select: bool,
+    ) -> (SetPlayhead, R) {
+        self.tracks.rect_select = rect_select;
         let Self {
             ref mut ui,
             ref tracks,
             bottom_bar_rect,
             top_panel_rect: _,
+            hover_indicator,
+            tracks_options,
+            scroll_to,
+            pinned_bottom_rect,
+            pinned_top_bottom,
+            link,
         } = self;
-        let rect = ui.available_rect_before_wrap();
+        let mut rect = ui.available_rect_before_wrap();
+        if let Some(pinned_rect) = pinned_bottom_rect {
+            rect.max.y = rect.max.y.min(pinned_rect.top()).max(rect.min.y);
+        }
         let enable_scrolling = !ui.input(|i| i.modifiers.ctrl);
-        let res = egui::ScrollArea::vertical()
+        let mut scroll_area = egui::ScrollArea::vertical()
+            .id_salt(tracks.timeline.id.with("tracks_scroll"))
             .max_height(rect.height())
             .enable_scrolling(enable_scrolling)
-            .animated(true)
-            .stick_to_bottom(true) // stick to new tracks as they're added
-            .show_viewport(ui, |ui, view| {
-                tracks_fn(tracks, view, ui, playhead_api, selection_api);
-            });
-        let timeline_rect = tracks.timeline.full_rect;
+            .animated(tracks_options.animated)
+            .stick_to_bottom(tracks_options.stick_to_bottom) // stick to new tracks as they're added
+            .scroll_bar_visibility(tracks_options.scrollbar_visibility);
+        // Follow the vertical scroll of linked timelines unless scrolled by the host.
+        let linked_offset = link.and_then(|link| link.receive_scroll(ui, tracks.timeline.id));
+        match (scroll_to, linked_offset) {
+            (Some(ScrollTo::Offset(offset)), _) | (None, Some(offset)) => {
+                scroll_area = scroll_area.vertical_scroll_offset(offset.max(0.0));
+            }
+            _ => (),
+        }
+        let res = scroll_area.show_viewport(ui, |ui, view| {
+            // Scroll vertically while panning with the middle mouse button.
+            if tracks.pan_y != 0.0 {
+                ui.scroll_with_delta(egui::Vec2::new(0.0, tracks.pan_y));
+            }
+            let inner = tracks_fn(tracks, view, ui, playhead_api, selection_api);
+            if rect_select {
+                crate::interaction::handle_rect_selection(ui, tracks, selection_api);
+            }
+            // Tracks are only known once shown, so scroll to them afterwards.
+            match scroll_to {
+                Some(ScrollTo::Track(track_id)) => {
+                    let track_rects = tracks.track_rects.borrow();
+                    if let Some(&(_, rect)) = track_rects.iter().find(|(id, _)| *id == track_id) {
+                        ui.scroll_to_rect(rect, None);
+                    }
+                }
+                Some(ScrollTo::Bottom) => ui.scroll_to_cursor(Some(egui::Align::BOTTOM)),
+                Some(ScrollTo::Offset(_)) | None => (),
+            }
+            (view, inner)
+        });
+        let (viewport, inner) = res.inner;
+        if let Some(link) = link {
+            link.share_scroll(ui, tracks.timeline.id, res.state.offset.y);
+        }
+        // Separate the pinned tracks from the tracks scrolled beneath them.
+        if let Some(y) = pinned_top_bottom
+            && let Some(shadow) = tracks.timeline.style.pinned_shadow
+            && res.state.offset.y > 0.0
+        {
+            let rect = egui::Rect::from_x_y_ranges(tracks.full_rect.x_range(), y..=y + shadow.height);
+            paint_drop_shadow(ui, rect.intersect(res.inner_rect), shadow.color);
+        }
+        paint_past_end(ui, &tracks.timeline);
+        paint_pre_roll(ui, &tracks.timeline);
+        // The playhead follows the contents of the tracks while overscrolled.
+        let timeline_rect = tracks.timeline.full_rect.translate(egui::Vec2::new(tracks.timeline.overscroll, 0.0));
         let tracks_bottom = res
             .inner_rect
             .bottom()
             .min(res.inner_rect.top() + res.content_size.y);
-        let mut set_playhead = SetPlayhead::new(timeline_rect, tracks_bottom);
+        if hover_indicator {
+            interaction::paint_hover_indicator(ui, &tracks.timeline, tracks_bottom, playhead_api);
+        }
+        let mut set_playhead = SetPlayhead::new(
+            tracks.timeline.id,
+            timeline_rect,
+            tracks_bottom,
+            tracks.timeline.style.clone(),
+            tracks.timeline.snap,
+        );
         set_playhead.bottom_bar_rect = bottom_bar_rect;
         set_playhead.top_panel_rect = self.top_panel_rect;
-        set_playhead
+        set_playhead.scroll_offset = res.state.offset.y;
+        set_playhead.tracks_viewport = viewport;
+        set_playhead.pinned_bottom = pinned_bottom_rect.map(|rect| rect.bottom());
+        set_playhead.track_rects = tracks.track_rects.borrow().clone();
+        set_playhead.timeline_end = tracks.timeline.timeline_end;
+        set_playhead.pre_roll = tracks.timeline.pre_roll;
+        interaction::remember_track_rects(ui, tracks.timeline.id, &set_playhead.track_rects);
+        (set_playhead, inner)
     }
 }
 
@@ -199,16 +827,203 @@ impl SetPlayhead {
         &self,
         ui: &mut egui::Ui,
         info: &mut dyn PlayheadApi,
-        playhead: crate::playhead::Playhead,
+        mut playhead: crate::playhead::Playhead,
     ) -> &Self {
-        crate::playhead::set(ui, info, self.timeline_rect(), self.tracks_bottom(), playhead);
+        // Show positions in the format chosen for the top panel's time readout by default.
+        if playhead.time_display.is_none() {
+            playhead.time_display = ui.data(|d| d.get_temp::<TimeDisplay>(self.id.with("time_display")));
+        }
+        playhead.pixel_snap = self.style.pixel_snap_playhead;
+        playhead.timeline_end = self.timeline_end;
+        let tracks_bottom = match self.pinned_bottom {
+            Some(bottom) if playhead.through_pinned_bottom => bottom,
+            _ => self.tracks_bottom(),
+        };
+        let response = crate::playhead::set(
+            ui,
+            info,
+            self.timeline_rect(),
+            tracks_bottom,
+            playhead,
+            self.style.playhead_stroke,
+            self.snap,
+        );
+        // Scroll the view when the playhead is dragged towards either edge.
+        if response.dragged_by(egui::PointerButton::Primary) {
+            interaction::request_edge_scroll(ui, self.id);
+        }
+        self
+    }
+
+    /// Draw read-only cursors such as a punch-in point or collaborators' playheads.
+    ///
+    /// Each ghost playhead is drawn as a dashed line through all tracks, with its label (if any)
+    /// at the top of the timeline. Call this before `playhead` so that the playhead is drawn over
+    /// the top.
+    pub fn ghost_playheads(&self, ui: &mut egui::Ui, api: &dyn PlayheadApi, ghosts: &[GhostPlayhead]) -> &Self {
+        crate::playhead::paint_ghosts(ui, api, self.timeline_rect(), self.tracks_bottom(), ghosts);
+        self
+    }
+
+    /// Paint a custom overlay over the tracks, e.g. a drop indicator or a custom playhead.
+    ///
+    /// The overlay is given a child `Ui` clipped to the timeline area, so that its painter may be
+    /// used directly, along with the areas of the timeline. The `Ui` takes up no space.
+    pub fn overlay(&self, ui: &mut egui::Ui, overlay: impl FnOnce(&mut egui::Ui, &TimelineRects)) -> &Self {
+        let rects = self.rects();
+        let ui = &mut ui.new_child(egui::UiBuilder::new().max_rect(rects.timeline));
+        ui.set_clip_rect(rects.timeline.intersect(ui.clip_rect()));
+        overlay(ui, &rects);
+        self
+    }
+
+    /// Accept files dragged from the OS, or payloads of type `P` dragged from other widgets via
+    /// `egui::DragAndDrop`, dropped onto the tracks.
+    ///
+    /// While a drag hovers over the timeline, a line is drawn across the track under the pointer
+    /// at the tick the drop would land at, snapped to the timeline's `SnapMode`. On release,
+    /// `on_drop` is called with the track's id, or `None` beneath the last track so that the host
+    /// may create a new one, along with the absolute tick. Only tracks with an id are targets.
+    pub fn drop_target<P>(
+        &self,
+        ui: &mut egui::Ui,
+        info: &dyn ruler::MusicalInfo,
+        on_drop: impl FnOnce(Option<TrackId>, f32, DropPayload<P>),
+    ) -> &Self
+    where
+        P: std::any::Any + Send + Sync,
+    {
+        let stroke = egui::Stroke::new(2.0, ui.visuals().selection.stroke.color);
+        crate::drop::drop_target(
+            ui,
+            info,
+            self.timeline_rect(),
+            self.tracks_bottom(),
+            &self.track_rects,
+            self.snap,
+            stroke,
+            on_drop,
+        );
+        self
+    }
+
+    /// Paint the loop region as a translucent overlay across all tracks.
+    ///
+    /// Nothing is painted while no region is set or looping is disabled. The region itself is
+    /// edited on the ruler when the host provides `MusicalRuler::loop_api`.
+    pub fn loop_region(
+        &self,
+        ui: &mut egui::Ui,
+        info: &dyn ruler::MusicalInfo,
+        loop_api: &dyn LoopApi,
+    ) -> &Self {
+        if let Some((start, end)) = loop_api.loop_range()
+            && loop_api.loop_enabled()
+        {
+            let timeline_rect = self.timeline_rect();
+            let ticks_per_point = info.ticks_per_point();
+            let timeline_start = info.timeline_start().unwrap_or(0.0);
+            let start_x = timeline_rect.left() + (start - timeline_start) / ticks_per_point;
+            let end_x = timeline_rect.left() + (end - timeline_start) / ticks_per_point;
+            let y_range = timeline_rect.top()..=self.tracks_bottom();
+            let rect = egui::Rect::from_x_y_ranges(start_x..=end_x, y_range).intersect(timeline_rect);
+            if rect.is_positive() {
+                ui.painter().rect_filled(rect, 0.0, self.style.loop_fill);
+            }
+        }
+        self
+    }
+
+    /// Paint the time selection as a translucent overlay across all tracks.
+    ///
+    /// Nothing is painted while there is no selection. The selection itself is edited via the
+    /// ruler when `MusicalRuler::time_selection_api` is provided.
+    pub fn time_selection(
+        &self,
+        ui: &mut egui::Ui,
+        info: &dyn ruler::MusicalInfo,
+        api: &dyn TimeSelectionApi,
+    ) -> &Self {
+        if let Some((start, end)) = api.time_selection() {
+            let timeline_rect = self.timeline_rect();
+            let ticks_per_point = info.ticks_per_point();
+            let timeline_start = info.timeline_start().unwrap_or(0.0);
+            let start_x = timeline_rect.left() + (start - timeline_start) / ticks_per_point;
+            let end_x = timeline_rect.left() + (end - timeline_start) / ticks_per_point;
+            let y_range = timeline_rect.top()..=self.tracks_bottom();
+            let rect = egui::Rect::from_x_y_ranges(start_x..=end_x, y_range).intersect(timeline_rect);
+            if rect.is_positive() {
+                ui.painter().rect_filled(rect, 0.0, self.style.time_selection_fill);
+            }
+        }
+        self
+    }
+
+    /// Draw the markers as flags along the top of the timeline with lines through all tracks.
+    ///
+    /// Flags may be dragged horizontally to move their marker, snapping to the timeline's
+    /// `SnapMode`. Right-clicking a flag offers to rename or delete its marker. Labels that
+    /// would overlap a label to their left are hidden.
+    pub fn markers(&self, ui: &mut egui::Ui, api: &dyn MarkerApi) -> &Self {
+        marker::show(
+            ui,
+            api,
+            self.id,
+            self.timeline_rect(),
+            self.tracks_bottom(),
+            self.snap,
+        );
+        self
+    }
+
+    /// Show a horizontal scrollbar visualising the view relative to the whole timeline.
+    ///
+    /// The scrollbar fills the available width of `ui` and fits within the bottom bar, though it
+    /// may be placed anywhere. Drag the thumb to scroll, drag either end of the thumb to zoom, or
+    /// click either side of the thumb to page left or right.
+    pub fn scrollbar(&self, ui: &mut egui::Ui, timeline_api: &mut dyn crate::TimelineApi) -> &Self {
+        crate::scrollbar::show(ui, self.id, self.timeline_rect().width(), self.pre_roll, timeline_api);
+        self
+    }
+
+    /// Show a minimap of the whole timeline with the given `height`.
+    ///
+    /// `draw` paints a compressed summary of the tracks, e.g. each clip as a thin bar, within the
+    /// area of the given `MinimapCtx`. The visible range is drawn over the top and may be dragged
+    /// to scroll or have its ends dragged to zoom, as with `scrollbar`. The playhead is drawn as
+    /// a thin line.
+    pub fn minimap(
+        &self,
+        ui: &mut egui::Ui,
+        height: f32,
+        timeline_api: &mut dyn FollowApi,
+        draw: impl FnOnce(&MinimapCtx, &mut egui::Ui),
+    ) -> &Self {
+        let width = self.timeline_rect().width();
+        crate::minimap::show(ui, self.id, height, width, self.pre_roll, timeline_api, &self.style, draw);
+        self
+    }
+
+    /// Scroll the timeline so that the playhead remains visible as it moves.
+    ///
+    /// See `FollowMode` for the available behaviours. Following is suspended while the user is
+    /// dragging within or scrolling over the timeline. Hosts usually pass `FollowMode::Off`
+    /// while the transport is stopped so that the user can freely scroll away.
+    pub fn follow_playhead(
+        &self,
+        ui: &egui::Ui,
+        api: &mut dyn crate::playhead::FollowApi,
+        mode: crate::playhead::FollowMode,
+    ) -> &Self {
+        crate::playhead::follow_with_pre_roll(ui, api, self.timeline_rect(), mode, self.pre_roll);
         self
     }
 
-    /// Display time in the top panel.
-    /// 
-    /// `playhead_api` should provide access to the current playhead position.
     /// Show the time in the top panel.
+    ///
+    /// Does nothing unless the panel was reserved with `Timeline::top_panel`. See `top_panel` for
+    /// a transport bar with standard buttons and room for host widgets.
+    ///
     /// `playhead_api` should provide access to the current playhead position.
     /// `get_is_playing` closure returns the current play state.
     /// `set_is_playing` closure sets the play state.
@@ -217,6 +1032,9 @@ impl SetPlayhead {
     /// `add_track_callback` closure is called when "Add Track" button is clicked.
     /// `remove_track_callback` closure is called when "Remove Track" button is clicked.
     /// `has_selected_track` closure returns whether a track is currently selected.
+    /// `time_display` is the initial format of the time readout. Clicking the readout cycles
+    /// through the built-in formats, remembering the choice in egui memory.
+    #[allow(clippy::too_many_arguments)]
     pub fn top_panel_time(
         &self,
         ui: &mut egui::Ui,
@@ -228,6 +1046,7 @@ impl SetPlayhead {
         mut add_track_callback: impl FnMut(),
         mut remove_track_callback: impl FnMut(),
         has_selected_track: impl Fn() -> bool,
+        time_display: TimeDisplay,
     ) -> &Self {
         if let Some(top_panel_rect) = self.top_panel_rect {
             // Create UI for top panel to display time
@@ -266,32 +1085,32 @@ impl SetPlayhead {
                             ui.add_space(4.0); // Spacing
                             
                             // "<" button - set playhead to start (position 0)
-                            if ui.button("<").clicked() {
-                                if let Some(api) = playhead_api {
-                                    // Get current timeline_start (scroll offset) from the API
-                                    let timeline_start = api.timeline_start().unwrap_or(0.0);
-                                    // Calculate relative ticks to set absolute position to 0
-                                    // new_pos = timeline_start + ticks = 0, so ticks = -timeline_start
-                                    let ticks = -timeline_start;
-                                    if ticks.is_finite() {
-                                        api.set_playhead_ticks(ticks);
-                                    }
+                            if ui.button("<").clicked()
+                                && let Some(api) = playhead_api
+                            {
+                                // Get current timeline_start (scroll offset) from the API
+                                let timeline_start = api.timeline_start().unwrap_or(0.0);
+                                // Calculate relative ticks to set absolute position to 0
+                                // new_pos = timeline_start + ticks = 0, so ticks = -timeline_start
+                                let ticks = -timeline_start;
+                                if ticks.is_finite() {
+                                    api.set_playhead_ticks(ticks);
                                 }
                             }
                             
                             ui.add_space(4.0); // Spacing
                             
                             // ">" button - set playhead to end (maximum position)
-                            if ui.button(">").clicked() {
-                                if let Some(api) = playhead_api {
-                                    // Get current timeline_start (scroll offset) from the API
-                                    let timeline_start = api.timeline_start().unwrap_or(0.0);
-                                    // Calculate relative ticks to set absolute position to max_playhead_pos
-                                    // new_pos = timeline_start + ticks = max_playhead_pos, so ticks = max_playhead_pos - timeline_start
-                                    let ticks = max_playhead_pos - timeline_start;
-                                    if ticks.is_finite() {
-                                        api.set_playhead_ticks(ticks);
-                                    }
+                            if ui.button(">").clicked()
+                                && let Some(api) = playhead_api
+                            {
+                                // Get current timeline_start (scroll offset) from the API
+                                let timeline_start = api.timeline_start().unwrap_or(0.0);
+                                // Calculate relative ticks to set absolute position to max_playhead_pos
+                                // new_pos = timeline_start + ticks = max_playhead_pos, so ticks = max_playhead_pos - timeline_start
+                                let ticks = max_playhead_pos - timeline_start;
+                                if ticks.is_finite() {
+                                    api.set_playhead_ticks(ticks);
                                 }
                             }
                             
@@ -316,139 +1135,124 @@ impl SetPlayhead {
                 
                 // Right side: Time display
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                if let Some(api) = playhead_api {
-                    // Get current playhead position in ticks (absolute from timeline start at 0)
-                    // playhead_ticks() returns relative ticks from timeline start (scroll position)
-                    // We need the absolute position from the beginning of the timeline (tick 0)
-                    let playhead_ticks_relative = api.playhead_ticks();
-                    // Get timeline_start from MusicalInfo trait (this is the scroll offset)
-                    let timeline_start = api.timeline_start().unwrap_or(0.0);
-                    // Absolute playhead position from the beginning of the timeline
-                    let absolute_playhead_ticks = timeline_start + playhead_ticks_relative;
-                    
-                    // Convert ticks to time based on bars
-                    // Each bar should be 1 second, so calculate which bar we're in and the fraction within that bar
-                    let ticks_per_beat = api.ticks_per_beat() as f32;
-                    // 4/4 time signature = 4 beats per bar
-                    const BEATS_PER_BAR: f32 = 4.0;
-                    let ticks_per_bar = ticks_per_beat * BEATS_PER_BAR;
-                    
-                    // Calculate which bar we're in and the fraction within that bar
-                    let bar_number = absolute_playhead_ticks / ticks_per_bar;
-                    let total_seconds = bar_number; // Each bar = 1 second
-                    
-                    let minutes = (total_seconds / 60.0).floor() as u32;
-                    let seconds = (total_seconds % 60.0).floor() as u32;
-                    let centiseconds = ((total_seconds % 1.0) * 100.0).floor() as u32;
-                    
-                    let time_string = format!("{:02}:{:02}:{:02}", minutes, seconds, centiseconds);
-                    ui.label(time_string);
-                } else {
-                    // Fallback if no playhead API
-                    ui.label("00:00:00");
-                }
+                    transport::time_readout(ui, self.id.with("time_display"), playhead_api, time_display);
                 });
             });
         }
         self
     }
 
-    /// Show the bottom bar with global buttons.
-    /// 
-    /// `global_panel_visible` should be a mutable reference to a bool that tracks
-    /// whether the global panel is visible. It will be toggled when the "Global" button is clicked.
-    pub fn bottom_bar(&self, ui: &mut egui::Ui, global_panel_visible: &mut bool) {
-        if let Some(bottom_bar_rect) = self.bottom_bar_rect {
-            // Get style before creating child UI
-            let vis = ui.style().noninteractive();
-            let bg_fill = vis.bg_fill;
-            let bg_stroke = vis.bg_stroke;
-            
-            // Draw bottom bar background
-            ui.painter().rect(bottom_bar_rect, 0.0, bg_fill, bg_stroke);
-            
-            // Create UI for bottom bar
-            let mut bottom_ui = ui.new_child(
+    /// Show the given `TopPanel` in the top panel: transport buttons on the left, a time readout
+    /// on the right and the panel's `extra` widgets in between.
+    ///
+    /// Does nothing unless the panel was reserved with `Timeline::top_panel`. The buttons occupy
+    /// the first row of the panel, leaving the second row to `top_panel_tools`.
+    pub fn top_panel(&self, ui: &mut egui::Ui, panel: TopPanel) -> &Self {
+        if let Some(top_panel_rect) = self.top_panel_rect {
+            panel.show(ui, top_panel_rect, self.id.with("time_display"));
+        }
+        self
+    }
+
+    /// Show a `snap::snap_selector` for choosing the timeline's snap mode, e.g. within the bottom
+    /// bar.
+    ///
+    /// The chosen mode is remembered in egui memory keyed by the timeline's id and takes
+    /// precedence over `Timeline::snap` from the next frame on.
+    pub fn snap_selector(&self, ui: &mut egui::Ui) -> &Self {
+        let mut mode = self.snap;
+        if snap::snap_selector(ui, &mut mode).changed() {
+            ui.data_mut(|d| d.insert_temp(self.id.with("snap_mode"), mode));
+        }
+        self
+    }
+
+    /// Show a `tool::toolbar` for choosing between the given tool `modes` in the second row of
+    /// the top panel, beneath the buttons of `top_panel_time`.
+    ///
+    /// Does nothing unless the panel was reserved with `Timeline::top_panel`. The chosen mode
+    /// takes effect once passed to `Timeline::tool_mode` on the next frame.
+    pub fn top_panel_tools(&self, ui: &mut egui::Ui, mode: &mut ToolMode, modes: &[ToolMode]) -> &Self {
+        if let Some(top_panel_rect) = self.top_panel_rect {
+            let mut rect = top_panel_rect;
+            rect.min.y = rect.center().y;
+            rect.min.x += 4.0; // Left padding, matching `top_panel_time`
+            let mut tools_ui = ui.new_child(
                 egui::UiBuilder::new()
-                    .max_rect(bottom_bar_rect)
+                    .max_rect(rect)
                     .layout(egui::Layout::left_to_right(egui::Align::Center)),
             );
-            
-            // Add "Global" button
-            if bottom_ui.button("Global").clicked() {
-                *global_panel_visible = !*global_panel_visible;
-            }
-            
-            // Draw global panel if visible (100px height, above everything)
-            if *global_panel_visible {
-                const PANEL_HEIGHT: f32 = 100.0;
+            tools_ui.set_clip_rect(top_panel_rect.intersect(ui.clip_rect()));
+            tool::toolbar(&mut tools_ui, mode, modes);
+        }
+        self
+    }
+
+    /// Show the bottom bar with the given contents.
+    ///
+    /// Does nothing unless the bar was reserved with `Timeline::bottom_bar`.
+    ///
+    /// The bar is painted with the timeline's background and `contents` is clipped to the bar.
+    pub fn bottom_bar(&self, ui: &mut egui::Ui, contents: impl FnOnce(&mut egui::Ui)) -> &Self {
+        if let Some(bottom_bar_rect) = self.bottom_bar_rect {
+            let mut bottom_ui = self.bottom_bar_ui(ui, bottom_bar_rect);
+            contents(&mut bottom_ui);
+        }
+        self
+    }
+
+    /// Show the bottom bar along with a pop-up panel above it.
+    ///
+    /// Does nothing unless the bar was reserved with `Timeline::bottom_bar`.
+    ///
+    /// `bar` is given `panel_visible` so that it may provide a button to toggle the panel. When
+    /// `panel_visible` is `true`, `panel` is shown in a 100px high area directly above the bar,
+    /// drawn over the top of the tracks.
+    pub fn bottom_bar_with_panel(
+        &self,
+        ui: &mut egui::Ui,
+        panel_visible: &mut bool,
+        bar: impl FnOnce(&mut egui::Ui, &mut bool),
+        panel: impl FnOnce(&mut egui::Ui),
+    ) -> &Self {
+        const PANEL_HEIGHT: f32 = 100.0;
+        if let Some(bottom_bar_rect) = self.bottom_bar_rect {
+            let mut bottom_ui = self.bottom_bar_ui(ui, bottom_bar_rect);
+            bar(&mut bottom_ui, panel_visible);
+
+            if *panel_visible {
                 let panel_rect = egui::Rect::from_min_max(
                     egui::Pos2::new(bottom_bar_rect.min.x, bottom_bar_rect.min.y - PANEL_HEIGHT),
                     egui::Pos2::new(bottom_bar_rect.max.x, bottom_bar_rect.min.y),
                 );
-                
+
                 // Draw panel background
-                ui.painter().rect(panel_rect, 0.0, bg_fill, bg_stroke);
-                
+                let bg_stroke = ui.style().noninteractive().bg_stroke;
+                ui.painter().rect(panel_rect, 0.0, self.style.background_fill, bg_stroke);
+
                 // Create UI for panel (using a new child to ensure it's above everything)
                 let mut panel_ui = ui.new_child(
                     egui::UiBuilder::new()
                         .max_rect(panel_rect)
                         .layout(egui::Layout::top_down(egui::Align::Min)),
                 );
-                
-                // Divide panel into 6 columns
-                panel_ui.horizontal(|ui| {
-                    // Column 1: "Global Panel" label
-                    ui.vertical(|ui| {
-                        ui.set_width(panel_rect.width() / 6.0);
-                        ui.label("Global Panel");
-                    });
-                    
-                    // Column 2: Available for widgets
-                    ui.vertical(|ui| {
-                        ui.set_width(panel_rect.width() / 6.0);
-                        // Add widgets here
-                    });
-                    
-                    // Column 3: Available for widgets
-                    ui.vertical(|ui| {
-                        ui.set_width(panel_rect.width() / 6.0);
-                        // Add widgets here
-                    });
-                    
-                    // Column 4: Available for widgets
-                    ui.vertical(|ui| {
-                        ui.set_width(panel_rect.width() / 6.0);
-                        // Add widgets here
-                    });
-                    
-                    // Column 5: Available for widgets
-                    ui.vertical(|ui| {
-                        ui.set_width(panel_rect.width() / 6.0);
-                        // Add widgets here
-                    });
-                    
-                    // Column 6: Available for widgets
-                    ui.vertical(|ui| {
-                        ui.set_width(panel_rect.width() / 6.0);
-                        // Add widgets here
-                    });
-                });
-                
-                // Draw 1px grey vertical borders between columns (100% height)
-                let grey_border = egui::Stroke {
-                    width: 1.0,
-                    color: egui::Color32::from_rgb(128, 128, 128), // Grey
-                };
-                let column_width = panel_rect.width() / 6.0;
-                for i in 1..6 {
-                    let x = panel_rect.min.x + (column_width * i as f32);
-                    let top = egui::Pos2::new(x, panel_rect.min.y);
-                    let bottom = egui::Pos2::new(x, panel_rect.max.y);
-                    ui.painter().line_segment([top, bottom], grey_border);
-                }
+                panel_ui.set_clip_rect(panel_rect.intersect(ui.clip_rect()));
+                panel(&mut panel_ui);
             }
         }
+        self
+    }
+
+    /// Paint the bottom bar background and create a `Ui` clipped to it.
+    fn bottom_bar_ui(&self, ui: &mut egui::Ui, bottom_bar_rect: egui::Rect) -> egui::Ui {
+        let bg_stroke = ui.style().noninteractive().bg_stroke;
+        ui.painter().rect(bottom_bar_rect, 0.0, self.style.background_fill, bg_stroke);
+        let mut bottom_ui = ui.new_child(
+            egui::UiBuilder::new()
+                .max_rect(bottom_bar_rect)
+                .layout(egui::Layout::left_to_right(egui::Align::Center)),
+        );
+        bottom_ui.set_clip_rect(bottom_bar_rect.intersect(ui.clip_rect()));
+        bottom_ui
     }
 }
@@ -0,0 +1,54 @@
+/// What pressing and dragging on the tracks does, set via `Timeline::tool_mode`.
+///
+/// The active mode is available to track contents via `TimelineCtx::tool_mode` so that they may
+/// adapt their own behaviour, e.g. drawing notes while in `Draw` mode.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ToolMode {
+    /// Dragging creates and edits selections, while clicking moves the playhead.
+    #[default]
+    Select,
+    /// Pressing and dragging moves the playhead, leaving selections untouched.
+    Scrub,
+    /// Pointer interaction on the tracks is left to the host, e.g. for drawing clips or notes.
+    Draw,
+    /// Pointer interaction on the tracks is left to the host, e.g. for erasing clips or notes.
+    Erase,
+}
+
+impl ToolMode {
+    /// All modes, in the order they are shown by `toolbar`.
+    pub const ALL: [Self; 4] = [Self::Select, Self::Scrub, Self::Draw, Self::Erase];
+
+    /// A short name for the mode, e.g. for a toolbar button.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Select => "Select",
+            Self::Scrub => "Scrub",
+            Self::Draw => "Draw",
+            Self::Erase => "Erase",
+        }
+    }
+
+    /// Whether the timeline leaves pointer interaction on the tracks to the host.
+    pub fn is_passthrough(&self) -> bool {
+        matches!(self, Self::Draw | Self::Erase)
+    }
+}
+
+/// A row of buttons for choosing between the given tool `modes`, e.g. `ToolMode::ALL`.
+///
+/// The returned response is marked as changed when a different mode is chosen.
+pub fn toolbar(ui: &mut egui::Ui, mode: &mut ToolMode, modes: &[ToolMode]) -> egui::Response {
+    let mut changed = false;
+    let mut response = ui
+        .horizontal(|ui| {
+            for &m in modes {
+                changed |= ui.selectable_value(mode, m, m.label()).changed();
+            }
+        })
+        .response;
+    if changed {
+        response.mark_changed();
+    }
+    response
+}
@@ -0,0 +1,283 @@
+//! A transport bar for the top panel, shown via `SetPlayhead::top_panel`.
+
+use crate::{playhead::PlayheadApi, time_display::TimeDisplay};
+use egui::{Color32, Pos2, Rect, Stroke, Vec2};
+
+/// The fill of the record button's icon while recording.
+pub const RECORD_COLOR: Color32 = Color32::from_rgb(220, 60, 60);
+
+/// The state of the transport, toggled by the buttons of a `TopPanel`.
+///
+/// The host applies changes to its own playback engine, e.g. by comparing against a copy taken
+/// before showing the panel.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TransportState {
+    /// Whether the transport is playing.
+    pub playing: bool,
+    /// Whether playback loops within the loop region.
+    pub looping: bool,
+    /// Whether the transport is recording.
+    pub recording: bool,
+}
+
+/// One of the standard buttons shown by a `TopPanel`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TransportButton {
+    /// Moves the playhead to tick 0.
+    ReturnToStart,
+    /// Stops playback and recording.
+    Stop,
+    /// Toggles `TransportState::playing`, showing a pause icon while playing.
+    PlayPause,
+    /// Toggles `TransportState::recording`.
+    Record,
+    /// Toggles `TransportState::looping`.
+    Loop,
+}
+
+impl TransportButton {
+    /// All buttons, in the order they are shown.
+    pub const ALL: [Self; 5] = [Self::ReturnToStart, Self::Stop, Self::PlayPause, Self::Record, Self::Loop];
+
+    /// A short description of the button, shown when hovered.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::ReturnToStart => "Return to start",
+            Self::Stop => "Stop",
+            Self::PlayPause => "Play/Pause",
+            Self::Record => "Record",
+            Self::Loop => "Loop",
+        }
+    }
+}
+
+/// Host widgets shown by a `TopPanel`.
+type Extra<'a> = Box<dyn FnOnce(&mut egui::Ui) + 'a>;
+
+/// The contents of the top panel: transport buttons on the left, a time readout on the right and
+/// any host widgets in between.
+///
+/// Shown via `SetPlayhead::top_panel`.
+pub struct TopPanel<'a> {
+    transport: Option<&'a mut TransportState>,
+    playhead: Option<&'a dyn PlayheadApi>,
+    time_display: TimeDisplay,
+    hidden: [bool; TransportButton::ALL.len()],
+    extra: Option<Extra<'a>>,
+}
+
+impl<'a> TopPanel<'a> {
+    /// A panel showing only the time readout until a `transport` or `playhead` is given.
+    pub fn new() -> Self {
+        Self {
+            transport: None,
+            playhead: None,
+            time_display: TimeDisplay::default(),
+            hidden: [false; TransportButton::ALL.len()],
+            extra: None,
+        }
+    }
+
+    /// The transport state toggled by the stop, play/pause, record and loop buttons.
+    ///
+    /// These buttons are only shown when a transport is given.
+    pub fn transport(mut self, state: &'a mut TransportState) -> Self {
+        self.transport = Some(state);
+        self
+    }
+
+    /// Access to the playhead, read by the time readout and moved by the return-to-start button.
+    ///
+    /// The return-to-start button is only shown when a playhead is given.
+    pub fn playhead(mut self, api: &'a dyn PlayheadApi) -> Self {
+        self.playhead = Some(api);
+        self
+    }
+
+    /// The initial format of the time readout. Clicking the readout cycles through the built-in
+    /// formats, remembering the choice in egui memory.
+    ///
+    /// Default: `TimeDisplay::BarsBeatsTicks`
+    pub fn time_display(mut self, display: TimeDisplay) -> Self {
+        self.time_display = display;
+        self
+    }
+
+    /// Hide the given button.
+    pub fn hide(mut self, button: TransportButton) -> Self {
+        self.hidden[button as usize] = true;
+        self
+    }
+
+    /// Host widgets shown between the transport buttons and the time readout, laid out left to
+    /// right across the rest of the panel's first row.
+    pub fn extra(mut self, add_contents: impl FnOnce(&mut egui::Ui) + 'a) -> Self {
+        self.extra = Some(Box::new(add_contents));
+        self
+    }
+
+    /// Show the panel within the given rect of the top panel.
+    pub(crate) fn show(self, ui: &mut egui::Ui, rect: Rect, display_id: egui::Id) {
+        let Self { mut transport, playhead, time_display, hidden, extra } = self;
+        let mut panel_ui = ui.new_child(
+            egui::UiBuilder::new()
+                .max_rect(rect)
+                .layout(egui::Layout::top_down(egui::Align::Min)),
+        );
+        panel_ui.add_space(2.0); // Top padding, matching `top_panel_time`
+        panel_ui.horizontal(|ui| {
+            ui.add_space(4.0); // Left padding
+            let item_spacing = std::mem::replace(&mut ui.spacing_mut().item_spacing.x, 2.0);
+            for button in TransportButton::ALL {
+                if hidden[button as usize] {
+                    continue;
+                }
+                if button == TransportButton::ReturnToStart {
+                    if let Some(api) = playhead
+                        && transport_button(ui, button, &TransportState::default()).clicked()
+                    {
+                        // The playhead is relative to the start of the view
+                        let ticks = -api.timeline_start_f64().unwrap_or(0.0);
+                        if ticks.is_finite() {
+                            api.set_playhead_ticks_f64(ticks);
+                        }
+                    }
+                    continue;
+                }
+                let Some(state) = transport.as_deref_mut() else {
+                    continue;
+                };
+                if transport_button(ui, button, state).clicked() {
+                    match button {
+                        TransportButton::Stop => {
+                            state.playing = false;
+                            state.recording = false;
+                        }
+                        TransportButton::PlayPause => state.playing = !state.playing,
+                        TransportButton::Record => state.recording = !state.recording,
+                        TransportButton::Loop => state.looping = !state.looping,
+                        TransportButton::ReturnToStart => {}
+                    }
+                }
+            }
+            ui.spacing_mut().item_spacing.x = item_spacing;
+            ui.add_space(4.0);
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                ui.add_space(4.0); // Right padding
+                time_readout(ui, display_id, playhead, time_display);
+                if let Some(extra) = extra {
+                    ui.with_layout(egui::Layout::left_to_right(egui::Align::Center), extra);
+                }
+            });
+        });
+    }
+}
+
+impl Default for TopPanel<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A label showing the playhead position in the format stored under `display_id`, cycling to
+/// the next format when clicked.
+pub(crate) fn time_readout(
+    ui: &mut egui::Ui,
+    display_id: egui::Id,
+    playhead: Option<&dyn PlayheadApi>,
+    time_display: TimeDisplay,
+) -> egui::Response {
+    let display = ui.data(|d| d.get_temp::<TimeDisplay>(display_id)).unwrap_or(time_display);
+    let time_string = match playhead {
+        // The playhead is relative to the start of the view
+        Some(api) => {
+            let absolute_ticks = api.timeline_start_f64().unwrap_or(0.0) + api.playhead_ticks_f64();
+            display.format(absolute_ticks as f32, api)
+        }
+        None => "-".to_string(),
+    };
+    let response = ui
+        .add(egui::Label::new(time_string).sense(egui::Sense::click()))
+        .on_hover_text("Click to change the time display");
+    if response.clicked() {
+        ui.data_mut(|d| d.insert_temp(display_id, display.next()));
+    }
+    response
+}
+
+/// A square button painted with the icon of the given transport button.
+fn transport_button(ui: &mut egui::Ui, button: TransportButton, state: &TransportState) -> egui::Response {
+    let size = Vec2::splat(ui.spacing().interact_size.y);
+    let (rect, response) = ui.allocate_exact_size(size, egui::Sense::click());
+    let active = match button {
+        TransportButton::ReturnToStart | TransportButton::Stop => false,
+        TransportButton::PlayPause => state.playing,
+        TransportButton::Record => state.recording,
+        TransportButton::Loop => state.looping,
+    };
+    response.widget_info(|| egui::WidgetInfo::selected(egui::WidgetType::Button, ui.is_enabled(), active, button.label()));
+
+    if ui.is_rect_visible(rect) {
+        let visuals = ui.style().interact_selectable(&response, active);
+        let stroke = if response.has_focus() {
+            ui.visuals().selection.stroke
+        } else {
+            visuals.bg_stroke
+        };
+        ui.painter().rect(rect, visuals.rounding, visuals.weak_bg_fill, stroke);
+        let color = match button {
+            TransportButton::Record if active => RECORD_COLOR,
+            _ => visuals.fg_stroke.color,
+        };
+        paint_icon(ui.painter(), rect.shrink(rect.height() * 0.25), button, state.playing, color);
+    }
+    response.on_hover_text(button.label())
+}
+
+/// Paint the icon of the given button within `rect`.
+fn paint_icon(painter: &egui::Painter, rect: Rect, button: TransportButton, playing: bool, color: Color32) {
+    let triangle = |points: Vec<Pos2>| egui::Shape::convex_polygon(points, color, Stroke::NONE);
+    match button {
+        TransportButton::ReturnToStart => {
+            let bar = Rect::from_min_max(rect.left_top(), Pos2::new(rect.left() + rect.width() * 0.2, rect.bottom()));
+            painter.rect_filled(bar, 0.0, color);
+            painter.add(triangle(vec![
+                Pos2::new(bar.right(), rect.center().y),
+                rect.right_top(),
+                rect.right_bottom(),
+            ]));
+        }
+        TransportButton::Stop => {
+            painter.rect_filled(rect.shrink(rect.width() * 0.05), 1.0, color);
+        }
+        TransportButton::PlayPause if playing => {
+            let w = rect.width() * 0.35;
+            painter.rect_filled(Rect::from_min_size(rect.left_top(), Vec2::new(w, rect.height())), 0.0, color);
+            painter.rect_filled(
+                Rect::from_min_size(Pos2::new(rect.right() - w, rect.top()), Vec2::new(w, rect.height())),
+                0.0,
+                color,
+            );
+        }
+        TransportButton::PlayPause => {
+            painter.add(triangle(vec![rect.left_top(), Pos2::new(rect.right(), rect.center().y), rect.left_bottom()]));
+        }
+        TransportButton::Record => {
+            painter.circle_filled(rect.center(), rect.width() * 0.5, color);
+        }
+        TransportButton::Loop => {
+            // A closed loop with an arrowhead on its top edge
+            let stroke = Stroke::new(1.5, color);
+            let body = rect.shrink2(Vec2::new(0.0, rect.height() * 0.15));
+            painter.rect_stroke(body, body.height() * 0.5, stroke);
+            let tip = Pos2::new(body.center().x + body.width() * 0.2, body.top());
+            let half = rect.height() * 0.22;
+            painter.add(triangle(vec![
+                tip,
+                Pos2::new(tip.x - half * 1.4, tip.y - half),
+                Pos2::new(tip.x - half * 1.4, tip.y + half),
+            ]));
+        }
+    }
+}
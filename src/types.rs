@@ -5,6 +5,7 @@ pub const MIN_STEP_GAP: f32 = 4.0;
 
 /// Represents a musical bar with its time signature and tick range.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Bar {
     /// The start and end offsets of the bar.
     pub tick_range: Range<f32>,
@@ -12,8 +13,46 @@ pub struct Bar {
     pub time_sig: TimeSig,
 }
 
+/// A lightweight identifier for a track, used to key selections and per-track state.
+///
+/// Unlike a `String`, a `TrackId` is `Copy` and may be compared and hashed without allocating.
+/// Ids created from equal values are equal, e.g. `TrackId::new("drums") == TrackId::from("drums")`.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct TrackId(egui::Id);
+
+impl TrackId {
+    /// Create a track id from any hashable value, e.g. a name, index or database key.
+    pub fn new(source: impl std::hash::Hash) -> Self {
+        Self(egui::Id::new(source))
+    }
+
+    /// The underlying `egui::Id`.
+    pub fn id(&self) -> egui::Id {
+        self.0
+    }
+}
+
+impl From<&str> for TrackId {
+    fn from(s: &str) -> Self {
+        Self::new(s)
+    }
+}
+
+impl From<&String> for TrackId {
+    fn from(s: &String) -> Self {
+        Self::new(s.as_str())
+    }
+}
+
+impl From<String> for TrackId {
+    fn from(s: String) -> Self {
+        Self::new(s.as_str())
+    }
+}
+
 /// Represents a musical time signature.
-#[derive(Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TimeSig {
     pub top: u16,
     pub bottom: u16,
@@ -25,3 +64,194 @@ impl TimeSig {
         4.0 * self.top as f32 / self.bottom as f32
     }
 }
+
+/// A tempo map describing how the tempo changes over the course of the timeline.
+///
+/// Each event is an absolute tick paired with the tempo in beats per minute that applies from
+/// that tick until the next event. The first event's tempo also applies to all ticks before it.
+/// An empty map behaves as a constant tempo of 120 BPM.
+///
+/// With the `serde` feature, only the ticks per beat and the events are serialized.
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "TempoMapData", into = "TempoMapData")
+)]
+pub struct TempoMap {
+    ticks_per_beat: u32,
+    /// `(tick, bpm)` events sorted by tick.
+    events: Vec<(f32, f32)>,
+    /// The time in seconds at each event, in the same order as `events`.
+    seconds: Vec<f32>,
+}
+
+impl TempoMap {
+    /// The tempo used when the map has no events.
+    pub const DEFAULT_BPM: f32 = 120.0;
+
+    /// Create a tempo map from the given `(tick, bpm)` events.
+    ///
+    /// Events need not be sorted. Ticks are absolute and beats are `ticks_per_beat` long.
+    pub fn new(ticks_per_beat: u32, events: impl IntoIterator<Item = (f32, f32)>) -> Self {
+        let mut events: Vec<(f32, f32)> = events.into_iter().collect();
+        events.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let ticks_per_beat_f = ticks_per_beat as f32;
+        let mut seconds = Vec::with_capacity(events.len());
+        let mut prev: Option<(f32, f32, f32)> = None;
+        for &(tick, bpm) in &events {
+            // The first event's tempo extends back to tick zero.
+            let s = match prev {
+                None => tick / ticks_per_beat_f * 60.0 / bpm,
+                Some((prev_tick, prev_bpm, prev_s)) => {
+                    prev_s + (tick - prev_tick) / ticks_per_beat_f * 60.0 / prev_bpm
+                }
+            };
+            seconds.push(s);
+            prev = Some((tick, bpm, s));
+        }
+        Self {
+            ticks_per_beat,
+            events,
+            seconds,
+        }
+    }
+
+    /// The `(tick, bpm)` events of the map, sorted by tick.
+    pub fn events(&self) -> &[(f32, f32)] {
+        &self.events
+    }
+
+    /// The tempo in beats per minute at the given absolute tick.
+    pub fn bpm_at_tick(&self, tick: f32) -> f32 {
+        let ix = self.events.partition_point(|e| e.0 <= tick).saturating_sub(1);
+        self.events.get(ix).map_or(Self::DEFAULT_BPM, |e| e.1)
+    }
+
+    /// The time in seconds at the given absolute tick.
+    pub fn tick_to_seconds(&self, tick: f32) -> f32 {
+        let ticks_per_beat = self.ticks_per_beat as f32;
+        let ix = self.events.partition_point(|e| e.0 <= tick).saturating_sub(1);
+        match self.events.get(ix) {
+            None => tick / ticks_per_beat * 60.0 / Self::DEFAULT_BPM,
+            Some(&(event_tick, bpm)) => {
+                self.seconds[ix] + (tick - event_tick) / ticks_per_beat * 60.0 / bpm
+            }
+        }
+    }
+
+    /// The same as `tick_to_seconds`, but at double precision, e.g. for long timelines.
+    pub fn tick_to_seconds_f64(&self, tick: f64) -> f64 {
+        let ticks_per_beat = self.ticks_per_beat as f64;
+        let ix = self.events.partition_point(|e| e.0 as f64 <= tick).saturating_sub(1);
+        match self.events.get(ix) {
+            None => tick / ticks_per_beat * 60.0 / Self::DEFAULT_BPM as f64,
+            Some(&(event_tick, bpm)) => {
+                self.seconds[ix] as f64 + (tick - event_tick as f64) / ticks_per_beat * 60.0 / bpm as f64
+            }
+        }
+    }
+
+    /// The same as `seconds_to_tick`, but at double precision.
+    pub fn seconds_to_tick_f64(&self, seconds: f64) -> f64 {
+        let ticks_per_beat = self.ticks_per_beat as f64;
+        let ix = self.seconds.partition_point(|s| *s as f64 <= seconds).saturating_sub(1);
+        match self.events.get(ix) {
+            None => seconds * Self::DEFAULT_BPM as f64 / 60.0 * ticks_per_beat,
+            Some(&(event_tick, bpm)) => {
+                event_tick as f64 + (seconds - self.seconds[ix] as f64) * bpm as f64 / 60.0 * ticks_per_beat
+            }
+        }
+    }
+
+    /// The absolute tick at the given time in seconds, the inverse of `tick_to_seconds`.
+    pub fn seconds_to_tick(&self, seconds: f32) -> f32 {
+        let ticks_per_beat = self.ticks_per_beat as f32;
+        let ix = self.seconds.partition_point(|s| *s <= seconds).saturating_sub(1);
+        match self.events.get(ix) {
+            None => seconds * Self::DEFAULT_BPM / 60.0 * ticks_per_beat,
+            Some(&(event_tick, bpm)) => {
+                event_tick + (seconds - self.seconds[ix]) * bpm / 60.0 * ticks_per_beat
+            }
+        }
+    }
+}
+
+/// The serialized form of a `TempoMap`, from which the times of its events are recomputed.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct TempoMapData {
+    ticks_per_beat: u32,
+    events: Vec<(f32, f32)>,
+}
+
+#[cfg(feature = "serde")]
+impl From<TempoMapData> for TempoMap {
+    fn from(data: TempoMapData) -> Self {
+        Self::new(data.ticks_per_beat, data.events)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<TempoMap> for TempoMapData {
+    fn from(map: TempoMap) -> Self {
+        Self {
+            ticks_per_beat: map.ticks_per_beat,
+            events: map.events,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 120 BPM for a bar of 4/4, 60 BPM for two beats, then 240 BPM, at 960 PPQN.
+    fn tempo_map() -> TempoMap {
+        TempoMap::new(960, [(5760.0, 240.0), (0.0, 120.0), (3840.0, 60.0)])
+    }
+
+    #[test]
+    fn tempo_changes() {
+        let map = tempo_map();
+        assert_eq!(map.tick_to_seconds(3840.0), 2.0);
+        assert_eq!(map.tick_to_seconds(5760.0), 4.0);
+        assert_eq!(map.tick_to_seconds(6720.0), 4.25);
+        assert_eq!(map.bpm_at_tick(3839.0), 120.0);
+        assert_eq!(map.bpm_at_tick(3840.0), 60.0);
+        assert_eq!(map.seconds_to_tick(3.0), 4800.0);
+    }
+
+    #[test]
+    fn round_trip_across_tempo_changes() {
+        let map = tempo_map();
+        // Step across each tempo change, landing exactly on the events along the way.
+        for tick in (-960..10_000).step_by(40).map(|tick| tick as f32) {
+            let seconds = map.tick_to_seconds(tick);
+            assert!((map.seconds_to_tick(seconds) - tick).abs() < 1e-2, "{tick}");
+            let seconds = map.tick_to_seconds_f64(tick as f64);
+            assert!((map.seconds_to_tick_f64(seconds) - tick as f64).abs() < 1e-2, "{tick}");
+        }
+    }
+
+    #[test]
+    fn ticks_before_first_event() {
+        // The first event's tempo applies before it
+        let map = TempoMap::new(960, [(1920.0, 60.0), (3840.0, 120.0)]);
+        assert_eq!(map.bpm_at_tick(0.0), 60.0);
+        assert_eq!(map.tick_to_seconds(0.0), 0.0);
+        assert_eq!(map.tick_to_seconds(960.0), 1.0);
+        assert_eq!(map.tick_to_seconds(-960.0), -1.0);
+        assert_eq!(map.seconds_to_tick(1.0), 960.0);
+        assert_eq!(map.seconds_to_tick(-1.0), -960.0);
+        assert_eq!(map.tick_to_seconds(3840.0 + 960.0), 4.5);
+    }
+
+    #[test]
+    fn empty_map_is_default_tempo() {
+        let map = TempoMap::new(960, []);
+        assert_eq!(map.bpm_at_tick(1000.0), TempoMap::DEFAULT_BPM);
+        assert_eq!(map.tick_to_seconds(960.0), 0.5);
+        assert_eq!(map.seconds_to_tick(0.5), 960.0);
+    }
+}
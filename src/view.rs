@@ -0,0 +1,227 @@
+//! Programmatic control of the visible range of the timeline, e.g. zooming to fit a selection.
+
+use crate::{interaction::{self, TrackSelectionApi}, TimelineApi};
+use std::ops::Range;
+
+/// The margin left either side of a range fitted by `fit_range`, as a fraction of its length.
+pub const FIT_MARGIN: f32 = 0.05;
+
+/// Zoom and scroll so that the given range of absolute ticks fills a view of the given width,
+/// with a margin of `FIT_MARGIN` either side.
+///
+/// The zoom is set via `TimelineApi::set_ticks_per_point`. Hosts that don't implement it are
+/// zoomed via `TimelineApi::zoom` instead, which is exact for hosts that zoom exponentially with
+/// the delta as egui does. If the host limits the zoom, the range is centred within the view.
+pub fn fit_range(api: &mut dyn TimelineApi, range: Range<f32>, viewport_width_points: f32) {
+    fit_range_with_pre_roll(api, range, viewport_width_points, 0.0);
+}
+
+/// The same as `fit_range`, but allowing the view to start as early as `-pre_roll`, as set via
+/// `Timeline::pre_roll`.
+pub(crate) fn fit_range_with_pre_roll(
+    api: &mut dyn TimelineApi,
+    range: Range<f32>,
+    viewport_width_points: f32,
+    pre_roll: f32,
+) {
+    if viewport_width_points <= 0.0 {
+        return;
+    }
+    let len = (range.end - range.start).max(1.0);
+    set_ticks_per_point(api, len * (1.0 + 2.0 * FIT_MARGIN) / viewport_width_points);
+    let visible_ticks = api.musical_ruler_info().ticks_per_point() * viewport_width_points;
+    let centre = (range.start as f64 + range.end as f64) * 0.5;
+    interaction::scroll_to_start(api, centre - visible_ticks as f64 * 0.5, visible_ticks, pre_roll);
+}
+
+/// Set the zoom via `TimelineApi::set_ticks_per_point`, falling back to `TimelineApi::zoom` for
+/// hosts that don't implement it.
+pub(crate) fn set_ticks_per_point(api: &mut dyn TimelineApi, target: f32) {
+    api.set_ticks_per_point(target);
+    let current = api.musical_ruler_info().ticks_per_point();
+    if (current - target).abs() > target * 0.001 {
+        api.zoom(interaction::zoom_delta_for_scale(target / current));
+    }
+}
+
+/// The position of the view onto the timeline, e.g. to save and restore along with a project.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ViewState {
+    /// The absolute tick at the left edge of the timeline area.
+    pub timeline_start: f64,
+    /// The zoom of the timeline.
+    pub ticks_per_point: f32,
+    /// The offset of the tracks' scroll area, as given by `SetPlayhead::scroll_offset`.
+    pub vertical_scroll: f32,
+}
+
+impl ViewState {
+    /// Capture the current view of the given timeline, along with the tracks' `vertical_scroll`.
+    pub fn capture(api: &dyn TimelineApi, vertical_scroll: f32) -> Self {
+        Self {
+            timeline_start: api.timeline_start_f64(),
+            ticks_per_point: api.musical_ruler_info().ticks_per_point(),
+            vertical_scroll,
+        }
+    }
+
+    /// Apply the zoom and horizontal position of the view to the given timeline.
+    ///
+    /// The zoom is set as by `fit_range`. The vertical scroll is applied by passing
+    /// `ScrollTo::Offset(vertical_scroll)` to `Show::scroll_to`.
+    pub fn apply(&self, api: &mut dyn TimelineApi) {
+        set_ticks_per_point(api, self.ticks_per_point);
+        let start = api.timeline_start_f64();
+        if self.timeline_start != start {
+            api.shift_timeline_start_f64(self.timeline_start - start);
+        }
+    }
+}
+
+/// The view of a timeline, stored in egui memory by `Timeline::remember_view`.
+#[derive(Copy, Clone, Debug)]
+struct RememberedView {
+    view: ViewState,
+    /// The pass on which the view was stored.
+    pass_nr: u64,
+}
+
+/// Restore the view stored under the given `id` if the timeline was not shown on the previous
+/// pass, e.g. after switching back to its tab.
+pub(crate) fn restore(ui: &egui::Ui, id: egui::Id, api: &mut dyn TimelineApi) {
+    let pass_nr = ui.ctx().cumulative_pass_nr();
+    if let Some(remembered) = ui.data(|d| d.get_temp::<RememberedView>(id))
+        && remembered.pass_nr + 1 < pass_nr
+    {
+        remembered.view.apply(api);
+    }
+}
+
+/// Store the current view under the given `id` to be restored via `restore`.
+pub(crate) fn remember(ui: &egui::Ui, id: egui::Id, api: &dyn TimelineApi) {
+    let view = RememberedView {
+        view: ViewState::capture(api, 0.0),
+        pass_nr: ui.ctx().cumulative_pass_nr(),
+    };
+    ui.data_mut(|d| d.insert_temp(id, view));
+}
+
+/// A handle linking the views of several timelines so that they scroll and zoom together, e.g. an
+/// arrangement stacked above a piano roll.
+///
+/// Pass copies of the same link to `Timeline::link`. The view is shared via egui memory, so linked
+/// timelines must be shown with the same `egui::Context`. Each timeline adopts changes made by the
+/// others at the start of `Timeline::show`, applying them via its own `TimelineApi`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ViewLink {
+    id: egui::Id,
+    vertical: bool,
+}
+
+/// A value shared between linked timelines, along with a version incremented on each change.
+#[derive(Clone, Debug)]
+struct Shared<T> {
+    value: T,
+    version: u64,
+}
+
+impl ViewLink {
+    /// Create a link from any hashable value. Links created from equal values are the same link.
+    pub fn new(id_salt: impl std::hash::Hash) -> Self {
+        Self {
+            id: egui::Id::new("egui_timeline_view_link").with(id_salt),
+            vertical: false,
+        }
+    }
+
+    /// Also link the vertical scroll of the tracks, rather than only the horizontal view.
+    ///
+    /// Default: `false`
+    pub fn vertical(mut self, linked: bool) -> Self {
+        self.vertical = linked;
+        self
+    }
+
+    /// Whether the vertical scroll of the tracks is linked.
+    pub fn is_vertical(&self) -> bool {
+        self.vertical
+    }
+
+    /// Adopt the horizontal view shared by another timeline if it has changed since the timeline
+    /// with the given id last saw it, otherwise share this timeline's view.
+    pub(crate) fn sync_view(&self, ui: &egui::Ui, timeline_id: egui::Id, api: &mut dyn TimelineApi) {
+        let seen_id = timeline_id.with("view_link_seen");
+        match receive::<ViewState>(ui, self.id, seen_id) {
+            Some(view) => view.apply(api),
+            None => self.share_view(ui, timeline_id, api),
+        }
+    }
+
+    /// Share this timeline's horizontal view if it differs from the linked view.
+    pub(crate) fn share_view(&self, ui: &egui::Ui, timeline_id: egui::Id, api: &dyn TimelineApi) {
+        let seen_id = timeline_id.with("view_link_seen");
+        let view = ViewState::capture(api, 0.0);
+        share(ui, self.id, seen_id, view, |a, b| {
+            (a.timeline_start - b.timeline_start).abs() > 1e-3
+                || (a.ticks_per_point - b.ticks_per_point).abs() > a.ticks_per_point * 1e-4
+        });
+    }
+
+    /// The vertical scroll offset shared by another timeline, if linked and changed since the
+    /// timeline with the given id last saw it.
+    pub(crate) fn receive_scroll(&self, ui: &egui::Ui, timeline_id: egui::Id) -> Option<f32> {
+        if !self.vertical {
+            return None;
+        }
+        receive(ui, self.id.with("vertical"), timeline_id.with("view_link_scroll_seen"))
+    }
+
+    /// Share this timeline's vertical scroll offset if linked and changed.
+    pub(crate) fn share_scroll(&self, ui: &egui::Ui, timeline_id: egui::Id, offset: f32) {
+        if self.vertical {
+            let seen_id = timeline_id.with("view_link_scroll_seen");
+            share(ui, self.id.with("vertical"), seen_id, offset, |a, b| (a - b).abs() > 0.5);
+        }
+    }
+}
+
+/// The value shared under `shared_id` if another timeline changed it since it was last seen.
+fn receive<T>(ui: &egui::Ui, shared_id: egui::Id, seen_id: egui::Id) -> Option<T>
+where
+    T: 'static + Clone + Send + Sync,
+{
+    let shared = ui.data(|d| d.get_temp::<Shared<T>>(shared_id))?;
+    let seen = ui.data(|d| d.get_temp::<u64>(seen_id));
+    if seen == Some(shared.version) {
+        return None;
+    }
+    ui.data_mut(|d| d.insert_temp(seen_id, shared.version));
+    Some(shared.value)
+}
+
+/// Share the given value under `shared_id` if it `differs` from the value already shared.
+fn share<T>(ui: &egui::Ui, shared_id: egui::Id, seen_id: egui::Id, value: T, differs: impl Fn(&T, &T) -> bool)
+where
+    T: 'static + Clone + Send + Sync,
+{
+    let shared = ui.data(|d| d.get_temp::<Shared<T>>(shared_id));
+    if shared.as_ref().is_some_and(|shared| !differs(&shared.value, &value)) {
+        return;
+    }
+    let version = shared.map_or(0, |shared| shared.version) + 1;
+    ui.data_mut(|d| {
+        d.insert_temp(shared_id, Shared { value, version });
+        d.insert_temp(seen_id, version);
+    });
+}
+
+/// The range of absolute ticks spanning every track's selections, or `None` if nothing is
+/// selected.
+pub fn selection_range(api: &dyn TrackSelectionApi) -> Option<Range<f32>> {
+    api.all_selections()
+        .into_iter()
+        .flat_map(|(_, ranges)| ranges)
+        .map(|(start, end)| start.min(end)..start.max(end))
+        .reduce(|a, b| a.start.min(b.start)..a.end.max(b.end))
+}
@@ -0,0 +1,171 @@
+//! A shared zoom curve for hosts implementing `TimelineApi::zoom`, and a slider for zooming.
+
+use crate::{view, TimelineApi};
+use std::ops::RangeInclusive;
+
+/// The factor by which each of the `zoom_slider`'s buttons scales the ticks per point.
+pub const ZOOM_STEP: f32 = 1.5;
+
+/// How a zoom delta, as passed to `TimelineApi::zoom`, changes the ticks per point.
+///
+/// Hosts may apply these via `apply_zoom` rather than implementing their own curve, so that
+/// scrolling, pinch gestures and keyboard shortcuts all zoom consistently.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ZoomSettings {
+    /// The fewest ticks per point, i.e. the furthest the view can zoom in.
+    pub min_ticks_per_point: f32,
+    /// The most ticks per point, i.e. the furthest the view can zoom out.
+    pub max_ticks_per_point: f32,
+    /// The zoom per unit of delta.
+    ///
+    /// When `exponential`, each unit scales the ticks per point by `exp(speed)`. Otherwise each
+    /// unit adds `speed` ticks per point.
+    pub speed: f32,
+    /// Whether the zoom scales the ticks per point rather than offsetting it, so that each step
+    /// feels the same at every zoom level.
+    pub exponential: bool,
+}
+
+impl ZoomSettings {
+    /// The default `speed`, matching egui's conversion between `Ctrl` + scroll and zoom factors.
+    ///
+    /// Pinch gestures are converted to a delta using this speed, so they scale the view by
+    /// exactly the pinched amount when zooming exponentially at this speed.
+    pub const DEFAULT_SPEED: f32 = 1.0 / 200.0;
+
+    /// Set the range of ticks per point that the zoom is clamped to.
+    pub fn range(mut self, min_ticks_per_point: f32, max_ticks_per_point: f32) -> Self {
+        self.min_ticks_per_point = min_ticks_per_point;
+        self.max_ticks_per_point = max_ticks_per_point;
+        self
+    }
+
+    /// Set the zoom per unit of delta.
+    pub fn speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Set whether the zoom scales the ticks per point rather than offsetting it.
+    pub fn exponential(mut self, exponential: bool) -> Self {
+        self.exponential = exponential;
+        self
+    }
+
+    /// Clamp the given ticks per point to the zoom range.
+    pub fn clamp(&self, ticks_per_point: f32) -> f32 {
+        ticks_per_point.max(self.min_ticks_per_point).min(self.max_ticks_per_point)
+    }
+}
+
+impl Default for ZoomSettings {
+    fn default() -> Self {
+        Self {
+            min_ticks_per_point: 0.1,
+            max_ticks_per_point: 10_000.0,
+            speed: Self::DEFAULT_SPEED,
+            exponential: true,
+        }
+    }
+}
+
+/// Apply a zoom delta, as passed to `TimelineApi::zoom`, to the given ticks per point.
+///
+/// Positive deltas zoom out. The result is clamped to the range of the `settings`.
+pub fn apply_zoom(ticks_per_point: &mut f32, delta: f32, settings: &ZoomSettings) {
+    let zoomed = if settings.exponential {
+        *ticks_per_point * (delta * settings.speed).exp()
+    } else {
+        *ticks_per_point + delta * settings.speed
+    };
+    *ticks_per_point = settings.clamp(zoomed);
+}
+
+/// A horizontal slider with `-` and `+` buttons for zooming the timeline, e.g. within the bottom
+/// bar.
+///
+/// The slider maps logarithmically onto the given `range` of ticks per point, zooming in towards
+/// the right, and each button scales the zoom by `ZOOM_STEP`. The slider reads the zoom from
+/// `musical_ruler_info().ticks_per_point()` each frame, so it follows zooming by other means such
+/// as `Ctrl` + scroll. Changes are applied as by `view::fit_range`, keeping the start of the view in
+/// place. The returned response is marked as changed when the zoom changes.
+pub fn zoom_slider(ui: &mut egui::Ui, api: &mut dyn TimelineApi, range: RangeInclusive<f32>) -> egui::Response {
+    let (min, max) = (range.start().min(*range.end()), range.start().max(*range.end()));
+    let current = api.musical_ruler_info().ticks_per_point();
+    let mut target = current.clamp(min, max);
+    let mut changed = false;
+    let mut response = ui
+        .horizontal(|ui| {
+            ui.spacing_mut().item_spacing.x = 2.0;
+            if ui.small_button("-").on_hover_text("Zoom out").clicked() {
+                target = (target * ZOOM_STEP).min(max);
+                changed = true;
+            }
+            // The range is reversed so that moving right zooms in
+            let slider = egui::Slider::new(&mut target, max..=min).logarithmic(true).show_value(false);
+            changed |= ui.add(slider).on_hover_text("Zoom").changed();
+            if ui.small_button("+").on_hover_text("Zoom in").clicked() {
+                target = (target / ZOOM_STEP).max(min);
+                changed = true;
+            }
+        })
+        .response;
+    if changed && target != current {
+        view::set_ticks_per_point(api, target);
+        response.mark_changed();
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CURVES: [bool; 2] = [true, false];
+
+    /// Zoom between 1 and 1000 ticks per point, by half a tick per point for each unit of delta
+    /// when zooming linearly.
+    fn settings(exponential: bool) -> ZoomSettings {
+        let speed = if exponential { ZoomSettings::DEFAULT_SPEED } else { 0.5 };
+        ZoomSettings::default().range(1.0, 1000.0).exponential(exponential).speed(speed)
+    }
+
+    fn zoomed(ticks_per_point: f32, delta: f32, settings: &ZoomSettings) -> f32 {
+        let mut ticks_per_point = ticks_per_point;
+        apply_zoom(&mut ticks_per_point, delta, settings);
+        ticks_per_point
+    }
+
+    #[test]
+    fn zoom_is_monotonic() {
+        for exponential in CURVES {
+            let settings = settings(exponential);
+            for start in [2.0, 60.0, 500.0] {
+                let mut last = zoomed(start, -100.0, &settings);
+                for delta in (-99..=100).map(|delta| delta as f32) {
+                    let next = zoomed(start, delta, &settings);
+                    assert!(next >= last, "exponential: {exponential}, {start} by {delta}");
+                    last = next;
+                }
+                assert_eq!(zoomed(start, 0.0, &settings), start);
+                assert!(zoomed(start, 10.0, &settings) > start);
+                assert!(zoomed(start, -10.0, &settings) < start);
+            }
+        }
+    }
+
+    #[test]
+    fn zoom_is_clamped() {
+        for exponential in CURVES {
+            let settings = settings(exponential);
+            // Zooming far in or out stops at either end of the range
+            assert_eq!(zoomed(60.0, -1e6, &settings), 1.0);
+            assert_eq!(zoomed(60.0, 1e6, &settings), 1000.0);
+            assert_eq!(zoomed(1.0, -1.0, &settings), 1.0);
+            assert_eq!(zoomed(1000.0, 1.0, &settings), 1000.0);
+            // Values outside of the range are brought back within it
+            assert_eq!(zoomed(0.01, 0.0, &settings), 1.0);
+            assert_eq!(zoomed(1e5, 0.0, &settings), 1000.0);
+        }
+    }
+}
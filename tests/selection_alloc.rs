@@ -0,0 +1,237 @@
+//! Checks that the selection queries made for every track on every frame do not allocate.
+//!
+//! The counting allocator is global to this test binary, so it holds a single test.
+
+use egui_timeline::{DefaultSelectionState, SimpleTimelineState, Timeline, TrackId, TrackSelectionApi};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    /// Whether allocations on this thread are counted, so the test harness's own threads are not.
+    static COUNTING: Cell<bool> = const { Cell::new(false) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if COUNTING.with(Cell::get) {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        }
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        if COUNTING.with(Cell::get) {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        }
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// The number of allocations made on this thread by `f`.
+fn count_allocations<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    COUNTING.with(|counting| counting.set(true));
+    let result = f();
+    COUNTING.with(|counting| counting.set(false));
+    (result, ALLOCATIONS.load(Ordering::Relaxed) - before)
+}
+
+/// Counts the allocations made by the selection queries that the timeline makes of `api`.
+///
+/// Allocations made by the timeline itself, e.g. while painting the selections visited via
+/// `for_each_selection`, are not counted.
+struct Counted<'a> {
+    api: &'a dyn TrackSelectionApi,
+    queries: Cell<usize>,
+    allocations: Cell<usize>,
+}
+
+impl Counted<'_> {
+    fn query<R>(&self, f: impl FnOnce() -> R) -> R {
+        let (result, allocations) = count_allocations(f);
+        self.queries.set(self.queries.get() + 1);
+        self.allocations.set(self.allocations.get() + allocations);
+        result
+    }
+}
+
+impl TrackSelectionApi for Counted<'_> {
+    fn ticks_per_point(&self) -> f32 {
+        self.query(|| self.api.ticks_per_point())
+    }
+
+    fn timeline_start(&self) -> f32 {
+        self.query(|| self.api.timeline_start())
+    }
+
+    fn start_selection_drag(&self, track_id: TrackId, start_tick: f32) {
+        self.api.start_selection_drag(track_id, start_tick);
+    }
+
+    fn update_selection_drag(&self, track_id: TrackId, end_tick: f32) {
+        self.api.update_selection_drag(track_id, end_tick);
+    }
+
+    fn get_drag_start(&self) -> Option<(TrackId, f32)> {
+        self.query(|| self.api.get_drag_start())
+    }
+
+    fn end_selection_drag(&self) {
+        self.api.end_selection_drag();
+    }
+
+    fn set_selection(&self, track_id: TrackId, start_tick: f32, end_tick: f32) {
+        self.api.set_selection(track_id, start_tick, end_tick);
+    }
+
+    fn clear_selection(&self, track_id: TrackId) {
+        self.api.clear_selection(track_id);
+    }
+
+    fn clear_all_selections(&self) {
+        self.api.clear_all_selections();
+    }
+
+    fn get_selection(&self, track_id: TrackId) -> Option<(f32, f32)> {
+        self.query(|| self.api.get_selection(track_id))
+    }
+
+    fn get_selected_track_id(&self) -> Option<TrackId> {
+        self.query(|| self.api.get_selected_track_id())
+    }
+
+    fn drag_track(&self) -> Option<TrackId> {
+        self.query(|| self.api.drag_track())
+    }
+
+    fn drag_start_tick(&self) -> Option<f32> {
+        self.query(|| self.api.drag_start_tick())
+    }
+
+    fn for_each_selection(&self, track_id: TrackId, visit: &mut dyn FnMut(f32, f32)) {
+        self.query(|| {
+            self.api.for_each_selection(track_id, &mut |start, end| {
+                COUNTING.with(|counting| counting.set(false));
+                visit(start, end);
+                COUNTING.with(|counting| counting.set(true));
+            })
+        });
+    }
+
+    fn all_selections(&self) -> Vec<(TrackId, Vec<(f32, f32)>)> {
+        self.api.all_selections()
+    }
+
+    fn add_selection(&self, track_id: TrackId, start_tick: f32, end_tick: f32) {
+        self.api.add_selection(track_id, start_tick, end_tick);
+    }
+
+    fn selections(&self, track_id: TrackId) -> Vec<(f32, f32)> {
+        self.api.selections(track_id)
+    }
+
+    fn remove_selection(&self, track_id: TrackId, index: usize) {
+        self.api.remove_selection(track_id, index);
+    }
+}
+
+/// Show 100 tracks with selections from `api`, as a frame with the given pointer events.
+///
+/// Returns the area of each track's timeline.
+fn frame(
+    ctx: &egui::Context,
+    time: &mut f64,
+    view: &mut SimpleTimelineState,
+    api: &Counted,
+    events: Vec<egui::Event>,
+) -> Vec<egui::Rect> {
+    let screen_rect = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(800.0, 2000.0));
+    *time += 0.1;
+    let input = egui::RawInput { screen_rect: Some(screen_rect), time: Some(*time), events, ..Default::default() };
+    let mut rects = vec![];
+    let _ = ctx.run(input, |ctx| {
+        rects.clear();
+        egui::CentralPanel::default().show(ctx, |ui| {
+            Timeline::new().show(ui, &mut *view).tracks(
+                |tracks, _viewport, ui, _, _| {
+                    for i in 0..100 {
+                        let response = tracks.next(ui).with_id(TrackId::new(i)).fixed_height(16.0).show(
+                            |_timeline, _ui| (),
+                            None,
+                            Some(api),
+                            None::<fn(TrackId)>,
+                            false,
+                        );
+                        rects.push(response.timeline_rect);
+                    }
+                },
+                None,
+                None,
+            );
+        });
+    });
+    rects
+}
+
+#[test]
+fn per_frame_selection_queries_do_not_allocate() {
+    let simple = SimpleTimelineState::new();
+    let default = DefaultSelectionState::new();
+    let apis: [&dyn TrackSelectionApi; 2] = [&simple, &default];
+    for api in apis {
+        for i in 0..100 {
+            let track_id = TrackId::new(i);
+            api.add_selection(track_id, i as f32 * 100.0, i as f32 * 100.0 + 50.0);
+            api.add_selection(track_id, i as f32 * 100.0 + 60.0, i as f32 * 100.0 + 80.0);
+        }
+        let api = Counted { api, queries: Cell::new(0), allocations: Cell::new(0) };
+        let ctx = egui::Context::default();
+        let mut time = 0.0;
+        let mut view = SimpleTimelineState::new();
+        let mut frame = |events| frame(&ctx, &mut time, &mut view, &api, events);
+
+        // Press on a track and start dragging out a selection.
+        let rects = frame(vec![]);
+        let start = egui::pos2(rects[42].left() + 600.0, rects[42].center().y);
+        let button = |pressed| egui::Event::PointerButton {
+            pos: start,
+            button: egui::PointerButton::Primary,
+            pressed,
+            modifiers: egui::Modifiers::NONE,
+        };
+        frame(vec![egui::Event::PointerMoved(start)]);
+        frame(vec![button(true)]);
+        for x in 1..=5 {
+            frame(vec![egui::Event::PointerMoved(start + egui::vec2(x as f32 * 10.0, 0.0))]);
+        }
+        assert_eq!(api.drag_track(), Some(TrackId::new(42)));
+
+        // Continue the drag, then idle once it has ended.
+        api.queries.set(0);
+        api.allocations.set(0);
+        for x in 6..=15 {
+            frame(vec![egui::Event::PointerMoved(start + egui::vec2(x as f32 * 10.0, 0.0))]);
+        }
+        frame(vec![button(false)]);
+        assert_eq!(api.drag_track(), None);
+        for _ in 0..10 {
+            frame(vec![]);
+        }
+        assert!(api.queries.get() >= 20 * 100);
+        assert_eq!(api.allocations.get(), 0);
+    }
+}